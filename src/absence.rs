@@ -0,0 +1,163 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::classify::fold;
+
+const MONTHS: &[(&str, u32)] = &[
+    ("janeiro", 1),
+    ("fevereiro", 2),
+    ("marco", 3),
+    ("abril", 4),
+    ("maio", 5),
+    ("junho", 6),
+    ("julho", 7),
+    ("agosto", 8),
+    ("setembro", 9),
+    ("outubro", 10),
+    ("novembro", 11),
+    ("dezembro", 12),
+    ("january", 1),
+    ("february", 2),
+    ("march", 3),
+    ("april", 4),
+    ("may", 5),
+    ("june", 6),
+    ("july", 7),
+    ("august", 8),
+    ("september", 9),
+    ("october", 10),
+    ("november", 11),
+    ("december", 12),
+    ("janvier", 1),
+    ("fevrier", 2),
+    ("mars", 3),
+    ("avril", 4),
+    ("mai", 5),
+    ("juin", 6),
+    ("juillet", 7),
+    ("aout", 8),
+    ("septembre", 9),
+    ("octobre", 10),
+    ("novembre", 11),
+    ("decembre", 12),
+    // abreviaturas inglesas; as portuguesas ("out", "set") são palavras a mais
+    ("jan", 1),
+    ("feb", 2),
+    ("mar", 3),
+    ("apr", 4),
+    ("jun", 6),
+    ("jul", 7),
+    ("aug", 8),
+    ("sep", 9),
+    ("sept", 9),
+    ("oct", 10),
+    ("nov", 11),
+    ("dec", 12),
+];
+
+// uma ausência não dura mais do que isto; datas mais longe são outra coisa
+const MAX_ABSENCE_DAYS: i64 = 180;
+
+fn month(word: &str) -> Option<u32> {
+    MONTHS.iter().find(|(name, _)| *name == word).map(|(_, m)| *m)
+}
+
+// "20", "20th", "1er", "1.º" → dia
+fn day(word: &str) -> Option<u32> {
+    let digits: String = word.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let rest = &word[digits.len()..];
+    let suffix = ["", "st", "nd", "rd", "th", "er", "o", "º"].contains(&rest);
+    digits.parse().ok().filter(|d| (1..=31).contains(d) && suffix)
+}
+
+fn year(word: &str) -> Option<i32> {
+    word.parse().ok().filter(|y| (2000..=2100).contains(y))
+}
+
+// sem ano: o do dia em que chegou, ou o seguinte se já tiver passado
+fn complete(day: u32, month: u32, year: Option<i32>, received: NaiveDate) -> Option<NaiveDate> {
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+        None => {
+            let date = NaiveDate::from_ymd_opt(received.year(), month, day)?;
+            if date < received {
+                NaiveDate::from_ymd_opt(received.year() + 1, month, day)
+            } else {
+                Some(date)
+            }
+        }
+    }
+}
+
+// "20/10", "20.10.2026", "2026-10-20"; dia primeiro, salvo se não puder ser
+fn numeric(token: &str, received: NaiveDate) -> Option<NaiveDate> {
+    let parts: Vec<&str> = token.split(['/', '.', '-']).collect();
+    let numbers: Vec<u32> = parts.iter().map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    match numbers.as_slice() {
+        [y, m, d] if parts[0].len() == 4 => NaiveDate::from_ymd_opt(*y as i32, *m, *d),
+        [a, b, rest @ ..] if rest.len() <= 1 && parts[0].len() <= 2 && parts[1].len() <= 2 => {
+            let year = match rest {
+                [y] if parts[2].len() == 4 => Some(*y as i32),
+                [y] if parts[2].len() == 2 => Some(2000 + *y as i32),
+                [] => None,
+                _ => return None,
+            };
+            let (d, m) = if *b > 12 { (*b, *a) } else { (*a, *b) };
+            complete(d, m, year, received)
+        }
+        _ => None,
+    }
+}
+
+/// O dia em que quem respondeu volta, pela última data futura da mensagem
+/// ("ausente até 20/10", "back on October 20th", "de retour le 3 novembre").
+pub fn return_date(text: &str, received: NaiveDate) -> Option<NaiveDate> {
+    let text = fold(text);
+    let mut dates = vec![];
+    for token in text.split(|c: char| c.is_whitespace() || ",;()".contains(c)) {
+        let token = token.trim_end_matches(['.', ':']);
+        if token.contains(['/', '.', '-']) {
+            dates.extend(numeric(token, received));
+        }
+    }
+    let words: Vec<&str> = text.split(|c: char| !c.is_alphanumeric() && c != 'º').filter(|w| !w.is_empty()).collect();
+    for (i, word) in words.iter().enumerate() {
+        let Some(m) = month(word) else { continue };
+        let next = |n: usize| words.get(i + n).copied().unwrap_or_default();
+        let prev = |n: usize| if i >= n { words[i - n] } else { "" };
+        // "20 de outubro de 2026", "20 october 2026", "october 20th, 2026"
+        let before = day(prev(1)).or_else(|| (prev(1) == "de").then(|| day(prev(2))).flatten());
+        let (d, after) = match before {
+            Some(d) => (Some(d), if next(1) == "de" { next(2) } else { next(1) }),
+            None => (day(next(1)), next(2)),
+        };
+        if let Some(d) = d {
+            dates.extend(complete(d, m, year(after), received));
+        }
+    }
+    let limit = received + Duration::days(MAX_ABSENCE_DAYS);
+    dates.into_iter().filter(|d| *d >= received && *d <= limit).max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_return_dates() {
+        let received = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let date = |m, d| NaiveDate::from_ymd_opt(2026, m, d);
+        assert_eq!(return_date("Estou ausente de 10/10 a 20/10. Regresso a 21/10/2026.", received), date(10, 21));
+        assert_eq!(return_date("Estarei de férias até 3 de novembro.", received), date(11, 3));
+        assert_eq!(return_date("I'm out of the office until October 20th, 2026.", received), date(10, 20));
+        assert_eq!(return_date("Back on Mon 19 Oct", received), date(10, 19));
+        assert_eq!(return_date("Je serai de retour le 2 novembre", received), date(11, 2));
+        assert_eq!(return_date("Returning 2026-10-26", received), date(10, 26));
+        // mês primeiro quando o segundo número não pode ser mês
+        assert_eq!(return_date("back 10/25", received), date(10, 25));
+        // sem ano e já passado: o do ano seguinte
+        assert_eq!(return_date("ausente até 5 de janeiro", received), NaiveDate::from_ymd_opt(2027, 1, 5));
+        assert_eq!(return_date("Obrigado pelo seu email.", received), None);
+        assert_eq!(return_date("Ausente desde 1/10", received), None);
+        assert_eq!(return_date("Versão 1.2.3", received), None);
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::{bail, Context, Result};
+use console::style;
+use dialoguer::{theme::ColorfulTheme, Input, Select};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::{fs, io::IsTerminal, sync::atomic::Ordering, time::Duration};
+use tokio::{net::TcpStream, time::timeout};
+
+use crate::{enrich, overrides, presets, verify, SmtpConfig, SmtpSecurity, CHECK, CONFIG_FILE, CROSS, NOTE};
+
+/// Base de dados de fornecedores do Thunderbird (ISPDB).
+const ISPDB: &str = "https://autoconfig.thunderbird.net/v1.1/";
+
+/// Configuração SMTP proposta e de onde veio.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub smtp: SmtpConfig,
+    pub source: String,
+}
+
+impl Candidate {
+    fn new(host: &str, port: u16, security: SmtpSecurity, source: &str) -> Self {
+        let host = host.trim_end_matches('.').to_lowercase();
+        Candidate { smtp: SmtpConfig { host, port, security, accept_invalid_certs: false }, source: source.to_string() }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}:{} ({:?}) — {}", self.smtp.host, self.smtp.port, self.smtp.security, self.source)
+    }
+}
+
+fn tag<'a>(block: &'a str, name: &str) -> Option<&'a str> {
+    let start = block.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = block[start..].find(&format!("</{}>", name))? + start;
+    Some(block[start..end].trim())
+}
+
+/// Os `<outgoingServer type="smtp">` de um ficheiro autoconfig (formato do
+/// Thunderbird), com `%EMAILDOMAIN%` já trocado.
+pub fn parse_autoconfig(xml: &str, domain: &str, source: &str) -> Vec<Candidate> {
+    xml.split("<outgoingServer")
+        .skip(1)
+        .filter(|block| block.trim_start().starts_with("type=\"smtp\""))
+        .filter_map(|block| {
+            let block = &block[..block.find("</outgoingServer>")?];
+            let host = tag(block, "hostname")?.replace("%EMAILDOMAIN%", domain);
+            let port = tag(block, "port")?.parse().ok()?;
+            let security = match tag(block, "socketType")? {
+                "SSL" => SmtpSecurity::Tls,
+                "STARTTLS" => SmtpSecurity::Starttls,
+                "plain" => SmtpSecurity::None,
+                _ => return None,
+            };
+            Some(Candidate::new(&host, port, security, source))
+        })
+        .collect()
+}
+
+async fn fetch(client: &Client, url: &str) -> Option<String> {
+    let resp = client.get(url).send().await.ok()?.error_for_status().ok()?;
+    resp.text().await.ok()
+}
+
+// o autoconfig publicado pelo próprio domínio e, sem ele, o do ISPDB
+async fn from_autoconfig(client: &Client, email: &str, domain: &str) -> Vec<Candidate> {
+    let own = format!("https://autoconfig.{}/mail/config-v1.1.xml?emailaddress={}", domain, email);
+    if let Some(xml) = fetch(client, &own).await {
+        let found = parse_autoconfig(&xml, domain, &format!("autoconfig.{}", domain));
+        if !found.is_empty() {
+            return found;
+        }
+    }
+    match fetch(client, &format!("{}{}", ISPDB, domain)).await {
+        Some(xml) => parse_autoconfig(&xml, domain, "ISPDB do Thunderbird"),
+        None => vec![],
+    }
+}
+
+// RFC 6186/8314: _submissions (TLS) e _submission (STARTTLS)
+async fn from_srv(domain: &str) -> Vec<Candidate> {
+    let mut found = vec![];
+    for (service, security) in [("_submissions", SmtpSecurity::Tls), ("_submission", SmtpSecurity::Starttls)] {
+        let name = format!("{}._tcp.{}", service, domain);
+        for (host, port) in verify::srv_targets(&name).await.unwrap_or_default() {
+            found.push(Candidate::new(&host, port, security, &format!("DNS {}", name)));
+        }
+    }
+    found
+}
+
+/// O domínio de quem recebe o email ("aspmx.l.google.com" → "google.com"):
+/// domínios próprios alojados no Gmail, Outlook, Zoho...
+fn mx_provider(mx: &str) -> Option<String> {
+    let labels: Vec<&str> = mx.trim_end_matches('.').split('.').collect();
+    (labels.len() >= 2).then(|| labels[labels.len() - 2..].join(".").to_lowercase())
+}
+
+async fn from_mx(client: &Client, domain: &str) -> Vec<Candidate> {
+    let Ok(hosts) = verify::mx_hosts(domain).await else {
+        return vec![];
+    };
+    let Some(provider) = hosts.first().and_then(|mx| mx_provider(mx)).filter(|p| p != domain) else {
+        return vec![];
+    };
+    match fetch(client, &format!("{}{}", ISPDB, provider)).await {
+        Some(xml) => parse_autoconfig(&xml, domain, &format!("ISPDB pelo MX ({})", provider)),
+        None => vec![],
+    }
+}
+
+// nomes habituais, só os que aceitam ligação
+async fn from_patterns(domain: &str) -> Vec<Candidate> {
+    let guesses = [
+        (format!("smtp.{}", domain), 587, SmtpSecurity::Starttls),
+        (format!("smtp.{}", domain), 465, SmtpSecurity::Tls),
+        (format!("mail.{}", domain), 587, SmtpSecurity::Starttls),
+        (format!("mail.{}", domain), 465, SmtpSecurity::Tls),
+    ];
+    let mut found = vec![];
+    for (host, port, security) in guesses {
+        let connect = TcpStream::connect((host.as_str(), port));
+        if matches!(timeout(Duration::from_secs(3), connect).await, Ok(Ok(_))) {
+            found.push(Candidate::new(&host, port, security, "nome habitual, a porta responde"));
+        }
+    }
+    found
+}
+
+/// Propostas de servidor SMTP para um endereço, da fonte mais fiável para a
+/// menos: autoconfig do domínio ou ISPDB, SRV, ISPDB do fornecedor do MX e,
+/// sem nada disso, nomes habituais que respondam.
+pub async fn discover(email: &str) -> Result<Vec<Candidate>> {
+    let domain = enrich::email_domain(email).with_context(|| format!("Invalid email: {}", email))?.to_lowercase();
+    let client = enrich::http_client();
+    let mut found = from_autoconfig(&client, email, &domain).await;
+    found.extend(from_srv(&domain).await);
+    if found.is_empty() {
+        found.extend(from_mx(&client, &domain).await);
+    }
+    if found.is_empty() {
+        found.extend(from_patterns(&domain).await);
+    }
+    let mut unique: Vec<Candidate> = vec![];
+    for candidate in found {
+        if !unique.iter().any(|u| u.smtp.host == candidate.smtp.host && u.smtp.port == candidate.smtp.port) {
+            unique.push(candidate);
+        }
+    }
+    Ok(unique)
+}
+
+// config.json novo só com o necessário; o resto fica com os valores do exemplo
+fn starter_config(email: &str, smtp: Value) -> Value {
+    json!({
+        "profile": {
+            "name": "Nome Completo",
+            "email": email,
+            "phone": "",
+            "title": "Cargo Pretendido",
+            "summary": "Descricao profissional breve.",
+            "skills": [],
+            "experience_years": 0
+        },
+        "smtp": smtp,
+        "template": {
+            "subject": "Candidatura - {{title}} - {{name}}",
+            "body": "Olá,\n\nSegue em anexo o meu CV.\n\nCumprimentos,\n{{name}}"
+        }
+    })
+}
+
+// a secção smtp a gravar: só `provider` quando o servidor é de um preset
+fn smtp_section(smtp: &SmtpConfig) -> Result<Value> {
+    match presets::for_server(&smtp.host, smtp.port) {
+        Some(preset) => Ok(json!({ "provider": preset.name })),
+        None => Ok(serde_json::to_value(smtp)?),
+    }
+}
+
+async fn choose_server(email: &str, interactive: bool) -> Result<SmtpConfig> {
+    say!("{} A procurar o servidor SMTP de {}...", NOTE, style(email).yellow());
+    let candidates = discover(email).await?;
+    let chosen = match candidates.len() {
+        0 => {
+            let domain = enrich::email_domain(email).unwrap_or_default();
+            say!("{} Nada encontrado: fica smtp.{}:587, confirma nas instruções do fornecedor.", CROSS, domain);
+            Candidate::new(&format!("smtp.{}", domain), 587, SmtpSecurity::Starttls, "por omissão")
+        }
+        1 => candidates[0].clone(),
+        _ if !interactive => candidates[0].clone(),
+        _ => {
+            let items: Vec<String> = candidates.iter().map(Candidate::label).collect();
+            let sel = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("Servidor SMTP")
+                .items(&items)
+                .default(0)
+                .interact()?;
+            candidates[sel].clone()
+        }
+    };
+    say!("{} {}", CHECK, chosen.label());
+    Ok(chosen.smtp)
+}
+
+/// `init`: descobre o servidor SMTP do endereço (ou usa o preset pedido) e
+/// grava-o no config.json (novo, ou só a secção `smtp` de um já existente).
+pub async fn run_init(email: Option<String>, provider: Option<String>) -> Result<()> {
+    let interactive = std::io::stdin().is_terminal() && !crate::ASSUME_YES.load(Ordering::Relaxed);
+    let email = match email {
+        Some(email) => email,
+        None if interactive => {
+            Input::with_theme(&ColorfulTheme::default()).with_prompt("O teu email").interact_text()?
+        }
+        None => bail!("No email address; use `job-mailer init <email>`"),
+    };
+    let email = email.trim().to_string();
+
+    let smtp = match provider {
+        Some(name) => {
+            let preset = presets::find(&name)
+                .with_context(|| format!("Unknown SMTP provider '{}' (known: {})", name, presets::names().join(", ")))?;
+            say!("{} {}: {}:{} ({:?})", CHECK, preset.name, preset.host, preset.port, preset.security);
+            json!({ "provider": preset.name })
+        }
+        None => smtp_section(&choose_server(&email, interactive).await?)?,
+    };
+    if let Some(preset) = smtp["provider"].as_str().and_then(presets::find).filter(|p| !p.note.is_empty()) {
+        say!("{} {}", NOTE, preset.note);
+    }
+
+    let path = overrides::path(CONFIG_FILE);
+    let config = match fs::read_to_string(&path) {
+        Ok(content) => {
+            let mut config: Value = serde_json::from_str(&content).with_context(|| format!("Invalid {}", path))?;
+            if !crate::confirm(&format!("Trocar a secção smtp de {}?", path), true)? {
+                say!("Cancelado!");
+                return Ok(());
+            }
+            config["smtp"] = smtp;
+            config
+        }
+        Err(_) => starter_config(&email, smtp),
+    };
+    crate::write_atomic(&path, serde_json::to_string_pretty(&config)?)?;
+    say!("{} {} gravado", CHECK, style(&path).green());
+    say!("{} Põe SMTP_USER e SMTP_PASS no .env e confirma com `job-mailer smtp test`.", NOTE);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_autoconfig() {
+        let xml = r#"<clientConfig version="1.1">
+  <emailProvider id="acme.com">
+    <incomingServer type="imap">
+      <hostname>imap.acme.com</hostname><port>993</port><socketType>SSL</socketType>
+    </incomingServer>
+    <outgoingServer type="smtp">
+      <hostname>smtp.%EMAILDOMAIN%</hostname>
+      <port>465</port>
+      <socketType>SSL</socketType>
+    </outgoingServer>
+    <outgoingServer type="smtp">
+      <hostname>relay.acme.com</hostname><port>587</port><socketType>STARTTLS</socketType>
+    </outgoingServer>
+  </emailProvider>
+</clientConfig>"#;
+        let found = parse_autoconfig(xml, "acme.com", "ISPDB");
+        let servers: Vec<(&str, u16, SmtpSecurity)> =
+            found.iter().map(|c| (c.smtp.host.as_str(), c.smtp.port, c.smtp.security)).collect();
+        assert_eq!(
+            servers,
+            vec![("smtp.acme.com", 465, SmtpSecurity::Tls), ("relay.acme.com", 587, SmtpSecurity::Starttls)]
+        );
+        assert!(parse_autoconfig("<html>not found</html>", "acme.com", "ISPDB").is_empty());
+    }
+
+    #[test]
+    fn test_known_servers_are_saved_as_presets() {
+        let gmail = Candidate::new("SMTP.gmail.com.", 587, SmtpSecurity::Starttls, "ISPDB");
+        assert_eq!(smtp_section(&gmail.smtp).unwrap(), json!({"provider": "gmail"}));
+        let own = Candidate::new("mail.acme.com", 465, SmtpSecurity::Tls, "ISPDB");
+        assert_eq!(smtp_section(&own.smtp).unwrap(), json!({"host": "mail.acme.com", "port": 465, "security": "tls"}));
+    }
+
+    #[test]
+    fn test_mx_provider() {
+        assert_eq!(mx_provider("aspmx.l.google.com.").as_deref(), Some("google.com"));
+        assert_eq!(mx_provider("acme-com.mail.protection.outlook.com").as_deref(), Some("outlook.com"));
+        assert_eq!(mx_provider("localhost"), None);
+    }
+}
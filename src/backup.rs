@@ -0,0 +1,178 @@
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use console::style;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Component, Path},
+};
+
+use crate::{
+    blocklist::BLOCKLIST_FILE, contacts::CONTACTS_FILE, correlation::REPLIES_FILE, dead::DEAD_FILE, leads::LEADS_FILE, outbox::OUTBOX_FILE, CHECK, CONFIG_FILE, CROSS,
+    CV_FILE, LOG_FILE,
+};
+
+// o .env fica de fora de propósito: não queremos passwords num arquivo
+const BACKUP_FILES: &[&str] = &[CONFIG_FILE, CONTACTS_FILE, LOG_FILE, OUTBOX_FILE, DEAD_FILE, BLOCKLIST_FILE, REPLIES_FILE, LEADS_FILE, CV_FILE];
+
+pub fn default_backup_name() -> String {
+    format!("job-mailer-backup-{}.tar.gz", Local::now().format("%Y%m%d-%H%M%S"))
+}
+
+pub fn create_backup(src_dir: &Path, archive: &Path) -> Result<Vec<String>> {
+    let file = File::create(archive).with_context(|| format!("Cannot create {}", archive.display()))?;
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let mut included = vec![];
+    for name in BACKUP_FILES {
+        let path = src_dir.join(name);
+        if path.is_file() {
+            tar.append_path_with_name(&path, name)?;
+            included.push(name.to_string());
+        }
+    }
+    tar.into_inner()?.finish()?;
+
+    if included.is_empty() {
+        fs::remove_file(archive)?;
+        bail!("Nothing to back up in {}", src_dir.display());
+    }
+    Ok(included)
+}
+
+// só os ficheiros que o create_backup põe lá, e só ficheiros normais: nada de
+// ../, caminhos absolutos, .env, links simbólicos ou hardlinks
+fn entry_name<R: Read>(entry: &tar::Entry<R>) -> Result<String> {
+    let path = entry.path()?;
+    let name = match path.components().collect::<Vec<_>>()[..] {
+        [Component::Normal(name)] => name.to_string_lossy().to_string(),
+        _ => bail!("Unexpected path in backup: {}", path.display()),
+    };
+    if !BACKUP_FILES.contains(&name.as_str()) {
+        bail!("Unexpected file in backup: {}", name);
+    }
+    if !entry.header().entry_type().is_file() {
+        bail!("{} in backup is not a regular file", name);
+    }
+    Ok(name)
+}
+
+pub fn backup_entries(archive: &Path) -> Result<Vec<String>> {
+    let file = File::open(archive).with_context(|| format!("{} not found", archive.display()))?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+
+    let mut names = vec![];
+    for entry in tar.entries().context("Invalid backup archive")? {
+        names.push(entry_name(&entry?)?);
+    }
+    Ok(names)
+}
+
+pub fn extract_backup(archive: &Path, dest_dir: &Path) -> Result<Vec<String>> {
+    // tudo verificado antes de escrever o primeiro ficheiro
+    let names = backup_entries(archive)?;
+    let file = File::open(archive)?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let name = entry_name(&entry)?;
+        entry.unpack(dest_dir.join(&name)).with_context(|| format!("Cannot restore {}", name))?;
+    }
+    Ok(names)
+}
+
+pub fn run_backup(output: Option<String>) -> Result<()> {
+    let archive = output.unwrap_or_else(default_backup_name);
+    let included = create_backup(&crate::overrides::data_dir(), Path::new(&archive))?;
+
+    say!("{} Backup criado: {}", CHECK, style(&archive).green());
+    for name in included {
+        say!("   {}", name);
+    }
+    Ok(())
+}
+
+pub fn run_restore(archive: &str) -> Result<()> {
+    let names = backup_entries(Path::new(archive))?;
+    let dir = crate::overrides::data_dir();
+    let existing: Vec<&String> = names.iter().filter(|n| dir.join(n).exists()).collect();
+
+    if !existing.is_empty() {
+        say!("{} Estes ficheiros vão ser substituídos:", CROSS);
+        for name in &existing {
+            say!("   {}", name);
+        }
+        if !crate::confirm("Continuar?", false)? {
+            say!("Cancelado!");
+            return Ok(());
+        }
+    }
+
+    let restored = extract_backup(Path::new(archive), &dir)?;
+    crate::git_history::record(&format!("restaurado de {}", archive));
+    say!("{} {} ficheiros restaurados de {}", CHECK, restored.len(), style(archive).green());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_backup_and_restore_roundtrip() {
+        let src = TempDir::new().unwrap();
+        fs::write(src.path().join(CONFIG_FILE), "{\"profile\":{}}").unwrap();
+        fs::write(src.path().join(LOG_FILE), "{\"records\":[]}").unwrap();
+        fs::write(src.path().join(".env"), "SMTP_PASS=secret").unwrap();
+
+        let archive = src.path().join("backup.tar.gz");
+        let included = create_backup(src.path(), &archive).unwrap();
+        assert_eq!(included, vec![CONFIG_FILE, LOG_FILE]);
+
+        let dest = TempDir::new().unwrap();
+        let restored = extract_backup(&archive, dest.path()).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(
+            fs::read_to_string(dest.path().join(CONFIG_FILE)).unwrap(),
+            "{\"profile\":{}}"
+        );
+        assert!(!dest.path().join(".env").exists());
+    }
+
+    #[test]
+    fn test_restore_rejects_unknown_files_and_links() {
+        let dir = TempDir::new().unwrap();
+        let archive_with = |name: &str, kind: tar::EntryType| {
+            let archive = dir.path().join("backup.tar.gz");
+            let mut tar = tar::Builder::new(GzEncoder::new(File::create(&archive).unwrap(), Compression::default()));
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(kind);
+            header.set_size(0);
+            header.set_mode(0o644);
+            if kind.is_symlink() {
+                tar.append_link(&mut header, name, "/etc/passwd").unwrap();
+            } else {
+                tar.append_data(&mut header, name, &b""[..]).unwrap();
+            }
+            tar.into_inner().unwrap().finish().unwrap();
+            archive
+        };
+        let dest = TempDir::new().unwrap();
+
+        let archive = archive_with(".bashrc", tar::EntryType::Regular);
+        assert!(extract_backup(&archive, dest.path()).unwrap_err().to_string().contains("Unexpected file"));
+        let archive = archive_with(CONFIG_FILE, tar::EntryType::Symlink);
+        assert!(extract_backup(&archive, dest.path()).unwrap_err().to_string().contains("not a regular file"));
+        assert_eq!(fs::read_dir(dest.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_backup_with_nothing_to_save() {
+        let src = TempDir::new().unwrap();
+        let archive = src.path().join("backup.tar.gz");
+        assert!(create_backup(src.path(), &archive).is_err());
+        assert!(!archive.exists());
+    }
+}
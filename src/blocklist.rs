@@ -0,0 +1,182 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use console::style;
+use serde::{Deserialize, Serialize};
+
+use crate::{contacts::load_contacts, git_history, CHECK, CROSS};
+
+pub const BLOCKLIST_FILE: &str = "blocklist.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BlockPattern {
+    pub pattern: String,
+    pub added_at: DateTime<Local>,
+}
+
+/// Domínios e endereços a quem nunca se escreve, por padrão: `spam.com`,
+/// `*.recruiter-spam.com` ou `hr@*.gov`. Ao contrário dos endereços mortos,
+/// é mantida à mão.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Blocklist {
+    pub patterns: Vec<BlockPattern>,
+}
+
+// `*` apanha qualquer sequência, incluindo nenhuma
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|i| glob(rest, &text[i..])),
+        Some((c, rest)) => text.split_first().is_some_and(|(t, text)| t == c && glob(rest, text)),
+    }
+}
+
+// `*.x.com` também apanha o próprio x.com
+fn domain_matches(pattern: &str, domain: &str) -> bool {
+    glob(pattern.as_bytes(), domain.as_bytes())
+        || pattern.strip_prefix("*.").is_some_and(|bare| bare == domain)
+}
+
+/// Sem `@` o padrão é de domínio; com `@` compara a parte local e o domínio.
+pub fn pattern_matches(pattern: &str, email: &str) -> bool {
+    let (pattern, email) = (pattern.trim().to_lowercase(), email.trim().to_lowercase());
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return false;
+    };
+    match pattern.split_once('@') {
+        Some((p_local, p_domain)) => glob(p_local.as_bytes(), local.as_bytes()) && domain_matches(p_domain, domain),
+        None => domain_matches(&pattern, domain),
+    }
+}
+
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    let trimmed = pattern.trim();
+    if trimmed.is_empty() || trimmed.matches('@').count() > 1 || trimmed.contains(char::is_whitespace) {
+        bail!("Invalid blocklist pattern: {}", pattern);
+    }
+    if trimmed.split('@').any(|p| p.is_empty()) {
+        bail!("Invalid blocklist pattern: {} (use * for any part)", pattern);
+    }
+    Ok(())
+}
+
+impl Blocklist {
+    /// O primeiro padrão que apanha o endereço.
+    pub fn matching(&self, email: &str) -> Option<&str> {
+        self.patterns.iter().find(|p| pattern_matches(&p.pattern, email)).map(|p| p.pattern.as_str())
+    }
+
+    /// Devolve `true` se o padrão ainda não estava na lista.
+    pub fn add(&mut self, pattern: &str) -> bool {
+        let pattern = pattern.trim().to_lowercase();
+        if self.patterns.iter().any(|p| p.pattern == pattern) {
+            return false;
+        }
+        self.patterns.push(BlockPattern { pattern, added_at: Local::now() });
+        true
+    }
+
+    pub fn remove(&mut self, pattern: &str) -> bool {
+        let before = self.patterns.len();
+        self.patterns.retain(|p| !p.pattern.eq_ignore_ascii_case(pattern.trim()));
+        self.patterns.len() != before
+    }
+}
+
+pub fn load_blocklist() -> Blocklist {
+    crate::schema::INITIAL.load(&crate::overrides::path(BLOCKLIST_FILE))
+}
+
+pub fn save_blocklist(list: &Blocklist) -> Result<()> {
+    crate::schema::INITIAL.save(&crate::overrides::path(BLOCKLIST_FILE), list)
+}
+
+/// Falha se o endereço estiver bloqueado; para quem envia um de cada vez.
+pub fn check(email: &str) -> Result<()> {
+    if let Some(pattern) = load_blocklist().matching(email) {
+        bail!("{} is blocked by blocklist pattern {}", email, pattern);
+    }
+    Ok(())
+}
+
+pub fn add_pattern(pattern: &str) -> Result<()> {
+    validate_pattern(pattern)?;
+    let mut list = load_blocklist();
+    if !list.add(pattern) {
+        say!("{} {} já está na lista", CHECK, pattern);
+        return Ok(());
+    }
+    save_blocklist(&list)?;
+    git_history::record(&format!("{} bloqueado", pattern));
+    say!("{} {} bloqueado", CHECK, style(pattern).red());
+
+    let book = load_contacts();
+    let hit: Vec<&str> = book
+        .contacts
+        .iter()
+        .map(|c| c.email.as_str())
+        .filter(|e| pattern_matches(pattern, e))
+        .collect();
+    if !hit.is_empty() {
+        say!("   {} contactos apanhados: {}", hit.len(), hit.join(", "));
+    }
+    Ok(())
+}
+
+pub fn list_blocklist() {
+    let list = load_blocklist();
+    if list.patterns.is_empty() {
+        println!("{} Nada bloqueado", CHECK);
+        return;
+    }
+    println!("{} Bloqueados ({}):", CROSS, list.patterns.len());
+    println!("{}", style("─".repeat(60)).dim());
+    for p in &list.patterns {
+        println!("  {} {}", style(p.added_at.format("%d/%m/%Y")).dim(), style(&p.pattern).red());
+    }
+    println!("{}", style("─".repeat(60)).dim());
+}
+
+pub fn remove_pattern(pattern: &str) -> Result<()> {
+    let mut list = load_blocklist();
+    if !list.remove(pattern) {
+        bail!("{} is not in the blocklist", pattern);
+    }
+    save_blocklist(&list)?;
+    git_history::record(&format!("{} desbloqueado", pattern));
+    say!("{} {} desbloqueado", CHECK, style(pattern).green());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns() {
+        assert!(pattern_matches("*.recruiter-spam.com", "jobs@eu.recruiter-spam.com"));
+        assert!(pattern_matches("*.recruiter-spam.com", "jobs@Recruiter-Spam.com"));
+        assert!(!pattern_matches("*.recruiter-spam.com", "jobs@notrecruiter-spam.com"));
+        assert!(pattern_matches("spam.com", "a@spam.com"));
+        assert!(!pattern_matches("spam.com", "a@mail.spam.com"));
+        assert!(pattern_matches("hr@*.gov", "HR@agency.gov"));
+        assert!(!pattern_matches("hr@*.gov", "jobs@agency.gov"));
+        assert!(!pattern_matches("*noreply*@*", "no-reply@acme.com"));
+        assert!(pattern_matches("*noreply*@*", "xnoreply@acme.com"));
+
+        assert!(validate_pattern("hr@*.gov").is_ok());
+        assert!(validate_pattern("a@b@c").is_err());
+        assert!(validate_pattern("hr@").is_err());
+        assert!(validate_pattern(" ").is_err());
+    }
+
+    #[test]
+    fn test_blocklist_add_and_remove() {
+        let mut list = Blocklist::default();
+        assert!(list.add("*.Spam.com"));
+        assert!(!list.add("*.spam.com"));
+        assert_eq!(list.matching("rh@x.spam.com"), Some("*.spam.com"));
+        assert_eq!(list.matching("rh@acme.com"), None);
+        assert!(list.remove("*.SPAM.com"));
+        assert!(list.matching("rh@x.spam.com").is_none());
+    }
+}
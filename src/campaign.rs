@@ -0,0 +1,417 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::{
+    confirm,
+    contacts::{load_contacts, same_email},
+    delay::Delay,
+    git_history, load_log,
+    merge::{self, MergeRow},
+    outbox::{self, Outbox},
+    sequence::{self, Step},
+    use_template, ApplicationStatus, Config, SentLog, SentRecord, CHECK, CLOCK, MAIL, NOTE, ROCKET, SPARKLE,
+};
+
+// abaixo disto por campanha as percentagens ainda dizem pouco
+const MIN_SAMPLE: usize = 10;
+
+/// Uma entrada de `campaigns` no config.json: envios do mesmo tipo, com o seu
+/// template, destinatários, hora e follow-ups.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Campaign {
+    // template da candidatura (chave de `templates`); None = o principal
+    #[serde(default)]
+    pub template: Option<String>,
+    // destinatários: endereços soltos e/ou um CSV de mail-merge
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    #[serde(default)]
+    pub file: Option<String>,
+    // hora das candidaturas como no `schedule` ("YYYY-MM-DD HH:MM" ou "HH:MM"); None = já
+    #[serde(default)]
+    pub schedule: Option<String>,
+    // os follow-ups depois da candidatura (o dia 0), por ordem
+    #[serde(default)]
+    pub sequence: Vec<Step>,
+}
+
+/// Passa a enviar para a campanha `name`: os envios ficam marcados com ela, usam
+/// o seu template (salvo `--template`) e o daemon trata dos passos da sequência.
+pub fn use_campaign(config: &mut Config, name: &str) -> Result<()> {
+    let campaign = config
+        .campaigns
+        .get(name)
+        .cloned()
+        .with_context(|| format!("Campaign '{}' not found in campaigns", name))?;
+    let mut last = 0;
+    for step in &campaign.sequence {
+        if step.day <= last {
+            bail!("Campaign '{}': sequence days must go up from day 1 ({} after {})", name, step.day, last);
+        }
+        if !config.templates.contains_key(&step.template) {
+            bail!("Campaign '{}': template '{}' not found in templates", name, step.template);
+        }
+        last = step.day;
+    }
+    if let Some(template) = campaign.template.as_deref().filter(|_| config.template_name.is_none()) {
+        use_template(config, template)?;
+    }
+    config.campaign = Some(name.to_string());
+    Ok(())
+}
+
+/// Os destinatários da campanha: as linhas do `file` e depois os `recipients`, sem repetidos.
+pub fn members(campaign: &Campaign) -> Result<Vec<MergeRow>> {
+    let mut rows = match &campaign.file {
+        Some(file) => merge::parse_merge_csv(&fs::read_to_string(file).with_context(|| format!("{} not found", file))?)?,
+        None => vec![],
+    };
+    for email in &campaign.recipients {
+        if !rows.iter().any(|r| same_email(&r.email, email)) {
+            rows.push(MergeRow { email: email.clone(), vars: Default::default() });
+        }
+    }
+    Ok(rows)
+}
+
+/// Onde está um destinatário da campanha.
+#[derive(Debug)]
+pub enum Progress<'a> {
+    Pending,
+    Queued(DateTime<Utc>),
+    // o último envio com sucesso da campanha (a candidatura ou um follow-up)
+    Sent(&'a SentRecord),
+}
+
+/// Os destinatários da campanha, e depois os endereços que entraram nela por
+/// fora (`send --campaign`), cada um com o seu progresso.
+pub fn progress<'a>(name: &str, members: &[MergeRow], log: &'a SentLog, outbox: &Outbox) -> Vec<(String, Progress<'a>)> {
+    let in_campaign = |r: &&SentRecord| r.success && !r.sandbox && r.campaign.as_deref() == Some(name);
+    let records: Vec<&SentRecord> = log.records.iter().filter(in_campaign).collect();
+    let mut emails: Vec<String> = members.iter().map(|r| r.email.clone()).collect();
+    for r in &records {
+        if !emails.iter().any(|e| same_email(e, &r.email)) {
+            emails.push(r.email.clone());
+        }
+    }
+    emails
+        .into_iter()
+        .map(|email| {
+            let sent = records.iter().rev().find(|r| same_email(&r.email, &email));
+            let queued = outbox
+                .items
+                .iter()
+                .filter(|i| i.campaign.as_deref() == Some(name) && same_email(&i.to, &email))
+                .map(|i| i.send_at)
+                .min();
+            let progress = match (sent, queued) {
+                (Some(r), _) => Progress::Sent(r),
+                (None, Some(at)) => Progress::Queued(at),
+                (None, None) => Progress::Pending,
+            };
+            (email, progress)
+        })
+        .collect()
+}
+
+fn find<'a>(config: &'a Config, name: &str) -> Result<&'a Campaign> {
+    config.campaigns.get(name).with_context(|| format!("Campaign '{}' not found in campaigns", name))
+}
+
+/// `campaign list`: cada campanha com quantas candidaturas já saíram e responderam.
+pub fn list_campaigns(config: &Config) -> Result<()> {
+    if config.campaigns.is_empty() {
+        println!("{} Nenhuma campanha em campaigns.", MAIL);
+        return Ok(());
+    }
+    let (log, outbox) = (load_log(), outbox::load_outbox());
+    println!("{} {} campanhas:", MAIL, style(config.campaigns.len()).cyan());
+    println!("{}", style("─".repeat(60)).dim());
+    for (name, campaign) in &config.campaigns {
+        let progress = progress(name, &members(campaign)?, &log, &outbox);
+        let sent: Vec<&SentRecord> = progress.iter().filter_map(|(_, p)| if let Progress::Sent(r) = p { Some(*r) } else { None }).collect();
+        let replied = sent.iter().filter(|r| r.status != ApplicationStatus::Sent).count();
+        let queued = progress.iter().filter(|(_, p)| matches!(p, Progress::Queued(_))).count();
+        let pending = progress.len() - sent.len() - queued;
+        println!(
+            "  {:<16} {} enviadas, {} com resposta, {} na fila, {} por enviar",
+            style(name).yellow(),
+            style(sent.len()).cyan(),
+            style(replied).green(),
+            queued,
+            pending
+        );
+    }
+    println!("{}", style("─".repeat(60)).dim());
+    Ok(())
+}
+
+/// `campaign status`: cada destinatário da campanha, a sua fase e o passo seguinte.
+pub fn show_status(config: &Config, name: &str) -> Result<()> {
+    let campaign = find(config, name)?;
+    let (log, outbox) = (load_log(), outbox::load_outbox());
+    let progress = progress(name, &members(campaign)?, &log, &outbox);
+    let template = campaign.template.as_deref().unwrap_or("principal");
+    println!(
+        "{} {}: template {}, {} follow-ups, {}",
+        ROCKET,
+        style(name).bold().yellow(),
+        template,
+        campaign.sequence.len(),
+        campaign.schedule.as_deref().map_or("envio imediato".to_string(), |at| format!("às {}", at))
+    );
+    println!("{}", style("─".repeat(60)).dim());
+    for (email, p) in &progress {
+        let line = match p {
+            Progress::Pending => style("por enviar").dim().to_string(),
+            Progress::Queued(at) => format!("na fila para {}", style(at.with_timezone(&Local).format("%d/%m %H:%M")).cyan()),
+            Progress::Sent(r) => {
+                let step = match r.step {
+                    0 => "candidatura".to_string(),
+                    n => format!("follow-up {}/{}", n, campaign.sequence.len()),
+                };
+                let mut line = format!("{} · {} a {}", style(r.status.label()).cyan(), step, r.sent_at.format("%d/%m"));
+                if let Some((next, at)) = sequence::next_step(campaign, r) {
+                    line.push_str(&format!(" · {} a {}", next.template, at.format("%d/%m")));
+                }
+                if let Some(stop) = &r.sequence_stop {
+                    line.push_str(&format!(" · {} ({})", style(format!("parada: {}", stop.reason.label())).red(), stop.detail));
+                }
+                line
+            }
+        };
+        println!("  {:<32} {}", email, line);
+    }
+    println!("{}", style("─".repeat(60)).dim());
+    Ok(())
+}
+
+/// `campaign run`: envia (ou põe na fila, com `schedule`) a candidatura a quem
+/// da campanha ainda não a recebeu nem a tem na fila.
+pub async fn run_campaign(config: &Config, cv: &[u8], log: &mut SentLog, name: &str, delay: &Delay, preview_only: bool) -> Result<()> {
+    let mut config = config.clone();
+    use_campaign(&mut config, name)?;
+    let campaign = find(&config, name)?.clone();
+    let rows: Vec<MergeRow> = {
+        let all = members(&campaign)?;
+        let progress = progress(name, &all, log, &outbox::load_outbox());
+        let pending = |email: &str| progress.iter().any(|(e, p)| same_email(e, email) && matches!(p, Progress::Pending));
+        all.iter().filter(|row| pending(&row.email)).cloned().collect()
+    };
+    if rows.is_empty() {
+        say!("{} {}: nada por enviar", CHECK, name);
+        return Ok(());
+    }
+
+    let preview = merge::merge_preview(&config, &load_contacts(), &rows);
+    merge::print_preview(&preview);
+    merge::check_rows(&config, &rows, &preview)?;
+    if preview_only {
+        return Ok(());
+    }
+    match &campaign.schedule {
+        None => merge::send_rows(&config, cv, log, &rows, delay).await,
+        Some(at) => schedule_rows(&config, name, &rows, at),
+    }
+}
+
+// as candidaturas vão para a fila com a linha do CSV já no template; o daemon envia-as
+fn schedule_rows(config: &Config, name: &str, rows: &[MergeRow], at: &str) -> Result<()> {
+    let when = outbox::parse_send_time(at)?;
+    if !confirm(&format!("Agendar {} candidaturas de {} ({})?", rows.len(), name, at), true)? {
+        say!("Cancelado!");
+        return Ok(());
+    }
+    let book = load_contacts();
+    let now = Utc::now();
+    outbox::update_outbox(|outbox| {
+        for row in rows {
+            let merged = merge::config_for_row(config, row);
+            let tz = book.find(&row.email).and_then(|c| c.timezone.clone());
+            let send_at = outbox::resolve_send_at(when, tz.as_deref(), now)?;
+            outbox.push_prepared(&row.email, &merged, send_at, tz);
+        }
+        Ok(())
+    })?;
+    git_history::record(&format!("campanha {}: agendadas {} candidaturas", name, rows.len()));
+    say!("{} {} candidaturas de {} na fila ({})", CLOCK, style(rows.len()).cyan(), style(name).yellow(), at);
+    Ok(())
+}
+
+/// Os números de uma campanha, para as estatísticas e o `campaign compare`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CampaignStats {
+    pub name: String,
+    // candidaturas (um destinatário cada), sem contar os follow-ups
+    pub sent: usize,
+    pub replied: usize,
+    // chegaram a entrevista ou proposta
+    pub interviews: usize,
+}
+
+impl CampaignStats {
+    pub fn reply_rate(&self) -> usize {
+        self.replied * 100 / self.sent.max(1)
+    }
+
+    pub fn interview_rate(&self) -> usize {
+        self.interviews * 100 / self.sent.max(1)
+    }
+}
+
+/// Por campanha, pela ordem do primeiro envio. Conta a fase a que cada
+/// candidatura chegou em qualquer momento, nos envios da campanha ou depois dela.
+pub fn stats_by_campaign(log: &SentLog) -> Vec<CampaignStats> {
+    let sent: Vec<&SentRecord> = log.records.iter().filter(|r| r.success && !r.sandbox).collect();
+    let mut stats: Vec<CampaignStats> = vec![];
+    let mut counted: Vec<(&str, &str)> = vec![];
+    for r in &sent {
+        let Some(name) = r.campaign.as_deref() else { continue };
+        if counted.iter().any(|(c, e)| *c == name && same_email(e, &r.email)) {
+            continue;
+        }
+        counted.push((name, &r.email));
+        let since: Vec<&&SentRecord> = sent.iter().filter(|t| same_email(&t.email, &r.email) && t.sent_at >= r.sent_at).collect();
+        let reached = |phases: &[ApplicationStatus]| {
+            since.iter().any(|t| phases.contains(&t.status) || t.status_history.iter().any(|c| phases.contains(&c.status)))
+        };
+        let idx = match stats.iter().position(|s| s.name == name) {
+            Some(idx) => idx,
+            None => {
+                stats.push(CampaignStats { name: name.to_string(), sent: 0, replied: 0, interviews: 0 });
+                stats.len() - 1
+            }
+        };
+        stats[idx].sent += 1;
+        if reached(&[ApplicationStatus::Replied, ApplicationStatus::Interview, ApplicationStatus::Offer, ApplicationStatus::Rejected]) {
+            stats[idx].replied += 1;
+        }
+        if reached(&[ApplicationStatus::Interview, ApplicationStatus::Offer]) {
+            stats[idx].interviews += 1;
+        }
+    }
+    stats
+}
+
+/// `campaign compare`: as campanhas lado a lado, e qual responde e leva a mais entrevistas.
+pub fn compare_campaigns(log: &SentLog, names: &[String]) -> Result<()> {
+    let mut stats = stats_by_campaign(log);
+    if let Some(name) = names.iter().find(|n| !stats.iter().any(|s| s.name == **n)) {
+        bail!("No sends in campaign '{}' in the log", name);
+    }
+    if !names.is_empty() {
+        stats.retain(|s| names.contains(&s.name));
+    }
+    if stats.is_empty() {
+        println!("{} Ainda não há envios em campanhas.", MAIL);
+        return Ok(());
+    }
+    stats.sort_by_key(|s| std::cmp::Reverse((s.reply_rate(), s.interview_rate())));
+
+    println!("{} {}", SPARKLE, style("Comparação de campanhas").bold().yellow());
+    println!("{}", style("─".repeat(60)).dim());
+    println!("  {:<20} {:>9} {:>13} {:>13}", "Campanha", "Enviadas", "Respostas", "Entrevistas");
+    for s in &stats {
+        let replied = format!("{} ({}%)", s.replied, s.reply_rate());
+        let interviews = format!("{} ({}%)", s.interviews, s.interview_rate());
+        println!("  {:<20} {:>9} {:>13} {:>13}", style(&s.name).yellow(), s.sent, replied, interviews);
+    }
+    println!("{}", style("─".repeat(60)).dim());
+    if stats.len() > 1 {
+        let most_interviews = stats.iter().max_by_key(|s| (s.interview_rate(), s.reply_rate())).map(|s| s.name.as_str()).unwrap_or_default();
+        println!("  Mais respostas: {}; mais entrevistas: {}", style(&stats[0].name).green(), style(most_interviews).green());
+    }
+    let small: Vec<&str> = stats.iter().filter(|s| s.sent < MIN_SAMPLE).map(|s| s.name.as_str()).collect();
+    if !small.is_empty() {
+        println!("  {} Menos de {} candidaturas em {}: as percentagens ainda dizem pouco", NOTE, MIN_SAMPLE, small.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_progress_of_each_recipient() {
+        let campaign = Campaign {
+            recipients: vec!["rh@acme.com".to_string(), "jobs@beta.pt".to_string(), "hr@gamma.io".to_string(), "RH@acme.com".to_string()],
+            ..Default::default()
+        };
+        let members = members(&campaign).unwrap();
+        assert_eq!(members.len(), 3);
+
+        let at = Local.with_ymd_and_hms(2026, 10, 1, 10, 0, 0).unwrap();
+        let sent = |email: &str, campaign: Option<&str>, step| SentRecord {
+            email: email.to_string(),
+            sent_at: at,
+            success: true,
+            campaign: campaign.map(str::to_string),
+            step,
+            ..Default::default()
+        };
+        let log = SentLog {
+            records: vec![
+                sent("rh@acme.com", Some("backend-q3"), 0),
+                sent("rh@acme.com", Some("backend-q3"), 1),
+                // outra campanha não conta
+                sent("jobs@beta.pt", Some("frontend"), 0),
+                // entrou por `send --campaign`
+                sent("ceo@delta.com", Some("backend-q3"), 0),
+            ],
+        };
+        let mut outbox = Outbox::default();
+        let id = outbox.push("hr@gamma.io", Utc::now(), None, None, None, false);
+        outbox.get_mut(id).unwrap().campaign = Some("backend-q3".to_string());
+
+        let progress = progress("backend-q3", &members, &log, &outbox);
+        let emails: Vec<&str> = progress.iter().map(|(e, _)| e.as_str()).collect();
+        assert_eq!(emails, vec!["rh@acme.com", "jobs@beta.pt", "hr@gamma.io", "ceo@delta.com"]);
+        assert!(matches!(progress[0].1, Progress::Sent(r) if r.step == 1));
+        assert!(matches!(progress[1].1, Progress::Pending));
+        assert!(matches!(progress[2].1, Progress::Queued(_)));
+        assert!(matches!(progress[3].1, Progress::Sent(_)));
+    }
+
+    #[test]
+    fn test_stats_by_campaign() {
+        let at = Local.with_ymd_and_hms(2026, 10, 1, 10, 0, 0).unwrap();
+        let sent = |email: &str, campaign: Option<&str>, step, status| SentRecord {
+            email: email.to_string(),
+            sent_at: at + chrono::Duration::days(step as i64),
+            success: true,
+            campaign: campaign.map(str::to_string),
+            step,
+            status,
+            ..Default::default()
+        };
+        let mut rejected = sent("c@x.com", Some("spontaneous"), 0, ApplicationStatus::Rejected);
+        rejected.status_history.push(crate::StatusChange { at, status: ApplicationStatus::Interview });
+        let log = SentLog {
+            records: vec![
+                sent("a@x.com", Some("spontaneous"), 0, ApplicationStatus::Sent),
+                // o follow-up não é outra candidatura; a resposta veio depois dele
+                sent("a@x.com", Some("spontaneous"), 1, ApplicationStatus::Replied),
+                sent("b@x.com", Some("spontaneous"), 0, ApplicationStatus::Sent),
+                rejected,
+                sent("d@y.com", Some("postings"), 0, ApplicationStatus::Offer),
+                sent("e@y.com", None, 0, ApplicationStatus::Replied),
+                SentRecord { sandbox: true, ..sent("f@y.com", Some("postings"), 0, ApplicationStatus::Sent) },
+            ],
+        };
+        let stats = stats_by_campaign(&log);
+        assert_eq!(
+            stats,
+            vec![
+                CampaignStats { name: "spontaneous".to_string(), sent: 3, replied: 2, interviews: 1 },
+                CampaignStats { name: "postings".to_string(), sent: 1, replied: 1, interviews: 1 },
+            ]
+        );
+        assert_eq!((stats[0].reply_rate(), stats[0].interview_rate()), (66, 33));
+        assert!(compare_campaigns(&log, &["outra".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::ApplicationStatus;
+
+/// O que diz uma resposta, pelas palavras que tem.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplyKind {
+    Rejection,
+    Interview,
+    // pedem mais alguma coisa: pretensões, portefólio, referências
+    MoreInfo,
+    // resposta automática ou ausência: não é uma resposta a sério
+    AutoReply,
+    #[default]
+    Other,
+}
+
+impl ReplyKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReplyKind::Rejection => "rejeição",
+            ReplyKind::Interview => "pedido de entrevista",
+            ReplyKind::MoreInfo => "pedido de informação",
+            ReplyKind::AutoReply => "resposta automática",
+            ReplyKind::Other => "resposta",
+        }
+    }
+
+    /// A fase da candidatura depois de uma resposta destas; `None` = fica como está.
+    pub fn status(&self) -> Option<ApplicationStatus> {
+        match self {
+            ReplyKind::Rejection => Some(ApplicationStatus::Rejected),
+            ReplyKind::Interview => Some(ApplicationStatus::Interview),
+            ReplyKind::MoreInfo | ReplyKind::Other => Some(ApplicationStatus::Replied),
+            ReplyKind::AutoReply => None,
+        }
+    }
+}
+
+/// Palavras de uma língua para cada tipo de resposta.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Keywords {
+    #[serde(default)]
+    pub rejection: Vec<String>,
+    #[serde(default)]
+    pub interview: Vec<String>,
+    #[serde(default)]
+    pub more_info: Vec<String>,
+    #[serde(default)]
+    pub auto_reply: Vec<String>,
+}
+
+/// `replies` do config.json: palavras por língua que se juntam às de origem.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RepliesConfig {
+    #[serde(default)]
+    pub keywords: BTreeMap<String, Keywords>,
+}
+
+const BUILTIN: &[(&str, [&[&str]; 4])] = &[
+    (
+        "pt",
+        [
+            &[
+                "infelizmente",
+                "nao foi selecionad",
+                "nao foi seleccionad",
+                "nao vamos avancar",
+                "nao iremos avancar",
+                "outros candidatos",
+                "outro candidato",
+                "nao corresponde ao perfil",
+                "vaga ja foi preenchida",
+                "decidimos nao",
+            ],
+            &["entrevista", "agendar uma conversa", "marcar uma conversa", "videochamada", "disponibilidade para conversar"],
+            &["pretensoes salariais", "pretensao salarial", "poderia enviar", "pode enviar", "mais informacoes", "referencias", "portefolio", "portfolio"],
+            &["ausente do escritorio", "fora do escritorio", "resposta automatica", "estou de ferias", "sem acesso ao email"],
+        ],
+    ),
+    (
+        "en",
+        [
+            &[
+                "unfortunately",
+                "not be moving forward",
+                "not moving forward",
+                "decided to pursue other",
+                "other candidates",
+                "position has been filled",
+                "regret to inform",
+                "will not be progressing",
+            ],
+            &["interview", "schedule a call", "set up a call", "your availability", "calendly"],
+            &["could you send", "can you send", "salary expectations", "more information", "references", "notice period", "portfolio"],
+            &["out of office", "out of the office", "automatic reply", "auto-reply", "autoreply", "on vacation", "on annual leave", "limited access to email"],
+        ],
+    ),
+    (
+        "fr",
+        [
+            &["malheureusement", "pas ete retenu", "ne pas donner suite", "autres candidats", "poste a ete pourvu"],
+            &["entretien", "convenir d'un rendez-vous", "vos disponibilites", "echange telephonique"],
+            &["pourriez-vous", "pretentions salariales", "plus d'informations", "references"],
+            &["absent du bureau", "reponse automatique", "en conge", "en vacances"],
+        ],
+    ),
+];
+
+// sem acentos nem maiúsculas, para "Entrevista" e "ENTREVISTA" e "entrevísta" contarem igual
+pub fn fold(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            '’' => '\'',
+            c => c,
+        })
+        .collect()
+}
+
+// a palavra tem de começar numa fronteira: "interview" não está em "preinterview"
+fn contains_word(text: &str, keyword: &str) -> bool {
+    let keyword = fold(keyword);
+    if keyword.is_empty() {
+        return false;
+    }
+    text.match_indices(&keyword)
+        .any(|(at, _)| text[..at].chars().next_back().is_none_or(|c| !c.is_alphanumeric()))
+}
+
+/// Só o texto novo da resposta: sem as linhas citadas (">") nem o email
+/// original por baixo ("Em ... escreveu:", "On ... wrote:", "-----Original Message-----").
+pub fn fresh_text(body: &str) -> String {
+    let mut lines = vec![];
+    for line in body.lines() {
+        let trimmed = line.trim();
+        let lower = fold(trimmed);
+        let quote_header = (lower.starts_with("em ") && lower.ends_with("escreveu:"))
+            || (lower.starts_with("on ") && lower.ends_with("wrote:"))
+            || (lower.starts_with("le ") && lower.ends_with("a ecrit :"))
+            || lower.starts_with("-----original message")
+            || lower.starts_with("-----mensagem original")
+            || lower.starts_with("de: ")
+            || lower.starts_with("from: ");
+        if quote_header {
+            break;
+        }
+        if !trimmed.starts_with('>') {
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+impl RepliesConfig {
+    // as palavras de origem e as do config, por tipo
+    fn keywords(&self, kind: ReplyKind) -> Vec<String> {
+        let slot = match kind {
+            ReplyKind::Rejection => 0,
+            ReplyKind::Interview => 1,
+            ReplyKind::MoreInfo => 2,
+            ReplyKind::AutoReply => 3,
+            ReplyKind::Other => return vec![],
+        };
+        let builtin = BUILTIN.iter().flat_map(|(_, lists)| lists[slot].iter().map(|k| k.to_string()));
+        let configured = self.keywords.values().flat_map(|k| match kind {
+            ReplyKind::Rejection => k.rejection.clone(),
+            ReplyKind::Interview => k.interview.clone(),
+            ReplyKind::MoreInfo => k.more_info.clone(),
+            _ => k.auto_reply.clone(),
+        });
+        builtin.chain(configured).collect()
+    }
+
+    /// Classifica uma resposta pelo assunto e pelo texto novo do corpo. Uma
+    /// resposta automática ganha a tudo, depois uma rejeição (que costuma
+    /// agradecer a "entrevista"), depois o pedido de entrevista.
+    pub fn classify(&self, subject: &str, body: &str, auto_submitted: bool) -> ReplyKind {
+        if auto_submitted {
+            return ReplyKind::AutoReply;
+        }
+        let text = fold(&format!("{}\n{}", subject, fresh_text(body)));
+        let order = [ReplyKind::AutoReply, ReplyKind::Rejection, ReplyKind::Interview, ReplyKind::MoreInfo];
+        order
+            .into_iter()
+            .find(|kind| self.keywords(*kind).iter().any(|k| contains_word(&text, k)))
+            .unwrap_or(ReplyKind::Other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_by_keywords() {
+        let rules = RepliesConfig::default();
+        let body = "Olá João,\n\nInfelizmente decidimos avançar com outro perfil.\n\nEm 1 de out. de 2026, João escreveu:\n> Envio a minha candidatura para entrevista";
+        assert_eq!(rules.classify("Re: Candidatura", body, false), ReplyKind::Rejection);
+        assert_eq!(rules.classify("Re: Candidatura", "Podemos marcar uma ENTREVÍSTA na terça?", false), ReplyKind::Interview);
+        assert_eq!(rules.classify("RE: Application", "Could you send your salary expectations?", false), ReplyKind::MoreInfo);
+        assert_eq!(rules.classify("Automatic reply: Application", "I am away", false), ReplyKind::AutoReply);
+        assert_eq!(rules.classify("Re: Candidatura", "Obrigado, vamos analisar.", true), ReplyKind::AutoReply);
+        // o texto citado não conta, nem uma palavra a meio de outra
+        assert_eq!(rules.classify("Re: Candidatura", "Recebido.\n> Disponível para entrevista", false), ReplyKind::Other);
+        assert_eq!(rules.classify("Re: x", "see the preinterview notes", false), ReplyKind::Other);
+
+        let rules: RepliesConfig =
+            serde_json::from_str(r#"{"keywords": {"pt": {"rejection": ["processo encerrado"]}}}"#).unwrap();
+        assert_eq!(rules.classify("Re: Candidatura", "O processo encerrado na semana passada.", false), ReplyKind::Rejection);
+        assert_eq!(ReplyKind::Rejection.status(), Some(ApplicationStatus::Rejected));
+        assert_eq!(ReplyKind::AutoReply.status(), None);
+    }
+}
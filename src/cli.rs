@@ -0,0 +1,858 @@
+use anyhow::{bail, Context, Result};
+use clap::{ArgAction, Parser, Subcommand};
+use std::{collections::BTreeMap, sync::atomic::Ordering};
+
+use crate::{
+    autoconfig, backup, blocklist, campaign, contacts, correlation, cv, dead, delay::Delay, drafting, editor, forget, history, interview, jobs, leads, merge, oauth, report, server, smtp_check, sync, templates, tracking, verify, cv_path, load_checked_cv, load_config,
+    check_portfolio, load_cv_variant, load_log, load_profile_config, outbox, render_message, send_one, confirm_recent_domains, choose_languages, use_template, ApplicationStatus, Config,
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "job-mailer", version, about = "Envia candidaturas fácil!")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Escreve os emails em outbox/ em vez de os enviar
+    #[arg(long, global = true)]
+    pub sandbox: bool,
+    /// Envia com um dos `profiles` do config.json (identidade, assinatura e conta SMTP próprias)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    /// Responde sim a todas as confirmações (para scripts e cron)
+    #[arg(long, short, global = true)]
+    pub yes: bool,
+    /// Só mostra erros
+    #[arg(long, short, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Mais detalhe: -v resolução do template, -vv diálogo SMTP
+    #[arg(long, short, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Envia uma candidatura (ou coloca-a na fila com --send-at)
+    Send {
+        /// Email do destinatário (repetir para vários na mesma mensagem)
+        #[arg(long, required = true)]
+        to: Vec<String>,
+        /// Em cópia (repetir para vários)
+        #[arg(long)]
+        cc: Vec<String>,
+        /// Adiar o envio: "YYYY-MM-DD HH:MM" ou "HH:MM", no fuso do destinatário
+        #[arg(long)]
+        send_at: Option<String>,
+        /// Variante do CV (chave de `profile.cvs`, ex.: backend); por omissão o cv.pdf
+        #[arg(long)]
+        cv: Option<String>,
+        /// Anexa também o portefólio (`profile.portfolio`)
+        #[arg(long)]
+        portfolio: bool,
+        /// Sem pixel de abertura neste envio, mesmo com `tracking` configurado
+        #[arg(long)]
+        no_track: bool,
+        /// Template com nome (chave de `templates`, ex.: follow-up-1); por omissão o `template`
+        #[arg(long)]
+        template: Option<String>,
+        /// Campanha (chave de `campaigns`): o daemon envia os follow-ups da sua sequência
+        #[arg(long)]
+        campaign: Option<String>,
+        /// Anúncio da vaga (ficheiro ou URL): {{matched_skills}} fica só com as skills que ele pede
+        #[arg(long)]
+        job: Option<String>,
+        /// Pede ao modelo de `drafting` um parágrafo para {{cover_letter}}, revisto antes do envio
+        #[arg(long, requires = "job")]
+        draft: bool,
+    },
+    /// Reenvia o último email para este endereço tal como foi (mesmo assunto e corpo)
+    Resend { email: String },
+    /// Agenda envios para uma hora no fuso de cada destinatário
+    Schedule {
+        /// "YYYY-MM-DD HH:MM" ou "HH:MM" (próxima ocorrência)
+        #[arg(long)]
+        at: String,
+        /// Destinatário (repetir para vários)
+        #[arg(long)]
+        to: Vec<String>,
+        /// Agendar para todos os contactos
+        #[arg(long, conflicts_with = "to")]
+        all_contacts: bool,
+        /// Variante do CV (chave de `profile.cvs`)
+        #[arg(long)]
+        cv: Option<String>,
+        /// Sem pixel de abertura
+        #[arg(long)]
+        no_track: bool,
+    },
+    /// Gestão da fila de envios agendados
+    Queue {
+        #[command(subcommand)]
+        action: QueueAction,
+    },
+    /// Cria um arquivo com config, contactos, fila, log e CV
+    Backup {
+        /// Caminho do arquivo (por omissão job-mailer-backup-<data>.tar.gz)
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+    /// Restaura um arquivo criado com `backup`
+    Restore { archive: String },
+    /// Sincroniza log e contactos com o remoto (S3/WebDAV) configurado
+    Sync,
+    /// Gestão de contactos
+    Contacts {
+        #[command(subcommand)]
+        action: ContactsAction,
+    },
+    /// Anúncios de emprego: tira o email de candidatura, a empresa e a vaga
+    Jobs {
+        #[command(subcommand)]
+        action: JobsAction,
+    },
+    /// Recrutadores e vagas apanhados no browser (`POST /api/capture` do `serve`)
+    Leads {
+        #[command(subcommand)]
+        action: LeadsAction,
+    },
+    /// Endereços rejeitados de vez pelo servidor (5xx), que os envios em massa saltam
+    Dead {
+        #[command(subcommand)]
+        action: DeadAction,
+    },
+    /// Pergunta ao servidor de cada domínio se os endereços existem (MX + RCPT, sem enviar)
+    Verify {
+        emails: Vec<String>,
+        /// Verifica também todos os contactos
+        #[arg(long)]
+        all_contacts: bool,
+    },
+    /// Domínios e endereços bloqueados por padrão (*.spam.com, hr@*.gov)
+    Block {
+        #[command(subcommand)]
+        action: BlockAction,
+    },
+    /// Envia os emails agendados quando chega a hora
+    Daemon {
+        /// Intervalo entre verificações da fila (segundos)
+        #[arg(long, default_value_t = 60)]
+        interval: u64,
+        /// Processa a fila uma vez e sai
+        #[arg(long)]
+        once: bool,
+    },
+    /// Templates: editor com preview, exportar, importar ou instalar de um catálogo remoto
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Envio bulk: pergunta os emails, ou lê-os do stdin com --stdin
+    Bulk {
+        /// Lê os destinatários do stdin (um por linha, ou CSV com coluna `email`) e não pergunta nada
+        #[arg(long)]
+        stdin: bool,
+        /// Delay mínimo entre envios (segundos, 30 sem este nem `sending.delay`)
+        #[arg(long, requires = "stdin")]
+        min_delay: Option<u64>,
+        /// Delay máximo entre envios (segundos, 60 sem este nem `sending.delay`)
+        #[arg(long, requires = "stdin")]
+        max_delay: Option<u64>,
+        /// Template com nome (chave de `templates`)
+        #[arg(long, requires = "stdin")]
+        template: Option<String>,
+        /// Variante do CV (chave de `profile.cvs`)
+        #[arg(long, requires = "stdin")]
+        cv: Option<String>,
+        /// Campanha (chave de `campaigns`) de todos os envios
+        #[arg(long, requires = "stdin")]
+        campaign: Option<String>,
+    },
+    /// Mail-merge: cada coluna do CSV vira uma variável {{coluna}} do template
+    Merge {
+        /// CSV com cabeçalho e uma coluna `email`
+        file: String,
+        /// Delay mínimo entre envios (segundos, 30 sem este nem `sending.delay`)
+        #[arg(long)]
+        min_delay: Option<u64>,
+        /// Delay máximo entre envios (segundos, 60 sem este nem `sending.delay`)
+        #[arg(long)]
+        max_delay: Option<u64>,
+        /// Grava também o preview num relatório HTML
+        #[arg(long)]
+        report: Option<String>,
+        /// Mostra o preview e sai sem enviar
+        #[arg(long)]
+        preview: bool,
+        /// Campanha (chave de `campaigns`) de todos os envios
+        #[arg(long)]
+        campaign: Option<String>,
+    },
+    /// Continua um bulk ou mail-merge parado a meio (pausa ou Ctrl-C)
+    Resume,
+    /// Campanhas de `campaigns`: estado por destinatário e envio das candidaturas
+    Campaign {
+        #[command(subcommand)]
+        action: CampaignAction,
+    },
+    /// Respostas recebidas: liga-as aos envios e marca as candidaturas como respondidas
+    Replies {
+        #[command(subcommand)]
+        action: RepliesAction,
+    },
+    /// Muda a fase de uma candidatura (respondeu, entrevista, proposta...)
+    Status {
+        email: String,
+        #[arg(value_enum)]
+        status: ApplicationStatus,
+    },
+    /// Põe uma candidatura de lado até um dia ("voltamos a falar no próximo mês")
+    Snooze {
+        email: String,
+        /// YYYY-MM-DD; some dos follow-ups e do painel até lá (um dia passado tira o snooze)
+        #[arg(long)]
+        until: chrono::NaiveDate,
+    },
+    /// Interface web e API HTTP local (log, estatísticas, fila e envios)
+    Serve {
+        /// Endereço onde escutar; fora do localhost exige SERVE_TOKEN no .env
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+    },
+    /// Recebe os pedidos ao pixel de abertura e regista-os no log (`tracking`)
+    Track {
+        /// Endereço onde escutar; por omissão `tracking.listen`
+        #[arg(long)]
+        listen: Option<String>,
+    },
+    /// Descobre o servidor SMTP do teu email e grava-o no config.json
+    Init {
+        /// O teu endereço; sem ele é perguntado
+        email: Option<String>,
+        /// Usa um preset em vez de procurar (gmail, outlook, office365, yahoo, zoho, zoho-eu, icloud, proton)
+        #[arg(long)]
+        provider: Option<String>,
+    },
+    /// Login OAuth (Gmail, Microsoft) por código, sem browser nesta máquina
+    Oauth {
+        #[command(subcommand)]
+        action: OauthAction,
+    },
+    /// Verifica o servidor SMTP (ligação, TLS, password, tamanho máximo) sem enviar nada
+    Smtp {
+        #[command(subcommand)]
+        action: SmtpAction,
+    },
+    /// Verifica o cv.pdf (páginas, tamanho, metadados) sem enviar nada
+    Cv {
+        /// Verifica esta variante de `profile.cvs` em vez do cv.pdf
+        variant: Option<String>,
+    },
+    /// Relatório da procura: funil, envios por semana, empresas e cronologia
+    Report {
+        /// html (funil e cronologia), pdf (registo de candidaturas para entregar) ou anonymized (csv sem endereços)
+        #[arg(long, value_enum, default_value_t = report::ReportFormat::Html)]
+        format: report::ReportFormat,
+        /// Caminho do ficheiro (por omissão job-mailer-report-<data>.<formato>)
+        #[arg(long, short)]
+        output: Option<String>,
+        /// Só envios a partir deste dia (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+        /// Só envios até este dia, inclusive (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<chrono::NaiveDate>,
+    },
+    /// Mostra tudo o que aconteceu com um endereço: envios, falhas, fases, entrevistas
+    History { email: String },
+    /// Apaga tudo o que está guardado sobre um endereço (RGPD) e bloqueia-o
+    Forget {
+        email: String,
+        /// Grava também o relatório de confirmação neste ficheiro
+        #[arg(long)]
+        report: Option<String>,
+    },
+    /// Marca uma entrevista e gera o convite .ics
+    Interview {
+        /// Email do recrutador (tem de haver um envio para ele no log)
+        email: String,
+        /// "YYYY-MM-DD HH:MM" na hora local
+        #[arg(long)]
+        at: String,
+        /// Duração em minutos
+        #[arg(long, default_value_t = 60)]
+        duration: u32,
+        /// Nome de quem faz a entrevista
+        #[arg(long)]
+        interviewer: Option<String>,
+        /// Morada ou link da videochamada
+        #[arg(long)]
+        location: Option<String>,
+        /// Envia também um email de confirmação com o .ics em anexo
+        #[arg(long)]
+        send: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CampaignAction {
+    /// Cada campanha com enviadas, respostas, na fila e por enviar
+    List,
+    /// Fase, passo e próximo follow-up de cada destinatário
+    Status { name: String },
+    /// Compara as campanhas: enviadas, taxa de respostas e de entrevistas
+    Compare {
+        /// Só estas campanhas; por omissão todas as que têm envios
+        names: Vec<String>,
+    },
+    /// Envia (ou agenda, com `schedule`) a candidatura a quem ainda não a recebeu
+    Run {
+        name: String,
+        /// Mostra o preview e sai sem enviar
+        #[arg(long)]
+        preview: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum QueueAction {
+    /// Lista os envios pendentes
+    List,
+    /// Remove um envio da fila
+    Cancel { id: u64 },
+    /// Reagenda ou muda o destinatário de um envio
+    Edit {
+        id: u64,
+        /// Nova hora: "YYYY-MM-DD HH:MM" ou "HH:MM"
+        #[arg(long)]
+        send_at: Option<String>,
+        /// Novo destinatário
+        #[arg(long)]
+        to: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ContactsAction {
+    /// Adiciona ou actualiza um contacto
+    Add {
+        email: String,
+        #[arg(long)]
+        name: Option<String>,
+        /// Se omitido, tenta descobrir pelo site do domínio
+        #[arg(long)]
+        company: Option<String>,
+        /// Fuso horário IANA (ex.: Europe/Lisbon)
+        #[arg(long)]
+        timezone: Option<String>,
+        /// Língua do email para este contacto (variante de `template.languages`, ex.: en)
+        #[arg(long)]
+        language: Option<String>,
+        /// Não consultar o site do domínio
+        #[arg(long)]
+        no_enrich: bool,
+    },
+    /// Importa contactos de um CSV (colunas: email, name, company, timezone, language, title), vCard (.vcf) ou de uma pasta
+    Import {
+        #[arg(required_unless_present = "dir", conflicts_with = "dir")]
+        file: Option<String>,
+        /// Importa todos os .csv, .vcf e .txt (um endereço por linha) desta pasta
+        #[arg(long)]
+        dir: Option<String>,
+        /// Preenche a empresa em falta a partir do site de cada domínio
+        #[arg(long)]
+        enrich: bool,
+    },
+    /// Guarda a chave pública PGP de um contacto (os emails passam a ir cifrados)
+    SetKey { email: String, key_file: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum JobsAction {
+    /// Lê um anúncio (ficheiro de texto/HTML ou URL) e guarda-o como lead
+    Parse { source: String },
+    /// Os anúncios guardados, da vaga que mais serve (skills e nível) para a que menos
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LeadsAction {
+    /// Lista os leads por tratar, numerados
+    List,
+    /// Passa o lead com este número a contacto
+    Accept {
+        number: usize,
+        /// O email, se o lead só tiver a página
+        #[arg(long)]
+        email: Option<String>,
+        #[arg(long)]
+        company: Option<String>,
+    },
+    /// Deita fora o lead com este número
+    Drop { number: usize },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DeadAction {
+    /// Lista os endereços mortos
+    List,
+    /// Tira um endereço da lista (ex.: a caixa voltou a existir)
+    Remove { email: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RepliesAction {
+    /// Lê a caixa de entrada (IMAP) ou ficheiros .eml/mbox e liga as respostas aos envios
+    Scan {
+        /// Ficheiros .eml ou mbox; sem eles lê o `imap.inbox`
+        files: Vec<String>,
+        /// Só as mensagens destes últimos dias (IMAP)
+        #[arg(long, default_value_t = 30)]
+        days: i64,
+    },
+    /// Lista as respostas que podiam ser a mais de uma candidatura
+    Pending,
+    /// Decide uma resposta de `replies pending`: a candidatura de EMAIL fica respondida
+    Resolve {
+        /// Número em `replies pending`
+        number: usize,
+        /// Endereço da candidatura a que responde
+        #[arg(required_unless_present = "ignore")]
+        email: Option<String>,
+        /// Não é resposta a nenhuma: só sai da lista
+        #[arg(long, conflicts_with = "email")]
+        ignore: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BlockAction {
+    /// Lista os padrões bloqueados
+    List,
+    /// Bloqueia um domínio (spam.com, *.spam.com) ou endereço (hr@*.gov)
+    Add { pattern: String },
+    /// Tira um padrão da lista
+    Remove { pattern: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OauthAction {
+    /// Mostra um endereço e um código para autorizar noutro dispositivo e guarda o token
+    Login,
+    /// Apaga o token guardado
+    Logout,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SmtpAction {
+    /// Liga, negocia TLS e autentica; mostra as extensões e o tamanho máximo
+    Test,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplateAction {
+    /// Grava um template de `templates` num ficheiro à parte (<nome>.template.json)
+    Export {
+        name: String,
+        /// Ficheiro de destino
+        #[arg(long, short)]
+        output: Option<String>,
+        /// Descrição para quem o importar
+        #[arg(long)]
+        description: Option<String>,
+        /// Área a que se destina (tecnologia, saúde...), para catálogos
+        #[arg(long)]
+        industry: Option<String>,
+    },
+    /// Junta a `templates` do config.json um template exportado
+    Import {
+        file: String,
+        /// Guarda com outro nome
+        #[arg(long)]
+        name: Option<String>,
+        /// Substitui um template com o mesmo nome
+        #[arg(long)]
+        force: bool,
+    },
+    /// Editor em ecrã dividido: o template e o email preenchido lado a lado
+    Edit {
+        /// Template de `templates`; por omissão o principal
+        name: Option<String>,
+        /// Contacto para o preview; por omissão um destinatário de exemplo
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Lista os templates de um catálogo (repositório git ou índice HTTPS) e instala um
+    Fetch {
+        /// URL git (https://.../templates.git, git@...) ou de um index.json
+        source: String,
+        /// Só os templates desta área
+        #[arg(long)]
+        industry: Option<String>,
+        /// Instala este template em vez de listar
+        #[arg(long)]
+        install: Option<String>,
+        /// Guarda com outro nome
+        #[arg(long)]
+        name: Option<String>,
+        /// Substitui um template com o mesmo nome
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+fn load_config_for(cli_sandbox: bool, profile: Option<&str>) -> Result<Config> {
+    let mut config = load_profile_config(profile)?;
+    config.sending.sandbox |= cli_sandbox;
+    Ok(config)
+}
+
+// as flags passam à frente do `sending.delay`
+fn delay_from_flags(config: &Config, min_delay: Option<u64>, max_delay: Option<u64>) -> Delay {
+    match (min_delay, max_delay) {
+        (None, None) => config.sending.delay.clone().unwrap_or_default(),
+        (min, max) => {
+            let min = min.unwrap_or(30);
+            Delay::Uniform { min, max: max.unwrap_or(60).max(min) }
+        }
+    }
+}
+
+pub async fn run(cli: Cli) -> Result<()> {
+    let Some(command) = cli.command else {
+        return Ok(());
+    };
+    match command {
+        Command::Send { to, cc, send_at: Some(at), cv, portfolio, no_track, template, campaign, job, draft: _ } => {
+            if to.len() > 1 || !cc.is_empty() {
+                bail!("--send-at works with a single --to and no --cc");
+            }
+            if portfolio {
+                bail!("--portfolio does not work with --send-at");
+            }
+            if template.is_some() {
+                bail!("--template does not work with --send-at");
+            }
+            if campaign.is_some() {
+                bail!("--campaign does not work with --send-at");
+            }
+            if job.is_some() {
+                bail!("--job does not work with --send-at");
+            }
+            outbox::schedule(&at, &to, false, cli.profile.as_deref(), cv.as_deref(), no_track)
+        }
+        Command::Send { to, cc, send_at: None, cv, portfolio, no_track, template, campaign, job, draft } => {
+            let mut config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            config.cv_variant = cv;
+            config.no_track = no_track;
+            if let Some(name) = &template {
+                use_template(&mut config, name)?;
+            }
+            if let Some(name) = &campaign {
+                campaign::use_campaign(&mut config, name)?;
+            }
+            if let Some(source) = &job {
+                let description = jobs::read_source(source).await?;
+                let matched = jobs::matched_skills(&config.profile.skills, &description);
+                verbose!("{{{{matched_skills}}}} = {}", matched.join(", "));
+                let mut vars: BTreeMap<String, String> = [("job_description".to_string(), description.clone())].into();
+                if draft {
+                    let settings = config.drafting.clone().context("--draft needs a `drafting` section in config.json")?;
+                    drafting::check_template(&config.template)?;
+                    let text = drafting::draft(&settings, &config.secrets, &config.profile, &description).await?;
+                    vars.extend(drafting::review(text)?.map(|p| ("cover_letter".to_string(), p)));
+                }
+                config = merge::config_for_row(&config, &merge::MergeRow { email: to[0].clone(), vars });
+            }
+            if portfolio {
+                check_portfolio(&mut config)?;
+            }
+            let cv = load_checked_cv(&config)?;
+            let mut log = load_log();
+            if !confirm_recent_domains(&config, &log, &to)? {
+                say!("Cancelado!");
+                return Ok(());
+            }
+            config.language = choose_languages(&config, &to[..1])?.remove(&to[0]);
+            // uma só mensagem: o primeiro --to é o destinatário da candidatura
+            let message = (to.len() > 1 || !cc.is_empty())
+                .then(|| render_message(&config, &to[0], &cv).with_copies(&to[0], &to[1..], &cc));
+            send_one(&config, &cv, &mut log, &to[0], message).await
+        }
+        Command::Resend { email } => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            let cv = load_checked_cv(&config)?;
+            let mut log = load_log();
+            let message = log
+                .latest_message(&email)
+                .cloned()
+                .with_context(|| format!("No stored message for {} in the log", email))?;
+            send_one(&config, &cv, &mut log, &email, Some(message)).await
+        }
+        Command::Schedule { at, to, all_contacts, cv, no_track } => {
+            outbox::schedule(&at, &to, all_contacts, cli.profile.as_deref(), cv.as_deref(), no_track)
+        }
+        Command::Queue { action } => match action {
+            QueueAction::List => {
+                outbox::list_queue();
+                Ok(())
+            }
+            QueueAction::Cancel { id } => outbox::cancel_queued(id),
+            QueueAction::Edit { id, send_at, to } => outbox::edit_queued(id, send_at, to),
+        },
+        Command::Backup { output } => backup::run_backup(output),
+        Command::Restore { archive } => backup::run_restore(&archive),
+        Command::Sync => sync::run_sync(&load_config()?).await,
+        Command::Contacts { action } => match action {
+            ContactsAction::Add { email, name, company, timezone, language, no_enrich } => {
+                let language = language.map(|l| l.to_lowercase());
+                let contact = contacts::Contact { email, name, company, timezone, language, ..Default::default() };
+                contacts::add_contact(contact, !no_enrich).await
+            }
+            ContactsAction::Import { dir: Some(dir), enrich, .. } => contacts::import_contacts_dir(&dir, enrich).await,
+            ContactsAction::Import { file, enrich, .. } => {
+                contacts::import_contacts_file(&file.context("Missing file")?, enrich).await
+            }
+            ContactsAction::SetKey { email, key_file } => contacts::set_contact_key(&email, &key_file),
+        },
+        Command::Jobs { action } => match action {
+            JobsAction::Parse { source } => jobs::run_parse(&source).await,
+            JobsAction::List => {
+                let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+                jobs::list_jobs(&config.profile);
+                Ok(())
+            }
+        },
+        Command::Leads { action } => match action {
+            LeadsAction::List => {
+                leads::list_leads();
+                Ok(())
+            }
+            LeadsAction::Accept { number, email, company } => leads::accept_lead(number, email, company),
+            LeadsAction::Drop { number } => leads::drop_lead(number),
+        },
+        Command::Dead { action } => match action {
+            DeadAction::List => {
+                dead::list_dead();
+                Ok(())
+            }
+            DeadAction::Remove { email } => dead::remove_dead(&email),
+        },
+        Command::Verify { emails, all_contacts } => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            verify::run_verify(&config.profile.email, emails, all_contacts).await
+        }
+        Command::Template { action } => match action {
+            TemplateAction::Export { name, output, description, industry } => {
+                templates::run_export(&name, output, description, industry)
+            }
+            TemplateAction::Import { file, name, force } => templates::run_import(&file, name, force),
+            TemplateAction::Edit { name, to } => editor::run_edit(name, to),
+            TemplateAction::Fetch { source, industry, install, name, force } => {
+                templates::run_fetch(&source, industry, install, name, force).await
+            }
+        },
+        Command::Block { action } => match action {
+            BlockAction::List => {
+                blocklist::list_blocklist();
+                Ok(())
+            }
+            BlockAction::Add { pattern } => blocklist::add_pattern(&pattern),
+            BlockAction::Remove { pattern } => blocklist::remove_pattern(&pattern),
+        },
+        Command::Daemon { interval, once } => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            let cv = load_checked_cv(&config)?;
+            let mut log = load_log();
+            outbox::run_daemon(&config, &cv, &mut log, interval, once).await
+        }
+        Command::Bulk { stdin: false, .. } => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            let cv = load_checked_cv(&config)?;
+            let mut log = load_log();
+            crate::send_bulk(&config, &cv, &mut log).await
+        }
+        Command::Bulk { stdin: true, min_delay, max_delay, template, cv, campaign } => {
+            // o stdin é o pipe: não há onde responder a perguntas
+            crate::ASSUME_YES.store(true, Ordering::Relaxed);
+            let mut config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            config.cv_variant = cv;
+            if let Some(name) = &template {
+                use_template(&mut config, name)?;
+            }
+            if let Some(name) = &campaign {
+                campaign::use_campaign(&mut config, name)?;
+            }
+            let cv = load_checked_cv(&config)?;
+            let mut log = load_log();
+            let delay = delay_from_flags(&config, min_delay, max_delay);
+            merge::run_stdin(&config, &cv, &mut log, &delay).await
+        }
+        Command::Merge { file, min_delay, max_delay, report, preview, campaign } => {
+            let mut config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            if let Some(name) = &campaign {
+                campaign::use_campaign(&mut config, name)?;
+            }
+            let cv = load_checked_cv(&config)?;
+            let mut log = load_log();
+            let delay = delay_from_flags(&config, min_delay, max_delay);
+            merge::run_merge(&config, &cv, &mut log, &file, &delay, report.as_deref(), preview).await
+        }
+        Command::Resume => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            let cv = load_checked_cv(&config)?;
+            let mut log = load_log();
+            outbox::run_resume(&config, &cv, &mut log).await
+        }
+        Command::Campaign { action } => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            match action {
+                CampaignAction::List => campaign::list_campaigns(&config),
+                CampaignAction::Status { name } => campaign::show_status(&config, &name),
+                CampaignAction::Compare { names } => campaign::compare_campaigns(&load_log(), &names),
+                CampaignAction::Run { name, preview } => {
+                    let cv = load_checked_cv(&config)?;
+                    let mut log = load_log();
+                    let delay = config.sending.delay.clone().unwrap_or_default();
+                    campaign::run_campaign(&config, &cv, &mut log, &name, &delay, preview).await
+                }
+            }
+        }
+        Command::Status { email, status } => interview::set_status(&email, status),
+        Command::Snooze { email, until } => interview::snooze(&email, until),
+        Command::Replies { action } => match action {
+            RepliesAction::Scan { files, days } => {
+                let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+                correlation::run_scan(&config, files, days).await
+            }
+            RepliesAction::Pending => {
+                correlation::list_pending();
+                Ok(())
+            }
+            RepliesAction::Resolve { number, email, .. } => correlation::resolve(number, email.as_deref()),
+        },
+        Command::History { email } => history::show_history(&email),
+        Command::Forget { email, report } => forget::run_forget(&email, report),
+        Command::Report { format, output, since, until } => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            report::run_report(format, output, (&config.profile.name, &config.profile.email), since, until)
+        }
+        Command::Serve { listen } => server::serve(&listen, cli.profile.as_deref()).await,
+        Command::Track { listen } => {
+            let config = load_config()?;
+            let tracking = config.tracking.context("tracking not set in config.json")?;
+            tracking::serve(&tracking, listen.as_deref()).await
+        }
+        Command::Init { email, provider } => autoconfig::run_init(email, provider).await,
+        Command::Oauth { action } => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            match action {
+                OauthAction::Login => oauth::run_login(&config).await,
+                OauthAction::Logout => oauth::run_logout(&config),
+            }
+        }
+        Command::Smtp { action: SmtpAction::Test } => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            smtp_check::run_test(&config).await
+        }
+        Command::Cv { variant } => {
+            let mut config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            config.cv_variant = variant;
+            let cv = load_cv_variant(&config)?;
+            cv::print_report(&cv_path(&config)?, &cv, &config.profile.name);
+            Ok(())
+        }
+        Command::Interview { email, at, duration, interviewer, location, send } => {
+            let config = load_config_for(cli.sandbox, cli.profile.as_deref())?;
+            interview::schedule_interview(&config, &email, &at, duration, interviewer, location, send).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_send_at() {
+        let cli = Cli::try_parse_from([
+            "job-mailer",
+            "send",
+            "--to",
+            "x@y.com",
+            "--send-at",
+            "2024-07-01 09:00",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Send { to, send_at, .. }) => {
+                assert_eq!(to, vec!["x@y.com"]);
+                assert_eq!(send_at.as_deref(), Some("2024-07-01 09:00"));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_send_with_copies() {
+        let cli = Cli::try_parse_from([
+            "job-mailer", "send", "--to", "joana@x.com", "--to", "rh@x.com", "--cc", "talent@x.com",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Send { to, cc, .. }) => {
+                assert_eq!(to, vec!["joana@x.com", "rh@x.com"]);
+                assert_eq!(cc, vec!["talent@x.com"]);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["job-mailer", "send", "--cc", "talent@x.com"]).is_err());
+        // o rascunho precisa do anúncio
+        assert!(Cli::try_parse_from(["job-mailer", "send", "--to", "rh@x.com", "--draft"]).is_err());
+        assert!(Cli::try_parse_from(["job-mailer", "send", "--to", "rh@x.com", "--job", "vaga.html", "--draft"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let cli = Cli::try_parse_from(["job-mailer", "status", "rh@acme.com", "interview"]).unwrap();
+        match cli.command {
+            Some(Command::Status { status, .. }) => assert_eq!(status, ApplicationStatus::Interview),
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["job-mailer", "status", "rh@acme.com", "ghosted"]).is_err());
+        let cli = Cli::try_parse_from(["job-mailer", "snooze", "rh@acme.com", "--until", "2026-11-15"]).unwrap();
+        match cli.command {
+            Some(Command::Snooze { until, .. }) => assert_eq!(until, chrono::NaiveDate::from_ymd_opt(2026, 11, 15).unwrap()),
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["job-mailer", "snooze", "rh@acme.com", "--until", "próximo mês"]).is_err());
+    }
+
+    #[test]
+    fn test_bulk_flags_need_stdin() {
+        let cli = Cli::try_parse_from(["job-mailer", "bulk", "--stdin", "--template", "follow-up-1"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Bulk { stdin: true, template: Some(_), .. })));
+        assert!(Cli::try_parse_from(["job-mailer", "bulk", "--min-delay", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_yes_is_global() {
+        let cli = Cli::try_parse_from(["job-mailer", "restore", "b.tar.gz", "-y"]).unwrap();
+        assert!(cli.yes);
+        let cli = Cli::try_parse_from(["job-mailer", "--yes", "merge", "vagas.csv"]).unwrap();
+        assert!(cli.yes);
+    }
+
+    #[test]
+    fn test_verbosity_flags() {
+        let cli = Cli::try_parse_from(["job-mailer", "sync", "-vv"]).unwrap();
+        assert_eq!(cli.verbose, 2);
+        assert!(Cli::try_parse_from(["job-mailer", "-q", "sync"]).unwrap().quiet);
+        assert!(Cli::try_parse_from(["job-mailer", "-q", "-v", "sync"]).is_err());
+    }
+
+    #[test]
+    fn test_no_subcommand_opens_menu() {
+        let cli = Cli::try_parse_from(["job-mailer"]).unwrap();
+        assert!(cli.command.is_none());
+    }
+}
@@ -0,0 +1,597 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+pub const CONTACTS_FILE: &str = "contacts.json";
+
+/// Forma canónica para comparar endereços: minúsculas e, só no Gmail, sem
+/// `+etiqueta` nem pontos (j.oao+jobs@gmail.com → joao@gmail.com). Noutros
+/// domínios `jobs+backend@` pode ser outra caixa.
+pub fn normalize_email(email: &str) -> String {
+    let email = ascii_domain(email).to_lowercase();
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return email;
+    };
+    match domain {
+        "gmail.com" | "googlemail.com" => {
+            let local = local.split('+').next().unwrap_or(local);
+            format!("{}@gmail.com", local.replace('.', ""))
+        }
+        _ => email,
+    }
+}
+
+/// Domínio em punycode (rh@café.pt → rh@xn--caf-dma.pt), que qualquer servidor
+/// aceita; só uma parte local acentuada precisa de SMTPUTF8.
+pub fn ascii_domain(email: &str) -> String {
+    match email.trim().rsplit_once('@') {
+        Some((local, domain)) if !domain.is_ascii() => match idna::domain_to_ascii(domain) {
+            Ok(domain) => format!("{}@{}", local, domain),
+            Err(_) => email.trim().to_string(),
+        },
+        _ => email.trim().to_string(),
+    }
+}
+
+pub fn same_email(a: &str, b: &str) -> bool {
+    normalize_email(a) == normalize_email(b)
+}
+
+/// Todos os endereços de um texto qualquer (uma página de anúncio colada,
+/// um `mailto:`), pela ordem em que aparecem e sem repetidos.
+pub fn extract_emails(text: &str) -> Vec<String> {
+    let pattern = regex::Regex::new(r"[\p{L}\p{N}._%+-]+@[\p{L}\p{N}-]+(?:\.[\p{L}\p{N}-]+)*\.\p{L}{2,}")
+        .expect("email pattern");
+    let mut emails: Vec<String> = vec![];
+    for found in pattern.find_iter(text) {
+        let email = found.as_str().trim_start_matches(['.', '-']);
+        // "logo@2x.png" é uma imagem, não um endereço
+        let image = [".png", ".jpg", ".jpeg", ".gif", ".svg", ".webp"].iter().any(|ext| email.to_lowercase().ends_with(ext));
+        if !image && !emails.iter().any(|e| same_email(e, email)) {
+            emails.push(email.to_string());
+        }
+    }
+    emails
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Contact {
+    pub email: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub company: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    // chave pública OpenPGP (armored); com ela o email vai cifrado
+    #[serde(default)]
+    pub pgp_key: Option<String>,
+    // "en", "fr", ...: escolhe a variante de `template.languages`
+    #[serde(default)]
+    pub language: Option<String>,
+    // cargo de quem recebe (TITLE do vCard, coluna `title` do CSV)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ContactBook {
+    pub contacts: Vec<Contact>,
+}
+
+impl ContactBook {
+    pub fn find(&self, email: &str) -> Option<&Contact> {
+        self.contacts
+            .iter()
+            .find(|c| same_email(&c.email, email))
+    }
+
+    /// Insere ou actualiza um contacto; devolve `true` se for novo.
+    pub fn upsert(&mut self, contact: Contact) -> bool {
+        match self
+            .contacts
+            .iter_mut()
+            .find(|c| same_email(&c.email, &contact.email))
+        {
+            Some(existing) => {
+                if contact.name.is_some() {
+                    existing.name = contact.name;
+                }
+                if contact.company.is_some() {
+                    existing.company = contact.company;
+                }
+                if contact.timezone.is_some() {
+                    existing.timezone = contact.timezone;
+                }
+                if contact.language.is_some() {
+                    existing.language = contact.language;
+                }
+                if contact.pgp_key.is_some() {
+                    existing.pgp_key = contact.pgp_key;
+                }
+                if contact.title.is_some() {
+                    existing.title = contact.title;
+                }
+                false
+            }
+            None => {
+                self.contacts.push(contact);
+                true
+            }
+        }
+    }
+}
+
+pub fn load_contacts() -> ContactBook {
+    crate::schema::INITIAL.load(&crate::overrides::path(CONTACTS_FILE))
+}
+
+pub fn save_contacts(book: &ContactBook) -> Result<()> {
+    crate::schema::INITIAL.save(&crate::overrides::path(CONTACTS_FILE), book)
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Texto de um ficheiro exportado por outro programa: UTF-8 (com ou sem BOM),
+/// UTF-16 com BOM (exportações antigas do Google) ou Windows-1252 (Outlook).
+pub fn decode_text(bytes: &[u8]) -> String {
+    if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(bytes) {
+        return encoding.decode(bytes).0.into_owned();
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::WINDOWS_1252.decode(bytes).0.into_owned(),
+    }
+}
+
+/// De onde vem o CSV, pelo cabeçalho: o nosso (`email`, `name`, ...) ou as
+/// exportações do Google Contacts e do Outlook, com os nomes de coluna deles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvDialect {
+    Native,
+    Google,
+    Outlook,
+}
+
+// colunas por ordem de preferência; a primeira com valor ganha
+struct Columns {
+    email: &'static [&'static str],
+    name: &'static [&'static str],
+    first_name: &'static [&'static str],
+    last_name: &'static [&'static str],
+    company: &'static [&'static str],
+    title: &'static [&'static str],
+}
+
+impl CsvDialect {
+    pub fn detect(headers: &[String]) -> Option<CsvDialect> {
+        if headers.iter().any(|h| h == "email") {
+            Some(CsvDialect::Native)
+        } else if headers.iter().any(|h| h.starts_with("e-mail 1 - ")) {
+            Some(CsvDialect::Google)
+        } else if headers.iter().any(|h| h == "e-mail address") {
+            Some(CsvDialect::Outlook)
+        } else {
+            None
+        }
+    }
+
+    fn columns(&self) -> Columns {
+        match self {
+            CsvDialect::Native => Columns {
+                email: &["email"],
+                name: &["name"],
+                first_name: &[],
+                last_name: &[],
+                company: &["company"],
+                title: &["title"],
+            },
+            CsvDialect::Google => Columns {
+                email: &["e-mail 1 - value", "e-mail 2 - value", "e-mail 3 - value"],
+                name: &["name"],
+                first_name: &["first name", "given name"],
+                last_name: &["last name", "family name"],
+                company: &["organization 1 - name", "organization name"],
+                title: &["organization 1 - title", "organization title"],
+            },
+            // no Outlook "Title" é o tratamento (Sr., Dra.); o cargo é "Job Title"
+            CsvDialect::Outlook => Columns {
+                email: &["e-mail address", "e-mail 2 address", "e-mail 3 address"],
+                name: &[],
+                first_name: &["first name"],
+                last_name: &["last name"],
+                company: &["company"],
+                title: &["job title"],
+            },
+        }
+    }
+}
+
+/// Lê um CSV com cabeçalho: o nosso, em que só a coluna `email` é obrigatória,
+/// ou uma exportação do Google Contacts ou do Outlook. Devolve também quantas
+/// linhas ficaram de fora por não terem endereço.
+pub fn parse_contacts_csv(content: &str) -> Result<(Vec<Contact>, usize)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .context("Invalid CSV header")?
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+    let dialect = CsvDialect::detect(&headers).context("CSV has no 'email' column")?;
+    let columns = dialect.columns();
+    let cols = |names: &[&str]| -> Vec<usize> { names.iter().filter_map(|n| headers.iter().position(|h| h == n)).collect() };
+    let col = |name: &str| headers.iter().position(|h| h == name);
+    let (email_cols, name_cols, company_cols, title_cols) =
+        (cols(columns.email), cols(columns.name), cols(columns.company), cols(columns.title));
+    let (first_cols, last_cols) = (cols(columns.first_name), cols(columns.last_name));
+    let (tz_col, lang_col) = (col("timezone"), col("language"));
+
+    let (mut contacts, mut invalid) = (vec![], 0);
+    for row in reader.records() {
+        let row = row.context("Invalid CSV row")?;
+        let field = |idx: Option<usize>| non_empty(idx.and_then(|i| row.get(i)));
+        let first = |cols: &[usize]| cols.iter().find_map(|i| field(Some(*i)));
+        // o Google junta vários num campo com " ::: "; o Outlook pode ter um endereço X.500 em vez de email
+        let email = match dialect {
+            CsvDialect::Native => first(&email_cols).filter(|e| e.contains('@')),
+            _ => email_cols.iter().find_map(|i| field(Some(*i)).and_then(|v| extract_emails(&v).into_iter().next())),
+        };
+        let Some(email) = email else {
+            invalid += 1;
+            continue;
+        };
+        let full_name = [first(&first_cols), first(&last_cols)].into_iter().flatten().collect::<Vec<_>>().join(" ");
+        contacts.push(Contact {
+            email,
+            name: first(&name_cols).or_else(|| non_empty(Some(&full_name))),
+            company: first(&company_cols),
+            timezone: field(tz_col),
+            pgp_key: None,
+            language: field(lang_col).map(|l| l.to_lowercase()),
+            title: first(&title_cols),
+        });
+    }
+    Ok((contacts, invalid))
+}
+
+/// Um endereço por linha (o primeiro, se houver mais); linhas vazias e
+/// `#` não contam, as que não têm endereço contam como inválidas.
+pub fn parse_contacts_txt(content: &str) -> (Vec<Contact>, usize) {
+    let (mut contacts, mut invalid) = (vec![], 0);
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        match extract_emails(line).into_iter().next() {
+            Some(email) => contacts.push(Contact { email, ..Default::default() }),
+            None => invalid += 1,
+        }
+    }
+    (contacts, invalid)
+}
+
+/// O que aconteceu aos contactos de um ficheiro importado.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ImportCount {
+    pub added: usize,
+    pub updated: usize,
+    // já vistos noutra linha ou noutro ficheiro desta importação
+    pub duplicates: usize,
+    pub invalid: usize,
+    // mortos ou bloqueados
+    pub skipped: usize,
+}
+
+struct Importer {
+    book: ContactBook,
+    seen: Vec<String>,
+    dead: crate::dead::DeadList,
+    blocklist: crate::blocklist::Blocklist,
+    client: reqwest::Client,
+    enrich: bool,
+}
+
+impl Importer {
+    fn new(enrich: bool) -> Self {
+        Importer {
+            book: load_contacts(),
+            seen: vec![],
+            dead: crate::dead::load_dead(),
+            blocklist: crate::blocklist::load_blocklist(),
+            client: crate::enrich::http_client(),
+            enrich,
+        }
+    }
+
+    async fn import(&mut self, contacts: Vec<Contact>) -> Result<ImportCount> {
+        let mut count = ImportCount::default();
+        for mut contact in contacts {
+            if self.seen.iter().any(|e| same_email(e, &contact.email)) {
+                count.duplicates += 1;
+                continue;
+            }
+            self.seen.push(contact.email.clone());
+            if self.dead.contains(&contact.email) {
+                say!("   {} {} - endereço morto, ignorado", crate::CROSS, contact.email);
+                count.skipped += 1;
+                continue;
+            }
+            if let Some(pattern) = self.blocklist.matching(&contact.email) {
+                say!("   {} {} - bloqueado ({}), ignorado", crate::CROSS, contact.email, pattern);
+                count.skipped += 1;
+                continue;
+            }
+            if let Some(tz) = &contact.timezone {
+                crate::outbox::parse_timezone(tz)?;
+            }
+            // não vale a pena ir à web se o contacto já tem empresa guardada
+            let known = self.book.find(&contact.email).is_some_and(|c| c.company.is_some());
+            if self.enrich && !known && crate::enrich::fill_company(&self.client, &mut contact).await {
+                say!("   {} → {}", contact.email, contact.company.as_deref().unwrap_or(""));
+            }
+            if self.book.upsert(contact) {
+                count.added += 1;
+            } else {
+                count.updated += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+// pela extensão: .csv com cabeçalho, .vcf (vCard), o resto um endereço por linha
+fn parse_contacts_file(path: &Path, content: &str) -> Result<(Vec<Contact>, usize)> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+    match ext.as_str() {
+        "csv" => parse_contacts_csv(content),
+        "vcf" => Ok(crate::vcard::parse_vcards(content)),
+        _ => Ok(parse_contacts_txt(content)),
+    }
+}
+
+pub async fn import_contacts_file(path: &str, enrich: bool) -> Result<()> {
+    let content = decode_text(&fs::read(path).with_context(|| format!("{} not found", path))?);
+    let (imported, invalid) = parse_contacts_file(Path::new(path), &content)?;
+
+    let mut importer = Importer::new(enrich);
+    let count = ImportCount { invalid, ..importer.import(imported).await? };
+    save_contacts(&importer.book)?;
+    let total = count.added + count.updated;
+    crate::git_history::record(&format!("importados {} contactos", total));
+
+    say!(
+        "{} {} contactos importados ({} novos, {} actualizados)",
+        crate::CHECK,
+        total,
+        count.added,
+        count.updated
+    );
+    Ok(())
+}
+
+/// Importa todos os .csv, .txt e .vcf de uma pasta, com as contas de cada ficheiro.
+pub async fn import_contacts_dir(dir: &str, enrich: bool) -> Result<()> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("{} not found", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+            path.is_file() && ["csv", "txt", "vcf"].contains(&ext.as_str())
+        })
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        say!("{} Nenhum .csv, .txt ou .vcf em {}", crate::CROSS, dir);
+        return Ok(());
+    }
+
+    let mut importer = Importer::new(enrich);
+    let mut total = ImportCount::default();
+    for path in &files {
+        let content = decode_text(&fs::read(path).with_context(|| format!("Cannot read {}", path.display()))?);
+        let (contacts, invalid) = match parse_contacts_file(path, &content) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                say!("{} {}: {:#}", crate::CROSS, path.display(), err);
+                continue;
+            }
+        };
+        let count = ImportCount { invalid, ..importer.import(contacts).await? };
+        say!(
+            "   {}: {} novos, {} actualizados, {} repetidos, {} inválidos, {} ignorados",
+            path.display(),
+            count.added,
+            count.updated,
+            count.duplicates,
+            count.invalid,
+            count.skipped
+        );
+        total.added += count.added;
+        total.updated += count.updated;
+        total.duplicates += count.duplicates;
+        total.invalid += count.invalid;
+        total.skipped += count.skipped;
+    }
+    save_contacts(&importer.book)?;
+    crate::git_history::record(&format!("importados {} contactos de {}", total.added + total.updated, dir));
+
+    say!(
+        "{} {} ficheiros: {} novos, {} actualizados, {} repetidos, {} inválidos, {} ignorados",
+        crate::CHECK,
+        files.len(),
+        total.added,
+        total.updated,
+        total.duplicates,
+        total.invalid,
+        total.skipped
+    );
+    Ok(())
+}
+
+pub async fn add_contact(mut contact: Contact, enrich: bool) -> Result<()> {
+    if !contact.email.contains('@') {
+        anyhow::bail!("Invalid email: {}", contact.email);
+    }
+    crate::blocklist::check(&contact.email)?;
+    if let Some(tz) = &contact.timezone {
+        crate::outbox::parse_timezone(tz)?;
+    }
+
+    let mut book = load_contacts();
+    let known = book.find(&contact.email).is_some_and(|c| c.company.is_some());
+    if enrich && !known {
+        crate::enrich::fill_company(&crate::enrich::http_client(), &mut contact).await;
+    }
+    let email = contact.email.clone();
+    let added = book.upsert(contact);
+    save_contacts(&book)?;
+    crate::git_history::record(&format!("contacto {} guardado", email));
+
+    let company = book.find(&email).and_then(|c| c.company.clone());
+    say!(
+        "{} Contacto {} {}{}",
+        crate::CHECK,
+        email,
+        if added { "adicionado" } else { "actualizado" },
+        company.map(|c| format!(" (empresa: {})", c)).unwrap_or_default()
+    );
+    Ok(())
+}
+
+pub fn set_contact_key(email: &str, key_file: &str) -> Result<()> {
+    let key = fs::read_to_string(key_file).with_context(|| format!("{} not found", key_file))?;
+    if !crate::pgp::is_public_key(&key) {
+        anyhow::bail!("{} is not an armored PGP public key", key_file);
+    }
+
+    let mut book = load_contacts();
+    book.upsert(Contact {
+        email: email.to_string(),
+        pgp_key: Some(key),
+        ..Default::default()
+    });
+    save_contacts(&book)?;
+    crate::git_history::record(&format!("chave PGP de {} guardada", email));
+
+    say!("{} Emails para {} vão passar a ser cifrados", crate::CHECK, email);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_contacts_csv() {
+        let csv = "Email,Name,Company,Timezone,Language\n\
+                   joana@acme.com, Joana ,Acme,Europe/Lisbon,\n\
+                   invalido,,,,\n\
+                   rh@globex.de,,Globex,,EN\n";
+        let (contacts, invalid) = parse_contacts_csv(csv).unwrap();
+        assert_eq!(invalid, 1);
+
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[0].name.as_deref(), Some("Joana"));
+        assert_eq!(contacts[0].timezone.as_deref(), Some("Europe/Lisbon"));
+        assert_eq!(contacts[1].timezone, None);
+        assert_eq!(contacts[0].language, None);
+        assert_eq!(contacts[1].language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn test_parse_google_and_outlook_exports() {
+        let google = "\u{feff}Name,Given Name,Family Name,Organization 1 - Name,Organization 1 - Title,E-mail 1 - Type,E-mail 1 - Value\n\
+                      Joana Silva,Joana,Silva,Acme,Recruiter,* Work,joana@acme.com ::: joana@gmail.com\n\
+                      Sem Email,Sem,Email,,,,\n";
+        let (contacts, invalid) = parse_contacts_csv(google).unwrap();
+        assert_eq!(invalid, 1);
+        assert_eq!(contacts[0].email, "joana@acme.com");
+        assert_eq!(contacts[0].name.as_deref(), Some("Joana Silva"));
+        assert_eq!(contacts[0].company.as_deref(), Some("Acme"));
+        assert_eq!(contacts[0].title.as_deref(), Some("Recruiter"));
+
+        // Outlook em Windows-1252, com o tratamento em "Title" e um endereço X.500 antes do email
+        let mut outlook = b"Title,First Name,Last Name,Company,Job Title,E-mail Address,E-mail 2 Address\r\n".to_vec();
+        outlook.extend(b"Dr.,Jo\xe3o,Ferr\xe3o,Globex,Head of Talent,/o=ExchangeLabs/ou=Recipients/cn=joao,joao@globex.com\r\n");
+        let (contacts, invalid) = parse_contacts_csv(&decode_text(&outlook)).unwrap();
+        assert_eq!(invalid, 0);
+        assert_eq!(contacts[0].email, "joao@globex.com");
+        assert_eq!(contacts[0].name.as_deref(), Some("João Ferrão"));
+        assert_eq!(contacts[0].title.as_deref(), Some("Head of Talent"));
+
+        let utf16: Vec<u8> = [0xff, 0xfe].into_iter().chain("E-mail 1 - Value\nana@initech.com\n".encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        let (contacts, _) = parse_contacts_csv(&decode_text(&utf16)).unwrap();
+        assert_eq!(contacts[0].email, "ana@initech.com");
+    }
+
+    #[test]
+    fn test_parse_contacts_txt() {
+        let (contacts, invalid) = parse_contacts_txt("rh@acme.com\n\n# leads de outubro\nJoana <joana@globex.com>\nligar à Ana\n");
+        let emails: Vec<&str> = contacts.iter().map(|c| c.email.as_str()).collect();
+        assert_eq!(emails, vec!["rh@acme.com", "joana@globex.com"]);
+        assert_eq!(invalid, 1);
+    }
+
+    #[test]
+    fn test_parse_contacts_csv_without_email_column() {
+        assert!(parse_contacts_csv("name,company\nAna,Acme\n").is_err());
+    }
+
+    #[test]
+    fn test_normalize_email() {
+        assert_eq!(normalize_email(" J.Oao+Jobs@GMail.com"), "joao@gmail.com");
+        assert_eq!(normalize_email("joao@googlemail.com"), "joao@gmail.com");
+        assert!(same_email("jobs@acme.com", "JOBS@acme.com"));
+        // fora do Gmail a etiqueta e os pontos contam
+        assert!(!same_email("jobs+backend@acme.com", "jobs@acme.com"));
+        assert!(!same_email("j.silva@acme.com", "jsilva@acme.com"));
+        assert_eq!(normalize_email("sem-arroba"), "sem-arroba");
+        assert_eq!(ascii_domain("joão@Café.pt"), "joão@xn--caf-dma.pt");
+        assert!(same_email("rh@café.pt", "RH@xn--caf-dma.pt"));
+
+        let mut book = ContactBook::default();
+        book.upsert(Contact { email: "joao@gmail.com".to_string(), ..Default::default() });
+        assert!(!book.upsert(Contact { email: "j.oao+jobs@gmail.com".to_string(), ..Default::default() }));
+        assert_eq!(book.contacts.len(), 1);
+        assert!(book.find("JOAO+x@gmail.com").is_some());
+    }
+
+    #[test]
+    fn test_extract_emails() {
+        let page = "Envie o CV para <a href=\"mailto:Jobs@Acme.com?subject=Dev\">jobs@acme.com</a>.\n\
+                    Dúvidas: rh.lisboa@acme.pt, ou RH.Lisboa+vagas@acme.pt; equipa@café.pt\n\
+                    <img src=\"logo@2x.png\"> @acme no twitter";
+        // fora do Gmail a etiqueta faz outro endereço
+        assert_eq!(
+            extract_emails(page),
+            vec!["Jobs@Acme.com", "rh.lisboa@acme.pt", "RH.Lisboa+vagas@acme.pt", "equipa@café.pt"]
+        );
+        assert!(extract_emails("Sem contactos aqui.").is_empty());
+    }
+
+    #[test]
+    fn test_upsert_keeps_existing_fields() {
+        let mut book = ContactBook::default();
+        assert!(book.upsert(Contact {
+            email: "rh@acme.com".to_string(),
+            company: Some("Acme".to_string()),
+            ..Default::default()
+        }));
+        assert!(!book.upsert(Contact {
+            email: "RH@acme.com".to_string(),
+            timezone: Some("America/Sao_Paulo".to_string()),
+            ..Default::default()
+        }));
+
+        let c = book.find("rh@acme.com").unwrap();
+        assert_eq!(c.company.as_deref(), Some("Acme"));
+        assert_eq!(c.timezone.as_deref(), Some("America/Sao_Paulo"));
+    }
+}
@@ -0,0 +1,558 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use console::style;
+use mail_parser::{mailbox::mbox::MessageIterator, MessageParser};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fs};
+
+use crate::{
+    absence,
+    classify::{fresh_text, RepliesConfig, ReplyKind},
+    contacts::same_email,
+    enrich::{email_domain, is_free_mail},
+    git_history, imap, update_log, ApplicationStatus, Config, SentLog, SentRecord, CHECK, CLOCK, MAIL, NOTE,
+};
+
+pub const REPLIES_FILE: &str = "replies.json";
+
+// parte das palavras do assunto em comum para dois assuntos contarem como o mesmo
+const SIMILAR: f64 = 0.6;
+// entre vários parecidos, o melhor só ganha com esta vantagem
+const CLEARLY_BETTER: f64 = 0.2;
+
+// prefixos de resposta e reencaminhamento em várias línguas
+const SUBJECT_PREFIXES: &[&str] = &["re:", "res:", "fw:", "fwd:", "enc:", "aw:", "wg:", "sv:", "tr:", "rv:", "r:"];
+
+/// Message-ID para um envio, no domínio do remetente.
+pub fn new_message_id(sender: &str) -> String {
+    let domain = email_domain(sender).unwrap_or("job-mailer.local");
+    format!("<{}@{}>", hex::encode(rand::thread_rng().gen::<[u8; 16]>()), domain)
+}
+
+/// Uma mensagem recebida, só com o que serve para a ligar a um envio.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Inbound {
+    pub message_id: Option<String>,
+    pub from: String,
+    pub subject: String,
+    pub date: Option<DateTime<Local>>,
+    // In-Reply-To e References, sem os <>
+    pub references: Vec<String>,
+    // o corpo em texto (do HTML se não houver texto)
+    pub text: String,
+    // Auto-Submitted, X-Autoreply e afins: resposta do servidor, não de uma pessoa
+    pub auto_submitted: bool,
+}
+
+impl Inbound {
+    // para não tratar duas vezes a mesma mensagem; sem Message-ID, remetente + data + assunto
+    fn key(&self) -> String {
+        match &self.message_id {
+            Some(id) => id.clone(),
+            None => format!("{}|{}|{}", self.from, self.date.map(|d| d.to_rfc3339()).unwrap_or_default(), self.subject),
+        }
+    }
+}
+
+fn strip_id(id: &str) -> &str {
+    id.trim().trim_start_matches('<').trim_end_matches('>')
+}
+
+pub fn parse(raw: &[u8]) -> Option<Inbound> {
+    let message = MessageParser::default().parse(raw)?;
+    let from = message.from()?.first()?.address()?.to_string();
+    let mut references: Vec<String> = vec![];
+    for header in [message.in_reply_to(), message.references()] {
+        for id in header.as_text_list().unwrap_or_default() {
+            if !references.iter().any(|r| r == strip_id(id)) {
+                references.push(strip_id(id).to_string());
+            }
+        }
+    }
+    let header = |name: &'static str| message.header_raw(name).map(|v| v.trim().to_lowercase());
+    let auto_submitted = header("Auto-Submitted").is_some_and(|v| v != "no")
+        || header("X-Autoreply").is_some()
+        || header("X-Autorespond").is_some()
+        || header("Precedence").is_some_and(|v| v == "auto_reply");
+    Some(Inbound {
+        message_id: message.message_id().map(|id| strip_id(id).to_string()),
+        from,
+        subject: message.subject().unwrap_or_default().to_string(),
+        date: message.date().and_then(|d| Local.timestamp_opt(d.to_timestamp(), 0).single()),
+        references,
+        text: message.body_text(0).unwrap_or_default().into_owned(),
+        auto_submitted,
+    })
+}
+
+// "RE: Fwd: Candidatura" → "candidatura"
+fn base_subject(subject: &str) -> String {
+    let mut rest = subject.trim().to_lowercase();
+    while let Some(prefix) = SUBJECT_PREFIXES.iter().find(|p| rest.starts_with(*p)) {
+        rest = rest[prefix.len()..].trim_start().to_string();
+    }
+    rest
+}
+
+/// Quantas palavras os dois assuntos têm em comum (0 a 1), sem os Re:/Fwd:.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let words = |s: &str| -> BTreeSet<String> {
+        base_subject(s)
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchedBy {
+    // o In-Reply-To/References aponta para o Message-ID do envio
+    Thread,
+    // o remetente é o destinatário do envio
+    Sender,
+    // outra pessoa, mas só um envio tem um assunto parecido
+    Subject,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Correlation {
+    // índice em `log.records`
+    Matched(usize, MatchedBy),
+    // vários envios possíveis (ou um só, mas de outra pessoa sem assunto parecido): decide-se à mão
+    Ambiguous(Vec<String>),
+    Unrelated,
+}
+
+/// A que envio responde a mensagem. Só contam os envios feitos antes dela.
+pub fn correlate(log: &SentLog, inbound: &Inbound) -> Correlation {
+    let sent: Vec<(usize, &SentRecord)> = log
+        .records
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.success && !r.cancelled && !r.sandbox && !r.redacted)
+        .filter(|(_, r)| inbound.date.is_none_or(|d| r.sent_at <= d))
+        .collect();
+    let answers = |r: &SentRecord| {
+        let id = r.message.as_ref().and_then(|m| m.message_id.as_deref()).map(strip_id);
+        id.is_some_and(|id| inbound.references.iter().any(|x| x == id))
+    };
+    let thread = sent.iter().rev().find(|(_, r)| answers(r));
+    if let Some((i, _)) = thread {
+        return Correlation::Matched(*i, MatchedBy::Thread);
+    }
+    // o próprio destinatário: a candidatura é o último envio para ele
+    if let Some((i, _)) = sent.iter().rev().find(|(_, r)| r.involves(&inbound.from)) {
+        return Correlation::Matched(*i, MatchedBy::Sender);
+    }
+    // outra pessoa da mesma empresa, ou de um email pessoal com um assunto parecido
+    let domain = email_domain(&inbound.from).filter(|d| !is_free_mail(d));
+    let same_domain = |r: &SentRecord| domain.is_some_and(|d| email_domain(&r.email).is_some_and(|e| e.eq_ignore_ascii_case(d)));
+    let mut candidates: Vec<(usize, f64)> = vec![];
+    for (i, r) in sent.iter().rev() {
+        if candidates.iter().any(|(j, _)| same_email(&log.records[*j].email, &r.email)) {
+            continue;
+        }
+        let score = r.message.as_ref().map_or(0.0, |m| similarity(&m.subject, &inbound.subject));
+        if same_domain(r) || score >= SIMILAR {
+            candidates.push((*i, score));
+        }
+    }
+    let mut similar: Vec<(usize, f64)> = candidates.iter().copied().filter(|(_, s)| *s >= SIMILAR).collect();
+    similar.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let emails = |list: &[(usize, f64)]| list.iter().map(|(i, _)| log.records[*i].email.clone()).collect();
+    match similar.as_slice() {
+        _ if candidates.is_empty() => Correlation::Unrelated,
+        [(i, _)] => Correlation::Matched(*i, MatchedBy::Subject),
+        [(i, best), (_, next), ..] if best - next >= CLEARLY_BETTER => Correlation::Matched(*i, MatchedBy::Subject),
+        [] => Correlation::Ambiguous(emails(&candidates)),
+        _ => Correlation::Ambiguous(emails(&similar)),
+    }
+}
+
+/// Resposta que podia ser a mais de um envio, à espera do `replies resolve`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PendingReply {
+    pub key: String,
+    pub from: String,
+    pub subject: String,
+    #[serde(default)]
+    pub received_at: Option<DateTime<Local>>,
+    pub candidates: Vec<String>,
+    #[serde(default)]
+    pub kind: ReplyKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct RepliesState {
+    // mensagens já ligadas a um envio, para um novo scan não as contar outra
+    // vez; as que não têm nada a ver não ficam, senão isto só crescia
+    #[serde(default)]
+    pub seen: BTreeSet<String>,
+    #[serde(default)]
+    pub pending: Vec<PendingReply>,
+}
+
+pub fn load_state() -> RepliesState {
+    crate::schema::INITIAL.load(&crate::overrides::path(REPLIES_FILE))
+}
+
+pub fn save_state(state: &RepliesState) -> Result<()> {
+    crate::schema::INITIAL.save(&crate::overrides::path(REPLIES_FILE), state)
+}
+
+// a fase só avança: uma resposta não desfaz uma entrevista, nada mexe numa proposta
+fn advance(record: &mut SentRecord, status: ApplicationStatus) -> bool {
+    let allowed = match status {
+        ApplicationStatus::Replied => record.status == ApplicationStatus::Sent,
+        ApplicationStatus::Interview => matches!(record.status, ApplicationStatus::Sent | ApplicationStatus::Replied),
+        ApplicationStatus::Rejected => !matches!(record.status, ApplicationStatus::Rejected | ApplicationStatus::Offer),
+        ApplicationStatus::Sent | ApplicationStatus::Offer => false,
+    };
+    if allowed {
+        record.set_status(status);
+    }
+    allowed
+}
+
+/// Uma mensagem ligada (ou quase) a um envio.
+#[derive(Debug)]
+pub struct Outcome {
+    pub inbound: Inbound,
+    pub correlation: Correlation,
+    pub kind: ReplyKind,
+    // a fase da candidatura mudou
+    pub changed: bool,
+    // ausente até este dia (resposta automática com data): o follow-up foi adiado
+    pub back: Option<NaiveDate>,
+}
+
+// numa ausência com data de regresso, o follow-up passa para o dia a seguir
+fn postpone(record: &mut SentRecord, inbound: &Inbound) -> Option<NaiveDate> {
+    if record.status != ApplicationStatus::Sent {
+        return None;
+    }
+    let received = inbound.date.unwrap_or_else(Local::now).date_naive();
+    let back = absence::return_date(&format!("{}\n{}", inbound.subject, fresh_text(&inbound.text)), received)?;
+    record.follow_up_after = back.succ_opt();
+    Some(back)
+}
+
+/// Liga as mensagens novas aos envios, classifica-as e muda a fase das
+/// candidaturas; as ambíguas ficam em `pending`. As próprias (cópias, BCC)
+/// ficam de fora.
+pub fn apply(log: &mut SentLog, state: &mut RepliesState, messages: Vec<Inbound>, own: &str, rules: &RepliesConfig) -> Vec<Outcome> {
+    let mut outcomes = vec![];
+    for inbound in messages {
+        let key = inbound.key();
+        if state.seen.contains(&key) || same_email(&inbound.from, own) {
+            continue;
+        }
+        let correlation = correlate(log, &inbound);
+        if correlation != Correlation::Unrelated {
+            state.seen.insert(key.clone());
+        }
+        let kind = rules.classify(&inbound.subject, &inbound.text, inbound.auto_submitted);
+        let mut back = None;
+        let changed = match &correlation {
+            Correlation::Matched(i, _) if kind == ReplyKind::AutoReply => {
+                back = postpone(&mut log.records[*i], &inbound);
+                false
+            }
+            Correlation::Matched(i, _) => kind.status().is_some_and(|status| advance(&mut log.records[*i], status)),
+            // uma resposta automática ambígua não vale a pena decidir
+            Correlation::Ambiguous(_) if kind == ReplyKind::AutoReply => false,
+            Correlation::Ambiguous(candidates) => {
+                state.pending.push(PendingReply {
+                    key,
+                    from: inbound.from.clone(),
+                    subject: inbound.subject.clone(),
+                    received_at: inbound.date,
+                    candidates: candidates.clone(),
+                    kind,
+                });
+                false
+            }
+            Correlation::Unrelated => continue,
+        };
+        outcomes.push(Outcome { inbound, correlation, kind, changed, back });
+    }
+    outcomes
+}
+
+// um .eml por ficheiro, ou uma caixa mbox inteira
+fn read_messages(files: &[String]) -> Result<Vec<Vec<u8>>> {
+    let mut raws = vec![];
+    for file in files {
+        let bytes = fs::read(file).with_context(|| format!("Cannot read {}", file))?;
+        if bytes.starts_with(b"From ") {
+            for message in MessageIterator::new(&bytes[..]) {
+                raws.push(message.with_context(|| format!("Cannot read mbox {}", file))?.unwrap_contents());
+            }
+        } else {
+            raws.push(bytes);
+        }
+    }
+    Ok(raws)
+}
+
+/// `replies scan`: lê as mensagens (ficheiros ou a caixa IMAP dos últimos
+/// `days` dias) e liga as respostas aos envios.
+pub async fn run_scan(config: &Config, files: Vec<String>, days: i64) -> Result<()> {
+    let raws = if files.is_empty() {
+        let imap = config.imap.as_ref().context("imap not set in config.json (or pass .eml/mbox files)")?;
+        let since = (Local::now() - Duration::days(days)).date_naive();
+        say!("{} A ler {} desde {}...", MAIL, imap.inbox, since.format("%d/%m/%Y"));
+        let profile = config.active_profile.as_deref();
+        imap::fetch_inbox(imap, since, profile, &config.secrets, config.oauth.as_ref()).await?
+    } else {
+        read_messages(&files)?
+    };
+    let messages: Vec<Inbound> = raws.iter().filter_map(|raw| parse(raw)).collect();
+    verbose!("{} mensagens lidas", messages.len());
+
+    let mut state = load_state();
+    let mut log = SentLog::default();
+    let own = &config.profile.email;
+    let outcomes = update_log(&mut log, |log| Ok(apply(log, &mut state, messages, own, &config.replies)))?;
+    save_state(&state)?;
+
+    let mut updated = 0;
+    let mut postponed = 0;
+    for Outcome { inbound, correlation, kind, changed, back } in &outcomes {
+        match correlation {
+            Correlation::Matched(i, by) => {
+                let record = &log.records[*i];
+                let how = match by {
+                    MatchedBy::Thread | MatchedBy::Sender => String::new(),
+                    MatchedBy::Subject => format!(" (de {}, pelo assunto)", inbound.from),
+                };
+                if *changed {
+                    updated += 1;
+                    let (email, status) = (style(&record.email).yellow(), style(record.status.label()).cyan());
+                    say!("{} {} → {} ({}){}: {}", CHECK, email, status, kind.label(), how, inbound.subject);
+                } else if let (Some(back), Some(after)) = (back, record.follow_up_after) {
+                    postponed += 1;
+                    let (back, after) = (back.format("%d/%m/%Y"), after.format("%d/%m/%Y"));
+                    say!("{} {}: ausente até {}, follow-up adiado para {}", CLOCK, record.email, back, after);
+                } else if *kind == ReplyKind::AutoReply {
+                    say!("{} {}: {}, fica como está", NOTE, record.email, kind.label());
+                } else {
+                    verbose!("{} já em {} ({}): {}", record.email, record.status.label(), kind.label(), inbound.subject);
+                }
+            }
+            Correlation::Ambiguous(candidates) if *kind != ReplyKind::AutoReply => {
+                say!("{} {} ({}) pode responder a: {}", NOTE, inbound.from, inbound.subject, candidates.join(", "));
+            }
+            Correlation::Ambiguous(_) | Correlation::Unrelated => {}
+        }
+    }
+    if updated + postponed > 0 {
+        git_history::record(&format!("replies: {} candidaturas actualizadas, {} follow-ups adiados", updated, postponed));
+    }
+    let pending = state.pending.len();
+    say!("{} {} candidaturas actualizadas, {} respostas por decidir", CHECK, updated, pending);
+    if pending > 0 {
+        say!("   job-mailer replies pending / replies resolve <n> <email>");
+    }
+    Ok(())
+}
+
+pub fn list_pending() {
+    let state = load_state();
+    if state.pending.is_empty() {
+        println!("{} Nenhuma resposta por decidir", CHECK);
+        return;
+    }
+    for (n, reply) in state.pending.iter().enumerate() {
+        let at = reply.received_at.map(|d| d.format("%d/%m %H:%M").to_string()).unwrap_or_default();
+        println!("[{}] {} {} - {} ({})", n + 1, style(at).dim(), style(&reply.from).yellow(), reply.subject, reply.kind.label());
+        println!("    pode ser: {}", reply.candidates.join(", "));
+    }
+}
+
+/// Decide uma resposta ambígua: a candidatura de `email` muda de fase
+/// conforme o tipo de resposta; sem email só sai da lista.
+pub fn resolve(number: usize, email: Option<&str>) -> Result<()> {
+    let mut state = load_state();
+    if number == 0 || number > state.pending.len() {
+        bail!("No pending reply number {} (see replies pending)", number);
+    }
+    let reply = state.pending.remove(number - 1);
+    if let Some(email) = email {
+        let status = reply.kind.status().unwrap_or(ApplicationStatus::Replied);
+        let changed = update_log(&mut SentLog::default(), |log| {
+            let record = log
+                .latest_application_mut(email)
+                .with_context(|| format!("No successful send to {} in the log", email))?;
+            Ok(advance(record, status))
+        })?;
+        if changed {
+            git_history::record(&format!("{}: {}", email, status.label()));
+            say!("{} {} → {} ({})", CHECK, style(email).yellow(), status.label(), reply.subject);
+        } else {
+            say!("{} {}: a fase fica como está ({})", CHECK, style(email).yellow(), reply.subject);
+        }
+    } else {
+        say!("{} Ignorada: {} - {}", CHECK, reply.from, reply.subject);
+    }
+    save_state(&state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SentMessage;
+
+    fn sent(email: &str, subject: &str, message_id: &str) -> SentRecord {
+        SentRecord {
+            email: email.to_string(),
+            sent_at: Local.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap(),
+            success: true,
+            message: Some(SentMessage {
+                subject: subject.to_string(),
+                message_id: Some(message_id.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn inbound(from: &str, subject: &str, references: &[&str]) -> Inbound {
+        Inbound {
+            from: from.to_string(),
+            subject: subject.to_string(),
+            date: Some(Local.with_ymd_and_hms(2026, 10, 3, 9, 0, 0).unwrap()),
+            references: references.iter().map(|r| r.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_reply_headers() {
+        let raw = "From: Maria Costa <maria@acme.com>\r\n\
+                   Subject: =?utf-8?q?RE=3A_Candidatura_-_Jo=C3=A3o?=\r\n\
+                   Date: Sat, 3 Oct 2026 10:00:00 +0100\r\n\
+                   Message-ID: <r1@acme.com>\r\n\
+                   In-Reply-To: <a1@joao.pt>\r\n\
+                   References: <a0@joao.pt> <a1@joao.pt>\r\n\r\nObrigada!\r\n";
+        let parsed = parse(raw.as_bytes()).unwrap();
+        assert_eq!(parsed.from, "maria@acme.com");
+        assert_eq!(parsed.subject, "RE: Candidatura - João");
+        assert_eq!(parsed.message_id.as_deref(), Some("r1@acme.com"));
+        assert_eq!(parsed.references, vec!["a1@joao.pt", "a0@joao.pt"]);
+        assert!(parsed.date.is_some());
+    }
+
+    #[test]
+    fn test_subject_similarity() {
+        assert_eq!(base_subject("RE: Fwd:  Candidatura"), "candidatura");
+        assert_eq!(similarity("Re: Candidatura - Dev Rust", "Candidatura - Dev Rust"), 1.0);
+        assert!(similarity("AW: Candidatura Dev Rust na Acme", "Candidatura - Dev Rust na Beta") >= SIMILAR);
+        assert!(similarity("Newsletter de Outubro", "Candidatura - Dev Rust") < SIMILAR);
+        assert_eq!(similarity("Re:", "Candidatura"), 0.0);
+    }
+
+    #[test]
+    fn test_correlate() {
+        let log = SentLog {
+            records: vec![
+                sent("rh@acme.com", "Candidatura - Dev Rust na Acme", "<a1@joao.pt>"),
+                sent("jobs@acme.com", "Candidatura - Dev Backend na Acme", "<a2@joao.pt>"),
+                sent("rh@beta.pt", "Candidatura - Dev Rust na Beta", "<a3@joao.pt>"),
+            ],
+        };
+        // pelo fio da conversa, mesmo de outro endereço
+        let reply = inbound("ceo@gmail.com", "Olá", &["a3@joao.pt"]);
+        assert_eq!(correlate(&log, &reply), Correlation::Matched(2, MatchedBy::Thread));
+        assert_eq!(correlate(&log, &inbound("RH@acme.com", "Olá", &[])), Correlation::Matched(0, MatchedBy::Sender));
+        // outra pessoa da Acme: decide o assunto
+        let reply = inbound("maria@acme.com", "RE: Candidatura - Dev Backend na Acme", &[]);
+        assert_eq!(correlate(&log, &reply), Correlation::Matched(1, MatchedBy::Subject));
+        let reply = inbound("maria@acme.com", "Convite", &[]);
+        assert_eq!(
+            correlate(&log, &reply),
+            Correlation::Ambiguous(vec!["jobs@acme.com".to_string(), "rh@acme.com".to_string()])
+        );
+        // de um gmail, só com um assunto parecido
+        let reply = inbound("recrutadora@gmail.com", "Re: Candidatura - Dev Rust na Beta", &[]);
+        assert_eq!(correlate(&log, &reply), Correlation::Matched(2, MatchedBy::Subject));
+        assert_eq!(correlate(&log, &inbound("promo@gmail.com", "Descontos", &[])), Correlation::Unrelated);
+        // uma mensagem de antes do envio não responde a ele
+        let early = Inbound { date: Some(Local.with_ymd_and_hms(2026, 9, 1, 9, 0, 0).unwrap()), ..inbound("rh@acme.com", "Olá", &[]) };
+        assert_eq!(correlate(&log, &early), Correlation::Unrelated);
+    }
+
+    #[test]
+    fn test_apply_moves_the_status_once_and_keeps_ambiguous() {
+        let mut log = SentLog {
+            records: vec![
+                sent("rh@acme.com", "Candidatura - Dev Rust", "<a1@joao.pt>"),
+                sent("jobs@acme.com", "Candidatura - Dev Backend", "<a2@joao.pt>"),
+            ],
+        };
+        log.records[1].status = ApplicationStatus::Interview;
+        let mut state = RepliesState::default();
+        let messages = vec![
+            Inbound { message_id: Some("r1".to_string()), ..inbound("rh@acme.com", "Re: Candidatura", &[]) },
+            Inbound { message_id: Some("r2".to_string()), ..inbound("jobs@acme.com", "Re: Candidatura", &[]) },
+            Inbound { message_id: Some("r3".to_string()), ..inbound("maria@acme.com", "Reunião", &[]) },
+            Inbound { message_id: Some("r4".to_string()), ..inbound("joao@joao.pt", "Candidatura - Dev Rust", &[]) },
+            Inbound { message_id: Some("r6".to_string()), ..inbound("promo@loja.pt", "Descontos", &[]) },
+            Inbound {
+                message_id: Some("r5".to_string()),
+                text: "Infelizmente seguimos com outro perfil.".to_string(),
+                ..inbound("rh@acme.com", "Re: Candidatura", &[])
+            },
+        ];
+        let rules = RepliesConfig::default();
+        let outcomes = apply(&mut log, &mut state, messages.clone(), "joao@joao.pt", &rules);
+        assert_eq!(outcomes.iter().map(|o| o.changed).collect::<Vec<_>>(), vec![true, false, false, true]);
+        assert_eq!(outcomes[3].kind, ReplyKind::Rejection);
+        assert_eq!(log.records[0].status, ApplicationStatus::Rejected);
+        // uma entrevista já marcada não volta para trás
+        assert_eq!(log.records[1].status, ApplicationStatus::Interview);
+        assert_eq!(state.pending.len(), 1);
+        assert_eq!(state.pending[0].from, "maria@acme.com");
+        // nem as próprias nem as que não têm nada a ver ficam guardadas
+        assert_eq!(state.seen.iter().map(String::as_str).collect::<Vec<_>>(), vec!["r1", "r2", "r3", "r5"]);
+
+        // um segundo scan das mesmas mensagens não faz nada
+        assert!(apply(&mut log, &mut state, messages, "joao@joao.pt", &rules).is_empty());
+        assert_eq!(log.records[0].status_history.len(), 2);
+    }
+
+    #[test]
+    fn test_out_of_office_postpones_the_follow_up() {
+        let mut log = SentLog { records: vec![sent("rh@acme.com", "Candidatura - Dev Rust", "<a1@joao.pt>")] };
+        let mut state = RepliesState::default();
+        let ooo = Inbound {
+            message_id: Some("r1".to_string()),
+            auto_submitted: true,
+            text: "Estou de férias até 20 de outubro, sem acesso ao email.".to_string(),
+            ..inbound("rh@acme.com", "Resposta automática: Candidatura - Dev Rust", &["a1@joao.pt"])
+        };
+        let rules = RepliesConfig::default();
+        let outcomes = apply(&mut log, &mut state, vec![ooo], "joao@joao.pt", &rules);
+        assert_eq!(outcomes[0].kind, ReplyKind::AutoReply);
+        assert_eq!(outcomes[0].back, NaiveDate::from_ymd_opt(2026, 10, 20));
+        assert!(!outcomes[0].changed);
+        assert_eq!(log.records[0].status, ApplicationStatus::Sent);
+        assert_eq!(log.records[0].follow_up_after, NaiveDate::from_ymd_opt(2026, 10, 21));
+
+        // sem data, ou já com resposta, não mexe no follow-up
+        let away = Inbound { message_id: Some("r2".to_string()), auto_submitted: true, ..inbound("rh@acme.com", "Ausente", &[]) };
+        log.records[0].follow_up_after = None;
+        assert_eq!(apply(&mut log, &mut state, vec![away], "joao@joao.pt", &rules)[0].back, None);
+        log.records[0].status = ApplicationStatus::Replied;
+        let late = Inbound { message_id: Some("r3".to_string()), text: "Back on 25/10".to_string(), ..inbound("rh@acme.com", "Out of office", &[]) };
+        assert_eq!(apply(&mut log, &mut state, vec![late], "joao@joao.pt", &rules)[0].back, None);
+        assert_eq!(log.records[0].follow_up_after, None);
+    }
+}
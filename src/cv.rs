@@ -0,0 +1,450 @@
+use anyhow::{bail, Context, Result};
+use console::style;
+use flate2::read::ZlibDecoder;
+use std::{fs, io::Read, process::Command};
+use tempfile::TempDir;
+
+use crate::{CHECK, CROSS, NOTE};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CvFormat {
+    Pdf,
+    Docx,
+    Odt,
+    Doc,
+    Other,
+}
+
+impl CvFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            CvFormat::Pdf => "pdf",
+            CvFormat::Docx => "docx",
+            CvFormat::Odt => "odt",
+            CvFormat::Doc => "doc",
+            CvFormat::Other => "bin",
+        }
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Pelo conteúdo, não pelo nome: um .docx renomeado para cv.pdf continua a ser um zip.
+pub fn detect(bytes: &[u8]) -> CvFormat {
+    // o "%PDF-" pode vir depois de lixo no início; os leitores procuram no primeiro KB
+    if contains(&bytes[..bytes.len().min(1024)], b"%PDF-") {
+        return CvFormat::Pdf;
+    }
+    if bytes.starts_with(b"PK\x03\x04") {
+        // o .odt começa pela entrada "mimetype", sem compressão; o .docx tem as partes em word/
+        if contains(bytes, b"application/vnd.oasis.opendocument.text") {
+            return CvFormat::Odt;
+        }
+        if contains(bytes, b"word/") {
+            return CvFormat::Docx;
+        }
+        return CvFormat::Other;
+    }
+    if bytes.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]) {
+        return CvFormat::Doc;
+    }
+    CvFormat::Other
+}
+
+fn convert_to_pdf(bytes: &[u8], format: CvFormat) -> Result<Vec<u8>> {
+    let work = TempDir::new()?;
+    let input = work.path().join(format!("cv.{}", format.extension()));
+    fs::write(&input, bytes)?;
+
+    let out = Command::new("libreoffice")
+        .args(["--headless", "--convert-to", "pdf", "--outdir"])
+        .arg(work.path())
+        .arg(&input)
+        .output()
+        .context("libreoffice not found in PATH")?;
+    let output = work.path().join("cv.pdf");
+    if !out.status.success() || !output.exists() {
+        bail!("libreoffice conversion failed: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    let pdf = fs::read(output)?;
+    if detect(&pdf) != CvFormat::Pdf {
+        bail!("libreoffice did not produce a PDF");
+    }
+    Ok(pdf)
+}
+
+/// Só sai um CV que seja mesmo PDF. Word/OpenDocument são convertidos com o
+/// LibreOffice quando `convert` está ligado; tudo o resto é recusado.
+pub fn ensure_pdf(bytes: Vec<u8>, convert: bool) -> Result<Vec<u8>> {
+    match detect(&bytes) {
+        CvFormat::Pdf => Ok(bytes),
+        CvFormat::Other => bail!("The CV is not a PDF (unrecognised content)"),
+        format if convert => {
+            eprintln!(
+                "{} O CV é um .{}, não um PDF: a converter com o LibreOffice",
+                CROSS,
+                format.extension()
+            );
+            convert_to_pdf(&bytes, format)
+        }
+        format => bail!(
+            "The CV is a .{} file, not a PDF (export it as PDF or set sending.convert_cv)",
+            format.extension()
+        ),
+    }
+}
+
+/// O que se consegue ler de um PDF sem o renderizar.
+#[derive(Debug, Default, PartialEq)]
+pub struct PdfInfo {
+    pub version: Option<String>,
+    pub pages: usize,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    // `startxref` aponta para uma tabela (ou stream) xref
+    pub xref_ok: bool,
+    pub eof: bool,
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|i| i + from)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+fn skip_ws(bytes: &[u8], mut at: usize) -> usize {
+    while bytes.get(at).is_some_and(|b| b.is_ascii_whitespace()) {
+        at += 1;
+    }
+    at
+}
+
+fn number(bytes: &[u8], at: usize) -> Option<(usize, usize)> {
+    let start = skip_ws(bytes, at);
+    let len = bytes.get(start..)?.iter().take_while(|b| b.is_ascii_digit()).count();
+    let n = std::str::from_utf8(&bytes[start..start + len]).ok()?.parse().ok()?;
+    Some((n, start + len))
+}
+
+fn xref_ok(bytes: &[u8]) -> bool {
+    let Some(at) = rfind(bytes, b"startxref") else {
+        return false;
+    };
+    let Some((offset, _)) = number(bytes, at + b"startxref".len()) else {
+        return false;
+    };
+    let Some(target) = bytes.get(offset..) else {
+        return false;
+    };
+    // tabela clássica ou, desde o PDF 1.5, um objecto "N 0 obj" com a stream xref
+    target.starts_with(b"xref")
+        || number(target, 0)
+            .and_then(|(_, end)| number(target, end))
+            .is_some_and(|(_, end)| target[skip_ws(target, end)..].starts_with(b"obj"))
+}
+
+// páginas e metadados podem estar dentro de streams comprimidas (object streams)
+fn inflated_streams(bytes: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    let mut at = 0;
+    while let Some(pos) = find(bytes, b"stream", at) {
+        at = pos + b"stream".len();
+        if pos > 0 && bytes[pos - 1] == b'd' {
+            continue;
+        }
+        let start = match bytes.get(at..) {
+            Some([b'\r', b'\n', ..]) => at + 2,
+            Some([b'\n', ..]) => at + 1,
+            _ => continue,
+        };
+        let Some(end) = find(bytes, b"endstream", start) else {
+            break;
+        };
+        let mut inflated = vec![];
+        if ZlibDecoder::new(&bytes[start..end]).read_to_end(&mut inflated).is_ok() {
+            out.extend_from_slice(&inflated);
+            out.push(b'\n');
+        }
+        at = end;
+    }
+    out
+}
+
+// "/Type /Page" conta, "/Type /Pages" não
+fn count_pages(text: &[u8]) -> usize {
+    let mut count = 0;
+    let mut at = 0;
+    while let Some(pos) = find(text, b"/Type", at) {
+        at = skip_ws(text, pos + b"/Type".len());
+        if text[at..].starts_with(b"/Page") && !text.get(at + 5).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn decode_text(raw: &[u8]) -> String {
+    match raw {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        // PDFDocEncoding coincide com latin-1 nos caracteres que interessam
+        _ => raw.iter().map(|&b| b as char).collect(),
+    }
+}
+
+// valor de `/Key (literal)` ou `/Key <hex>`
+fn string_value(text: &[u8], key: &[u8]) -> Option<String> {
+    let mut at = 0;
+    while let Some(pos) = find(text, key, at) {
+        at = pos + key.len();
+        if text.get(at).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            continue;
+        }
+        let start = skip_ws(text, at);
+        let raw = match text.get(start)? {
+            b'(' => {
+                let mut raw = vec![];
+                let mut depth = 1;
+                let mut i = start + 1;
+                while let Some(&b) = text.get(i) {
+                    match b {
+                        b'\\' => {
+                            i += 1;
+                            match text.get(i) {
+                                Some(b'n') => raw.push(b'\n'),
+                                Some(b'r') => raw.push(b'\r'),
+                                Some(b't') => raw.push(b'\t'),
+                                Some(&c) => raw.push(c),
+                                None => break,
+                            }
+                        }
+                        b'(' => {
+                            depth += 1;
+                            raw.push(b);
+                        }
+                        b')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                            raw.push(b);
+                        }
+                        _ => raw.push(b),
+                    }
+                    i += 1;
+                }
+                raw
+            }
+            b'<' => {
+                let end = find(text, b">", start)?;
+                let digits: Vec<u8> = text[start + 1..end].iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+                hex::decode(digits).ok()?
+            }
+            _ => continue,
+        };
+        let value = decode_text(&raw).trim().to_string();
+        return (!value.is_empty()).then_some(value);
+    }
+    None
+}
+
+// o dicionário /Info do trailer; sem ele, a primeira ocorrência serve
+fn info_dict(text: &[u8]) -> &[u8] {
+    let reference = rfind(text, b"/Info").and_then(|at| {
+        let (id, end) = number(text, at + b"/Info".len())?;
+        let (gen, _) = number(text, end)?;
+        Some((id, gen))
+    });
+    let Some((id, gen)) = reference else {
+        return text;
+    };
+    let header = format!("{} {} obj", id, gen);
+    let mut at = 0;
+    while let Some(pos) = find(text, header.as_bytes(), at) {
+        at = pos + header.len();
+        // "12 0 obj" não pode ser o fim de "112 0 obj"
+        if pos == 0 || !text[pos - 1].is_ascii_digit() {
+            let end = find(text, b"endobj", at).unwrap_or(text.len());
+            return &text[at..end];
+        }
+    }
+    text
+}
+
+pub fn inspect(bytes: &[u8]) -> PdfInfo {
+    let version = find(bytes, b"%PDF-", 0).map(|at| {
+        let rest = &bytes[at + 5..];
+        let len = rest.iter().take_while(|b| b.is_ascii_digit() || **b == b'.').count();
+        String::from_utf8_lossy(&rest[..len]).into_owned()
+    });
+    let tail = &bytes[bytes.len().saturating_sub(1024)..];
+
+    let mut text = bytes.to_vec();
+    text.extend(inflated_streams(bytes));
+    let info = info_dict(&text);
+    PdfInfo {
+        version: version.filter(|v| !v.is_empty()),
+        pages: count_pages(&text),
+        title: string_value(info, b"/Title").or_else(|| string_value(&text, b"/Title")),
+        author: string_value(info, b"/Author").or_else(|| string_value(&text, b"/Author")),
+        xref_ok: xref_ok(bytes),
+        eof: contains(tail, b"%%EOF"),
+    }
+}
+
+// autor que não partilha nenhum nome com o dono ("Administrator", "Microsoft Office User", outra pessoa)
+fn odd_author(author: &str, owner: &str) -> bool {
+    let author = author.to_lowercase();
+    !owner
+        .to_lowercase()
+        .split_whitespace()
+        .filter(|w| w.chars().count() > 2)
+        .any(|w| author.contains(w))
+}
+
+const LARGE_CV: usize = 5 * 1024 * 1024;
+
+/// Problemas a corrigir antes de enviar; nenhum impede o envio.
+pub fn warnings(info: &PdfInfo, size: usize, owner: &str) -> Vec<String> {
+    let mut out = vec![];
+    if !info.eof {
+        out.push("sem %%EOF no fim: o ficheiro parece cortado".to_string());
+    }
+    if !info.xref_ok {
+        out.push("startxref não aponta para a tabela xref: o PDF pode estar corrompido".to_string());
+    }
+    if info.pages == 0 {
+        out.push("não foi possível contar as páginas".to_string());
+    }
+    if info.title.is_none() {
+        out.push("sem título nos metadados (é o que o leitor de PDF mostra)".to_string());
+    }
+    match &info.author {
+        None => out.push("sem autor nos metadados".to_string()),
+        Some(author) if odd_author(author, owner) => {
+            out.push(format!("o autor nos metadados é \"{}\", não {}", author, owner))
+        }
+        Some(_) => {}
+    }
+    if size > LARGE_CV {
+        out.push(format!(
+            "{:.1}MB: alguns servidores recusam anexos grandes",
+            size as f64 / (1024.0 * 1024.0)
+        ));
+    }
+    out
+}
+
+pub fn summary(info: &PdfInfo, size: usize) -> String {
+    let pages = match info.pages {
+        0 => "? páginas".to_string(),
+        1 => "1 página".to_string(),
+        n => format!("{} páginas", n),
+    };
+    format!("{}, {}KB, PDF {}", pages, size / 1024, info.version.as_deref().unwrap_or("?"))
+}
+
+/// `job-mailer cv`: o relatório completo, sem enviar nada.
+pub fn print_report(path: &str, bytes: &[u8], owner: &str) {
+    let info = inspect(bytes);
+    println!("{} {}: {}", NOTE, path, style(summary(&info, bytes.len())).cyan());
+    println!("{}", style("─".repeat(60)).dim());
+    println!("  Título: {}", info.title.as_deref().unwrap_or("-"));
+    println!("  Autor:  {}", info.author.as_deref().unwrap_or("-"));
+    println!("{}", style("─".repeat(60)).dim());
+    let warnings = warnings(&info, bytes.len(), owner);
+    if warnings.is_empty() {
+        println!("{} Tudo certo", CHECK);
+    }
+    for w in &warnings {
+        println!("{} {}", CROSS, w);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_by_content() {
+        assert_eq!(detect(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3"), CvFormat::Pdf);
+        assert_eq!(detect(b"PK\x03\x04\x14\0\0\0[Content_Types].xml word/document.xml"), CvFormat::Docx);
+        assert_eq!(
+            detect(b"PK\x03\x04\0\0mimetypeapplication/vnd.oasis.opendocument.text"),
+            CvFormat::Odt
+        );
+        assert_eq!(detect(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1, 0]), CvFormat::Doc);
+        assert_eq!(detect(b"PK\x03\x04 fotos.zip"), CvFormat::Other);
+        assert_eq!(detect(b""), CvFormat::Other);
+    }
+
+    #[test]
+    fn test_ensure_pdf_rejects_documents_without_convert() {
+        assert!(ensure_pdf(b"%PDF-1.4 ok".to_vec(), false).is_ok());
+        let err = ensure_pdf(b"PK\x03\x04 word/document.xml".to_vec(), false).unwrap_err();
+        assert!(err.to_string().contains(".docx"));
+        assert!(ensure_pdf(b"texto".to_vec(), true).is_err());
+    }
+
+    // PDF mínimo com os offsets certos no xref
+    fn sample_pdf(info: &str) -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R >>".to_string(),
+            "<< /Type/Page /Parent 2 0 R >>".to_string(),
+            info.to_string(),
+        ];
+        let mut pdf = b"%PDF-1.7\n".to_vec();
+        let mut offsets = vec![];
+        for (i, obj) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, obj).bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        pdf.extend(format!("trailer\n<< /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n", xref).bytes());
+        pdf
+    }
+
+    #[test]
+    fn test_inspect_valid_pdf() {
+        let pdf = sample_pdf("<< /Title (CV \\(2024\\)) /Author <FEFF004A006F00E3006F002000530069006C00760061> >>");
+        let info = inspect(&pdf);
+        assert_eq!(info.version.as_deref(), Some("1.7"));
+        assert_eq!(info.pages, 2);
+        assert_eq!(info.title.as_deref(), Some("CV (2024)"));
+        assert_eq!(info.author.as_deref(), Some("João Silva"));
+        assert!(info.xref_ok && info.eof);
+        assert!(warnings(&info, pdf.len(), "João Silva").is_empty());
+        assert!(summary(&info, pdf.len()).starts_with("2 páginas, 0KB, PDF 1.7"));
+    }
+
+    #[test]
+    fn test_warnings_for_broken_or_anonymous_pdf() {
+        let pdf = sample_pdf("<< /Author (Microsoft Office User) >>");
+        let info = inspect(&pdf);
+        let found = warnings(&info, pdf.len(), "João Silva");
+        assert_eq!(found.len(), 2);
+        assert!(found[0].contains("título"));
+        assert!(found[1].contains("Microsoft Office User"));
+
+        let cut = inspect(&pdf[..pdf.len() / 2]);
+        assert!(!cut.eof && !cut.xref_ok);
+        assert_eq!(warnings(&cut, 10, "João Silva").len(), 4);
+        assert!(warnings(&info, 6 * 1024 * 1024, "Microsoft").last().unwrap().contains("MB"));
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use console::style;
+use serde::{Deserialize, Serialize};
+
+use crate::{contacts::same_email, failure::SendError, git_history, CHECK, CROSS};
+
+pub const DEAD_FILE: &str = "dead_addresses.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeadAddress {
+    pub email: String,
+    pub added_at: DateTime<Local>,
+    // resposta do servidor que levou o endereço para a lista
+    pub reason: String,
+}
+
+/// Endereços que o servidor rejeitou de vez (5xx "user unknown"); nunca
+/// mais se tenta enviar nem importar para eles.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct DeadList {
+    pub addresses: Vec<DeadAddress>,
+}
+
+impl DeadList {
+    pub fn contains(&self, email: &str) -> bool {
+        self.addresses.iter().any(|a| same_email(&a.email, email))
+    }
+
+    /// Devolve `true` se o endereço ainda não estava na lista.
+    pub fn add(&mut self, email: &str, reason: &str) -> bool {
+        if self.contains(email) {
+            return false;
+        }
+        self.addresses.push(DeadAddress {
+            email: email.to_string(),
+            added_at: Local::now(),
+            reason: reason.to_string(),
+        });
+        true
+    }
+
+    pub fn remove(&mut self, email: &str) -> bool {
+        let before = self.addresses.len();
+        self.addresses.retain(|a| !same_email(&a.email, email));
+        self.addresses.len() != before
+    }
+}
+
+/// Só a rejeição permanente do destinatário conta: um 4xx ou um bloqueio
+/// por spam não quer dizer que a caixa não existe.
+pub fn is_dead(error: &SendError) -> bool {
+    error.kind == crate::failure::ErrorKind::RecipientRejected && error.code.is_some_and(|c| c >= 500)
+}
+
+pub fn load_dead() -> DeadList {
+    crate::schema::INITIAL.load(&crate::overrides::path(DEAD_FILE))
+}
+
+pub fn save_dead(list: &DeadList) -> Result<()> {
+    crate::schema::INITIAL.save(&crate::overrides::path(DEAD_FILE), list)
+}
+
+pub fn list_dead() {
+    let list = load_dead();
+    if list.addresses.is_empty() {
+        println!("{} Nenhum endereço morto", CHECK);
+        return;
+    }
+    println!("{} Endereços mortos ({}):", CROSS, list.addresses.len());
+    println!("{}", style("─".repeat(60)).dim());
+    for a in &list.addresses {
+        println!(
+            "  {} {} - {}",
+            style(a.added_at.format("%d/%m/%Y")).dim(),
+            style(&a.email).red(),
+            a.reason
+        );
+    }
+    println!("{}", style("─".repeat(60)).dim());
+}
+
+pub fn remove_dead(email: &str) -> Result<()> {
+    let mut list = load_dead();
+    if !list.remove(email) {
+        bail!("{} is not in the dead-address list", email);
+    }
+    save_dead(&list)?;
+    git_history::record(&format!("{} removido dos endereços mortos", email));
+    say!("{} {} pode voltar a receber emails", CHECK, style(email).green());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_list_add_and_remove() {
+        let mut list = DeadList::default();
+        assert!(list.add("Nobody@Acme.com", "550 5.1.1 User unknown"));
+        assert!(!list.add("nobody@acme.com", "550 de novo"));
+        assert!(list.contains("NOBODY@acme.com"));
+        assert_eq!(list.addresses.len(), 1);
+
+        assert!(list.remove("nobody@ACME.com"));
+        assert!(!list.remove("nobody@acme.com"));
+        assert!(!list.contains("nobody@acme.com"));
+    }
+
+    #[test]
+    fn test_only_permanent_recipient_rejections_are_dead() {
+        let error = |m: &str| SendError::classify(m, None);
+        assert!(is_dead(&error("permanent error (550): 5.1.1 User unknown")));
+        assert!(!is_dead(&error("transient error (450): 4.1.1 mailbox busy, no such user yet")));
+        assert!(!is_dead(&error("permanent error (550): 5.7.1 Message rejected as spam")));
+        assert!(!is_dead(&error("no such user")));
+    }
+}
@@ -0,0 +1,180 @@
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+
+fn default_pause_chance() -> f64 {
+    0.1
+}
+
+fn default_pause() -> [u64; 2] {
+    [300, 900]
+}
+
+/// Espera entre dois envios do bulk e do mail-merge (`sending.delay`), em segundos.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "strategy", rename_all = "lowercase")]
+pub enum Delay {
+    // qualquer valor entre min e max, todos igualmente prováveis
+    Uniform { min: u64, max: u64 },
+    // quase sempre perto de `mean`; `min`/`max` cortam as pontas da curva
+    Gaussian {
+        mean: u64,
+        stddev: u64,
+        #[serde(default)]
+        min: u64,
+        #[serde(default)]
+        max: Option<u64>,
+    },
+    // uniforme, mas de vez em quando (`pause_chance`) uma pausa longa entre `pause` segundos
+    Humanized {
+        min: u64,
+        max: u64,
+        #[serde(default = "default_pause_chance")]
+        pause_chance: f64,
+        #[serde(default = "default_pause")]
+        pause: [u64; 2],
+    },
+}
+
+impl Default for Delay {
+    fn default() -> Self {
+        Delay::Uniform { min: 30, max: 60 }
+    }
+}
+
+impl Delay {
+    pub fn next(&self, rng: &mut impl Rng) -> u64 {
+        match *self {
+            Delay::Uniform { min, max } => rng.gen_range(min..=max.max(min)),
+            Delay::Gaussian { mean, stddev, min, max } => {
+                let max = max.unwrap_or(u64::MAX).max(min);
+                let value = match Normal::new(mean as f64, stddev as f64) {
+                    Ok(normal) => normal.sample(rng).round(),
+                    Err(_) => mean as f64,
+                };
+                (value.max(0.0) as u64).clamp(min, max)
+            }
+            Delay::Humanized { min, max, pause_chance, pause: [from, to] } => {
+                if rng.gen_bool(pause_chance.clamp(0.0, 1.0)) {
+                    rng.gen_range(from..=to.max(from))
+                } else {
+                    rng.gen_range(min..=max.max(min))
+                }
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Delay::Uniform { min, max } => format!("{}s-{}s", min, max),
+            Delay::Gaussian { mean, stddev, .. } => format!("~{}s ±{}s", mean, stddev),
+            Delay::Humanized { min, max, pause_chance, pause: [from, to] } => format!(
+                "{}s-{}s, {:.0}% de pausas de {}s-{}s",
+                min,
+                max,
+                pause_chance * 100.0,
+                from,
+                to
+            ),
+        }
+    }
+}
+
+// a espera nunca passa de 16x a configurada
+const MAX_FACTOR: u64 = 16;
+// envios aceites seguidos até a espera voltar a metade
+const RECOVER_AFTER: u32 = 5;
+// abrandado, um delay de 0-5s ainda martelava o servidor
+const MIN_THROTTLED: u64 = 30;
+
+/// Abranda o lote quando o servidor responde com rate limit: cada recusa dobra
+/// a espera e cada `RECOVER_AFTER` envios aceites seguidos tiram metade.
+#[derive(Debug)]
+pub struct Throttle {
+    factor: u64,
+    streak: u32,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Throttle { factor: 1, streak: 0 }
+    }
+}
+
+impl Throttle {
+    pub fn scale(&self, secs: u64) -> u64 {
+        match self.factor {
+            1 => secs,
+            factor => secs.max(MIN_THROTTLED) * factor,
+        }
+    }
+
+    /// O servidor pediu para abrandar; devolve o novo factor.
+    pub fn slow_down(&mut self) -> u64 {
+        self.streak = 0;
+        self.factor = (self.factor * 2).min(MAX_FACTOR);
+        self.factor
+    }
+
+    /// Envio aceite; Some(factor) quando a espera volta a descer.
+    pub fn accepted(&mut self) -> Option<u64> {
+        if self.factor == 1 {
+            return None;
+        }
+        self.streak += 1;
+        if self.streak < RECOVER_AFTER {
+            return None;
+        }
+        self.streak = 0;
+        self.factor /= 2;
+        Some(self.factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_delay_strategies_stay_in_range() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let delay: Delay = serde_json::from_str(r#"{"strategy": "gaussian", "mean": 45, "stddev": 30, "min": 20, "max": 90}"#).unwrap();
+        let samples: Vec<u64> = (0..500).map(|_| delay.next(&mut rng)).collect();
+        assert!(samples.iter().all(|s| (20..=90).contains(s)));
+        let mean = samples.iter().sum::<u64>() as f64 / samples.len() as f64;
+        assert!((40.0..50.0).contains(&mean), "média {}", mean);
+
+        let delay: Delay = serde_json::from_str(r#"{"strategy": "humanized", "min": 30, "max": 60}"#).unwrap();
+        assert_eq!(delay, Delay::Humanized { min: 30, max: 60, pause_chance: 0.1, pause: [300, 900] });
+        let samples: Vec<u64> = (0..500).map(|_| delay.next(&mut rng)).collect();
+        let pauses = samples.iter().filter(|s| **s >= 300).count();
+        assert!(samples.iter().all(|s| (30..=60).contains(s) || (300..=900).contains(s)));
+        assert!((20..=90).contains(&pauses), "{} pausas", pauses);
+
+        // min > max não rebenta: fica no min
+        assert_eq!(Delay::Uniform { min: 10, max: 5 }.next(&mut rng), 10);
+    }
+
+    #[test]
+    fn test_throttle_backs_off_and_recovers() {
+        let mut throttle = Throttle::default();
+        assert_eq!(throttle.scale(10), 10);
+        assert_eq!(throttle.accepted(), None);
+
+        assert_eq!(throttle.slow_down(), 2);
+        assert_eq!(throttle.scale(10), 60);
+        assert_eq!(throttle.scale(45), 90);
+        assert_eq!((0..5).map(|_| throttle.slow_down()).last(), Some(16));
+
+        for _ in 0..4 {
+            assert_eq!(throttle.accepted(), None);
+        }
+        assert_eq!(throttle.accepted(), Some(8));
+        // uma recusa a meio recomeça a contagem
+        throttle.accepted();
+        assert_eq!(throttle.slow_down(), 16);
+        assert_eq!((0..5).filter_map(|_| throttle.accepted()).collect::<Vec<_>>(), vec![8]);
+    }
+}
@@ -0,0 +1,168 @@
+use anyhow::{bail, Context, Result};
+use console::style;
+use dialoguer::{theme::ColorfulTheme, Editor, Select};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{sync::atomic::Ordering, time::Duration};
+
+use crate::{secrets::SecretProvider, Profile, CROSS, NOTE, SPARKLE};
+
+// o anúncio vai cortado: chega para o modelo e não gasta tokens à toa
+const MAX_DESCRIPTION: usize = 12_000;
+
+/// Rascunho do parágrafo `{{cover_letter}}` por uma API compatível com a da
+/// OpenAI (OpenAI, Azure, Ollama, LM Studio, ...). Só com esta secção e
+/// `send --job ... --draft`; o texto passa sempre pela minha revisão.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftingConfig {
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
+    pub model: String,
+    // conta no fornecedor de `secrets`; OPENAI_API_KEY no ambiente passa à frente
+    #[serde(default = "default_key")]
+    pub key: String,
+    // o que juntar ao pedido (tom, tamanho, língua...)
+    #[serde(default)]
+    pub instructions: Option<String>,
+}
+
+fn default_base_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+fn default_key() -> String {
+    "openai".to_string()
+}
+
+fn api_key(config: &DraftingConfig, secrets: &SecretProvider) -> Result<String> {
+    match std::env::var("OPENAI_API_KEY") {
+        Ok(key) if !key.is_empty() => Ok(key),
+        _ => crate::secrets::password(secrets, &config.key).context("OPENAI_API_KEY not set and no key in the secrets provider"),
+    }
+}
+
+fn request_body(config: &DraftingConfig, profile: &Profile, description: &str) -> Value {
+    let mut system = "You write one paragraph of a job application email: 3 to 5 sentences, first person, \
+                      in the language of the job posting. Connect the candidate's real experience to what the posting asks for. \
+                      No greeting, no sign-off, no placeholders, and never invent skills, employers or numbers."
+        .to_string();
+    if let Some(extra) = &config.instructions {
+        system.push(' ');
+        system.push_str(extra);
+    }
+    let description: String = description.chars().take(MAX_DESCRIPTION).collect();
+    let candidate = format!(
+        "Candidate: {}, {}\nExperience: {} years\nSkills: {}\nSummary: {}",
+        profile.name,
+        profile.title,
+        profile.experience_years,
+        profile.skills.join(", "),
+        profile.summary
+    );
+    json!({
+        "model": config.model,
+        "messages": [
+            { "role": "system", "content": system },
+            { "role": "user", "content": format!("{}\n\nJob posting:\n{}", candidate, description) },
+        ],
+    })
+}
+
+// `choices[0].message.content` da resposta
+fn draft_from_response(response: &Value) -> Result<String> {
+    let content = response["choices"][0]["message"]["content"].as_str().context("No draft in the response")?;
+    let draft = content.trim().trim_matches('"').trim().to_string();
+    if draft.is_empty() {
+        bail!("The model returned an empty draft");
+    }
+    Ok(draft)
+}
+
+/// Pede o parágrafo ao modelo com o anúncio e o meu perfil.
+pub async fn draft(config: &DraftingConfig, secrets: &SecretProvider, profile: &Profile, description: &str) -> Result<String> {
+    let key = api_key(config, secrets)?;
+    let url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
+    // o enrich::http_client() dá 5s, pouco para um modelo
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(90)).build().unwrap_or_default();
+    let resp = client
+        .post(&url)
+        .bearer_auth(key)
+        .json(&request_body(config, profile, &crate::jobs::plain_text(description)))
+        .send()
+        .await
+        .with_context(|| format!("Cannot reach {}", url))?;
+    if !resp.status().is_success() {
+        bail!("Draft request refused ({}): {}", resp.status(), resp.text().await.unwrap_or_default());
+    }
+    let response: Value = resp.json().await.context("Invalid response from the drafting API")?;
+    draft_from_response(&response)
+}
+
+/// Mostra o rascunho e deixa usá-lo, editá-lo ou deitá-lo fora; `None` = sem parágrafo.
+pub fn review(draft: String) -> Result<Option<String>> {
+    if crate::ASSUME_YES.load(Ordering::Relaxed) || !console::user_attended() {
+        bail!("--draft needs a terminal to review the paragraph");
+    }
+    println!("\n{} Rascunho para {{{{cover_letter}}}}:", SPARKLE);
+    println!("{}", style("─".repeat(60)).dim());
+    println!("{}", draft);
+    println!("{}", style("─".repeat(60)).dim());
+    let actions = ["Usar", "Editar antes de usar", "Deitar fora"];
+    let action = Select::with_theme(&ColorfulTheme::default()).items(&actions).default(0).interact()?;
+    let paragraph = match action {
+        0 => Some(draft),
+        // fechar o editor sem gravar fica com o rascunho como estava
+        1 => Some(Editor::new().extension(".txt").edit(&draft)?.unwrap_or(draft)),
+        _ => None,
+    };
+    match paragraph.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) {
+        Some(paragraph) => Ok(Some(paragraph)),
+        None => {
+            println!("{} Sem parágrafo: {{{{cover_letter}}}} fica vazio", CROSS);
+            Ok(None)
+        }
+    }
+}
+
+/// `send --draft`: o template tem de ter onde pôr o parágrafo, senão não vale a pena gastar o pedido.
+pub fn check_template(template: &crate::EmailTemplate) -> Result<()> {
+    let used = [Some(&template.body), template.html.as_ref()]
+        .into_iter()
+        .flatten()
+        .chain(template.languages.values().flat_map(|v| [Some(&v.body), v.html.as_ref()].into_iter().flatten()))
+        .any(|text| text.contains("{{cover_letter"));
+    if !used {
+        bail!("The template has no {{{{cover_letter}}}} placeholder");
+    }
+    println!("{} A pedir o rascunho ao modelo...", NOTE);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draft_request_and_response() {
+        let config: DraftingConfig = serde_json::from_value(json!({ "model": "gpt-4o-mini", "instructions": "Tom informal." })).unwrap();
+        assert_eq!(config.base_url, "https://api.openai.com/v1");
+        assert_eq!(config.key, "openai");
+        let profile: Profile = serde_json::from_value(json!({
+            "name": "Ana", "email": "ana@example.com", "phone": "", "title": "Backend Engineer",
+            "summary": "APIs em Rust", "skills": ["Rust", "Tokio"], "experience_years": 5
+        }))
+        .unwrap();
+        let body = request_body(&config, &profile, &"x".repeat(MAX_DESCRIPTION + 100));
+        assert_eq!(body["model"], "gpt-4o-mini");
+        assert!(body["messages"][0]["content"].as_str().unwrap().ends_with("Tom informal."));
+        let user = body["messages"][1]["content"].as_str().unwrap();
+        assert!(user.contains("Skills: Rust, Tokio"));
+        assert!(user.contains("Experience: 5 years"));
+        assert!(user.ends_with(&"x".repeat(MAX_DESCRIPTION)) && !user.contains(&"x".repeat(MAX_DESCRIPTION + 1)));
+
+        let response = json!({ "choices": [{ "message": { "role": "assistant", "content": "\n\"Há cinco anos que faço APIs em Rust.\"\n" } }] });
+        assert_eq!(draft_from_response(&response).unwrap(), "Há cinco anos que faço APIs em Rust.");
+        assert!(draft_from_response(&json!({ "choices": [{ "message": { "content": "  " } }] })).is_err());
+        assert!(draft_from_response(&json!({ "error": { "message": "invalid key" } })).is_err());
+    }
+}
@@ -0,0 +1,441 @@
+use anyhow::{bail, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    build_email,
+    contacts::{load_contacts, Contact},
+    git_history,
+    merge::{split_default, BUILTIN_VARS},
+    templates, use_template, Config, EmailTemplate,
+};
+
+// destinatário de exemplo quando não se escolhe um contacto com --to
+const SAMPLE_TO: &str = "rh@acme-corp.com";
+
+/// Texto editável com cursor; `col` conta caracteres, não bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buffer {
+    pub lines: Vec<String>,
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Buffer {
+    pub fn new(text: &str) -> Self {
+        Buffer { lines: text.split('\n').map(str::to_string).collect(), row: 0, col: 0 }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn byte(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices().nth(self.col).map_or(line.len(), |(i, _)| i)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let at = self.byte();
+        self.lines[self.row].insert(at, c);
+        self.col += 1;
+    }
+
+    pub fn newline(&mut self) {
+        let at = self.byte();
+        let rest = self.lines[self.row].split_off(at);
+        self.row += 1;
+        self.lines.insert(self.row, rest);
+        self.col = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let at = self.byte();
+            self.lines[self.row].remove(at);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.col < self.line_len() {
+            let at = self.byte();
+            self.lines[self.row].remove(at);
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    pub fn left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.col = self.line_len();
+    }
+}
+
+/// O que não vai sair bem do template: `{{` sem fecho, placeholders com
+/// espaços (não são substituídos) e variáveis sem alternativa que só o
+/// mail-merge preenche.
+pub fn problems(subject: &str, body: &str) -> Vec<String> {
+    let mut found = vec![];
+    if subject.trim().is_empty() {
+        found.push("assunto vazio".to_string());
+    }
+    let mut columns = vec![];
+    for (field, text) in [("assunto", subject), ("corpo", body)] {
+        for (n, line) in text.lines().enumerate() {
+            let mut rest = line;
+            while let Some(start) = rest.find("{{") {
+                let after = &rest[start + 2..];
+                let end = after.find("}}").filter(|end| !after[..*end].contains("{{"));
+                let Some(end) = end else {
+                    found.push(format!("{{{{ sem }}}} no {}, linha {}", field, n + 1));
+                    break;
+                };
+                let (var, default) = split_default(&after[..end]);
+                if var.trim() != var || var.is_empty() {
+                    found.push(format!("{{{{{}}}}} no {} não é substituído (sem espaços)", &after[..end], field));
+                } else if default.is_none() && !BUILTIN_VARS.contains(&var) && !columns.contains(&var.to_string()) {
+                    columns.push(var.to_string());
+                }
+                rest = &after[end + 2..];
+            }
+        }
+    }
+    if !columns.is_empty() {
+        found.push(format!("só o mail-merge preenche: {}", columns.join(", ")));
+    }
+    found
+}
+
+// placeholders a cores: do perfil a verde, do mail-merge a amarelo, `{{` sem fecho a vermelho
+fn highlight(line: &str) -> Line<'static> {
+    let mut spans = vec![];
+    let mut rest = line;
+    while let Some(start) = rest.find("{{") {
+        spans.push(Span::raw(rest[..start].to_string()));
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let var = &after[..end];
+                let color = if BUILTIN_VARS.contains(&split_default(var).0) { Color::Green } else { Color::Yellow };
+                spans.push(Span::styled(format!("{{{{{}}}}}", var), Style::default().fg(color)));
+                rest = &after[end + 2..];
+            }
+            None => {
+                spans.push(Span::styled(rest[start..].to_string(), Style::default().fg(Color::Red)));
+                rest = "";
+            }
+        }
+    }
+    spans.push(Span::raw(rest.to_string()));
+    Line::from(spans)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Subject,
+    Body,
+}
+
+struct Editor {
+    config: Config,
+    to: String,
+    contact: Option<Contact>,
+    subject: Buffer,
+    body: Buffer,
+    field: Field,
+    scroll: usize,
+    dirty: bool,
+    // Esc com alterações por gravar pede confirmação com um segundo Esc
+    quit_armed: bool,
+    message: String,
+}
+
+impl Editor {
+    fn template(&self) -> EmailTemplate {
+        EmailTemplate { subject: self.subject.text(), body: self.body.text(), ..self.config.template.clone() }
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let name = self.config.template_name.clone();
+        templates::save_template(name.as_deref(), &self.template())?;
+        git_history::record(&format!("template {} editado", name.as_deref().unwrap_or("principal")));
+        self.dirty = false;
+        self.message = "gravado no config.json".to_string();
+        Ok(())
+    }
+
+    // devolve false para sair
+    fn key(&mut self, key: KeyEvent) -> Result<bool> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let was_armed = std::mem::take(&mut self.quit_armed);
+        self.message.clear();
+        if key.code == KeyCode::Esc || (ctrl && key.code == KeyCode::Char('q')) {
+            if !self.dirty || was_armed {
+                return Ok(false);
+            }
+            self.quit_armed = true;
+            self.message = "alterações por gravar: Ctrl-S grava, Esc outra vez sai sem gravar".to_string();
+            return Ok(true);
+        }
+        let buffer = match self.field {
+            Field::Subject => &mut self.subject,
+            Field::Body => &mut self.body,
+        };
+        match key.code {
+            KeyCode::Char('s') if ctrl => self.save()?,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.field = if self.field == Field::Subject { Field::Body } else { Field::Subject };
+            }
+            // o assunto é uma linha só: Enter passa ao corpo
+            KeyCode::Enter if self.field == Field::Subject => self.field = Field::Body,
+            KeyCode::Enter => {
+                buffer.newline();
+                self.dirty = true;
+            }
+            KeyCode::Backspace => {
+                buffer.backspace();
+                self.dirty = true;
+            }
+            KeyCode::Delete => {
+                buffer.delete();
+                self.dirty = true;
+            }
+            KeyCode::Left => buffer.left(),
+            KeyCode::Right => buffer.right(),
+            KeyCode::Up => buffer.up(),
+            KeyCode::Down => buffer.down(),
+            KeyCode::Home => buffer.home(),
+            KeyCode::End => buffer.end(),
+            KeyCode::Char(c) if !ctrl => {
+                buffer.insert(c);
+                self.dirty = true;
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(6), Constraint::Length(4)]).areas(frame.area());
+        let [left, right] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(main);
+        let [subject_area, body_area] = Layout::vertical([Constraint::Length(3), Constraint::Min(3)]).areas(left);
+
+        let focused = |field: Field| {
+            if self.field == field {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default().add_modifier(Modifier::DIM)
+            }
+        };
+        let name = self.config.template_name.as_deref().unwrap_or("principal");
+        let dirty = if self.dirty { " *" } else { "" };
+
+        // sem quebra de linha no editor, para o cursor bater certo; desliza na horizontal
+        let inner_width = subject_area.width.saturating_sub(2) as usize;
+        let h = self.subject.col.saturating_sub(inner_width.saturating_sub(1));
+        frame.render_widget(
+            Paragraph::new(highlight(&self.subject.lines[0])).scroll((0, h as u16)).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(focused(Field::Subject))
+                    .title(format!(" Template {}{} - Assunto ", name, dirty)),
+            ),
+            subject_area,
+        );
+
+        let height = body_area.height.saturating_sub(2) as usize;
+        if self.body.row < self.scroll {
+            self.scroll = self.body.row;
+        } else if height > 0 && self.body.row >= self.scroll + height {
+            self.scroll = self.body.row + 1 - height;
+        }
+        let body_h = self.body.col.saturating_sub(inner_width.saturating_sub(1));
+        let lines: Vec<Line> = self.body.lines.iter().map(|l| highlight(l)).collect();
+        frame.render_widget(
+            Paragraph::new(lines).scroll((self.scroll as u16, body_h as u16)).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(focused(Field::Body))
+                    .title(" Corpo "),
+            ),
+            body_area,
+        );
+
+        let mut preview = self.config.clone();
+        preview.template = self.template();
+        let (subject, body) = build_email(&preview, &self.to, self.contact.as_ref());
+        let mut lines = vec![
+            Line::from(vec![Span::styled("Para: ", Style::default().fg(Color::Cyan)), Span::raw(self.to.clone())]),
+            Line::from(vec![Span::styled("Assunto: ", Style::default().fg(Color::Cyan)), Span::raw(subject)]),
+            Line::from(""),
+        ];
+        lines.extend(body.lines().map(|l| Line::from(l.to_string())));
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(" Preview ")),
+            right,
+        );
+
+        let found = problems(&self.subject.text(), &self.body.text());
+        let mut status_lines: Vec<Line> = if found.is_empty() {
+            vec![Line::styled("placeholders OK", Style::default().fg(Color::Green))]
+        } else {
+            found.iter().map(|p| Line::styled(p.clone(), Style::default().fg(Color::Yellow))).collect()
+        };
+        if !self.message.is_empty() {
+            status_lines.insert(0, Line::styled(self.message.clone(), Style::default().add_modifier(Modifier::BOLD)));
+        }
+        frame.render_widget(
+            Paragraph::new(status_lines).block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .title(" Tab muda de campo · Ctrl-S grava · Esc sai "),
+            ),
+            status,
+        );
+
+        let cursor = match self.field {
+            Field::Subject => position(subject_area, 0, self.subject.col - h),
+            Field::Body => position(body_area, self.body.row - self.scroll, self.body.col - body_h),
+        };
+        frame.set_cursor_position(cursor);
+    }
+}
+
+fn position(area: Rect, row: usize, col: usize) -> Position {
+    Position::new(area.x + 1 + col as u16, area.y + 1 + row as u16)
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, editor: &mut Editor) -> Result<()> {
+    loop {
+        terminal.draw(|frame| editor.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !editor.key(key)? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Editor em ecrã dividido: o template à esquerda, o email já preenchido à
+/// direita, para o contacto `to` (ou um destinatário de exemplo).
+pub fn run_edit(name: Option<String>, to: Option<String>) -> Result<()> {
+    if !console::user_attended() {
+        bail!("template edit needs a terminal");
+    }
+    let mut config = crate::load_config()?;
+    if let Some(name) = &name {
+        use_template(&mut config, name)?;
+    }
+    let to = to.unwrap_or_else(|| SAMPLE_TO.to_string());
+    let contact = load_contacts().find(&to).cloned();
+    let mut editor = Editor {
+        subject: Buffer::new(&config.template.subject),
+        body: Buffer::new(&config.template.body),
+        config,
+        to,
+        contact,
+        field: Field::Body,
+        scroll: 0,
+        dirty: false,
+        quit_armed: false,
+        message: String::new(),
+    };
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut editor);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_editing() {
+        let mut b = Buffer::new("Olá\nequipa");
+        b.end();
+        b.insert('!');
+        assert_eq!(b.text(), "Olá!\nequipa");
+        b.newline();
+        b.insert('x');
+        assert_eq!(b.text(), "Olá!\nx\nequipa");
+        b.home();
+        b.backspace();
+        assert_eq!((b.text().as_str(), b.row, b.col), ("Olá!x\nequipa", 0, 4));
+        b.delete();
+        b.end();
+        b.delete();
+        assert_eq!(b.text(), "Olá!equipa");
+        b.down();
+        b.left();
+        assert_eq!((b.row, b.col), (0, 3));
+    }
+
+    #[test]
+    fn test_problems() {
+        assert!(problems("Candidatura - {{title}}", "Olá {{company}},\n{{name}} {{recruiter|equipa}}").is_empty());
+        assert_eq!(problems("", "Olá {{role}} e {{role}}"), vec![
+            "assunto vazio",
+            "só o mail-merge preenche: role"
+        ]);
+        assert_eq!(problems("{{ name }}", "Olá {{company\nfim"), vec![
+            "{{ name }} no assunto não é substituído (sem espaços)",
+            "{{ sem }} no corpo, linha 1"
+        ]);
+    }
+}
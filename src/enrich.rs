@@ -0,0 +1,209 @@
+use reqwest::Client;
+use std::time::Duration;
+
+use crate::contacts::Contact;
+
+// domínios de email pessoal: o site não diz nada sobre a empresa
+const FREE_MAIL_DOMAINS: &[&str] = &[
+    "gmail.com",
+    "googlemail.com",
+    "outlook.com",
+    "hotmail.com",
+    "live.com",
+    "yahoo.com",
+    "icloud.com",
+    "proton.me",
+    "protonmail.com",
+    "gmx.com",
+    "aol.com",
+    "sapo.pt",
+];
+
+const TITLE_SEPARATORS: &[&str] = &[" | ", " - ", " – ", " — ", " · ", ": "];
+
+pub fn email_domain(email: &str) -> Option<&str> {
+    email
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.trim())
+        .filter(|d| d.contains('.'))
+}
+
+pub fn is_free_mail(domain: &str) -> bool {
+    FREE_MAIL_DOMAINS.iter().any(|d| d.eq_ignore_ascii_case(domain))
+}
+
+// "careers.acme-corp.co.uk" → "acme-corp": ignora subdomínios e sufixos tipo co.uk/com.br
+fn domain_stem(domain: &str) -> &str {
+    let labels: Vec<&str> = domain.split('.').filter(|l| !l.is_empty()).collect();
+    let idx = match labels.as_slice() {
+        [.., sld, tld] if labels.len() > 2 && tld.len() == 2 && sld.len() <= 3 => labels.len() - 3,
+        _ => labels.len().saturating_sub(2),
+    };
+    labels.get(idx).copied().unwrap_or("")
+}
+
+/// jobs@acme-corp.com → "Acme Corp". Vazio para domínios de email pessoal.
+pub fn company_from_domain(email: &str) -> Option<String> {
+    let domain = email_domain(email).filter(|d| !is_free_mail(d))?;
+    let words: Vec<String> = domain_stem(domain)
+        .split(['-', '_'])
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+fn alnum_lower(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#039;", "'")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+}
+
+fn attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let start = lower.find(&format!("{}=", name))? + name.len() + 1;
+    let quote = tag[start..].chars().next()?;
+    if quote != '"' && quote != '\'' {
+        return None;
+    }
+    let value = &tag[start + 1..];
+    value.find(quote).map(|end| &value[..end])
+}
+
+pub(crate) fn og_site_name(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find("<meta") {
+        let start = from + pos;
+        let end = start + lower[start..].find('>')?;
+        let tag = &html[start..end];
+        if attr(tag, "property") == Some("og:site_name") {
+            return attr(tag, "content").map(decode_entities);
+        }
+        from = end;
+    }
+    None
+}
+
+pub(crate) fn page_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title>")?;
+    let title = decode_entities(html[start..end].trim());
+    Some(title.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Nome da empresa a partir da página inicial: og:site_name se existir,
+/// senão o pedaço do <title> que mais se parece com o domínio.
+pub fn company_from_html(html: &str, domain: &str) -> Option<String> {
+    let stem = alnum_lower(domain_stem(domain));
+
+    let name = og_site_name(html).or_else(|| {
+        let title = page_title(html)?;
+        let mut parts: Vec<&str> = vec![title.as_str()];
+        for sep in TITLE_SEPARATORS {
+            parts = parts.iter().flat_map(|p| p.split(sep)).collect();
+        }
+        let parts: Vec<&str> = parts.into_iter().map(str::trim).filter(|p| !p.is_empty()).collect();
+        let matching = parts.iter().find(|p| {
+            let p = alnum_lower(p);
+            !p.is_empty() && !stem.is_empty() && (p.contains(&stem) || stem.contains(&p))
+        });
+        matching.or(parts.first()).map(|p| p.to_string())
+    })?;
+
+    let name = name.trim().to_string();
+    (!name.is_empty() && name.chars().count() <= 60).then_some(name)
+}
+
+pub fn http_client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(5))
+        .user_agent(concat!("job-mailer/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default()
+}
+
+pub async fn lookup_company(client: &Client, domain: &str) -> Option<String> {
+    let resp = client.get(format!("https://{}/", domain)).send().await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    let html = resp.text().await.ok()?;
+    company_from_html(&html, domain)
+}
+
+/// Preenche `company` se estiver vazio; devolve `true` se encontrou alguma coisa.
+pub async fn fill_company(client: &Client, contact: &mut Contact) -> bool {
+    if contact.company.is_some() {
+        return false;
+    }
+    let Some(domain) = email_domain(&contact.email) else {
+        return false;
+    };
+    if is_free_mail(domain) {
+        return false;
+    }
+    contact.company = lookup_company(client, domain).await;
+    contact.company.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_company_from_og_site_name() {
+        let html = r#"<html><head><title>Careers</title>
+            <meta content="Acme &amp; Sons" property="og:site_name"></head></html>"#;
+        assert_eq!(company_from_html(html, "acme.com").as_deref(), Some("Acme & Sons"));
+    }
+
+    #[test]
+    fn test_company_from_title() {
+        let title = |t: &str| format!("<html><head><TITLE>\n  {}\n</TITLE></head></html>", t);
+        assert_eq!(
+            company_from_html(&title("Home | Globex Corporation"), "globex.com").as_deref(),
+            Some("Globex Corporation")
+        );
+        assert_eq!(
+            company_from_html(&title("Acme Corp - Software for everyone"), "acme-corp.io").as_deref(),
+            Some("Acme Corp")
+        );
+        assert_eq!(company_from_html("<p>sem titulo</p>", "x.com"), None);
+    }
+
+    #[test]
+    fn test_company_from_domain() {
+        assert_eq!(company_from_domain("jobs@acme-corp.com").as_deref(), Some("Acme Corp"));
+        assert_eq!(company_from_domain("rh@careers.globex.co.uk").as_deref(), Some("Globex"));
+        assert_eq!(company_from_domain("a@tech_hub.co.ao").as_deref(), Some("Tech Hub"));
+        assert_eq!(company_from_domain("ana@gmail.com"), None);
+        assert_eq!(company_from_domain(""), None);
+    }
+
+    #[test]
+    fn test_free_mail_domains() {
+        assert_eq!(email_domain("ana@Gmail.com"), Some("Gmail.com"));
+        assert!(is_free_mail("Gmail.com"));
+        assert!(!is_free_mail("acme.com"));
+        assert_eq!(email_domain("sem-dominio"), None);
+    }
+}
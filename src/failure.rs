@@ -0,0 +1,199 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Auth,
+    Connect,
+    Timeout,
+    RecipientRejected,
+    Policy,
+    Size,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorKind::Auth => "autenticação",
+            ErrorKind::Connect => "ligação",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::RecipientRejected => "destinatário rejeitado",
+            ErrorKind::Policy => "política/spam",
+            ErrorKind::Size => "tamanho",
+            ErrorKind::Other => "outro",
+        }
+    }
+}
+
+/// Falha de um envio: a classe do erro, o código SMTP (se houve) e a mensagem original.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SendError {
+    pub kind: ErrorKind,
+    pub code: Option<u16>,
+    pub message: String,
+}
+
+// logs antigos guardavam só o texto do erro
+impl<'de> Deserialize<'de> for SendError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Text(String),
+            Full {
+                kind: ErrorKind,
+                #[serde(default)]
+                code: Option<u16>,
+                message: String,
+            },
+        }
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Text(message) => SendError::classify(&message, None),
+            Repr::Full { kind, code, message } => SendError { kind, code, message },
+        })
+    }
+}
+
+// "permanent error (550): ..." / "<< 550 5.1.1 ..."
+fn smtp_code(text: &str) -> Option<u16> {
+    let from_error = text
+        .split_once('(')
+        .and_then(|(_, rest)| rest.get(..3))
+        .and_then(|c| c.parse().ok());
+    let from_reply = text
+        .strip_prefix("<< ")
+        .and_then(|rest| rest.get(..3))
+        .and_then(|c| c.parse().ok());
+    from_error.or(from_reply).filter(|c| (200..600).contains(c))
+}
+
+impl SendError {
+    /// Classifica pelo código SMTP e pelo texto do erro; `transcript` dá o
+    /// código da última resposta do servidor quando o erro não o traz.
+    pub fn classify(message: &str, transcript: Option<&[String]>) -> Self {
+        let last_reply = transcript
+            .and_then(|t| t.iter().rev().find(|l| l.starts_with("<< ")))
+            .map(String::as_str);
+        let code = smtp_code(message).or_else(|| last_reply.and_then(smtp_code));
+        let text = format!("{} {}", message, last_reply.unwrap_or("")).to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|w| text.contains(w));
+
+        let kind = if matches!(code, Some(530 | 534 | 535 | 454))
+            || has(&["authentication", "credentials", "smtp_user", "smtp_pass", "5.7.8"])
+        {
+            ErrorKind::Auth
+        } else if code == Some(552) || has(&["5.3.4", "too large", "size limit", "message size"]) {
+            ErrorKind::Size
+        } else if has(&[
+            "5.1.1", "5.1.0", "5.1.2", "5.1.3", "5.1.10", "user unknown", "does not exist", "no such user",
+            "mailbox unavailable", "recipient address rejected", "invalid recipient",
+        ]) || (matches!(code, Some(550 | 551 | 553)) && !has(&["5.7.", "spam", "policy", "blocked"]))
+        {
+            ErrorKind::RecipientRejected
+        } else if code == Some(554)
+            || has(&["5.7.", "spam", "policy", "blocked", "blacklist", "blocklist", "reputation", "dmarc", "spf"])
+        {
+            ErrorKind::Policy
+        } else if has(&["timed out", "timeout"]) {
+            ErrorKind::Timeout
+        } else if has(&["connection", "connect", "network", "dns", "resolve", "tls", "refused"]) {
+            ErrorKind::Connect
+        } else {
+            ErrorKind::Other
+        };
+
+        SendError { kind, code, message: message.to_string() }
+    }
+}
+
+impl SendError {
+    /// 450/451 é recusa temporária (greylisting): o mesmo envio passa daqui a uns minutos.
+    pub fn is_greylisted(&self) -> bool {
+        matches!(self.code, Some(450 | 451)) && !self.is_rate_limited()
+    }
+
+    /// 4xx por envios a mais (421 ou rate limit no texto): o servidor pede para abrandar.
+    pub fn is_rate_limited(&self) -> bool {
+        let text = self.message.to_lowercase();
+        let words = ["rate limit", "ratelimit", "rate-limit", "unusual rate", "too many", "throttl", "slow down", "4.7.28"];
+        matches!(self.code, Some(400..=499)) && (self.code == Some(421) || words.iter().any(|w| text.contains(w)))
+    }
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.kind.label(), self.message)
+    }
+}
+
+/// Falhas por classe, da mais frequente para a menos frequente.
+pub fn count_by_kind<'a>(errors: impl Iterator<Item = &'a SendError>) -> Vec<(ErrorKind, usize)> {
+    let mut counts = std::collections::BTreeMap::new();
+    for e in errors {
+        *counts.entry(e.kind).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(ErrorKind, usize)> = counts.into_iter().collect();
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(message: &str) -> ErrorKind {
+        SendError::classify(message, None).kind
+    }
+
+    #[test]
+    fn test_classify_by_code_and_text() {
+        assert_eq!(kind("permanent error (535): 5.7.8 Username and Password not accepted"), ErrorKind::Auth);
+        assert_eq!(kind("SMTP_USER not set in .env"), ErrorKind::Auth);
+        assert_eq!(kind("permanent error (550): 5.1.1 User unknown"), ErrorKind::RecipientRejected);
+        assert_eq!(kind("permanent error (550): 5.7.1 Message rejected as spam"), ErrorKind::Policy);
+        assert_eq!(kind("permanent error (552): 5.3.4 Message too big"), ErrorKind::Size);
+        assert_eq!(kind("network error: Connection refused (os error 111)"), ErrorKind::Connect);
+        assert_eq!(kind("network error: operation timed out"), ErrorKind::Timeout);
+        assert_eq!(kind("algo estranho"), ErrorKind::Other);
+        assert_eq!(SendError::classify("permanent error (550): x", None).code, Some(550));
+
+        let greylisted = SendError::classify("transient error (451): 4.7.1 Greylisted, try again later", None);
+        assert!(greylisted.is_greylisted());
+        assert!(!SendError::classify("permanent error (550): x", None).is_greylisted());
+
+        let throttled = SendError::classify("transient error (450): 4.2.1 Too many messages, slow down", None);
+        assert!(throttled.is_rate_limited() && !throttled.is_greylisted());
+        assert!(SendError::classify("transient error (421): 4.7.0 Try again later, closing connection", None).is_rate_limited());
+        assert!(!greylisted.is_rate_limited());
+        assert!(!SendError::classify("permanent error (550): too many recipients", None).is_rate_limited());
+    }
+
+    #[test]
+    fn test_classify_uses_transcript_reply() {
+        let transcript = vec![">> RCPT TO:<a@x.com>".to_string(), "<< 550 5.1.1 No such user".to_string()];
+        let err = SendError::classify("permanent error: rejected", Some(&transcript));
+        assert_eq!(err.kind, ErrorKind::RecipientRejected);
+        assert_eq!(err.code, Some(550));
+        assert_eq!(err.message, "permanent error: rejected");
+    }
+
+    #[test]
+    fn test_count_by_kind() {
+        let errors = ["5.1.1 user unknown", "timed out", "no such user"].map(|m| SendError::classify(m, None));
+        assert_eq!(
+            count_by_kind(errors.iter()),
+            vec![(ErrorKind::RecipientRejected, 2), (ErrorKind::Timeout, 1)]
+        );
+    }
+
+    #[test]
+    fn test_old_string_errors_still_load() {
+        let err: SendError = serde_json::from_str(r#""permanent error (535): bad credentials""#).unwrap();
+        assert_eq!(err.kind, ErrorKind::Auth);
+
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(json.contains(r#""kind":"auth""#));
+        assert_eq!(serde_json::from_str::<SendError>(&json).unwrap(), err);
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use console::style;
+use mail_parser::MessageParser;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    blocklist::{load_blocklist, save_blocklist},
+    contacts::{load_contacts, same_email, save_contacts},
+    dead::{load_dead, save_dead},
+    git_history, interview, leads, outbox, overrides, update_log, SentLog, CHECK, CROSS, NOTE, SANDBOX_DIR, SENT_DIR,
+};
+
+/// O que o `forget` apagou, para o relatório de confirmação.
+#[derive(Debug, Default)]
+pub struct Erasure {
+    pub records: usize,
+    // o endereço em cópia ou como outro To de mensagens a terceiros
+    pub mentions: usize,
+    pub contact: bool,
+    pub queued: usize,
+    pub dead: bool,
+    pub leads: usize,
+    pub files: Vec<String>,
+}
+
+/// Tira do log os envios para `email` e o endereço das mensagens a outros;
+/// devolve os ficheiros desses envios (cópias .eml, convites .ics).
+pub fn erase_records(log: &mut SentLog, email: &str, erasure: &mut Erasure) -> Vec<PathBuf> {
+    let mut files = vec![];
+    log.records.retain(|r| {
+        if !same_email(&r.email, email) {
+            return true;
+        }
+        files.extend(r.message.as_ref().and_then(|m| m.eml.clone()).map(PathBuf::from));
+        files.extend(r.interview.as_ref().map(|i| PathBuf::from(interview::ics_file_name(&r.email, i))));
+        erasure.records += 1;
+        false
+    });
+    for message in log.records.iter_mut().filter_map(|r| r.message.as_mut()) {
+        let before = message.other_to.len() + message.cc.len();
+        message.other_to.retain(|a| !same_email(a, email));
+        message.cc.retain(|a| !same_email(a, email));
+        erasure.mentions += before - message.other_to.len() - message.cc.len();
+    }
+    files
+}
+
+// o endereço é um dos destinatários (To, Cc, Bcc) da mensagem; aparecer no
+// texto não chega: ana@x.pt está dentro de joana@x.pt
+fn addressed_to(raw: &[u8], email: &str) -> bool {
+    let Some(message) = MessageParser::default().parse(raw) else {
+        return false;
+    };
+    let found = [message.to(), message.cc(), message.bcc()]
+        .into_iter()
+        .flatten()
+        .flat_map(|list| list.iter())
+        .filter_map(|addr| addr.address())
+        .any(|address| same_email(address, email));
+    found
+}
+
+/// Mensagens guardadas (sent/, outbox/ do sandbox) enviadas ao endereço.
+pub fn files_mentioning(dir: &Path, email: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|path| fs::read(path).is_ok_and(|raw| addressed_to(&raw, email)))
+        .collect()
+}
+
+fn report_lines(email: &str, erasure: &Erasure) -> Vec<String> {
+    let mut lines = vec![
+        format!("Pedido de apagamento de dados: {}", email),
+        format!("Data: {}", Local::now().format("%d/%m/%Y %H:%M")),
+        String::new(),
+        format!("- {} envios apagados do registo", erasure.records),
+        format!("- {} menções em cópia removidas de outras mensagens", erasure.mentions),
+        format!("- contacto {}", if erasure.contact { "apagado" } else { "não existia" }),
+        format!("- {} envios agendados ou por retomar cancelados", erasure.queued),
+    ];
+    if erasure.dead {
+        lines.push("- retirado da lista de endereços mortos".to_string());
+    }
+    if erasure.leads > 0 {
+        lines.push(format!("- {} leads apagados", erasure.leads));
+    }
+    lines.push(format!("- {} ficheiros apagados", erasure.files.len()));
+    lines.extend(erasure.files.iter().map(|f| format!("    {}", f)));
+    lines.push("- endereço na lista de bloqueios: não volta a ser contactado".to_string());
+    lines
+}
+
+/// `forget <email>`: apaga tudo o que está guardado sobre o endereço e
+/// bloqueia-o. Fica só o próprio endereço na lista de bloqueios.
+pub fn run_forget(email: &str, report: Option<String>) -> Result<()> {
+    let email = email.trim();
+    if !email.contains('@') || email.contains(char::is_whitespace) {
+        bail!("Invalid email address: {}", email);
+    }
+    say!("{} Vai ser apagado tudo o que está guardado sobre {}: envios, contacto, fila e cópias das mensagens.", NOTE, style(email).yellow());
+    if !crate::confirm("Apagar?", false)? {
+        say!("Cancelado!");
+        return Ok(());
+    }
+
+    let mut erasure = Erasure::default();
+    let mut files = update_log(&mut SentLog::default(), |log| Ok(erase_records(log, email, &mut erasure)))?;
+
+    let mut book = load_contacts();
+    let before = book.contacts.len();
+    book.contacts.retain(|c| !same_email(&c.email, email));
+    if book.contacts.len() != before {
+        erasure.contact = true;
+        save_contacts(&book)?;
+    }
+
+    erasure.queued = outbox::remove_recipient(email)?;
+
+    let mut dead = load_dead();
+    if dead.remove(email) {
+        erasure.dead = true;
+        save_dead(&dead)?;
+    }
+
+    erasure.leads = leads::update_leads(|leads| {
+        let before = leads.leads.len();
+        leads.leads.retain(|l| !l.email.as_deref().is_some_and(|e| same_email(e, email)));
+        Ok(before - leads.leads.len())
+    })?;
+
+    let mut blocklist = load_blocklist();
+    if blocklist.add(email) {
+        save_blocklist(&blocklist)?;
+    }
+
+    for dir in [SENT_DIR, SANDBOX_DIR] {
+        files.extend(files_mentioning(Path::new(&overrides::path(dir)), email));
+    }
+    files.sort();
+    files.dedup();
+    for file in files.into_iter().filter(|f| f.is_file()) {
+        fs::remove_file(&file).with_context(|| format!("Cannot remove {}", file.display()))?;
+        erasure.files.push(file.display().to_string());
+    }
+    // a mensagem do commit não leva o endereço
+    git_history::record("dados de um contacto apagados");
+
+    let lines = report_lines(email, &erasure);
+    for line in &lines {
+        say!("{}", line);
+    }
+    say!(
+        "{} O histórico git (`git_history`), os backups e a cópia remota do `sync` têm versões antigas: apague-as à parte.",
+        CROSS
+    );
+    if let Some(path) = report {
+        crate::write_atomic(&path, lines.join("\n") + "\n")?;
+        say!("{} Relatório em {}", CHECK, style(&path).green());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{interview::Interview, SentMessage, SentRecord};
+
+    fn record(email: &str, cc: &[&str]) -> SentRecord {
+        SentRecord {
+            email: email.to_string(),
+            sent_at: Local::now(),
+            success: true,
+            message: Some(SentMessage {
+                cc: cc.iter().map(|c| c.to_string()).collect(),
+                eml: Some(format!("sent/{}.eml", email)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_erase_records_and_mentions() {
+        let mut interviewed = record("RH@acme.com", &[]);
+        interviewed.interview = Some(Interview::new(Local::now(), 60, "rh@acme.com"));
+        let mut log = SentLog {
+            records: vec![interviewed, record("jobs@globex.com", &["rh@acme.com", "ana@globex.com"]), record("rh@acme.com", &[])],
+        };
+        let mut erasure = Erasure::default();
+        let files = erase_records(&mut log, "rh@acme.com", &mut erasure);
+
+        assert_eq!((erasure.records, erasure.mentions), (2, 1));
+        assert_eq!(log.records.len(), 1);
+        assert_eq!(log.records[0].message.as_ref().unwrap().cc, vec!["ana@globex.com"]);
+        assert_eq!(files.len(), 3);
+        assert!(files.iter().any(|f| f.to_string_lossy().ends_with(".ics")));
+    }
+
+    #[test]
+    fn test_files_mentioning() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("a.eml"), "To: RH@Acme.com\r\n\r\nOlá").unwrap();
+        fs::write(dir.path().join("b.eml"), "To: jobs@globex.com\r\n\r\nOlá").unwrap();
+
+        fs::write(dir.path().join("c.eml"), "To: jobs@globex.com\r\nCc: Maria <rh@acme.com>\r\n\r\nOlá").unwrap();
+        fs::write(dir.path().join("d.eml"), "To: rh@acme.com.br\r\n\r\nrh@acme.com no texto").unwrap();
+
+        let mut found = files_mentioning(dir.path(), "rh@acme.com");
+        found.sort();
+        assert_eq!(found, vec![dir.path().join("a.eml"), dir.path().join("c.eml")]);
+        assert!(files_mentioning(&dir.path().join("missing"), "rh@acme.com").is_empty());
+    }
+
+    #[test]
+    fn test_files_mentioning_compares_whole_addresses() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("ana.eml"), "To: ana@x.pt\r\n\r\nOlá").unwrap();
+        fs::write(dir.path().join("joana.eml"), "To: joana@x.pt\r\n\r\nOlá").unwrap();
+
+        assert_eq!(files_mentioning(dir.path(), "ana@x.pt"), vec![dir.path().join("ana.eml")]);
+        assert_eq!(files_mentioning(dir.path(), "joana@x.pt"), vec![dir.path().join("joana.eml")]);
+        assert!(files_mentioning(dir.path(), "ana@x.p").is_empty());
+    }
+}
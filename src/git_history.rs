@@ -0,0 +1,177 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use crate::{
+    blocklist::BLOCKLIST_FILE, contacts::CONTACTS_FILE, correlation::REPLIES_FILE, dead::DEAD_FILE, load_config, outbox::OUTBOX_FILE, CONFIG_FILE,
+    CROSS, LOG_FILE,
+};
+
+// o .env e o CV ficam de fora: segredos e binários não interessam no histórico
+const TRACKED_FILES: &[&str] = &[CONFIG_FILE, CONTACTS_FILE, LOG_FILE, OUTBOX_FILE, DEAD_FILE, BLOCKLIST_FILE, REPLIES_FILE];
+
+fn git(dir: &Path, args: &[&str]) -> Result<Output> {
+    Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("git not found in PATH")
+}
+
+fn git_ok(dir: &Path, args: &[&str]) -> Result<()> {
+    let out = git(dir, args)?;
+    if !out.status.success() {
+        bail!("git {} failed: {}", args[0], String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(())
+}
+
+fn ensure_repo(dir: &Path) -> Result<()> {
+    if !dir.join(".git").exists() {
+        git_ok(dir, &["init", "-q"])?;
+    }
+    Ok(())
+}
+
+/// Faz commit dos ficheiros de dados alterados; devolve `false` se não havia nada.
+pub fn commit_in(dir: &Path, message: &str) -> Result<bool> {
+    ensure_repo(dir)?;
+
+    let files: Vec<&str> = TRACKED_FILES
+        .iter()
+        .copied()
+        .filter(|f| dir.join(f).exists())
+        .collect();
+    if files.is_empty() {
+        return Ok(false);
+    }
+    let mut add = vec!["add", "--"];
+    add.extend(&files);
+    git_ok(dir, &add)?;
+
+    if git(dir, &["diff", "--cached", "--quiet"])?.status.success() {
+        return Ok(false);
+    }
+
+    // sem identidade git configurada, usa uma local em vez de falhar
+    let has_identity = git(dir, &["config", "user.email"])?.status.success();
+    let mut commit = vec![];
+    if !has_identity {
+        commit.extend(["-c", "user.name=job-mailer", "-c", "user.email=job-mailer@localhost"]);
+    }
+    commit.extend(["commit", "-q", "-m", message]);
+    git_ok(dir, &commit)?;
+    Ok(true)
+}
+
+/// Descreve a alteração do config.json (em `config`, que pode estar fora da
+/// pasta com `JOB_MAILER_CONFIG`) face ao último commit, se houver.
+pub fn config_change_message(dir: &Path, config: &Path) -> Option<String> {
+    if !git(dir, &["rev-parse", "--verify", "-q", "HEAD"]).ok()?.status.success() {
+        return None;
+    }
+    let out = git(dir, &["show", &format!("HEAD:{}", CONFIG_FILE)]).ok()?;
+    if !out.status.success() {
+        return Some("config adicionado".to_string());
+    }
+
+    let current = std::fs::read_to_string(config).ok()?;
+    if current.as_bytes() == out.stdout.as_slice() {
+        return None;
+    }
+    // o JSON tal como está: um `smtp.provider` só se expande no load_config
+    let before: Option<serde_json::Value> = serde_json::from_slice(&out.stdout).ok();
+    let after: Option<serde_json::Value> = serde_json::from_str(&current).ok();
+    let template_changed = match (before, after) {
+        (Some(b), Some(a)) => ["subject", "body"].iter().any(|k| b["template"][k] != a["template"][k]),
+        _ => false,
+    };
+    Some(if template_changed { "template editado" } else { "config editado" }.to_string())
+}
+
+fn enabled() -> bool {
+    load_config().map(|c| c.git_history).unwrap_or(false)
+}
+
+/// Regista uma alteração no repositório de histórico, se activo. Nunca falha a operação principal.
+pub fn record(message: &str) {
+    if !enabled() {
+        return;
+    }
+    if let Err(e) = commit_in(&crate::overrides::data_dir(), message) {
+        eprintln!("{} Histórico git: {}", CROSS, e);
+    }
+}
+
+/// Chamado no arranque para apanhar edições manuais ao config.json.
+pub fn record_config_changes() {
+    if !enabled() {
+        return;
+    }
+    let dir = &crate::overrides::data_dir();
+    if let Err(e) = ensure_repo(dir) {
+        eprintln!("{} Histórico git: {}", CROSS, e);
+        return;
+    }
+    let config = crate::overrides::path(CONFIG_FILE);
+    let message = config_change_message(dir, Path::new(&config)).unwrap_or_else(|| "estado inicial".to_string());
+    if let Err(e) = commit_in(dir, &message) {
+        eprintln!("{} Histórico git: {}", CROSS, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn config_json(subject: &str, port: u16) -> String {
+        serde_json::json!({
+            "profile": {
+                "name": "Ana", "email": "ana@example.com", "phone": "1", "title": "Dev",
+                "summary": "s", "skills": [], "experience_years": 1,
+                "linkedin": null, "github": null
+            },
+            "smtp": { "host": "h", "port": port },
+            "template": { "subject": subject, "body": "b" }
+        })
+        .to_string()
+    }
+
+    fn last_message(dir: &Path) -> String {
+        let out = git(dir, &["log", "-1", "--format=%s"]).unwrap();
+        String::from_utf8_lossy(&out.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn test_commit_only_when_changed() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(LOG_FILE), "{\"records\":[]}").unwrap();
+        fs::write(dir.path().join(".env"), "SMTP_PASS=x").unwrap();
+
+        assert!(commit_in(dir.path(), "enviados 0 emails").unwrap());
+        assert!(!commit_in(dir.path(), "nada mudou").unwrap());
+        assert_eq!(last_message(dir.path()), "enviados 0 emails");
+
+        let tracked = git(dir.path(), &["ls-files"]).unwrap();
+        assert_eq!(String::from_utf8_lossy(&tracked.stdout).trim(), LOG_FILE);
+    }
+
+    #[test]
+    fn test_config_change_message() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(&path, config_json("Candidatura", 587)).unwrap();
+        commit_in(dir.path(), "estado inicial").unwrap();
+        assert_eq!(config_change_message(dir.path(), &path), None);
+
+        fs::write(&path, config_json("Candidatura", 465)).unwrap();
+        assert_eq!(config_change_message(dir.path(), &path).as_deref(), Some("config editado"));
+
+        fs::write(&path, config_json("Nova candidatura", 587)).unwrap();
+        assert_eq!(config_change_message(dir.path(), &path).as_deref(), Some("template editado"));
+    }
+}
@@ -0,0 +1,119 @@
+use lettre::message::header::{HeaderName, HeaderValue, Headers};
+use serde::{Deserialize, Serialize};
+
+/// Como vão os cabeçalhos com acentos (RFC 2047). Os clientes modernos lêem
+/// todos; `q` fica legível no código-fonte do email, `utf8` só serve quando o
+/// servidor aceita SMTPUTF8.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderEncoding {
+    // =?utf-8?b?...?= só nas palavras com acentos (o que o lettre faz)
+    #[default]
+    Base64,
+    // =?utf-8?q?Candidatura_=E2=80=94_Jo=C3=A3o?=
+    Q,
+    // UTF-8 sem codificação (RFC 6532)
+    Utf8,
+}
+
+// o que pode ir tal e qual numa encoded-word Q (RFC 2047, 5.3)
+fn q_plain(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'!' | b'*' | b'+' | b'-' | b'/')
+}
+
+fn q_encode(value: &str, first_line: usize) -> String {
+    const OPEN: &str = "=?utf-8?q?";
+    const MAX_WORD: usize = 75;
+    let mut words = vec![];
+    let mut word = String::new();
+    for c in value.chars() {
+        let mut buf = [0u8; 4];
+        let piece: String = c
+            .encode_utf8(&mut buf)
+            .bytes()
+            .map(|b| match b {
+                b' ' => "_".to_string(),
+                b if q_plain(b) => (b as char).to_string(),
+                b => format!("={:02X}", b),
+            })
+            .collect();
+        // um carácter nunca fica partido entre duas encoded-words
+        let limit = if words.is_empty() { MAX_WORD.min(76 - first_line) } else { MAX_WORD } - OPEN.len() - 2;
+        if word.len() + piece.len() > limit && !word.is_empty() {
+            words.push(format!("{}{}?=", OPEN, word));
+            word.clear();
+        }
+        word.push_str(&piece);
+    }
+    words.push(format!("{}{}?=", OPEN, word));
+    words.join("\r\n ")
+}
+
+/// O valor do cabeçalho tal como sai no email, já dobrado em linhas.
+pub fn encode(name: &str, value: &str, encoding: HeaderEncoding) -> String {
+    let value = value.replace(['\r', '\n'], " ");
+    match encoding {
+        HeaderEncoding::Q if !value.is_ascii() => q_encode(&value, name.len() + 2),
+        HeaderEncoding::Utf8 => value,
+        // sem acentos não há nada a codificar, só a dobrar as linhas
+        _ => {
+            let mut headers = Headers::new();
+            headers.insert_raw(HeaderValue::new(HeaderName::new_from_ascii(name.to_string()).unwrap(), value));
+            let line = headers.to_string();
+            line.trim_end().strip_prefix(&format!("{}: ", name)).unwrap_or_default().to_string()
+        }
+    }
+}
+
+/// O cabeçalho já codificado, para o lettre não voltar a codificar.
+pub fn header(name: &'static str, value: &str, encoding: HeaderEncoding) -> HeaderValue {
+    let encoded = encode(name, value, encoding);
+    HeaderValue::dangerous_new_pre_encoded(HeaderName::new_from_ascii_str(name), value.to_string(), encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // descodificador mínimo de encoded-words, só para confirmar a ida e volta
+    fn decode(encoded: &str) -> String {
+        let mut bytes = vec![];
+        for word in encoded.split("\r\n ") {
+            let inner = word.strip_prefix("=?utf-8?q?").and_then(|w| w.strip_suffix("?=")).unwrap();
+            let mut chars = inner.bytes();
+            while let Some(b) = chars.next() {
+                match b {
+                    b'_' => bytes.push(b' '),
+                    b'=' => {
+                        let hex = [chars.next().unwrap(), chars.next().unwrap()];
+                        bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).unwrap(), 16).unwrap());
+                    }
+                    b => bytes.push(b),
+                }
+            }
+        }
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_subject_encodings() {
+        let subject = "Candidatura — João";
+        assert_eq!(encode("Subject", subject, HeaderEncoding::Q), "=?utf-8?q?Candidatura_=E2=80=94_Jo=C3=A3o?=");
+        assert_eq!(encode("Subject", subject, HeaderEncoding::Base64), "Candidatura =?utf-8?b?4oCUIEpvw6Nv?=");
+        assert_eq!(encode("Subject", subject, HeaderEncoding::Utf8), subject);
+        // sem acentos não há nada a codificar
+        assert_eq!(encode("Subject", "Backend dev", HeaderEncoding::Q), "Backend dev");
+        assert_eq!(encode("Subject", "a\r\nBcc: x@y.com", HeaderEncoding::Utf8), "a  Bcc: x@y.com");
+    }
+
+    #[test]
+    fn test_long_q_subject_is_folded() {
+        let subject = "Candidatura espontânea — Programador Sénior de Sistemas Distribuídos — João Conceição";
+        let encoded = encode("Subject", subject, HeaderEncoding::Q);
+        let lines: Vec<&str> = encoded.split("\r\n ").collect();
+        assert!(lines.len() > 1);
+        assert!(lines[0].len() + "Subject: ".len() <= 76);
+        assert!(lines.iter().all(|l| l.len() <= 75));
+        assert_eq!(decode(&encoded), subject);
+    }
+}
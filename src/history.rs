@@ -0,0 +1,172 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use console::style;
+
+use crate::{
+    contacts::{load_contacts, same_email}, dead::load_dead, load_log, outbox::load_outbox, ApplicationStatus,
+    SentLog, CHECK, CLOCK, CROSS, MAIL, NOTE,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Sent { sandbox: bool },
+    Failed(String),
+    Cancelled,
+    Status(ApplicationStatus),
+    Interview { location: Option<String> },
+    Note(String),
+    Dead(String),
+    Queued { id: u64 },
+    Opened,
+}
+
+/// Tudo o que aconteceu com um endereço, por ordem cronológica.
+pub fn recipient_events(log: &SentLog, email: &str) -> Vec<(DateTime<Local>, Event)> {
+    let mut events = vec![];
+    for r in log.records.iter().filter(|r| r.involves(email)) {
+        let event = if r.cancelled {
+            Event::Cancelled
+        } else if r.success {
+            Event::Sent { sandbox: r.sandbox }
+        } else {
+            Event::Failed(r.error.as_ref().map(|e| e.to_string()).unwrap_or_default())
+        };
+        events.push((r.sent_at, event));
+        for change in &r.status_history {
+            events.push((change.at, Event::Status(change.status)));
+        }
+        for at in &r.opens {
+            events.push((*at, Event::Opened));
+        }
+        if let Some(i) = &r.interview {
+            events.push((i.at, Event::Interview { location: i.location.clone() }));
+        }
+        // as notas não têm data: ficam junto do envio a que pertencem
+        if let Some(notes) = &r.notes {
+            events.push((r.sent_at, Event::Note(notes.clone())));
+        }
+    }
+    events.sort_by_key(|(at, _)| *at);
+    events
+}
+
+fn describe(event: &Event) -> String {
+    match event {
+        Event::Sent { sandbox: false } => format!("{} enviado", CHECK),
+        Event::Sent { sandbox: true } => format!("{} enviado (sandbox)", CHECK),
+        Event::Failed(error) => format!("{} falhou: {}", CROSS, style(error).red()),
+        Event::Cancelled => format!("{} envio desfeito", CROSS),
+        Event::Status(status) => format!("{} fase → {}", MAIL, style(status.label()).cyan()),
+        Event::Interview { location: Some(l) } => format!("{} entrevista ({})", CLOCK, l),
+        Event::Interview { location: None } => format!("{} entrevista", CLOCK),
+        Event::Note(notes) => format!("{} {}", NOTE, notes.replace('\n', " / ")),
+        Event::Dead(reason) => format!("{} endereço morto: {}", CROSS, style(reason).red()),
+        Event::Queued { id } => format!("{} agendado (fila #{})", CLOCK, id),
+        Event::Opened => format!("{} aberto (pixel)", MAIL),
+    }
+}
+
+pub fn show_history(email: &str) -> Result<()> {
+    if !email.contains('@') {
+        bail!("Invalid email: {}", email);
+    }
+    let mut events = recipient_events(&load_log(), email);
+    if let Some(dead) = load_dead().addresses.iter().find(|a| same_email(&a.email, email)) {
+        events.push((dead.added_at, Event::Dead(dead.reason.clone())));
+    }
+    for item in load_outbox().items.iter().filter(|i| same_email(&i.to, email)) {
+        events.push((item.send_at.with_timezone(&Local), Event::Queued { id: item.id }));
+    }
+    events.sort_by_key(|(at, _)| *at);
+
+    println!("{} Histórico de {}", MAIL, style(email).yellow());
+    if let Some(contact) = load_contacts().find(email) {
+        let details: Vec<&str> = [contact.name.as_deref(), contact.company.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !details.is_empty() {
+            println!("   {}", details.join(" - "));
+        }
+    }
+    println!("{}", style("─".repeat(60)).dim());
+    if events.is_empty() {
+        println!("  Nenhum registo para este endereço");
+    }
+    let now = Local::now();
+    for (at, event) in &events {
+        let when = at.format("%d/%m/%Y %H:%M").to_string();
+        let when = if *at > now { style(when).cyan() } else { style(when).dim() };
+        println!("  {} {}", when, describe(event));
+    }
+    println!("{}", style("─".repeat(60)).dim());
+
+    let count = |f: fn(&Event) -> bool| events.iter().filter(|(_, e)| f(e)).count();
+    println!(
+        "  {} envios, {} falhados",
+        count(|e| matches!(e, Event::Sent { .. })),
+        count(|e| matches!(e, Event::Failed(_)))
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SentRecord, StatusChange};
+
+    fn at(text: &str) -> DateTime<Local> {
+        crate::interview::parse_interview_time(text).unwrap()
+    }
+
+    #[test]
+    fn test_recipient_events_in_order() {
+        let log = SentLog {
+            records: vec![
+                SentRecord {
+                    email: "rh@acme.com".to_string(),
+                    sent_at: at("2024-07-01 09:00"),
+                    error: Some(crate::failure::SendError::classify("timed out", None)),
+                    ..Default::default()
+                },
+                SentRecord {
+                    email: "outro@globex.com".to_string(),
+                    sent_at: at("2024-07-01 09:30"),
+                    success: true,
+                    ..Default::default()
+                },
+                SentRecord {
+                    email: "RH@acme.com".to_string(),
+                    sent_at: at("2024-07-02 10:00"),
+                    success: true,
+                    status: ApplicationStatus::Replied,
+                    status_history: vec![StatusChange {
+                        at: at("2024-07-05 15:00"),
+                        status: ApplicationStatus::Replied,
+                    }],
+                    notes: Some("pediu portfolio".to_string()),
+                    opens: vec![at("2024-07-03 08:00")],
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let events: Vec<Event> = recipient_events(&log, "rh@acme.com").into_iter().map(|(_, e)| e).collect();
+        assert_eq!(events.len(), 5);
+        assert!(matches!(events[0], Event::Failed(_)));
+        assert_eq!(events[1], Event::Sent { sandbox: false });
+        assert_eq!(events[2], Event::Note("pediu portfolio".to_string()));
+        assert_eq!(events[3], Event::Opened);
+        assert_eq!(events[4], Event::Status(ApplicationStatus::Replied));
+    }
+
+    #[test]
+    fn test_set_status_keeps_history() {
+        let mut record = SentRecord::default();
+        record.set_status(ApplicationStatus::Replied);
+        record.set_status(ApplicationStatus::Interview);
+        assert_eq!(record.status, ApplicationStatus::Interview);
+        let statuses: Vec<ApplicationStatus> = record.status_history.iter().map(|c| c.status).collect();
+        assert_eq!(statuses, vec![ApplicationStatus::Replied, ApplicationStatus::Interview]);
+    }
+}
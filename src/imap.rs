@@ -0,0 +1,323 @@
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use crate::{
+    account_var,
+    oauth::OAuthConfig,
+    secrets::{Account, SecretProvider},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImapConfig {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub security: ImapSecurity,
+    // pasta dos enviados: "Sent" na maioria, "[Gmail]/Sent Mail" no Gmail
+    #[serde(default = "default_folder")]
+    pub folder: String,
+    // onde chegam as respostas, para o `replies scan`
+    #[serde(default = "default_inbox")]
+    pub inbox: String,
+}
+
+fn default_port() -> u16 {
+    993
+}
+
+fn default_folder() -> String {
+    "Sent".to_string()
+}
+
+fn default_inbox() -> String {
+    "INBOX".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImapSecurity {
+    #[default]
+    Tls,
+    // sem encriptação, só para servidores locais e testes
+    None,
+}
+
+// normalmente a conta IMAP é a mesma do SMTP; as do perfil activo primeiro
+async fn imap_creds(profile: Option<&str>, secrets: &SecretProvider, oauth: Option<&OAuthConfig>) -> Result<Account> {
+    let user = account_var(&["IMAP_USER", "SMTP_USER"], profile)
+        .context("IMAP_USER (or SMTP_USER) not set in .env")?;
+    if let Some(oauth) = oauth {
+        let token = crate::oauth::access_token(oauth, &user).await?;
+        return Ok(Account::OAuth(user, token));
+    }
+    let pass = match account_var(&["IMAP_PASS", "SMTP_PASS"], profile) {
+        Some(pass) => pass,
+        None => crate::secrets::password(secrets, &user).context("IMAP_PASS (or SMTP_PASS) not set in .env")?,
+    };
+    Ok(Account::Password(user, pass))
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn append_command(tag: &str, folder: &str, size: usize) -> String {
+    format!("{} APPEND {} (\\Seen) {{{}}}\r\n", tag, quote(folder), size)
+}
+
+// "* 3 FETCH (UID 17 BODY[] {2048}" → 2048
+fn literal_size(line: &str) -> Option<usize> {
+    line.strip_suffix('}')?.rsplit_once('{')?.1.parse().ok()
+}
+
+// "* SEARCH 4 8 15" → [4, 8, 15]
+fn search_uids(line: &str) -> Vec<u32> {
+    line.strip_prefix("* SEARCH")
+        .map(|uids| uids.split_whitespace().filter_map(|u| u.parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+// o IMAP só aceita datas em inglês: 01-Oct-2026
+fn search_command(since: NaiveDate) -> String {
+    format!("UID SEARCH SINCE {}", since.format("%d-%b-%Y"))
+}
+
+/// O que fazer depois do login.
+enum Job<'a> {
+    Append { folder: &'a str, raw: &'a [u8] },
+    // as mensagens da pasta recebidas desde o dia, sem as marcar como lidas
+    Fetch { folder: &'a str, since: NaiveDate },
+}
+
+struct Session<S> {
+    stream: BufReader<S>,
+    next_tag: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    fn new(stream: S) -> Self {
+        Self { stream: BufReader::new(stream), next_tag: 0 }
+    }
+
+    fn tag(&mut self) -> String {
+        self.next_tag += 1;
+        format!("a{}", self.next_tag)
+    }
+
+    async fn read_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            bail!("IMAP server closed the connection");
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    // lê até à resposta com a tag; as linhas "*" pelo meio são ignoradas
+    async fn tagged_reply(&mut self, tag: &str, what: &str) -> Result<()> {
+        loop {
+            let line = self.read_line().await?;
+            if let Some(status) = line.strip_prefix(tag).map(str::trim_start) {
+                if status.starts_with("OK") {
+                    return Ok(());
+                }
+                bail!("IMAP {} failed: {}", what, status);
+            }
+        }
+    }
+
+    async fn command(&mut self, cmd: &str, what: &str) -> Result<()> {
+        let tag = self.tag();
+        self.stream.write_all(format!("{} {}\r\n", tag, cmd).as_bytes()).await?;
+        self.stream.flush().await?;
+        self.tagged_reply(&tag, what).await
+    }
+
+    async fn append(&mut self, folder: &str, raw: &[u8]) -> Result<()> {
+        let tag = self.tag();
+        self.stream.write_all(append_command(&tag, folder, raw.len()).as_bytes()).await?;
+        self.stream.flush().await?;
+        // o servidor tem de pedir o literal com "+" antes de recebermos a mensagem
+        loop {
+            let line = self.read_line().await?;
+            if line.starts_with('+') {
+                break;
+            }
+            if let Some(status) = line.strip_prefix(&tag) {
+                bail!("IMAP APPEND to {} failed: {}", folder, status.trim_start());
+            }
+        }
+        self.stream.write_all(raw).await?;
+        self.stream.write_all(b"\r\n").await?;
+        self.stream.flush().await?;
+        self.tagged_reply(&tag, "APPEND").await
+    }
+
+    async fn fetch_since(&mut self, folder: &str, since: NaiveDate) -> Result<Vec<Vec<u8>>> {
+        // EXAMINE em vez de SELECT: só leitura, nada fica marcado
+        self.command(&format!("EXAMINE {}", quote(folder)), "EXAMINE").await?;
+        let tag = self.tag();
+        self.stream.write_all(format!("{} {}\r\n", tag, search_command(since)).as_bytes()).await?;
+        self.stream.flush().await?;
+        let mut uids = vec![];
+        loop {
+            let line = self.read_line().await?;
+            if let Some(status) = line.strip_prefix(&tag).map(str::trim_start) {
+                if !status.starts_with("OK") {
+                    bail!("IMAP SEARCH in {} failed: {}", folder, status);
+                }
+                break;
+            }
+            uids.extend(search_uids(&line));
+        }
+        if uids.is_empty() {
+            return Ok(vec![]);
+        }
+        let set: Vec<String> = uids.iter().map(u32::to_string).collect();
+        let tag = self.tag();
+        self.stream.write_all(format!("{} UID FETCH {} BODY.PEEK[]\r\n", tag, set.join(",")).as_bytes()).await?;
+        self.stream.flush().await?;
+        let mut messages = vec![];
+        loop {
+            let line = self.read_line().await?;
+            if let Some(size) = literal_size(&line) {
+                let mut raw = vec![0; size];
+                self.stream.read_exact(&mut raw).await?;
+                messages.push(raw);
+            } else if let Some(status) = line.strip_prefix(&tag).map(str::trim_start) {
+                if !status.starts_with("OK") {
+                    bail!("IMAP FETCH in {} failed: {}", folder, status);
+                }
+                return Ok(messages);
+            }
+        }
+    }
+}
+
+async fn run<S: AsyncRead + AsyncWrite + Unpin>(stream: S, account: Account, job: Job<'_>) -> Result<Vec<Vec<u8>>> {
+    let mut session = Session::new(stream);
+    let greeting = session.read_line().await?;
+    if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+        bail!("Unexpected IMAP greeting: {}", greeting);
+    }
+    if greeting.starts_with("* OK") {
+        match account {
+            Account::Password(user, pass) => {
+                session.command(&format!("LOGIN {} {}", quote(&user), quote(&pass)), "LOGIN").await?
+            }
+            Account::OAuth(user, token) => {
+                let response = STANDARD.encode(crate::oauth::xoauth2(&user, &token));
+                session.command(&format!("AUTHENTICATE XOAUTH2 {}", response), "AUTHENTICATE").await?
+            }
+        }
+    }
+    let messages = match job {
+        Job::Append { folder, raw } => {
+            session.append(folder, raw).await?;
+            vec![]
+        }
+        Job::Fetch { folder, since } => session.fetch_since(folder, since).await?,
+    };
+    // o trabalho já está feito; um LOGOUT falhado não interessa
+    let _ = session.command("LOGOUT", "LOGOUT").await;
+    Ok(messages)
+}
+
+async fn connect(
+    config: &ImapConfig,
+    job: Job<'_>,
+    profile: Option<&str>,
+    secrets: &SecretProvider,
+    oauth: Option<&OAuthConfig>,
+    limit: Duration,
+) -> Result<Vec<Vec<u8>>> {
+    let creds = imap_creds(profile, secrets, oauth).await?;
+    let work = async {
+        let tcp = TcpStream::connect((config.host.as_str(), config.port))
+            .await
+            .with_context(|| format!("Cannot connect to IMAP {}:{}", config.host, config.port))?;
+        match config.security {
+            ImapSecurity::None => run(tcp, creds, job).await,
+            ImapSecurity::Tls => {
+                let connector = tokio_native_tls::native_tls::TlsConnector::new()?;
+                let tls = tokio_native_tls::TlsConnector::from(connector)
+                    .connect(&config.host, tcp)
+                    .await
+                    .context("IMAP TLS handshake failed")?;
+                run(tls, creds, job).await
+            }
+        }
+    };
+    tokio::time::timeout(limit, work).await.context("IMAP timed out")?
+}
+
+/// Guarda uma mensagem já enviada na pasta dos enviados da conta.
+pub async fn append_to_sent(
+    config: &ImapConfig,
+    raw: &[u8],
+    profile: Option<&str>,
+    secrets: &SecretProvider,
+    oauth: Option<&OAuthConfig>,
+) -> Result<()> {
+    let job = Job::Append { folder: &config.folder, raw };
+    connect(config, job, profile, secrets, oauth, Duration::from_secs(30)).await.map(drop)
+}
+
+/// As mensagens recebidas no `inbox` desde `since`, inteiras.
+pub async fn fetch_inbox(
+    config: &ImapConfig,
+    since: NaiveDate,
+    profile: Option<&str>,
+    secrets: &SecretProvider,
+    oauth: Option<&OAuthConfig>,
+) -> Result<Vec<Vec<u8>>> {
+    let job = Job::Fetch { folder: &config.inbox, since };
+    // uma caixa cheia demora mais a descarregar do que um APPEND
+    connect(config, job, profile, secrets, oauth, Duration::from_secs(120)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("joao@example.com"), "\"joao@example.com\"");
+        assert_eq!(quote(r#"pa"ss\word"#), r#""pa\"ss\\word""#);
+    }
+
+    #[test]
+    fn test_append_command() {
+        assert_eq!(
+            append_command("a2", "[Gmail]/Sent Mail", 1234),
+            "a2 APPEND \"[Gmail]/Sent Mail\" (\\Seen) {1234}\r\n"
+        );
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config: ImapConfig = serde_json::from_str(r#"{"host":"imap.gmail.com"}"#).unwrap();
+        assert_eq!(config.port, 993);
+        assert_eq!(config.security, ImapSecurity::Tls);
+        assert_eq!(config.folder, "Sent");
+        assert_eq!(config.inbox, "INBOX");
+    }
+
+    #[test]
+    fn test_search_and_fetch_lines() {
+        let since = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        assert_eq!(search_command(since), "UID SEARCH SINCE 01-Oct-2026");
+        assert_eq!(search_uids("* SEARCH 4 8 15"), vec![4, 8, 15]);
+        assert!(search_uids("* SEARCH").is_empty());
+        assert!(search_uids("* 3 EXISTS").is_empty());
+        assert_eq!(literal_size("* 3 FETCH (UID 17 BODY[] {2048}"), Some(2048));
+        assert_eq!(literal_size("a3 OK FETCH completed"), None);
+    }
+}
@@ -0,0 +1,291 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use console::style;
+use lettre::message::{header::ContentType, Attachment, MultiPart, SinglePart};
+use lettre::Message;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::{
+    deliver, git_history, update_log, ApplicationStatus, Config, SentLog, CHECK, CLOCK,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Interview {
+    pub at: DateTime<Local>,
+    pub duration_minutes: u32,
+    pub uid: String,
+    #[serde(default)]
+    pub interviewer: Option<String>,
+    // morada ou link da videochamada
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
+impl Interview {
+    pub fn new(at: DateTime<Local>, duration_minutes: u32, email: &str) -> Self {
+        let domain = email.split('@').nth(1).unwrap_or("localhost");
+        Self {
+            at,
+            duration_minutes,
+            uid: format!("{}-{}@job-mailer", at.timestamp(), domain),
+            interviewer: None,
+            location: None,
+        }
+    }
+
+    pub fn ends_at(&self) -> DateTime<Local> {
+        self.at + ChronoDuration::minutes(self.duration_minutes as i64)
+    }
+}
+
+pub fn parse_interview_time(input: &str) -> Result<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(input.trim(), "%Y-%m-%d %H:%M")
+        .with_context(|| format!("Invalid time '{}' (expected \"YYYY-MM-DD HH:MM\")", input))?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .context("Time does not exist in the local timezone (DST gap)")
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// RFC 5545: linhas com mais de 75 octetos dobram com CRLF + espaço
+fn fold_line(line: &str) -> String {
+    let mut out = String::new();
+    let mut len = 0;
+    for ch in line.chars() {
+        if len + ch.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            len = 1;
+        }
+        out.push(ch);
+        len += ch.len_utf8();
+    }
+    out
+}
+
+fn ics_time(dt: DateTime<Local>) -> String {
+    dt.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+pub fn build_ics(config: &Config, recruiter: &str, interview: &Interview) -> String {
+    let p = &config.profile;
+    let company = recruiter.split('@').nth(1).unwrap_or(recruiter);
+    let mut description = format!("Candidatura {} - {}", p.title, p.name);
+    if let Some(who) = &interview.interviewer {
+        description.push_str(&format!("\nEntrevistador: {}", who));
+    }
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//job-mailer//PT".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", interview.uid),
+        format!("DTSTAMP:{}", ics_time(Local::now())),
+        format!("DTSTART:{}", ics_time(interview.at)),
+        format!("DTEND:{}", ics_time(interview.ends_at())),
+        format!("SUMMARY:{}", escape_text(&format!("Entrevista {} - {}", company, p.name))),
+        format!("DESCRIPTION:{}", escape_text(&description)),
+        format!("ORGANIZER;CN={}:mailto:{}", escape_text(&p.name), p.email),
+        format!("ATTENDEE:mailto:{}", recruiter),
+    ];
+    if let Some(location) = &interview.location {
+        lines.push(format!("LOCATION:{}", escape_text(location)));
+        if location.starts_with("http://") || location.starts_with("https://") {
+            lines.push(format!("URL:{}", location));
+        }
+    }
+    lines.push("END:VEVENT".to_string());
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|l| fold_line(l) + "\r\n").collect()
+}
+
+pub fn set_status(email: &str, status: ApplicationStatus) -> Result<()> {
+    update_log(&mut SentLog::default(), |log| {
+        let record = log
+            .latest_application_mut(email)
+            .with_context(|| format!("No successful send to {} in the log", email))?;
+        record.set_status(status);
+        Ok(())
+    })?;
+    git_history::record(&format!("{}: {}", email, status.label()));
+
+    say!("{} {} → {}", CHECK, style(email).yellow(), style(status.label()).cyan());
+    Ok(())
+}
+
+/// `snooze`: esconde a candidatura dos follow-ups e do painel até `until`;
+/// um dia que já passou tira o snooze.
+pub fn snooze(email: &str, until: NaiveDate) -> Result<()> {
+    let active = until > Local::now().date_naive();
+    update_log(&mut SentLog::default(), |log| {
+        let record = log
+            .latest_application_mut(email)
+            .with_context(|| format!("No successful send to {} in the log", email))?;
+        record.snoozed_until = active.then_some(until);
+        Ok(())
+    })?;
+    if active {
+        git_history::record(&format!("{}: snooze até {}", email, until));
+        say!("{} {} fica de lado até {}", CLOCK, style(email).yellow(), style(until.format("%d/%m/%Y")).cyan());
+    } else {
+        git_history::record(&format!("{}: sem snooze", email));
+        say!("{} {} volta aos follow-ups", CHECK, style(email).yellow());
+    }
+    Ok(())
+}
+
+pub fn ics_file_name(email: &str, interview: &Interview) -> String {
+    let domain = email.split('@').nth(1).unwrap_or("empresa");
+    format!("entrevista-{}-{}.ics", interview.at.format("%Y%m%d-%H%M"), domain)
+}
+
+async fn send_confirmation(config: &Config, to: &str, interview: &Interview, ics: &str) -> Result<()> {
+    let place = match &interview.location {
+        Some(location) => format!(" ({})", location),
+        None => String::new(),
+    };
+    let body = format!(
+        "Olá,\n\nConfirmo a entrevista no dia {} às {}{}. Segue em anexo o convite para o calendário.\n\nAtenciosamente,\n{}",
+        interview.at.format("%d/%m/%Y"),
+        interview.at.format("%H:%M"),
+        place,
+        config.profile.name
+    );
+    let invite = Attachment::new("entrevista.ics".into()).body(
+        ics.to_string(),
+        ContentType::parse("text/calendar; charset=utf-8; method=PUBLISH")?,
+    );
+
+    let mut builder = Message::builder().from(config.profile.sender()?).to(to.parse()?);
+    if let Some(reply_to) = &config.profile.reply_to {
+        builder = builder.reply_to(reply_to.parse()?);
+    }
+    let msg = builder
+        .subject(format!("Confirmação de entrevista - {}", config.profile.name))
+        .multipart(MultiPart::mixed().singlepart(SinglePart::plain(body)).singlepart(invite))?;
+    deliver(config, msg).await
+}
+
+pub async fn schedule_interview(
+    config: &Config,
+    email: &str,
+    at: &str,
+    duration: u32,
+    interviewer: Option<String>,
+    location: Option<String>,
+    send: bool,
+) -> Result<()> {
+    let mut interview = Interview::new(parse_interview_time(at)?, duration, email);
+    interview.interviewer = interviewer;
+    interview.location = location;
+    let ics = build_ics(config, email, &interview);
+
+    update_log(&mut SentLog::default(), |log| {
+        let record = log
+            .latest_application_mut(email)
+            .with_context(|| format!("No successful send to {} in the log", email))?;
+        record.set_status(ApplicationStatus::Interview);
+        record.interview = Some(interview.clone());
+        Ok(())
+    })?;
+
+    let file = ics_file_name(email, &interview);
+    fs::write(&file, &ics)?;
+    say!(
+        "{} Entrevista com {} a {} → {}",
+        CLOCK,
+        style(email).yellow(),
+        style(interview.at.format("%d/%m/%Y %H:%M")).cyan(),
+        style(&file).green()
+    );
+
+    if send {
+        send_confirmation(config, email, &interview, &ics).await?;
+        say!("{} Confirmação enviada para {}", CHECK, style(email).green());
+    }
+    git_history::record(&format!("entrevista marcada com {}", email));
+    Ok(())
+}
+
+/// Entrevistas ainda por acontecer, da mais próxima para a mais distante.
+/// Candidaturas que já mudaram de fase (proposta, rejeitado) ficam de fora.
+pub fn upcoming_interviews(log: &SentLog, now: DateTime<Local>) -> Vec<(&str, &Interview)> {
+    let mut upcoming: Vec<(&str, &Interview)> = log
+        .records
+        .iter()
+        .filter(|r| r.status == ApplicationStatus::Interview)
+        .filter_map(|r| r.interview.as_ref().map(|i| (r.email.as_str(), i)))
+        .filter(|(_, i)| i.ends_at() >= now)
+        .collect();
+    upcoming.sort_by_key(|(_, i)| i.at);
+    upcoming
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_line() {
+        let long = format!("DESCRIPTION:{}", "á".repeat(50));
+        let folded = fold_line(&long);
+        for line in folded.split("\r\n") {
+            assert!(line.len() <= 75);
+        }
+        assert_eq!(folded.replace("\r\n ", ""), long);
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("a, b; c\\d\ne"), "a\\, b\\; c\\\\d\\ne");
+    }
+
+    #[test]
+    fn test_interview_times() {
+        let at = parse_interview_time("2024-07-10 14:00").unwrap();
+        let interview = Interview::new(at, 45, "rh@acme.com");
+        assert_eq!(interview.ends_at() - interview.at, ChronoDuration::minutes(45));
+        assert!(interview.uid.ends_with("-acme.com@job-mailer"));
+        assert!(parse_interview_time("10/07 14h").is_err());
+    }
+
+    #[test]
+    fn test_upcoming_interviews() {
+        let now = parse_interview_time("2024-07-10 12:00").unwrap();
+        let record = |email: &str, at: &str, status| crate::SentRecord {
+            email: email.to_string(),
+            success: true,
+            status,
+            interview: Some(Interview::new(parse_interview_time(at).unwrap(), 60, email)),
+            ..Default::default()
+        };
+        let log = SentLog {
+            records: vec![
+                record("late@x.com", "2024-07-12 09:00", ApplicationStatus::Interview),
+                record("past@x.com", "2024-07-09 09:00", ApplicationStatus::Interview),
+                record("now@x.com", "2024-07-10 11:30", ApplicationStatus::Interview),
+                record("offer@x.com", "2024-07-11 09:00", ApplicationStatus::Offer),
+            ],
+        };
+
+        let emails: Vec<&str> = upcoming_interviews(&log, now).iter().map(|(e, _)| *e).collect();
+        assert_eq!(emails, vec!["now@x.com", "late@x.com"]);
+    }
+
+    #[test]
+    fn test_old_interviews_load_without_details() {
+        let json = r#"{"at":"2024-07-10T14:00:00+01:00","duration_minutes":60,"uid":"1@job-mailer"}"#;
+        let interview: Interview = serde_json::from_str(json).unwrap();
+        assert!(interview.interviewer.is_none());
+        assert!(interview.location.is_none());
+    }
+}
@@ -0,0 +1,343 @@
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use console::style;
+use serde_json::Value;
+use std::fs;
+
+use crate::{
+    classify::fold,
+    contacts::{self, decode_text, extract_emails},
+    enrich,
+    leads::{self, Lead},
+    Profile, CHECK, NOTE,
+};
+
+// endereços de uma página que não servem para candidaturas
+const NOT_FOR_APPLICATIONS: &[&str] = &[
+    "noreply", "no-reply", "donotreply", "privacy", "privacidade", "dpo", "gdpr", "rgpd", "abuse", "webmaster", "postmaster",
+    "support", "suporte", "press", "imprensa",
+];
+
+// palavras à volta de um endereço que dizem "mande para aqui a candidatura"
+const APPLY_WORDS: &[&str] = &["apply", "application", "candidat", "curriculo", "cv", "resume", "send", "envie", "enviar", "postul"];
+
+/// O que se tira de um anúncio: os emails para onde mandar a candidatura
+/// (o mais provável primeiro), a empresa e o título da vaga.
+#[derive(Debug, Default, PartialEq)]
+pub struct Posting {
+    pub emails: Vec<String>,
+    pub company: Option<String>,
+    pub title: Option<String>,
+}
+
+// "rh [at] acme.com", "rh&#64;acme.com": os anúncios escondem os endereços dos robôs
+fn unobfuscate(text: &str) -> String {
+    let mut text = text.replace("&#64;", "@").replace("&#x40;", "@").replace("&commat;", "@");
+    for at in [" [at] ", " (at) ", "[at]", "(at)", " [arroba] ", " (arroba) "] {
+        text = text.replace(at, "@");
+    }
+    for dot in [" [dot] ", " (dot) ", "[dot]", "(dot)", " [ponto] "] {
+        text = text.replace(dot, ".");
+    }
+    text
+}
+
+// os blocos JSON-LD de schema.org: os sites de emprego descrevem a vaga num `JobPosting`
+fn job_posting(html: &str) -> Option<Value> {
+    let lower = html.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find("application/ld+json") {
+        let start = from + pos + lower[from + pos..].find('>')? + 1;
+        let end = start + lower[start..].find("</script>")?;
+        from = end;
+        let Ok(json) = serde_json::from_str::<Value>(&html[start..end]) else {
+            continue;
+        };
+        let mut candidates = match json {
+            Value::Array(items) => items,
+            Value::Object(ref map) if map.contains_key("@graph") => map["@graph"].as_array().cloned().unwrap_or_default(),
+            other => vec![other],
+        };
+        if let Some(at) = candidates.iter().position(|v| v["@type"] == "JobPosting") {
+            return Some(candidates.swap_remove(at));
+        }
+    }
+    None
+}
+
+fn text_of(value: &Value) -> Option<String> {
+    value.as_str().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+/// Lê o texto ou o HTML de um anúncio.
+pub fn parse_posting(content: &str) -> Posting {
+    let content = unobfuscate(content);
+    let lower = content.to_lowercase();
+    let mut emails: Vec<(bool, String)> = extract_emails(&content)
+        .into_iter()
+        .filter(|email| {
+            let local = email.split('@').next().unwrap_or_default().to_lowercase();
+            !NOT_FOR_APPLICATIONS.iter().any(|w| local.contains(w))
+        })
+        .map(|email| {
+            let at = lower.find(&email.to_lowercase()).unwrap_or(0);
+            let around: String = lower[..at].chars().rev().take(120).collect::<Vec<_>>().into_iter().rev().collect();
+            (APPLY_WORDS.iter().any(|w| around.contains(w)), email)
+        })
+        .collect();
+    // estável: entre os que têm palavras de candidatura à volta, fica a ordem da página
+    emails.sort_by_key(|(apply, _)| !apply);
+
+    let posting = job_posting(&content);
+    let company = posting
+        .as_ref()
+        .and_then(|p| text_of(&p["hiringOrganization"]["name"]).or_else(|| text_of(&p["hiringOrganization"])))
+        .or_else(|| enrich::og_site_name(&content))
+        .or_else(|| emails.first().and_then(|(_, e)| enrich::company_from_domain(e)));
+    let title = posting
+        .as_ref()
+        .and_then(|p| text_of(&p["title"]))
+        .or_else(|| enrich::page_title(&content).filter(|t| !t.is_empty()));
+    Posting { emails: emails.into_iter().map(|(_, e)| e).collect(), company, title }
+}
+
+// sem as tags, para "<li>Go</li>" contar e "<img src=go.png>" não
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// O texto do anúncio sem tags e com os espaços juntos, para guardar no lead.
+pub fn plain_text(html: &str) -> String {
+    strip_tags(html).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// `word` inteira em `text` (os dois já sem maiúsculas nem acentos)
+fn contains_word(text: &str, word: &str) -> bool {
+    let boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+    !word.is_empty()
+        && text
+            .match_indices(word)
+            .any(|(at, _)| boundary(text[..at].chars().next_back()) && boundary(text[at + word.len()..].chars().next()))
+}
+
+/// As skills do perfil que o anúncio pede, pela ordem do perfil. Conta a
+/// palavra inteira, sem maiúsculas nem acentos: "Go" não está em "Google".
+pub fn matched_skills(skills: &[String], description: &str) -> Vec<String> {
+    let text = fold(&strip_tags(description));
+    skills.iter().filter(|skill| contains_word(&text, &fold(skill.trim()))).cloned().collect()
+}
+
+// palavras de nível e os anos de experiência que querem dizer
+const LEVELS: &[(&[&str], (u8, u8))] = &[
+    (&["estagio", "estagiario", "internship", "intern", "trainee", "stagiaire"], (0, 1)),
+    (&["junior", "entry level", "entry-level", "graduate", "recem-licenciado"], (0, 2)),
+    (&["pleno", "mid-level", "mid level", "intermediate", "confirme"], (2, 5)),
+    (&["senior", "experienced", "experimente"], (5, 10)),
+    (&["tech lead", "lead", "principal", "staff", "head of"], (7, 40)),
+];
+
+// até 5 skills em comum dão os pontos todos das skills
+const SKILL_POINTS: u32 = 70;
+const SENIORITY_POINTS: u32 = 30;
+const FULL_MATCH: usize = 5;
+
+/// Quanto uma vaga me serve: skills em comum e o nível pedido contra os
+/// meus `experience_years`.
+#[derive(Debug, PartialEq)]
+pub struct Fit {
+    pub score: u32,
+    pub matched: Vec<String>,
+    // anos de experiência pedidos (mínimo, máximo); None = o anúncio não diz
+    pub years: Option<(u8, u8)>,
+}
+
+/// Os anos que a vaga pede: primeiro o nível no título, depois "5+ anos" /
+/// "3 years of experience" no texto, por fim o nível mais alto que o texto nomeia.
+pub fn required_years(title: Option<&str>, description: &str) -> Option<(u8, u8)> {
+    let level = |text: &str| {
+        LEVELS.iter().rev().find(|(words, _)| words.iter().any(|w| contains_word(text, w))).map(|(_, years)| *years)
+    };
+    if let Some(years) = title.and_then(|t| level(&fold(t))) {
+        return Some(years);
+    }
+    let text = fold(description);
+    let pattern = regex::Regex::new(r"(\d{1,2})\s*\+?\s*(?:years|anos|ans|yrs)").expect("years pattern");
+    let asked = pattern.captures_iter(&text).filter_map(|c| c[1].parse::<u8>().ok()).filter(|y| *y <= 20).max();
+    asked.map(|y| (y, 40)).or_else(|| level(&text))
+}
+
+pub fn fit(skills: &[String], experience_years: u8, title: Option<&str>, description: &str) -> Fit {
+    let matched = matched_skills(skills, description);
+    let full = skills.len().min(FULL_MATCH) as u32;
+    // um perfil sem skills não soma pontos aqui
+    let skill_points = (SKILL_POINTS * matched.len().min(FULL_MATCH) as u32).checked_div(full).unwrap_or(0);
+    let years = required_years(title, description);
+    let seniority_points = match years {
+        // não diz: meio caminho
+        None => SENIORITY_POINTS / 2,
+        Some((min, max)) if (min..=max).contains(&experience_years) => SENIORITY_POINTS,
+        Some((min, max)) if experience_years + 2 >= min && experience_years <= max + 2 => SENIORITY_POINTS / 2,
+        Some(_) => 0,
+    };
+    Fit { score: skill_points + seniority_points, matched, years }
+}
+
+/// O texto de um anúncio, de um ficheiro ou de um URL.
+pub async fn read_source(source: &str) -> Result<String> {
+    if is_url(source) {
+        let response = enrich::http_client().get(source).send().await.with_context(|| format!("Cannot fetch {}", source))?;
+        if !response.status().is_success() {
+            bail!("{} answered {}", source, response.status());
+        }
+        response.text().await.with_context(|| format!("Cannot read {}", source))
+    } else {
+        Ok(decode_text(&fs::read(source).with_context(|| format!("{} not found", source))?))
+    }
+}
+
+fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// `jobs parse`: lê um anúncio (ficheiro ou URL) e deixa um lead pronto a
+/// passar a contacto, com o email, a empresa e a vaga.
+pub async fn run_parse(source: &str) -> Result<()> {
+    let content = read_source(source).await?;
+    let posting = parse_posting(&content);
+    let Some(email) = posting.emails.first() else {
+        bail!("No application email found in {}", source);
+    };
+
+    say!("{} {}", NOTE, style(posting.title.as_deref().unwrap_or(source)).bold());
+    say!("   Email:   {}", style(email).cyan());
+    for other in &posting.emails[1..] {
+        say!("            {} (alternativa)", style(other).dim());
+    }
+    say!("   Empresa: {}", posting.company.as_deref().unwrap_or("-"));
+
+    let lead = Lead {
+        email: Some(email.clone()),
+        url: is_url(source).then(|| source.to_string()),
+        title: posting.title.clone(),
+        company: posting.company.clone(),
+        description: Some(plain_text(&content)),
+        captured_at: Local::now(),
+    };
+    let book = contacts::load_contacts();
+    if book.find(email).is_some() {
+        say!("{} {} já é contacto", CHECK, email);
+        return Ok(());
+    }
+    let (added, number) = leads::update_leads(|leads| {
+        let added = leads.add(lead);
+        let at = leads.leads.iter().position(|l| l.email.as_deref().is_some_and(|e| contacts::same_email(e, email)));
+        Ok((added, at.unwrap_or(0) + 1))
+    })?;
+    let state = if added { "pronto" } else { "já estava na lista" };
+    say!("{} Lead {} {}: `job-mailer leads accept {}`", CHECK, number, state, number);
+    Ok(())
+}
+
+/// `jobs list`: os anúncios guardados como leads, da vaga que mais me serve
+/// para a que menos.
+pub fn list_jobs(profile: &Profile) {
+    let leads = leads::load_leads();
+    let mut jobs: Vec<(usize, &Lead, Fit)> = leads
+        .leads
+        .iter()
+        .enumerate()
+        .filter_map(|(i, lead)| {
+            let description = lead.description.as_deref()?;
+            Some((i + 1, lead, fit(&profile.skills, profile.experience_years, lead.title.as_deref(), description)))
+        })
+        .collect();
+    if jobs.is_empty() {
+        println!("{} Nenhum anúncio guardado (`jobs parse` ou o bookmarklet do `serve`)", CHECK);
+        return;
+    }
+    jobs.sort_by_key(|(_, _, fit)| std::cmp::Reverse(fit.score));
+    println!("{} Vagas por afinidade ({}):", NOTE, jobs.len());
+    println!("{}", style("─".repeat(60)).dim());
+    for (number, lead, fit) in &jobs {
+        let score = match fit.score {
+            70.. => style(fit.score).green().bold(),
+            40.. => style(fit.score).yellow(),
+            _ => style(fit.score).red(),
+        };
+        let name = [&lead.title, &lead.company].into_iter().flatten().cloned().collect::<Vec<_>>().join(" · ");
+        println!("  {:>3}  {} {}", score, style(format!("#{}", number)).dim(), name);
+        let level = match fit.years {
+            Some((min, 40)) => format!("{}+ anos", min),
+            Some((min, max)) => format!("{}-{} anos", min, max),
+            None => "nível não indicado".to_string(),
+        };
+        let matched = if fit.matched.is_empty() { "nenhuma".to_string() } else { fit.matched.join(", ") };
+        println!("        {} · skills: {} · {}", style(lead.email.as_deref().unwrap_or("(sem email)")).cyan(), matched, level);
+    }
+    println!("{}", style("─".repeat(60)).dim());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_job_posting() {
+        let html = r#"<html><head><title>Backend Engineer | Jobs</title>
+            <meta property="og:site_name" content="JobBoard">
+            <script type="application/ld+json">{"@context": "https://schema.org", "@type": "JobPosting",
+              "title": "Backend Engineer (Rust)", "hiringOrganization": {"@type": "Organization", "name": "Acme Lda"}}</script></head>
+            <body><p>Dúvidas sobre privacidade: privacy@acme.com. Info geral: info@acme.com</p>
+            <p>Para se candidatar envie o CV para talent [at] acme.com</p></body></html>"#;
+        let posting = parse_posting(html);
+        assert_eq!(posting.emails, vec!["talent@acme.com", "info@acme.com"]);
+        assert_eq!(posting.company.as_deref(), Some("Acme Lda"));
+        assert_eq!(posting.title.as_deref(), Some("Backend Engineer (Rust)"));
+
+        // texto simples: a empresa sai do domínio
+        let posting = parse_posting("Vaga de DevOps. Candidaturas para jobs@globex-corp.com");
+        assert_eq!(posting.emails, vec!["jobs@globex-corp.com"]);
+        assert_eq!(posting.company.as_deref(), Some("Globex Corp"));
+        assert_eq!(posting.title, None);
+    }
+
+    #[test]
+    fn test_matched_skills() {
+        let skills: Vec<String> = ["Rust", "Go", "PostgreSQL", "C++", "Node.js", "Kubernetes"].iter().map(|s| s.to_string()).collect();
+        let description = "<h2>Requisitos</h2><ul><li>Experiência com RUST e postgresql</li><li>Conhecimentos de C++</li></ul>\
+                           <p>Trabalhamos com Google Cloud e node.js</p><img src=\"kubernetes.png\">";
+        assert_eq!(matched_skills(&skills, description), vec!["Rust", "PostgreSQL", "C++", "Node.js"]);
+        assert!(matched_skills(&skills, "Vaga de marketing").is_empty());
+    }
+
+    #[test]
+    fn test_fit_score() {
+        let skills: Vec<String> = ["Rust", "Tokio", "PostgreSQL", "Docker"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(required_years(Some("Senior Backend Engineer"), "mentoring junior devs"), Some((5, 10)));
+        assert_eq!(required_years(None, "Pelo menos 3+ anos de experiência em Rust"), Some((3, 40)));
+        assert_eq!(required_years(None, "Rust developer, team of juniors and a tech lead"), Some((7, 40)));
+        assert_eq!(required_years(Some("Backend"), "Rust e Docker"), None);
+
+        let fit_senior = fit(&skills, 6, Some("Sénior Rust Engineer"), "Rust, Tokio, PostgreSQL e Docker");
+        assert_eq!(fit_senior.score, 100);
+        let fit_junior = fit(&skills, 6, Some("Junior Developer"), "Rust");
+        assert_eq!(fit_junior.matched, vec!["Rust"]);
+        assert_eq!(fit_junior.score, 70 / 4);
+        // sem nível, meio caminho
+        assert_eq!(fit(&skills, 6, None, "Rust e Tokio").score, 35 + 15);
+        assert_eq!(fit(&[], 6, None, "Rust").score, 15);
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+
+use crate::{contacts::Contact, enrich, Config, EmailTemplate};
+
+// línguas faladas nos negócios de cada ccTLD; com mais de uma o domínio é ambíguo
+const TLD_LANGUAGES: &[(&str, &[&str])] = &[
+    ("pt", &["pt"]),
+    ("br", &["pt"]),
+    ("ao", &["pt"]),
+    ("mz", &["pt"]),
+    ("cv", &["pt"]),
+    ("st", &["pt"]),
+    ("gw", &["pt"]),
+    ("uk", &["en"]),
+    ("ie", &["en"]),
+    ("us", &["en"]),
+    ("au", &["en"]),
+    ("nz", &["en"]),
+    ("za", &["en"]),
+    ("ng", &["en"]),
+    ("ke", &["en"]),
+    ("gh", &["en"]),
+    ("sg", &["en"]),
+    ("fr", &["fr"]),
+    ("mc", &["fr"]),
+    ("sn", &["fr"]),
+    ("ci", &["fr"]),
+    ("ma", &["fr"]),
+    ("tn", &["fr"]),
+    ("dz", &["fr"]),
+    ("be", &["fr", "nl"]),
+    ("ca", &["en", "fr"]),
+    ("ch", &["de", "fr", "it"]),
+    ("lu", &["fr", "de"]),
+    ("cm", &["fr", "en"]),
+    ("es", &["es"]),
+    ("mx", &["es"]),
+    ("ar", &["es"]),
+    ("cl", &["es"]),
+    ("de", &["de"]),
+    ("at", &["de"]),
+    ("it", &["it"]),
+    ("nl", &["nl"]),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Guess {
+    Known(String),
+    // várias línguas possíveis com variante no template: pergunta-se ao compor
+    Ambiguous(Vec<String>),
+    Unknown,
+}
+
+// `acme.com` na tabela também vale para careers.acme.com
+fn mapped<'a>(domain: &str, domains: &'a BTreeMap<String, String>) -> Option<&'a String> {
+    domains.iter().find_map(|(d, language)| {
+        let d = d.trim().to_lowercase();
+        (domain == d || domain.ends_with(&format!(".{}", d))).then_some(language)
+    })
+}
+
+/// A língua provável do destinatário: `sending.domain_languages` primeiro,
+/// depois o TLD, só entre as línguas que o template tem.
+pub fn guess(email: &str, template: &EmailTemplate, domains: &BTreeMap<String, String>) -> Guess {
+    let Some(domain) = enrich::email_domain(email).map(str::to_lowercase) else {
+        return Guess::Unknown;
+    };
+    if let Some(language) = mapped(&domain, domains) {
+        return Guess::Known(language.to_lowercase());
+    }
+    // sem variantes não há nada a escolher
+    if template.languages.is_empty() {
+        return Guess::Unknown;
+    }
+    let tld = domain.rsplit('.').next().unwrap_or_default();
+    let candidates: Vec<String> = TLD_LANGUAGES
+        .iter()
+        .find(|(t, _)| *t == tld)
+        .map(|(_, languages)| languages.iter().filter(|l| template.has_language(l)).map(|l| l.to_string()).collect())
+        .unwrap_or_default();
+    match candidates.as_slice() {
+        [] => Guess::Unknown,
+        [language] => Guess::Known(language.clone()),
+        _ => Guess::Ambiguous(candidates),
+    }
+}
+
+/// Língua do envio: a pedida (coluna do mail-merge ou escolha ao compor), a do
+/// contacto ou a adivinhada pelo domínio, por esta ordem.
+pub fn recipient_language(config: &Config, email: &str, contact: Option<&Contact>) -> Option<String> {
+    config
+        .language
+        .clone()
+        .or_else(|| contact.and_then(|c| c.language.clone()))
+        .or_else(|| match guess(email, &config.template, &config.sending.domain_languages) {
+            Guess::Known(language) => Some(language),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LanguageTemplate;
+
+    #[test]
+    fn test_guess_from_tld_and_mapping() {
+        let mut template = EmailTemplate { subject: "Candidatura".to_string(), ..Default::default() };
+        let none = BTreeMap::new();
+        assert_eq!(guess("rh@acme.fr", &template, &none), Guess::Unknown);
+
+        for language in ["en", "fr"] {
+            template.languages.insert(language.to_string(), LanguageTemplate::default());
+        }
+        assert_eq!(guess("rh@acme.pt", &template, &none), Guess::Known("pt".to_string()));
+        assert_eq!(guess("hr@acme.co.uk", &template, &none), Guess::Known("en".to_string()));
+        assert_eq!(guess("rh@acme.fr", &template, &none), Guess::Known("fr".to_string()));
+        assert_eq!(guess("hr@acme.ca", &template, &none), Guess::Ambiguous(vec!["en".to_string(), "fr".to_string()]));
+        // sem variante "de" a Suíça só pode ser francês
+        assert_eq!(guess("hr@acme.ch", &template, &none), Guess::Known("fr".to_string()));
+        assert_eq!(guess("hr@acme.com", &template, &none), Guess::Unknown);
+
+        let domains = BTreeMap::from([("Acme.com".to_string(), "EN".to_string())]);
+        assert_eq!(guess("hr@careers.acme.com", &template, &domains), Guess::Known("en".to_string()));
+        assert_eq!(guess("hr@notacme.com", &template, &domains), Guess::Unknown);
+    }
+}
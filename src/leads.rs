@@ -0,0 +1,217 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use console::style;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    contacts::{self, extract_emails, same_email, Contact},
+    git_history, CHECK, CROSS, NOTE,
+};
+
+pub const LEADS_FILE: &str = "leads.json";
+
+/// Um recrutador ou uma vaga apanhados no browser, à espera de passar a
+/// contacto (`leads accept`) ou de ser deitados fora.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Lead {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    // a página onde foi apanhado (o anúncio)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub company: Option<String>,
+    // o texto do anúncio, para a afinidade do `jobs list`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub captured_at: DateTime<Local>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Leads {
+    pub leads: Vec<Lead>,
+}
+
+/// O que o bookmarklet manda: um email (ou texto seleccionado com emails lá
+/// dentro), o endereço da página e o título dela.
+#[derive(Debug, Deserialize, Default)]
+pub struct Capture {
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+pub fn load_leads() -> Leads {
+    crate::schema::INITIAL.load(&crate::overrides::path(LEADS_FILE))
+}
+
+pub fn save_leads(leads: &Leads) -> Result<()> {
+    crate::schema::INITIAL.save(&crate::overrides::path(LEADS_FILE), leads)
+}
+
+/// Lê, muda e grava o leads.json com o lock: o `serve` escreve nele enquanto a CLI lista.
+pub fn update_leads<R>(change: impl FnOnce(&mut Leads) -> Result<R>) -> Result<R> {
+    let _lock = crate::lock::lock(&crate::overrides::path(LEADS_FILE))?;
+    let mut leads = load_leads();
+    let result = change(&mut leads)?;
+    save_leads(&leads)?;
+    Ok(result)
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+impl Capture {
+    /// Os emails e a página da captura; sem nenhum dos dois não há lead.
+    pub fn emails_and_url(&self) -> Result<(Vec<String>, Option<String>)> {
+        let text = format!("{}\n{}", self.email.as_deref().unwrap_or_default(), self.text.as_deref().unwrap_or_default());
+        let emails = extract_emails(&text);
+        let url = non_empty(self.url.as_deref());
+        if emails.is_empty() && url.is_none() {
+            bail!("No email or url in the capture");
+        }
+        Ok((emails, url))
+    }
+}
+
+impl Leads {
+    /// Guarda o lead se ainda não houver um com o mesmo email (ou, sem email,
+    /// com a mesma página); devolve `true` se entrou.
+    pub fn add(&mut self, lead: Lead) -> bool {
+        let known = self.leads.iter().any(|l| match (&l.email, &lead.email) {
+            (Some(a), Some(b)) => same_email(a, b),
+            (None, None) => l.url == lead.url,
+            _ => false,
+        });
+        if !known {
+            self.leads.push(lead);
+        }
+        !known
+    }
+
+    /// Junta o que veio do browser: um lead por email novo, ou só a página
+    /// quando não há email. Devolve os que entraram; os repetidos (já leads
+    /// ou já contactos) ficam de fora.
+    pub fn capture(&mut self, capture: &Capture, book: &contacts::ContactBook, now: DateTime<Local>) -> Result<Vec<Lead>> {
+        let (emails, url) = capture.emails_and_url()?;
+        let title = non_empty(capture.title.as_deref());
+        let description = non_empty(capture.text.as_deref()).map(|t| crate::jobs::plain_text(&t));
+        let lead = |email: Option<String>| Lead {
+            email,
+            url: url.clone(),
+            title: title.clone(),
+            company: None,
+            description: description.clone(),
+            captured_at: now,
+        };
+        let candidates = if emails.is_empty() { vec![lead(None)] } else { emails.into_iter().map(|e| lead(Some(e))).collect() };
+        let mut added = vec![];
+        for lead in candidates {
+            let contact = lead.email.as_deref().is_some_and(|e| book.find(e).is_some());
+            if !contact && self.add(lead.clone()) {
+                added.push(lead);
+            }
+        }
+        Ok(added)
+    }
+}
+
+// "leads accept 2": contam a partir de 1, pela ordem da lista
+fn take(leads: &mut Leads, number: usize) -> Result<Lead> {
+    if number == 0 || number > leads.leads.len() {
+        bail!("No lead number {} (see `leads list`)", number);
+    }
+    Ok(leads.leads.remove(number - 1))
+}
+
+pub fn list_leads() {
+    let leads = load_leads();
+    if leads.leads.is_empty() {
+        println!("{} Nenhum lead por tratar", CHECK);
+        return;
+    }
+    println!("{} Leads por tratar ({}):", NOTE, leads.leads.len());
+    println!("{}", style("─".repeat(60)).dim());
+    for (i, lead) in leads.leads.iter().enumerate() {
+        println!(
+            "  {} {} {} {}",
+            style(format!("{:>3}.", i + 1)).dim(),
+            style(lead.captured_at.format("%d/%m/%Y")).dim(),
+            style(lead.email.as_deref().unwrap_or("(sem email)")).cyan(),
+            [&lead.company, &lead.title].into_iter().flatten().cloned().collect::<Vec<_>>().join(" · ")
+        );
+        if let Some(url) = &lead.url {
+            println!("        {}", style(url).dim());
+        }
+    }
+    println!("{}", style("─".repeat(60)).dim());
+}
+
+/// Passa um lead a contacto; sem email no lead tem de vir com `--email`.
+pub fn accept_lead(number: usize, email: Option<String>, company: Option<String>) -> Result<()> {
+    let lead = update_leads(|leads| {
+        let lead = take(leads, number)?;
+        if lead.email.is_none() && email.is_none() {
+            bail!("Lead {} has no email: use --email", number);
+        }
+        Ok(lead)
+    })?;
+    let email = email.or(lead.email).context("Missing email")?;
+    crate::blocklist::check(&email)?;
+    let mut book = contacts::load_contacts();
+    book.upsert(Contact { email: email.clone(), company: company.or(lead.company), ..Default::default() });
+    contacts::save_contacts(&book)?;
+    git_history::record(&format!("lead {} passou a contacto", email));
+    say!("{} {} passou a contacto", CHECK, style(&email).green());
+    Ok(())
+}
+
+pub fn drop_lead(number: usize) -> Result<()> {
+    let lead = update_leads(|leads| take(leads, number))?;
+    git_history::record("lead deitado fora");
+    say!("{} Lead {} deitado fora", CROSS, lead.email.or(lead.url).unwrap_or_default());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_emails_or_page() {
+        let now = Local::now();
+        let mut leads = Leads::default();
+        let book = contacts::ContactBook {
+            contacts: vec![Contact { email: "rh@acme.com".to_string(), ..Default::default() }],
+        };
+        let page = Capture {
+            text: Some("Candidaturas: jobs@globex.com ou RH@acme.com".to_string()),
+            url: Some("https://jobs.example/123".to_string()),
+            title: Some("Backend Engineer".to_string()),
+            ..Default::default()
+        };
+        let added = leads.capture(&page, &book, now).unwrap();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].email.as_deref(), Some("jobs@globex.com"));
+        assert_eq!(added[0].title.as_deref(), Some("Backend Engineer"));
+        // de novo: nada entra
+        assert!(leads.capture(&page, &book, now).unwrap().is_empty());
+
+        let only_page = Capture { url: Some("https://jobs.example/456".to_string()), ..Default::default() };
+        assert_eq!(leads.capture(&only_page, &book, now).unwrap()[0].email, None);
+        assert!(leads.capture(&only_page, &book, now).unwrap().is_empty());
+        assert!(leads.capture(&Capture::default(), &book, now).is_err());
+        assert_eq!(leads.leads.len(), 2);
+
+        assert!(take(&mut leads, 3).is_err());
+        assert_eq!(take(&mut leads, 2).unwrap().url.as_deref(), Some("https://jobs.example/456"));
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+/// Parâmetros acrescentados aos meus links no corpo, para a analytics do
+/// site mostrar de que candidatura veio cada visita.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkTagging {
+    // "ref=application&company={{company}}"; os valores aceitam placeholders
+    pub params: String,
+    // outros domínios a marcar além do github/linkedin do perfil (ex.: o portfolio)
+    #[serde(default)]
+    pub domains: Vec<String>,
+}
+
+fn encode(value: &str) -> String {
+    let mut out = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Query final para um destinatário; `render` preenche os placeholders de cada valor.
+pub fn query(params: &str, render: impl Fn(&str) -> String) -> String {
+    params
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = render(value);
+            // empresa desconhecida: melhor sem o parâmetro do que "company="
+            (!value.is_empty()).then(|| format!("{}={}", key, encode(&value)))
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+pub fn tag_url(url: &str, query: &str) -> String {
+    if query.is_empty() || url.contains(query) {
+        return url.to_string();
+    }
+    let (base, fragment) = match url.find('#') {
+        Some(i) => url.split_at(i),
+        None => (url, ""),
+    };
+    let sep = if base.contains('?') { '&' } else { '?' };
+    format!("{}{}{}{}", base, sep, query, fragment)
+}
+
+/// Domínio de um link, com ou sem esquema: "https://www.x.dev/a" e "x.dev/a" dão "x.dev".
+pub fn host(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#', ':']).next()?.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    host.contains('.').then_some(host)
+}
+
+fn matches(url: &str, domains: &[String]) -> bool {
+    host(url).is_some_and(|h| {
+        domains.iter().any(|d| {
+            let d = d.to_lowercase();
+            let d = d.strip_prefix("www.").unwrap_or(&d);
+            h == d || h.ends_with(&format!(".{}", d))
+        })
+    })
+}
+
+/// Marca os links http(s) do texto cujo domínio está em `domains`.
+pub fn tag_links(text: &str, domains: &[String], query: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = ["http://", "https://"].iter().filter_map(|s| rest.find(s)).min() {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        let end = tail
+            .find(|c: char| c.is_whitespace() || "<>\"'()[]".contains(c))
+            .unwrap_or(tail.len());
+        // a pontuação no fim da frase não faz parte do link
+        let url = tail[..end].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        if matches(url, domains) {
+            out.push_str(&tag_url(url, query));
+        } else {
+            out.push_str(url);
+        }
+        rest = &tail[url.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_renders_and_encodes() {
+        let render = |v: &str| v.replace("{{company}}", "Acme & Filhos");
+        assert_eq!(
+            query("ref=application&company={{company}}", render),
+            "ref=application&company=Acme%20%26%20Filhos"
+        );
+        assert_eq!(query("ref=app&company={{company}}", |v| v.replace("{{company}}", "")), "ref=app");
+    }
+
+    #[test]
+    fn test_tag_url() {
+        assert_eq!(tag_url("github.com/joao", "ref=app"), "github.com/joao?ref=app");
+        assert_eq!(tag_url("https://x.dev/?lang=pt#top", "ref=app"), "https://x.dev/?lang=pt&ref=app#top");
+        assert_eq!(tag_url("https://x.dev/?ref=app", "ref=app"), "https://x.dev/?ref=app");
+    }
+
+    #[test]
+    fn test_host() {
+        assert_eq!(host("https://www.JoaoSilva.dev/projectos").as_deref(), Some("joaosilva.dev"));
+        assert_eq!(host("github.com/joao").as_deref(), Some("github.com"));
+        assert_eq!(host("N/A"), None);
+    }
+
+    #[test]
+    fn test_tag_links_only_my_domains() {
+        let text = "Portfolio: https://joaosilva.dev/work. Vaga: https://acme.com/jobs/1 (ver https://blog.joaosilva.dev)";
+        let domains = vec!["joaosilva.dev".to_string()];
+        assert_eq!(
+            tag_links(text, &domains, "ref=app"),
+            "Portfolio: https://joaosilva.dev/work?ref=app. Vaga: https://acme.com/jobs/1 (ver https://blog.joaosilva.dev?ref=app)"
+        );
+    }
+}
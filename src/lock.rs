@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::{File, OpenOptions},
+    path::Path,
+};
+
+/// Lock exclusivo (advisory) num `.<ficheiro>.lock` ao lado do ficheiro: o
+/// daemon, o `serve` e a CLI noutro terminal esperam pela vez em vez de
+/// intercalarem leituras e escritas. Sai com o guard, ou com o processo se
+/// este morrer. Não é reentrante: dentro do mesmo processo não se pede duas vezes.
+pub struct FileLock(File);
+
+pub fn lock(path: &str) -> Result<FileLock> {
+    let target = Path::new(path);
+    let name = target.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    // não pode ser o próprio ficheiro: o write_atomic troca-o por outro
+    let lock_path = target.with_file_name(format!(".{}.lock", name));
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Cannot open {}", lock_path.display()))?;
+    file.lock().with_context(|| format!("Cannot lock {}", path))?;
+    Ok(FileLock(file))
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::mpsc, thread, time::Duration};
+
+    #[test]
+    fn test_second_lock_waits_for_the_first() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("outbox.json").to_string_lossy().into_owned();
+        let first = lock(&path).unwrap();
+        assert!(dir.path().join(".outbox.json.lock").exists());
+
+        let (tx, rx) = mpsc::channel();
+        let waiting = {
+            let path = path.clone();
+            thread::spawn(move || {
+                let _second = lock(&path).unwrap();
+                tx.send(()).unwrap();
+            })
+        };
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+        drop(first);
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        waiting.join().unwrap();
+    }
+}
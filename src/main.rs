@@ -1,17 +1,106 @@
+// output normal, escondido com -q
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if crate::output::verbosity() >= 1 {
+            println!($($arg)*);
+        }
+    };
+}
+
+// detalhe extra com -v, para stderr para não misturar com o output normal
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if crate::output::verbosity() >= 2 {
+            eprintln!("   {}", console::style(format!($($arg)*)).dim());
+        }
+    };
+}
+
+mod absence;
+mod autoconfig;
+mod backup;
+mod blocklist;
+mod campaign;
+mod classify;
+mod cli;
+mod contacts;
+mod correlation;
+mod cv;
+mod dead;
+mod delay;
+mod drafting;
+mod editor;
+mod enrich;
+mod failure;
+mod forget;
+mod git_history;
+mod headers;
+mod history;
+mod imap;
+mod interview;
+mod jobs;
+mod language;
+mod leads;
+mod links;
+mod lock;
+mod merge;
+mod notify;
+mod oauth;
+mod outbox;
+mod output;
+mod overrides;
+mod pdf;
+mod pgp;
+mod plain;
+mod portfolio;
+mod presets;
+mod report;
+mod retention;
+mod rotation;
+mod schema;
+mod secrets;
+mod sequence;
+mod server;
+mod smime;
+mod smtp_check;
+mod sync;
+mod templates;
+mod tracking;
+mod vcard;
+mod verify;
+mod warmup;
+
 use anyhow::{Context, Result};
-use chrono::{DateTime, Local};
-use console::{style, Emoji};
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use chrono::{DateTime, Local, NaiveDate};
+use clap::Parser;
+use contacts::{load_contacts, Contact};
+use console::{style, Emoji, Term};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use dialoguer::{theme::ColorfulTheme, Confirm, Editor, Input, MultiSelect, Select};
 use dotenvy::dotenv;
 use indicatif::{ProgressBar, ProgressStyle};
 use lettre::{
-    message::{header::ContentType, Attachment, MultiPart, SinglePart},
-    transport::smtp::authentication::Credentials,
-    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart},
+    transport::smtp::{
+        authentication::{Credentials, Mechanism},
+        client::{Tls, TlsParameters},
+        AsyncSmtpTransportBuilder,
+    },
+    AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
 };
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::{env, fs, time::Duration};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    env, fs,
+    io::Write,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
 
 static ROCKET: Emoji<'_, '_> = Emoji("🚀", "");
 static MAIL: Emoji<'_, '_> = Emoji("📧", "");
@@ -19,16 +108,128 @@ static CHECK: Emoji<'_, '_> = Emoji("✅", "");
 static CROSS: Emoji<'_, '_> = Emoji("❌", "");
 static CLOCK: Emoji<'_, '_> = Emoji("⏰", "");
 static SPARKLE: Emoji<'_, '_> = Emoji("✨", "");
+static NOTE: Emoji<'_, '_> = Emoji("📝", "*");
 
 const CONFIG_FILE: &str = "config.json";
 const CV_FILE: &str = "cv.pdf";
 const LOG_FILE: &str = "sent_log.json";
+const SANDBOX_DIR: &str = "outbox";
+const SENT_DIR: &str = "sent";
+
+// --yes: responde sim a todas as confirmações (scripts, cron, daemon)
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+// Ctrl-C durante um lote ou o daemon: pára no próximo ponto seguro
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static CATCHING: AtomicBool = AtomicBool::new(false);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub profile: Profile,
     pub smtp: SmtpConfig,
     pub template: EmailTemplate,
+    // outros templates com nome ("spontaneous", "follow-up-1"), escolhidos ao compor ou com --template
+    #[serde(default)]
+    pub templates: BTreeMap<String, EmailTemplate>,
+    // campanhas: template, destinatários, hora e sequência de follow-ups (`campaign`, `send --campaign`)
+    #[serde(default)]
+    pub campaigns: BTreeMap<String, campaign::Campaign>,
+    #[serde(default)]
+    pub sending: SendingConfig,
+    #[serde(default)]
+    pub sync: Option<sync::SyncConfig>,
+    // guarda cada envio na pasta dos enviados da conta
+    #[serde(default)]
+    pub imap: Option<imap::ImapConfig>,
+    // ?ref=...&company=... nos meus links, por destinatário
+    #[serde(default)]
+    pub links: Option<links::LinkTagging>,
+    // pixel de abertura; só com esta secção, e cada envio pode recusar (--no-track)
+    #[serde(default)]
+    pub tracking: Option<tracking::TrackingConfig>,
+    // notificações do sistema a partir do daemon
+    #[serde(default)]
+    pub notifications: Option<notify::NotifyConfig>,
+    // commit automático de config/log num repositório git local
+    #[serde(default)]
+    pub git_history: bool,
+    // o daemon anonimiza ou apaga os envios antigos
+    #[serde(default)]
+    pub retention: Option<retention::RetentionConfig>,
+    // de onde vêm as passwords sem SMTP_PASS/IMAP_PASS no ambiente
+    #[serde(default)]
+    pub secrets: secrets::SecretProvider,
+    // palavras que classificam as respostas (rejeição, entrevista...), além das de origem
+    #[serde(default)]
+    pub replies: classify::RepliesConfig,
+    // login OAuth (Gmail, Microsoft) em vez de password
+    #[serde(default)]
+    pub oauth: Option<oauth::OAuthConfig>,
+    // rascunho do {{cover_letter}} por uma API tipo OpenAI (`send --job ... --draft`)
+    #[serde(default)]
+    pub drafting: Option<drafting::DraftingConfig>,
+    // perfis alternativos, escolhidos com --profile
+    #[serde(default)]
+    pub profiles: BTreeMap<String, NamedProfile>,
+    // versão do template antes do mail-merge preencher as colunas
+    #[serde(skip)]
+    pub template_version: Option<String>,
+    // `subject` e `subjects` do template antes do mail-merge, para a rotação os reconhecer
+    #[serde(skip)]
+    pub subject_lines: Option<Vec<String>>,
+    // perfil escolhido com --profile (None = o `profile` principal)
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    // variante de `profile.cvs` a anexar (None = cv.pdf)
+    #[serde(skip)]
+    pub cv_variant: Option<String>,
+    // anexar também o `profile.portfolio`
+    #[serde(skip)]
+    pub attach_portfolio: bool,
+    // sem pixel neste envio, mesmo com `tracking` configurado
+    #[serde(skip)]
+    pub no_track: bool,
+    // língua pedida pela coluna `language` do mail-merge (senão vale a do contacto)
+    #[serde(skip)]
+    pub language: Option<String>,
+    // entrada de `templates` em `template` (None = o template principal)
+    #[serde(skip)]
+    pub template_name: Option<String>,
+    // campanha dos envios (--campaign) e passo da sequência (0 = a candidatura)
+    #[serde(skip)]
+    pub campaign: Option<String>,
+    #[serde(skip)]
+    pub sequence_step: usize,
+}
+
+/// Outra identidade (ex.: freelance vs emprego): o seu From, assinatura e,
+/// se indicado, a sua conta SMTP/IMAP. As credenciais vêm de
+/// `SMTP_USER_<NOME>`/`SMTP_PASS_<NOME>`, com as gerais como recurso.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedProfile {
+    pub profile: Profile,
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+    #[serde(default)]
+    pub imap: Option<imap::ImapConfig>,
+}
+
+impl Config {
+    pub fn select_profile(&mut self, name: &str) -> Result<()> {
+        let named = self
+            .profiles
+            .get(name)
+            .cloned()
+            .with_context(|| format!("Profile '{}' not found in config.json", name))?;
+        self.profile = named.profile;
+        if let Some(smtp) = named.smtp {
+            self.smtp = smtp;
+        }
+        if let Some(imap) = named.imap {
+            self.imap = Some(imap);
+        }
+        self.active_profile = Some(name.to_string());
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,110 +243,1082 @@ pub struct Profile {
     pub experience_years: u8,
     pub linkedin: Option<String>,
     pub github: Option<String>,
+    // chave privada OpenPGP (armored) para assinar os emails
+    #[serde(default)]
+    pub pgp_key: Option<String>,
+    // certificado e chave S/MIME (PEM); a cadeia intermédia é opcional
+    #[serde(default)]
+    pub smime_cert: Option<String>,
+    #[serde(default)]
+    pub smime_key: Option<String>,
+    #[serde(default)]
+    pub smime_chain: Option<String>,
+    // nome no From ("João Silva — Backend Engineer"); por omissão `name`
+    #[serde(default)]
+    pub display_name: Option<String>,
+    // para onde vão as respostas, se não for o `email`
+    #[serde(default)]
+    pub reply_to: Option<String>,
+    // acrescentada ao fim do corpo, depois de "-- "
+    #[serde(default)]
+    pub signature: Option<String>,
+    // variantes do CV por categoria ("backend": "cv-backend.pdf"); sem variante vai o cv.pdf
+    #[serde(default)]
+    pub cvs: BTreeMap<String, String>,
+    // anexado só quando pedido (--portfolio, coluna `portfolio` do mail-merge)
+    #[serde(default)]
+    pub portfolio: Option<portfolio::PortfolioConfig>,
+}
+
+impl Profile {
+    pub fn sender(&self) -> Result<Mailbox> {
+        let name = self.display_name.clone().unwrap_or_else(|| self.name.clone());
+        let email = mailbox(&self.email).with_context(|| format!("Invalid profile email: {}", self.email))?.email;
+        Ok(Mailbox::new(Some(name), email))
+    }
+}
+
+// "Nome <x@y>" ou só o endereço; domínios acentuados vão em punycode
+fn mailbox(address: &str) -> Result<Mailbox> {
+    let parsed: Mailbox = address.trim().parse().with_context(|| format!("Invalid email: {}", address))?;
+    let ascii = contacts::ascii_domain(parsed.email.as_ref());
+    Ok(Mailbox::new(parsed.name, ascii.parse().with_context(|| format!("Invalid email: {}", address))?))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmtpConfig {
     pub host: String,
     pub port: u16,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    // aceita um certificado autoassinado (Proton Mail Bridge, relays locais)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub accept_invalid_certs: bool,
 }
 
-fn get_smtp_creds() -> Result<Credentials> {
-    let user = env::var("SMTP_USER").context("SMTP_USER not set in .env")?;
-    let pass = env::var("SMTP_PASS").context("SMTP_PASS not set in .env")?;
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    // sem encriptação, só para relays locais e testes
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SendingConfig {
+    // segundos para desfazer um envio depois de confirmado (0 desactiva)
+    #[serde(default)]
+    pub undo_seconds: u64,
+    // escreve .eml em outbox/ em vez de enviar
+    #[serde(default)]
+    pub sandbox: bool,
+    // cópia oculta para o próprio endereço, para ficar na caixa de entrada
+    #[serde(default)]
+    pub bcc_self: bool,
+    // guarda cada mensagem construída em sent/ como .eml
+    #[serde(default)]
+    pub archive: bool,
+    // cv.pdf em Word/OpenDocument é convertido com o LibreOffice em vez de recusado
+    #[serde(default)]
+    pub convert_cv: bool,
+    // [mín, máx] minutos até voltar a tentar um destinatário em greylisting (por omissão 10-15)
+    #[serde(default)]
+    pub greylist_retry_minutes: Option<[u64; 2]>,
+    // avisa ao compor se o domínio foi contactado há menos de N dias (por omissão 30, 0 desactiva)
+    #[serde(default)]
+    pub recent_domain_days: Option<i64>,
+    // codificação do Subject com acentos: base64 (padrão), q ou utf8
+    #[serde(default)]
+    pub subject_encoding: headers::HeaderEncoding,
+    // domínio → língua do template, antes da adivinha pelo TLD ("acme.com": "en")
+    #[serde(default)]
+    pub domain_languages: BTreeMap<String, String>,
+    // espera entre envios do bulk/mail-merge; sem ela o bulk pergunta mín/máx
+    #[serde(default)]
+    pub delay: Option<delay::Delay>,
+    // envios por dia; o que passa num bulk/mail-merge vai para a fila dos dias seguintes
+    #[serde(default)]
+    pub max_per_day: Option<usize>,
+    // limite diário que sobe aos poucos nas primeiras semanas de uma conta nova
+    #[serde(default)]
+    pub warmup: Option<warmup::Warmup>,
+    // como se escolhe entre os `subjects` do template: bandit (padrão) ou random
+    #[serde(default)]
+    pub subject_rotation: rotation::Rotation,
+}
+
+impl SendingConfig {
+    pub fn recent_domain_days(&self) -> i64 {
+        self.recent_domain_days.unwrap_or(30)
+    }
+}
+
+// SMTP_USER_FREELANCE antes de SMTP_USER, para cada nome pela ordem dada
+fn account_var_names(names: &[&str], profile: Option<&str>) -> Vec<String> {
+    let suffix = profile.map(|p| {
+        p.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect::<String>()
+    });
+    let suffixed = suffix.iter().flat_map(|s| names.iter().map(move |n| format!("{}_{}", n, s)));
+    suffixed.chain(names.iter().map(|n| n.to_string())).collect()
+}
+
+pub(crate) fn account_var(names: &[&str], profile: Option<&str>) -> Option<String> {
+    account_var_names(names, profile).iter().find_map(|n| env::var(n).ok())
+}
+
+fn smtp_account(profile: Option<&str>, secrets: &secrets::SecretProvider) -> Result<(String, String)> {
+    let user = account_var(&["SMTP_USER"], profile).context("SMTP_USER not set in .env")?;
+    // sem SMTP_PASS: o fornecedor de `secrets`, o keyring, ou pergunta num terminal
+    let pass = match account_var(&["SMTP_PASS"], profile) {
+        Some(pass) => pass,
+        None => secrets::password(secrets, &user).context("SMTP_PASS not set in .env")?,
+    };
+    Ok((user, pass))
+}
+
+fn get_smtp_creds(profile: Option<&str>, secrets: &secrets::SecretProvider) -> Result<Credentials> {
+    let (user, pass) = smtp_account(profile, secrets)?;
     Ok(Credentials::new(user, pass))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct EmailTemplate {
     pub subject: String,
+    // alternativas ao `subject`, sorteadas a cada envio pela `sending.subject_rotation`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subjects: Vec<String>,
+    // pode ficar vazio com `html`: o texto simples sai do HTML
+    #[serde(default)]
     pub body: String,
+    // corpo em HTML, enviado ao lado do `body`; os valores entram escapados
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    // língua do subject/body acima, para um contacto com essa língua não procurar variante
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    // outras línguas ("en", "fr"), pelo `language` do contacto ou da coluna do mail-merge
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub languages: BTreeMap<String, LanguageTemplate>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct LanguageTemplate {
+    pub subject: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub html: Option<String>,
+    // sem ela vai a `profile.signature`
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl EmailTemplate {
+    /// A variante na língua pedida; None para a base (sem língua, a língua base ou uma desconhecida).
+    pub fn variant(&self, language: Option<&str>) -> Option<&LanguageTemplate> {
+        let language = language?.trim().to_lowercase();
+        self.languages.get(&language)
+    }
+
+    pub fn has_language(&self, language: &str) -> bool {
+        let language = language.trim().to_lowercase();
+        self.languages.contains_key(&language) || self.language.as_deref().unwrap_or("pt") == language
+    }
+
+    /// Só o subject/body que vão ser usados, para ver que variáveis pedem.
+    pub fn localized(&self, language: Option<&str>) -> EmailTemplate {
+        match self.variant(language) {
+            Some(v) => EmailTemplate {
+                subject: v.subject.clone(),
+                body: v.body.clone(),
+                html: v.html.clone(),
+                ..Default::default()
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SentRecord {
     pub email: String,
     pub sent_at: DateTime<Local>,
     pub success: bool,
-    pub error: Option<String>,
+    // logs antigos têm só o texto; é classificado ao carregar
+    pub error: Option<failure::SendError>,
+    #[serde(default)]
+    pub cancelled: bool,
+    #[serde(default)]
+    pub sandbox: bool,
+    // fase da candidatura depois do primeiro email
+    #[serde(default)]
+    pub status: ApplicationStatus,
+    #[serde(default)]
+    pub interview: Option<interview::Interview>,
+    // diálogo SMTP (sem credenciais) quando o envio falha
+    #[serde(default)]
+    pub smtp_transcript: Option<Vec<String>>,
+    // notas livres ("falei com a Maria, pediu pretensões salariais")
+    #[serde(default)]
+    pub notes: Option<String>,
+    // cada mudança de fase, para o `history`
+    #[serde(default)]
+    pub status_history: Vec<StatusChange>,
+    // o que foi enviado, para se poder reenviar igual
+    #[serde(default)]
+    pub message: Option<SentMessage>,
+    // pedidos ao pixel de abertura (`tracking`)
+    #[serde(default)]
+    pub opens: Vec<DateTime<Local>>,
+    // anonimizado pela `retention`: o endereço é um hash, sem textos nem notas
+    #[serde(default)]
+    pub redacted: bool,
+    // ausente até este dia (resposta automática): o follow-up fica para depois
+    #[serde(default)]
+    pub follow_up_after: Option<NaiveDate>,
+    // `snooze`: fora dos follow-ups e do painel até este dia
+    #[serde(default)]
+    pub snoozed_until: Option<NaiveDate>,
+    // campanha do envio e passo da sequência: 0 = a candidatura, 1 = o primeiro follow-up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub campaign: Option<String>,
+    #[serde(default)]
+    pub step: usize,
+    // a sequência parou antes do fim (resposta, rejeição, bloqueio) e porquê
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence_stop: Option<sequence::SequenceStop>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SentMessage {
+    pub subject: String,
+    pub body: String,
+    // o CV em si não vai para o log, só o hash para saber se mudou
+    pub cv_sha256: String,
+    // versão do template e do perfil usados, para comparar respostas entre versões
+    #[serde(default)]
+    pub template_version: String,
+    #[serde(default)]
+    pub profile_version: String,
+    // cópia exacta da mensagem em sent/ (com `sending.archive`)
+    #[serde(default)]
+    pub eml: Option<String>,
+    // outros destinatários da mesma mensagem; o `email` do registo é o To principal
+    #[serde(default)]
+    pub other_to: Vec<String>,
+    #[serde(default)]
+    pub cc: Vec<String>,
+    // perfil (--profile) com que foi enviado; None = o principal
+    #[serde(default)]
+    pub profile: Option<String>,
+    // variante do CV anexada; None = cv.pdf
+    #[serde(default)]
+    pub cv_variant: Option<String>,
+    // template de `templates` usado; None = o principal
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub portfolio: bool,
+    // id do pixel de abertura; None = enviado sem pixel
+    #[serde(default)]
+    pub track_id: Option<String>,
+    // versão HTML do corpo, quando o template a tem
+    #[serde(default)]
+    pub html: Option<String>,
+    // Message-ID do email enviado, para ligar as respostas pelo In-Reply-To/References
+    #[serde(default)]
+    pub message_id: Option<String>,
+    // subject sorteado entre os `subjects` do template e com que estratégia
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_choice: Option<rotation::SubjectChoice>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+impl SentMessage {
+    /// Junta To extra e CC, sem repetir o destinatário principal nem endereços entre si.
+    pub fn with_copies(mut self, primary: &str, to: &[String], cc: &[String]) -> Self {
+        let mut seen = vec![contacts::normalize_email(primary)];
+        let mut keep = |list: &[String]| -> Vec<String> {
+            let mut kept = vec![];
+            for address in list.iter().map(|a| a.trim()).filter(|a| !a.is_empty()) {
+                if !seen.contains(&contacts::normalize_email(address)) {
+                    seen.push(contacts::normalize_email(address));
+                    kept.push(address.to_string());
+                }
+            }
+            kept
+        };
+        self.other_to = keep(to);
+        self.cc = keep(cc);
+        self
+    }
+}
+
+impl SentRecord {
+    // o destinatário principal ou qualquer outro To/CC da mesma mensagem
+    pub fn involves(&self, email: &str) -> bool {
+        contacts::same_email(&self.email, email)
+            || self.message.as_ref().is_some_and(|m| {
+                m.other_to.iter().chain(&m.cc).any(|a| contacts::same_email(a, email))
+            })
+    }
+
+    pub fn set_status(&mut self, status: ApplicationStatus) {
+        self.status = status;
+        self.status_history.push(StatusChange { at: Local::now(), status });
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatusChange {
+    pub at: DateTime<Local>,
+    pub status: ApplicationStatus,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplicationStatus {
+    #[default]
+    Sent,
+    Replied,
+    Interview,
+    Offer,
+    Rejected,
+}
+
+impl ApplicationStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ApplicationStatus::Sent => "enviado",
+            ApplicationStatus::Replied => "respondeu",
+            ApplicationStatus::Interview => "entrevista",
+            ApplicationStatus::Offer => "proposta",
+            ApplicationStatus::Rejected => "rejeitado",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SentLog {
     pub records: Vec<SentRecord>,
 }
 
+impl SentLog {
+    // o último envio bem sucedido (fora do sandbox) representa a candidatura
+    pub fn latest_application_mut(&mut self, email: &str) -> Option<&mut SentRecord> {
+        self.records
+            .iter_mut()
+            .rev()
+            .find(|r| r.success && !r.sandbox && r.involves(email))
+    }
+
+    // o último envio com sucesso de cada endereço: uma candidatura por destinatário
+    pub fn applications(&self) -> Vec<&SentRecord> {
+        let mut latest: Vec<&SentRecord> = vec![];
+        for r in self.records.iter().filter(|r| r.success && !r.sandbox) {
+            match latest.iter().position(|l| contacts::same_email(&l.email, &r.email)) {
+                Some(i) => latest[i] = r,
+                None => latest.push(r),
+            }
+        }
+        latest
+    }
+
+    // a última mensagem guardada para o endereço, tenha o envio corrido bem ou não
+    pub fn latest_message(&self, email: &str) -> Option<&SentMessage> {
+        self.records
+            .iter()
+            .rev()
+            .filter(|r| r.involves(email))
+            .find_map(|r| r.message.as_ref())
+    }
+
+    /// Candidaturas no domínio do endereço desde `since`; no gmail & cia. só
+    /// o próprio endereço (ou um alias dele) conta.
+    pub fn recent_at_domain(&self, email: &str, since: DateTime<Local>) -> Vec<&SentRecord> {
+        let domain = enrich::email_domain(email).filter(|d| !enrich::is_free_mail(d));
+        let same = |r: &&SentRecord| match domain {
+            Some(domain) => enrich::email_domain(&r.email).is_some_and(|d| d.eq_ignore_ascii_case(domain)),
+            None => contacts::same_email(&r.email, email),
+        };
+        self.applications().into_iter().filter(|r| r.sent_at >= since).filter(same).collect()
+    }
+}
+
 fn load_config() -> Result<Config> {
-    let content = fs::read_to_string(CONFIG_FILE).context("config.json not found")?;
-    serde_json::from_str(&content).context("Invalid config.json")
+    let content = fs::read_to_string(overrides::path(CONFIG_FILE)).context("config.json not found")?;
+    let mut config: serde_json::Value = serde_json::from_str(&content).context("Invalid config.json")?;
+    overrides::apply_to_config(&mut config, overrides::config_vars());
+    presets::apply_to_config(&mut config)?;
+    serde_json::from_value(config).context("Invalid config.json")
+}
+
+fn load_profile_config(profile: Option<&str>) -> Result<Config> {
+    let mut config = load_config()?;
+    if let Some(name) = profile {
+        config.select_profile(name)?;
+    }
+    Ok(config)
+}
+
+// a mesma config com outro perfil; relê o config.json para não herdar a conta do perfil actual
+fn config_for_profile(config: &Config, profile: Option<&str>) -> Result<Config> {
+    if config.active_profile.as_deref() == profile {
+        return Ok(config.clone());
+    }
+    let mut other = load_profile_config(profile)?;
+    other.sending.sandbox = config.sending.sandbox;
+    Ok(other)
+}
+
+fn load_cv(convert: bool) -> Result<Vec<u8>> {
+    cv::ensure_pdf(fs::read(overrides::path(CV_FILE)).context("cv.pdf not found")?, convert)
+}
+
+fn cv_path(config: &Config) -> Result<String> {
+    match &config.cv_variant {
+        None => Ok(overrides::path(CV_FILE)),
+        Some(name) => config
+            .profile
+            .cvs
+            .get(name)
+            .cloned()
+            .with_context(|| format!("CV variant '{}' not found in profile.cvs", name)),
+    }
+}
+
+// a variante escolhida em `config.cv_variant`, ou o cv.pdf
+fn load_cv_variant(config: &Config) -> Result<Vec<u8>> {
+    if config.cv_variant.is_none() {
+        return load_cv(config.sending.convert_cv);
+    }
+    let path = cv_path(config)?;
+    let bytes = fs::read(&path).with_context(|| format!("{} not found", path))?;
+    cv::ensure_pdf(bytes, config.sending.convert_cv)
+}
+
+// o CV a anexar, com os avisos de `cv::warnings` antes de qualquer envio
+fn load_checked_cv(config: &Config) -> Result<Vec<u8>> {
+    let cv = load_cv_variant(config)?;
+    let path = cv_path(config)?;
+    let info = cv::inspect(&cv);
+    verbose!("{}: {}", path, cv::summary(&info, cv.len()));
+    if output::verbosity() >= 1 {
+        for warning in cv::warnings(&info, cv.len(), &config.profile.name) {
+            eprintln!("{} {}: {}", NOTE, path, warning);
+        }
+    }
+    Ok(cv)
 }
 
-fn load_cv() -> Result<Vec<u8>> {
-    fs::read(CV_FILE).context("cv.pdf not found")
+/// Escreve num ficheiro temporário ao lado e troca-o pelo original: um crash
+/// a meio deixa o ficheiro antigo inteiro, nunca metade do novo.
+fn write_atomic(path: &str, contents: impl AsRef<[u8]>) -> Result<()> {
+    write_atomic_with_mode(path, contents, 0o666)
+}
+
+/// O `write_atomic` com o temporário já criado com estas permissões (menos a
+/// umask): um segredo com 0o600 nunca fica legível por outros, nem por um instante.
+fn write_atomic_with_mode(path: &str, contents: impl AsRef<[u8]>, mode: u32) -> Result<()> {
+    let target = std::path::Path::new(path);
+    let name = target.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let temp = target.with_file_name(format!(".{}.tmp", name));
+    let write = || -> std::io::Result<()> {
+        // um temporário deixado por um crash manteria as permissões com que foi criado
+        let _ = fs::remove_file(&temp);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+        #[cfg(not(unix))]
+        let _ = mode;
+        let mut file = options.open(&temp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&temp, target)
+    };
+    write()
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })
+        .with_context(|| format!("Cannot write {}", path))
 }
 
+// v0 → v1: os erros guardados só como texto passam a vir já classificados
+fn classify_text_errors(value: &mut serde_json::Value) {
+    let Some(records) = value.get_mut("records").and_then(|r| r.as_array_mut()) else {
+        return;
+    };
+    for record in records {
+        let Some(text) = record.get("error").and_then(|e| e.as_str()) else {
+            continue;
+        };
+        let transcript: Option<Vec<String>> = record
+            .get("smtp_transcript")
+            .and_then(|t| serde_json::from_value(t.clone()).ok());
+        let error = failure::SendError::classify(text, transcript.as_deref());
+        record["error"] = serde_json::to_value(error).unwrap_or_default();
+    }
+}
+
+const LOG_SCHEMA: schema::Schema = schema::Schema { migrations: &[classify_text_errors] };
+
 fn load_log() -> SentLog {
-    fs::read_to_string(LOG_FILE)
-        .ok()
-        .and_then(|c| serde_json::from_str(&c).ok())
-        .unwrap_or_default()
+    LOG_SCHEMA.load(&overrides::path(LOG_FILE))
 }
 
 fn save_log(log: &SentLog) -> Result<()> {
-    fs::write(LOG_FILE, serde_json::to_string_pretty(log)?)?;
-    Ok(())
+    LOG_SCHEMA.save(&overrides::path(LOG_FILE), log)
+}
+
+/// Relê o log com o lock, aplica `change` e grava: entre a leitura e a escrita
+/// nenhuma outra instância (daemon, `serve`, outro terminal) mexe nele, e o
+/// `log` do chamador fica com o que os outros entretanto gravaram.
+fn update_log<R>(log: &mut SentLog, change: impl FnOnce(&mut SentLog) -> Result<R>) -> Result<R> {
+    let _lock = lock::lock(&overrides::path(LOG_FILE))?;
+    *log = load_log();
+    let result = change(log)?;
+    save_log(log)?;
+    Ok(result)
 }
 
-fn build_email(config: &Config) -> (String, String) {
+fn record_send(
+    log: &mut SentLog,
+    config: &Config,
+    email: &str,
+    message: SentMessage,
+    result: &Result<()>,
+) -> Result<()> {
+    let transcript = output::take_transcript();
+    let record = SentRecord {
+        email: email.to_string(),
+        sent_at: Local::now(),
+        success: result.is_ok(),
+        error: result
+            .as_ref()
+            .err()
+            .map(|e| failure::SendError::classify(&format!("{:#}", e), Some(&transcript))),
+        sandbox: config.sending.sandbox,
+        smtp_transcript: (result.is_err() && !transcript.is_empty()).then_some(transcript),
+        message: Some(message),
+        campaign: config.campaign.clone(),
+        step: config.sequence_step,
+        ..Default::default()
+    };
+    update_log(log, |log| {
+        log.records.push(record);
+        Ok(())
+    })
+}
+
+fn record_cancelled(log: &mut SentLog, emails: &[String]) -> Result<()> {
+    update_log(log, |log| {
+        for email in emails {
+            log.records.push(SentRecord {
+                email: email.clone(),
+                sent_at: Local::now(),
+                cancelled: true,
+                ..Default::default()
+            });
+        }
+        Ok(())
+    })
+}
+
+fn build_email(config: &Config, to: &str, contact: Option<&Contact>) -> (String, String) {
+    let rendered = render_email(config, to, contact);
+    (rendered.subject, rendered.body)
+}
+
+struct RenderedEmail {
+    subject: String,
+    body: String,
+    html: Option<String>,
+}
+
+fn render_email(config: &Config, to: &str, contact: Option<&Contact>) -> RenderedEmail {
     let p = &config.profile;
     let t = &config.template;
+    // sem empresa no contacto, usa o nome derivado do domínio
+    let from_domain = enrich::company_from_domain(to).unwrap_or_default();
+    let company = contact
+        .and_then(|c| c.company.clone())
+        .unwrap_or_else(|| from_domain.clone());
     
-    let subj = t.subject
-        .replace("{{name}}", &p.name)
-        .replace("{{title}}", &p.title);
+    let vars = [
+        ("name", p.name.clone()),
+        ("email", p.email.clone()),
+        ("phone", p.phone.clone()),
+        ("title", p.title.clone()),
+        ("summary", p.summary.clone()),
+        ("skills", p.skills.join(", ")),
+        // com anúncio (`--job`, coluna `job_description`) já vem preenchido; sem ele são todas
+        ("matched_skills", p.skills.join(", ")),
+        // só o `send --draft` o preenche
+        ("cover_letter", String::new()),
+        ("experience_years", p.experience_years.to_string()),
+        ("linkedin", p.linkedin.clone().unwrap_or_else(|| "N/A".to_string())),
+        ("github", p.github.clone().unwrap_or_else(|| "N/A".to_string())),
+        ("company", company),
+        ("company_from_domain", from_domain),
+    ];
+    // `{{nome|alternativa}}`: a alternativa quando o valor falta, vem vazio ou é N/A
+    let render_with = |text: &str, vars: &[(&str, String)]| {
+        merge::render_placeholders(text, |name, default| {
+            let value = vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.as_str());
+            match (value, default) {
+                (Some(v), Some(default)) if v.is_empty() || v == "N/A" => Some(default.to_string()),
+                (Some(v), _) => Some(v.to_string()),
+                (None, default) => default.map(str::to_string),
+            }
+        })
+    };
+    let render = |text: &str| render_with(text, &vars);
+    let language = language::recipient_language(config, to, contact);
+    let language = language.as_deref();
+    let variant = t.variant(language);
+    if let Some(language) = language.filter(|l| !t.has_language(l)) {
+        verbose!("sem variante '{}' em template.languages, vai o template base", language);
+    }
+    let (t_subject, t_body, t_html) =
+        variant.map_or((&t.subject, &t.body, &t.html), |v| (&v.subject, &v.body, &v.html));
+    let subj = render(t_subject);
+    let mut body = match &config.links {
+        // os meus links levam a origem da visita: github/linkedin e os `domains` configurados
+        Some(tagging) => {
+            let query = links::query(&tagging.params, render);
+            verbose!("links marcados com ?{}", query);
+            let tagged: Vec<(&str, String)> = vars
+                .iter()
+                .map(|(k, v)| match *k {
+                    "github" | "linkedin" if links::host(v).is_some() => (*k, links::tag_url(v, &query)),
+                    _ => (*k, v.clone()),
+                })
+                .collect();
+            links::tag_links(&render_with(t_body, &tagged), &tagging.domains, &query)
+        }
+        None => render(t_body),
+    };
+    // no HTML os valores podem trazer & ou < (nomes, resumos): entram escapados, o template não
+    let html = t_html.as_ref().map(|html| {
+        let escaped: Vec<(&str, String)> = vars.iter().map(|(k, v)| (*k, merge::escape_html(v))).collect();
+        let html = render_with(html, &escaped);
+        match &config.links {
+            Some(tagging) => links::tag_links(&html, &tagging.domains, &links::query(&tagging.params, render)),
+            None => html,
+        }
+    });
+    // só com `html`, a parte de texto sai dele: nunca vai um multipart com o texto vazio
+    if t_body.trim().is_empty() {
+        if let Some(html) = &html {
+            body = plain::html_to_text(html);
+        }
+    }
+    let signature = variant.and_then(|v| v.signature.as_ref()).or(p.signature.as_ref());
+    if let Some(signature) = signature {
+        body = format!("{}\n\n-- \n{}", body.trim_end(), signature);
+    }
+    let html = html.map(|html| match signature {
+        Some(signature) => tracking::append_html(
+            &html,
+            &format!("<p style=\"white-space: pre-wrap\">-- \n{}</p>", merge::escape_html(signature)),
+        ),
+        None => html,
+    });
     
-    let body = t.body
-        .replace("{{name}}", &p.name)
-        .replace("{{email}}", &p.email)
-        .replace("{{phone}}", &p.phone)
-        .replace("{{title}}", &p.title)
-        .replace("{{summary}}", &p.summary)
-        .replace("{{skills}}", &p.skills.join(", "))
-        .replace("{{experience_years}}", &p.experience_years.to_string())
-        .replace("{{linkedin}}", p.linkedin.as_deref().unwrap_or("N/A"))
-        .replace("{{github}}", p.github.as_deref().unwrap_or("N/A"));
+    if output::verbosity() >= 2 {
+        verbose!("template para {}:", if to.is_empty() { "preview" } else { to });
+        let t_html = t_html.as_deref().unwrap_or_default();
+        for var in merge::template_vars(&format!("{}\n{}\n{}", t_subject, t_body, t_html)) {
+            match vars.iter().find(|(k, _)| *k == var) {
+                Some((_, v)) if v.is_empty() => verbose!("  {{{{{}}}}} = (vazio)", var),
+                Some((_, v)) => verbose!("  {{{{{}}}}} = {}", var, v),
+                None => verbose!("  {{{{{}}}}} sem valor, fica no texto", var),
+            }
+        }
+    }
     
-    (subj, body)
+    RenderedEmail { subject: subj, body, html }
 }
 
-async fn send_email(config: &Config, to: &str, cv: &[u8]) -> Result<()> {
-    let (subj, body) = build_email(config);
-    
+fn cv_sha256(cv: &[u8]) -> String {
+    hex::encode(Sha256::digest(cv))
+}
+
+// 12 hex chegam para distinguir versões e cabem numa linha de estatísticas
+fn version_hash<T: Serialize>(value: &T) -> String {
+    let json = serde_json::to_string(value).unwrap_or_default();
+    cv_sha256(json.as_bytes())[..12].to_string()
+}
+
+/// Passa a usar o template `name` de `templates` no lugar do principal.
+fn use_template(config: &mut Config, name: &str) -> Result<()> {
+    let template = config
+        .templates
+        .get(name)
+        .with_context(|| format!("Template '{}' not found in templates", name))?;
+    config.template = template.clone();
+    config.template_name = Some(name.to_string());
+    Ok(())
+}
+
+fn template_version(config: &Config) -> String {
+    config
+        .template_version
+        .clone()
+        .unwrap_or_else(|| version_hash(&config.template))
+}
+
+fn build_message(config: &Config, to: &str, contact: Option<&Contact>, message: &SentMessage, cv: &[u8]) -> Result<Message> {
     let attach = Attachment::new("CV.pdf".into())
         .body(cv.to_vec(), ContentType::parse("application/pdf").unwrap());
     
-    let msg = Message::builder()
-        .from(config.profile.email.parse()?)
-        .to(to.parse()?)
-        .subject(subj)
-        .multipart(
-            MultiPart::mixed()
-                .singlepart(SinglePart::plain(body))
-                .singlepart(attach),
-        )?;
+    // com template HTML ou com pixel o corpo vai também em HTML, senão só texto
+    let pixel = match (&message.track_id, &config.tracking) {
+        (Some(id), Some(tracking)) => Some(tracking::pixel_url(tracking, id)),
+        _ => None,
+    };
+    let html = match (&message.html, &pixel) {
+        (Some(html), Some(pixel)) => Some(tracking::append_html(html, &tracking::pixel_img(pixel))),
+        (Some(html), None) => Some(html.clone()),
+        (None, Some(pixel)) => Some(tracking::html_body(&message.body, pixel)),
+        (None, None) => None,
+    };
+    let content = match html {
+        Some(html) => MultiPart::mixed().multipart(MultiPart::alternative_plain_html(message.body.clone(), html)),
+        None => MultiPart::mixed().singlepart(SinglePart::plain(message.body.clone())),
+    };
+    let mut content = content.singlepart(attach);
+    if message.portfolio {
+        let bundle = portfolio::build(config.profile.portfolio.as_ref().context("portfolio not set in profile")?)?;
+        content = content.singlepart(
+            Attachment::new(bundle.name.clone()).body(bundle.bytes.clone(), ContentType::parse(bundle.content_type).unwrap()),
+        );
+    }
+    let content = match (&config.profile.pgp_key, &config.profile.smime_cert) {
+        (Some(_), Some(_)) => anyhow::bail!("pgp_key and smime_cert are mutually exclusive"),
+        (Some(key), None) => pgp::sign(content, key)?,
+        (None, Some(_)) => smime::sign(content, &config.profile)?,
+        (None, None) => content,
+    };
+    let content = match contact.and_then(|c| c.pgp_key.as_deref()) {
+        Some(key) => pgp::encrypt(content, key, config.profile.pgp_key.as_deref())?,
+        None => content,
+    };
     
-    let creds = get_smtp_creds()?;
+    let mut builder = Message::builder()
+        .from(config.profile.sender()?)
+        .to(mailbox(to)?);
+    if let Some(reply_to) = &config.profile.reply_to {
+        builder = builder.reply_to(mailbox(reply_to).with_context(|| format!("Invalid reply_to: {}", reply_to))?);
+    }
+    for other in &message.other_to {
+        builder = builder.to(mailbox(other)?);
+    }
+    for cc in &message.cc {
+        builder = builder.cc(mailbox(cc)?);
+    }
+    if config.sending.bcc_self {
+        builder = builder.bcc(mailbox(&config.profile.email)?);
+    }
+    if let Some(id) = &message.message_id {
+        builder = builder.message_id(Some(id.clone()));
+    }
+    let msg = builder
+        .raw_header(headers::header("Subject", &message.subject, config.sending.subject_encoding))
+        .multipart(content)?;
+    Ok(msg)
+}
+
+// com `subjects` no template sorteia o subject deste envio; as variantes de língua têm o seu
+fn rotate_subject(config: &Config, to: &str, contact: Option<&Contact>) -> Option<(String, rotation::SubjectChoice)> {
+    let t = &config.template;
+    let language = language::recipient_language(config, to, contact);
+    if t.subjects.is_empty() || t.variant(language.as_deref()).is_some() {
+        return None;
+    }
+    let rendered: Vec<String> = std::iter::once(&t.subject).chain(&t.subjects).cloned().collect();
+    let lines = config.subject_lines.clone().unwrap_or_else(|| rendered.clone());
+    let strategy = config.sending.subject_rotation;
+    let idx = rotation::choose(&lines, strategy, &load_log(), Local::now(), &mut rand::thread_rng());
+    verbose!("subject {}/{} ({:?}): {}", idx + 1, lines.len(), strategy, lines[idx]);
+    Some((rendered[idx].clone(), rotation::SubjectChoice { subject: lines[idx].clone(), strategy }))
+}
+
+fn render_message(config: &Config, to: &str, cv: &[u8]) -> SentMessage {
+    let contact = load_contacts().find(to).cloned();
+    let version = template_version(config);
+    let rotated = rotate_subject(config, to, contact.as_ref());
+    let with_subject;
+    let config = match &rotated {
+        Some((subject, _)) => {
+            with_subject = Config { template: EmailTemplate { subject: subject.clone(), ..config.template.clone() }, ..config.clone() };
+            &with_subject
+        }
+        None => config,
+    };
+    let RenderedEmail { subject, body, html } = render_email(config, to, contact.as_ref());
+    SentMessage {
+        subject,
+        body,
+        html,
+        cv_sha256: cv_sha256(cv),
+        template_version: version,
+        subject_choice: rotated.map(|(_, choice)| choice),
+        profile_version: version_hash(&config.profile),
+        profile: config.active_profile.clone(),
+        cv_variant: config.cv_variant.clone(),
+        template: config.template_name.clone(),
+        portfolio: config.attach_portfolio,
+        track_id: (config.tracking.is_some() && !config.no_track).then(tracking::new_id),
+        ..Default::default()
+    }
+}
+
+// renderiza o template para o destinatário e envia; devolve também o que foi renderizado
+async fn send_email(config: &Config, to: &str, cv: &[u8]) -> (SentMessage, Result<()>) {
+    let mut message = render_message(config, to, cv);
+    let result = send_message(config, to, &mut message, cv).await;
+    (message, result)
+}
+
+// com `sending.archive` a mensagem enviada fica em sent/ e `message.eml` aponta para ela
+async fn send_message(config: &Config, to: &str, message: &mut SentMessage, cv: &[u8]) -> Result<()> {
+    let contact = load_contacts().find(to).cloned();
+    // um reenvio é outra mensagem: Message-ID novo
+    message.message_id = Some(correlation::new_message_id(&config.profile.email));
+    // o Subject em UTF-8 cru só passa num servidor com SMTPUTF8; sem ele vai em RFC 2047
+    let fallback;
+    let config = if config.sending.subject_encoding == headers::HeaderEncoding::Utf8
+        && !config.sending.sandbox
+        && !smtp_check::supports_smtputf8(config).await
+    {
+        verbose!("{} não anuncia SMTPUTF8: Subject em base64 (RFC 2047)", config.smtp.host);
+        let mut plain = config.clone();
+        plain.sending.subject_encoding = headers::HeaderEncoding::Base64;
+        fallback = plain;
+        &fallback
+    } else {
+        config
+    };
+    let msg = build_message(config, to, contact.as_ref(), message, cv)?;
+    let raw = msg.formatted();
+    message.eml = None;
+    deliver(config, msg).await?;
     
-    let mailer: AsyncSmtpTransport<Tokio1Executor> = 
-        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp.host)?
-            .port(config.smtp.port)
-            .credentials(creds)
-            .build();
+    // só depois de enviado; uma falha aqui não desfaz o envio
+    if config.sending.archive {
+        match archive_eml(to, &raw) {
+            Ok(path) => message.eml = Some(path),
+            Err(e) => eprintln!("   {} Enviado, mas sem cópia em {}/: {:#}", CROSS, SENT_DIR, e),
+        }
+    }
+    if let Some(imap) = config.imap.as_ref().filter(|_| !config.sending.sandbox) {
+        match imap::append_to_sent(imap, &raw, config.active_profile.as_deref(), &config.secrets, config.oauth.as_ref()).await {
+            Ok(()) => verbose!("cópia guardada em {} (IMAP)", imap.folder),
+            Err(e) => eprintln!("   {} Enviado, mas não ficou nos enviados (IMAP): {:#}", CROSS, e),
+        }
+    }
+    Ok(())
+}
+
+fn eml_file_name(to: &str, at: DateTime<Local>) -> String {
+    let safe: String = to
+        .chars()
+        .map(|c| if c.is_alphanumeric() || "@.-_".contains(c) { c } else { '_' })
+        .collect();
+    format!("{}-{}.eml", at.format("%Y%m%d-%H%M%S"), safe)
+}
+
+fn archive_eml(to: &str, raw: &[u8]) -> Result<String> {
+    let sent_dir = overrides::path(SENT_DIR);
+    fs::create_dir_all(&sent_dir)?;
+    let name = eml_file_name(to, Local::now());
+    // dois envios para o mesmo endereço no mesmo segundo
+    let mut path = std::path::Path::new(&sent_dir).join(&name);
+    let mut n = 1;
+    while path.exists() {
+        n += 1;
+        path = std::path::Path::new(&sent_dir).join(name.replace(".eml", &format!("-{}.eml", n)));
+    }
+    fs::write(&path, raw).with_context(|| format!("Cannot write {}", path.display()))?;
+    verbose!("cópia em {}", path.display());
+    Ok(path.to_string_lossy().into_owned())
+}
+
+async fn deliver(config: &Config, msg: Message) -> Result<()> {
+    output::clear_transcript();
+    if config.sending.sandbox {
+        let dir = overrides::path(SANDBOX_DIR);
+        verbose!("sandbox: a escrever .eml em {}/", dir);
+        fs::create_dir_all(&dir)?;
+        AsyncFileTransport::<Tokio1Executor>::new(&dir).send(msg).await?;
+        return Ok(());
+    }
     
+    verbose!("SMTP {}:{} ({:?})", config.smtp.host, config.smtp.port, config.smtp.security);
+    let mailer = smtp_transport(config).await?;
     mailer.send(msg).await?;
     Ok(())
 }
 
+async fn smtp_transport(config: &Config) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+    let smtp = &config.smtp;
+    if let Some(oauth) = &config.oauth {
+        let user = account_var(&["SMTP_USER"], config.active_profile.as_deref()).context("SMTP_USER not set in .env")?;
+        let token = oauth::access_token(oauth, &user).await?;
+        let builder = smtp_builder(smtp)?.authentication(vec![Mechanism::Xoauth2]);
+        return Ok(builder.credentials(Credentials::new(user, token)).build());
+    }
+    let creds = get_smtp_creds(config.active_profile.as_deref(), &config.secrets)?;
+    Ok(smtp_builder(smtp)?.credentials(creds).build())
+}
+
+fn smtp_builder(smtp: &SmtpConfig) -> Result<AsyncSmtpTransportBuilder> {
+    
+    let mut builder = match smtp.security {
+        SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?,
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)?,
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+    };
+    if smtp.accept_invalid_certs && smtp.security != SmtpSecurity::None {
+        let params = TlsParameters::builder(smtp.host.clone()).dangerous_accept_invalid_certs(true).build()?;
+        builder = builder.tls(match smtp.security {
+            SmtpSecurity::Tls => Tls::Wrapper(params),
+            _ => Tls::Required(params),
+        });
+    }
+    Ok(builder.port(smtp.port))
+}
+
+fn confirm(prompt: &str, default: bool) -> Result<bool> {
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        return Ok(true);
+    }
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(default)
+        .interact()?)
+}
+
+// janela para desfazer: devolve false se o utilizador premir uma tecla
+fn undo_window(seconds: u64) -> Result<bool> {
+    if seconds == 0 || !console::user_attended() || ASSUME_YES.load(Ordering::Relaxed) {
+        return Ok(true);
+    }
+    
+    let term = Term::stdout();
+    terminal::enable_raw_mode()?;
+    let deadline = Instant::now() + Duration::from_secs(seconds);
+    let outcome = (|| -> Result<bool> {
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            term.clear_line()?;
+            print!("{} A enviar em {}s... prime qualquer tecla para desfazer", CLOCK, left.as_secs() + 1);
+            std::io::stdout().flush()?;
+            if event::poll(Duration::from_millis(200))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        Ok(true)
+    })();
+    terminal::disable_raw_mode()?;
+    term.clear_line()?;
+    outcome
+}
+
+/// Enquanto vive, o Ctrl-C deixa de matar o processo a meio de um envio ou de
+/// uma escrita: só marca a interrupção, e o lote ou o daemon param a seguir ao
+/// envio em curso. O segundo Ctrl-C sai logo.
+struct Interruptible;
+
+impl Interruptible {
+    fn start() -> Self {
+        static LISTENING: std::sync::Once = std::sync::Once::new();
+        LISTENING.call_once(|| {
+            tokio::spawn(async {
+                while tokio::signal::ctrl_c().await.is_ok() {
+                    if !CATCHING.load(Ordering::Relaxed) || INTERRUPTED.swap(true, Ordering::Relaxed) {
+                        let _ = terminal::disable_raw_mode();
+                        eprintln!("\n{} Interrompido", CROSS);
+                        std::process::exit(130);
+                    }
+                    eprintln!("\n{} Ctrl-C: a parar depois do envio em curso (outra vez para sair já)", CLOCK);
+                }
+            });
+        });
+        INTERRUPTED.store(false, Ordering::Relaxed);
+        CATCHING.store(true, Ordering::Relaxed);
+        Interruptible
+    }
+}
+
+impl Drop for Interruptible {
+    fn drop(&mut self) {
+        CATCHING.store(false, Ordering::Relaxed);
+    }
+}
+
+fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Dorme `duration`, mas acorda logo com um Ctrl-C.
+async fn sleep_interruptible(duration: Duration) {
+    let deadline = Instant::now() + duration;
+    while let Some(left) = deadline.checked_duration_since(Instant::now()).filter(|l| !l.is_zero()) {
+        if interrupted() {
+            return;
+        }
+        tokio::time::sleep(left.min(Duration::from_millis(200))).await;
+    }
+}
+
+// `p`, espaço ou Ctrl-C (em raw mode não há SIGINT) premidos desde a última vez
+fn pause_key() -> Result<bool> {
+    let mut pressed = false;
+    while event::poll(Duration::ZERO)? {
+        if let Event::Key(key) = event::read()? {
+            let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if ctrl_c && key.kind == KeyEventKind::Press {
+                INTERRUPTED.store(true, Ordering::Relaxed);
+            }
+            pressed |= key.kind == KeyEventKind::Press && (ctrl_c || matches!(key.code, KeyCode::Char('p' | 'P' | ' ')));
+        }
+    }
+    Ok(pressed)
+}
+
+/// Espera `seconds` entre envios do lote; true se pediram pausa (ou Ctrl-C) entretanto.
+async fn wait_or_pause(seconds: u64) -> Result<bool> {
+    if !console::user_attended() || ASSUME_YES.load(Ordering::Relaxed) {
+        sleep_interruptible(Duration::from_secs(seconds)).await;
+        return Ok(interrupted());
+    }
+    
+    // raw mode só durante a espera: os println do progresso precisam do terminal normal
+    terminal::enable_raw_mode()?;
+    let deadline = Instant::now() + Duration::from_secs(seconds);
+    let outcome = loop {
+        match pause_key() {
+            Ok(false) => {}
+            other => break other,
+        }
+        let Some(left) = deadline.checked_duration_since(Instant::now()).filter(|l| !l.is_zero()) else {
+            break Ok(false);
+        };
+        tokio::time::sleep(left.min(Duration::from_millis(200))).await;
+    };
+    terminal::disable_raw_mode()?;
+    outcome
+}
+
 fn print_banner() {
     println!();
     println!("{}", style("╔═══════════════════════════════════════╗").cyan());
@@ -155,68 +1328,404 @@ fn print_banner() {
     println!();
 }
 
+/// Os números das estatísticas, para o menu e para o `serve`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Stats {
+    // tentativas, sem contar as desfeitas
+    pub total: usize,
+    pub success: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    pub failures: Vec<(failure::ErrorKind, usize)>,
+    // envios com pixel e quantos foram abertos
+    pub tracked: usize,
+    pub opened: usize,
+    // (versão, candidaturas, com resposta)
+    pub template_versions: Vec<(String, usize, usize)>,
+    // (subject sorteado, candidaturas, com resposta)
+    pub subjects: Vec<(String, usize, usize)>,
+    // candidaturas, respostas e entrevistas de cada campanha
+    pub campaigns: Vec<campaign::CampaignStats>,
+}
+
+impl Stats {
+    pub fn from_log(log: &SentLog) -> Self {
+        // envios em sandbox não contam
+        let records: Vec<&SentRecord> = log.records.iter().filter(|r| !r.sandbox).collect();
+        let success = records.iter().filter(|r| r.success).count();
+        let cancelled = records.iter().filter(|r| r.cancelled).count();
+        let tracked: Vec<&&SentRecord> = records
+            .iter()
+            .filter(|r| r.success && r.message.as_ref().is_some_and(|m| m.track_id.is_some()))
+            .collect();
+        Stats {
+            total: records.len() - cancelled,
+            success,
+            failed: records.len() - success - cancelled,
+            cancelled,
+            failures: failure::count_by_kind(records.iter().filter_map(|r| r.error.as_ref())),
+            tracked: tracked.len(),
+            opened: tracked.iter().filter(|r| !r.opens.is_empty()).count(),
+            template_versions: replies_by_template_version(log),
+            subjects: rotation::replies_by_subject(log),
+            campaigns: campaign::stats_by_campaign(log),
+        }
+    }
+}
+
 fn print_stats(log: &SentLog) {
-    let total = log.records.len();
-    let success = log.records.iter().filter(|r| r.success).count();
-    let failed = total - success;
+    let stats = Stats::from_log(log);
     
     println!();
     println!("{} {}", SPARKLE, style("Estatísticas").bold().yellow());
-    println!("   Total enviados: {}", style(total).cyan());
-    println!("   {} Sucesso: {}", CHECK, style(success).green());
-    println!("   {} Falhados: {}", CROSS, style(failed).red());
+    println!("   Total enviados: {}", style(stats.total).cyan());
+    println!("   {} Sucesso: {}", CHECK, style(stats.success).green());
+    println!("   {} Falhados: {}", CROSS, style(stats.failed).red());
+    for (kind, count) in &stats.failures {
+        println!("      {}: {}", kind.label(), style(count).red());
+    }
+    if stats.cancelled > 0 {
+        println!("   {} Cancelados: {}", CLOCK, style(stats.cancelled).dim());
+    }
+    // só conta os envios que levaram pixel
+    if stats.tracked > 0 {
+        println!("   {} Abertos: {}/{} (com pixel)", MAIL, style(stats.opened).green(), stats.tracked);
+    }
+    
+    if stats.template_versions.len() > 1 {
+        println!("   {} Respostas por versão do template:", MAIL);
+        for (version, sent, replied) in stats.template_versions {
+            println!("      {}: {}/{} ({}%)", style(version).dim(), style(replied).green(), sent, replied * 100 / sent);
+        }
+    }
+    if !stats.subjects.is_empty() {
+        println!("   {} Respostas por subject:", MAIL);
+        for (subject, sent, replied) in stats.subjects {
+            println!("      {}: {}/{} ({}%)", subject, style(replied).green(), sent, replied * 100 / sent);
+        }
+    }
+    if !stats.campaigns.is_empty() {
+        println!("   {} Por campanha:", MAIL);
+        for c in &stats.campaigns {
+            let rates = format!("{}% respostas, {}% entrevistas", c.reply_rate(), c.interview_rate());
+            println!("      {}: {} enviadas, {}", style(&c.name).yellow(), c.sent, style(rates).green());
+        }
+    }
+    
+    let upcoming = interview::upcoming_interviews(log, Local::now());
+    if !upcoming.is_empty() {
+        println!();
+        println!("{} {}", CLOCK, style("Próximas entrevistas").bold().yellow());
+        for (email, i) in upcoming {
+            let mut line = format!("   {} - {}", style(i.at.format("%d/%m %H:%M")).cyan(), email);
+            if let Some(who) = &i.interviewer {
+                line.push_str(&format!(" com {}", who));
+            }
+            if let Some(location) = &i.location {
+                line.push_str(&format!(" @ {}", style(location).dim()));
+            }
+            println!("{}", line);
+        }
+    }
     println!();
 }
 
+// (versão, candidaturas, com resposta) por ordem de primeiro uso; envios antigos sem versão ficam de fora
+fn replies_by_template_version(log: &SentLog) -> Vec<(String, usize, usize)> {
+    let mut versions: Vec<(String, usize, usize)> = vec![];
+    let applications = log.records.iter().filter(|r| r.success && !r.sandbox);
+    let versioned = applications.filter_map(|r| r.message.as_ref().map(|m| (r, m)));
+    for (r, m) in versioned.filter(|(_, m)| !m.template_version.is_empty()) {
+        let idx = match versions.iter().position(|(v, _, _)| *v == m.template_version) {
+            Some(idx) => idx,
+            None => {
+                versions.push((m.template_version.clone(), 0, 0));
+                versions.len() - 1
+            }
+        };
+        versions[idx].1 += 1;
+        if r.status != ApplicationStatus::Sent {
+            versions[idx].2 += 1;
+        }
+    }
+    versions
+}
+
+// liga o portefólio e falha já se não der para o construir (ficheiro em falta, acima de max_kb)
+fn check_portfolio(config: &mut Config) -> Result<()> {
+    let settings = config.profile.portfolio.as_ref().context("portfolio not set in profile")?;
+    let bundle = portfolio::build(settings)?;
+    verbose!("portefólio: {} ({}KB)", bundle.name, bundle.bytes.len() / 1024);
+    config.attach_portfolio = true;
+    Ok(())
+}
+
+// avisa de quem já foi contactado no mesmo domínio; false se o utilizador desistir
+fn confirm_recent_domains(config: &Config, log: &SentLog, emails: &[String]) -> Result<bool> {
+    let days = config.sending.recent_domain_days();
+    if days <= 0 {
+        return Ok(true);
+    }
+    let now = Local::now();
+    let mut warned = false;
+    for email in emails {
+        for r in log.recent_at_domain(email, now - chrono::Duration::days(days)) {
+            say!(
+                "{} {}: {} contactado a {} (há {} dias)",
+                CLOCK,
+                style(email).yellow(),
+                r.email,
+                r.sent_at.format("%d/%m/%Y"),
+                (now - r.sent_at).num_days()
+            );
+            warned = true;
+        }
+    }
+    // sem terminal (scripts, cron) fica só o aviso
+    if !warned || !console::user_attended() {
+        return Ok(true);
+    }
+    confirm("Contactar outra vez a mesma empresa?", true)
+}
+
+// o pixel é opt-in na config, mas cada envio pode ir sem ele
+fn ask_tracking(config: &mut Config) -> Result<()> {
+    if config.tracking.is_some() && !confirm("Incluir o pixel de abertura (saber se o email foi aberto)?", true)? {
+        config.no_track = true;
+    }
+    Ok(())
+}
+
+// com variantes em `profile.cvs` pergunta qual anexar; None = o cv.pdf já carregado
+fn choose_cv_variant(config: &Config) -> Result<Option<(Config, Vec<u8>)>> {
+    if config.profile.cvs.is_empty() {
+        return Ok(None);
+    }
+    let mut items = vec![format!("{} (padrão)", CV_FILE)];
+    items.extend(config.profile.cvs.iter().map(|(name, path)| format!("{} ({})", name, path)));
+    let sel = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Que CV anexar?")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    if sel == 0 {
+        return Ok(None);
+    }
+    let mut chosen = config.clone();
+    chosen.cv_variant = config.profile.cvs.keys().nth(sel - 1).cloned();
+    let cv = load_checked_cv(&chosen)?;
+    Ok(Some((chosen, cv)))
+}
+
+// destinatários sem língua cujo domínio tem várias possíveis (.ca, .ch, .be): pergunta qual.
+// sem terminal vai o template base
+fn choose_languages(config: &Config, emails: &[String]) -> Result<BTreeMap<String, String>> {
+    let book = contacts::load_contacts();
+    let mut chosen = BTreeMap::new();
+    for email in emails {
+        if config.language.is_some() || book.find(email).is_some_and(|c| c.language.is_some()) {
+            continue;
+        }
+        let language::Guess::Ambiguous(candidates) = language::guess(email, &config.template, &config.sending.domain_languages) else {
+            continue;
+        };
+        if !console::user_attended() || ASSUME_YES.load(Ordering::Relaxed) {
+            verbose!("{}: língua ambígua ({}), vai o template base", email, candidates.join("/"));
+            continue;
+        }
+        let mut items = candidates.clone();
+        items.push("template base".to_string());
+        let sel = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Em que língua escrever a {}?", email))
+            .items(&items)
+            .default(0)
+            .interact()?;
+        if let Some(language) = candidates.get(sel) {
+            chosen.insert(email.clone(), language.clone());
+        }
+    }
+    Ok(chosen)
+}
+
+// com `templates` pergunta qual usar; sem eles fica o template principal
+fn choose_template(config: &Config) -> Result<Config> {
+    let mut chosen = config.clone();
+    if config.templates.is_empty() {
+        return Ok(chosen);
+    }
+    let mut items = vec!["template (padrão)".to_string()];
+    items.extend(config.templates.iter().map(|(name, t)| format!("{} ({})", name, t.subject)));
+    let sel = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Que template usar?")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    if sel > 0 {
+        let name = config.templates.keys().nth(sel - 1).cloned().unwrap_or_default();
+        use_template(&mut chosen, &name)?;
+    }
+    Ok(chosen)
+}
+
 async fn send_single(config: &Config, cv: &[u8], log: &mut SentLog) -> Result<()> {
-    let email: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!("{} Email do destinatário", MAIL))
+    let input: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("{} Email do destinatário (vários separados por vírgula)", MAIL))
+        .interact_text()?;
+    let cc: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("   CC (opcional)")
+        .allow_empty(true)
         .interact_text()?;
     
-    let spinner = ProgressBar::new_spinner();
+    let split = |text: &str| -> Vec<String> { text.split(',').map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect() };
+    let to = split(&input);
+    let Some(email) = to.first() else {
+        return Ok(());
+    };
+    let cc = split(&cc);
+    if !confirm_recent_domains(config, log, &to)? {
+        say!("Cancelado!");
+        return Ok(());
+    }
+    let config = &choose_template(config)?;
+    let chosen = choose_cv_variant(config)?;
+    let (mut config, cv) = match &chosen {
+        Some((config, cv)) => (config.clone(), &cv[..]),
+        None => (config.clone(), cv),
+    };
+    if config.profile.portfolio.is_some() && confirm("Anexar também o portefólio?", false)? {
+        check_portfolio(&mut config)?;
+    }
+    ask_tracking(&mut config)?;
+    if let Some(language) = choose_languages(&config, &to[..1])?.remove(email) {
+        config.language = Some(language);
+    }
+    let config = &config;
+    if to.len() == 1 && cc.is_empty() {
+        return send_one(config, cv, log, email, None).await;
+    }
+    let message = render_message(config, email, cv).with_copies(email, &to[1..], &cc);
+    send_one(config, cv, log, email, Some(message)).await
+}
+
+// `prepared`: mensagem já pronta (reenvio do log, ou com To/CC extra) em vez de renderizar o template
+async fn send_one(
+    config: &Config,
+    cv: &[u8],
+    log: &mut SentLog,
+    email: &str,
+    prepared: Option<SentMessage>,
+) -> Result<()> {
+    // um reenvio sai pela identidade e com a variante do CV com que foi enviado
+    let (profiled, variant_cv);
+    let (config, cv) = match &prepared {
+        Some(message) if message.profile != config.active_profile || message.cv_variant != config.cv_variant => {
+            let mut other = config_for_profile(config, message.profile.as_deref())?;
+            other.cv_variant = message.cv_variant.clone();
+            variant_cv = load_checked_cv(&other)?;
+            profiled = other;
+            (&profiled, &variant_cv[..])
+        }
+        _ => (config, cv),
+    };
+    blocklist::check(email)?;
+    // os outros To e os CC (do `send` ou de um reenvio) também não podem estar bloqueados
+    for address in prepared.iter().flat_map(|m| m.other_to.iter().chain(&m.cc)) {
+        blocklist::check(address)?;
+    }
+    if let Some(message) = &prepared {
+        if message.cv_sha256 != cv_sha256(cv) && !confirm("O CV mudou desde esse envio. Reenviar com o CV actual?", false)? {
+            say!("Cancelado!");
+            return Ok(());
+        }
+    }
+
+    if !undo_window(config.sending.undo_seconds)? {
+        record_cancelled(log, &[email.to_string()])?;
+        git_history::record(&format!("envio para {} desfeito", email));
+        say!("{} Envio para {} desfeito", CROSS, style(email).yellow());
+        return Ok(());
+    }
+    
+    let spinner = if output::verbosity() == 0 { ProgressBar::hidden() } else { ProgressBar::new_spinner() };
     spinner.set_style(
         ProgressStyle::default_spinner()
             .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
             .template("{spinner:.cyan} {msg}")?,
     );
-    spinner.set_message(format!("Enviando para {}...", style(&email).yellow()));
+    spinner.set_message(format!("Enviando para {}...", style(email).yellow()));
     spinner.enable_steady_tick(Duration::from_millis(80));
     
-    let result = send_email(config, &email, cv).await;
+    let (message, result) = match prepared {
+        Some(mut message) => {
+            let result = send_message(config, email, &mut message, cv).await;
+            (message, result)
+        }
+        None => send_email(config, email, cv).await,
+    };
     spinner.finish_and_clear();
     
-    let record = SentRecord {
-        email: email.clone(),
-        sent_at: Local::now(),
-        success: result.is_ok(),
-        error: result.as_ref().err().map(|e| e.to_string()),
-    };
-    log.records.push(record);
-    save_log(log)?;
+    let copies = message.other_to.len() + message.cc.len();
+    let who = if copies > 0 { format!("{} (+{})", email, copies) } else { email.to_string() };
+    record_send(log, config, email, message, &result)?;
+    git_history::record(&match &result {
+        Ok(_) => format!("enviado para {}", who),
+        Err(_) => format!("falhou envio para {}", who),
+    });
     
     match result {
-        Ok(_) => println!("{} Enviado para {}", CHECK, style(&email).green()),
-        Err(e) => println!("{} Falhou {}: {}", CROSS, style(&email).red(), e),
+        Ok(_) => say!("{} Enviado para {}", CHECK, style(&who).green()),
+        Err(e) => {
+            eprintln!("{} Falhou {}: {}", CROSS, style(email).red(), e);
+            if let Some(transcript) = log.records.last().and_then(|r| r.smtp_transcript.as_ref()) {
+                eprintln!("   {} linhas do diálogo SMTP guardadas em {}", transcript.len(), LOG_FILE);
+            }
+        }
     }
     
     Ok(())
 }
 
+// os endereços que houver no texto copiado, marcados todos; desmarca-se o que não interessa
+fn emails_from_clipboard() -> Result<Vec<String>> {
+    let text = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .context("Cannot read the clipboard")?;
+    let found = contacts::extract_emails(&text);
+    if found.is_empty() {
+        return Ok(found);
+    }
+    println!("{} {} emails na área de transferência", NOTE, style(found.len()).cyan());
+    let chosen = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Quais enviar? (espaço marca/desmarca, enter confirma)")
+        .items(&found)
+        .defaults(&vec![true; found.len()])
+        .interact()?;
+    Ok(chosen.into_iter().map(|i| found[i].clone()).collect())
+}
+
 async fn send_bulk(config: &Config, cv: &[u8], log: &mut SentLog) -> Result<()> {
-    println!("{} Insere os emails (um por linha, linha vazia para terminar):", MAIL);
-    
-    let mut emails: Vec<String> = vec![];
-    loop {
-        let input: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt(format!("  [{}]", emails.len() + 1))
-            .allow_empty(true)
-            .interact_text()?;
+    let source = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("De onde vêm os emails?")
+        .items(&["Escrever um a um", "Ler da área de transferência"])
+        .default(0)
+        .interact()?;
+    let mut emails: Vec<String> = if source == 1 { emails_from_clipboard()? } else { vec![] };
+    if source == 0 {
+        println!("{} Insere os emails (um por linha, linha vazia para terminar):", MAIL);
+        loop {
+            let input: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("  [{}]", emails.len() + 1))
+                .allow_empty(true)
+                .interact_text()?;
         
-        if input.is_empty() { break; }
-        if input.contains('@') {
-            emails.push(input);
-        } else {
-            println!("   {} Email inválido, ignorado", CROSS);
+            if input.is_empty() { break; }
+            if let Some(first) = emails.iter().find(|e| contacts::same_email(e, &input)) {
+                println!("   {} Repetido ({}), ignorado", CROSS, first);
+            } else if input.contains('@') {
+                emails.push(input);
+            } else {
+                println!("   {} Email inválido, ignorado", CROSS);
+            }
         }
     }
     
@@ -224,35 +1733,89 @@ async fn send_bulk(config: &Config, cv: &[u8], log: &mut SentLog) -> Result<()>
         println!("{} Nenhum email inserido!", CROSS);
         return Ok(());
     }
+    if !confirm_recent_domains(config, log, &emails)? {
+        println!("Cancelado!");
+        return Ok(());
+    }
+    let config = &choose_template(config)?;
+    let chosen = choose_cv_variant(config)?;
+    let mut config = chosen.map_or_else(|| config.clone(), |(config, _)| config);
+    if config.profile.portfolio.is_some() && confirm("Anexar também o portefólio?", false)? {
+        check_portfolio(&mut config)?;
+    }
+    ask_tracking(&mut config)?;
+    let languages = choose_languages(&config, &emails)?;
+    let config = &config;
     
-    let min_delay: u64 = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!("{} Delay mínimo entre envios (segundos)", CLOCK))
-        .default(30)
-        .interact_text()?;
-    
-    let max_delay: u64 = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!("{} Delay máximo entre envios (segundos)", CLOCK))
-        .default(60)
-        .interact_text()?;
+    let delay = match &config.sending.delay {
+        Some(delay) => delay.clone(),
+        None => {
+            let min: u64 = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("{} Delay mínimo entre envios (segundos)", CLOCK))
+                .default(30)
+                .interact_text()?;
+            
+            let max: u64 = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("{} Delay máximo entre envios (segundos)", CLOCK))
+                .default(60)
+                .interact_text()?;
+            delay::Delay::Uniform { min, max }
+        }
+    };
     
     println!();
-    println!("{} Bulk send: {} emails, delay {}s-{}s", 
+    println!("{} Bulk send: {} emails, delay {}", 
         ROCKET, 
         style(emails.len()).cyan(),
-        style(min_delay).yellow(),
-        style(max_delay).yellow()
+        style(delay.describe()).yellow()
     );
+    if let Some(room) = outbox::room_today(&config.sending, log, Local::now()).filter(|room| *room < emails.len()) {
+        println!("{} Limite diário: {} hoje, {} ficam na fila para os dias seguintes", CLOCK, room, emails.len() - room);
+    }
     
-    if !Confirm::with_theme(&ColorfulTheme::default())
-        .with_prompt("Confirmar envio?")
-        .default(true)
-        .interact()? 
-    {
+    if !confirm("Confirmar envio?", true)? {
         println!("Cancelado!");
         return Ok(());
     }
     
-    let pb = ProgressBar::new(emails.len() as u64);
+    if !undo_window(config.sending.undo_seconds)? {
+        record_cancelled(log, &emails)?;
+        git_history::record(&format!("bulk de {} emails desfeito", emails.len()));
+        println!("{} Envio desfeito, nada foi enviado", CROSS);
+        return Ok(());
+    }
+    
+    let batch: Vec<(String, Config)> = emails
+        .iter()
+        .map(|e| {
+            let mut config = config.clone();
+            config.language = languages.get(e).cloned();
+            (e.clone(), config)
+        })
+        .collect();
+    let batch = outbox::carry_over(batch, &config.sending, log, &delay)?;
+    let (success, failed) = send_batch(cv, log, &batch, &delay).await?;
+    git_history::record(&format!("enviados {} emails ({} falhados)", success, failed));
+    
+    say!();
+    say!("{} Resultado: {} enviados, {} falhados", 
+        SPARKLE,
+        style(success).green().bold(),
+        style(failed).red().bold()
+    );
+    
+    Ok(())
+}
+
+// envia cada (destinatário, config) com o `delay` entre envios; devolve (ok, falhados).
+// quem leva um 450/451 (greylisting) volta à fila para uma segunda tentativa uns minutos depois
+async fn send_batch(
+    cv: &[u8],
+    log: &mut SentLog,
+    batch: &[(String, Config)],
+    delay: &delay::Delay,
+) -> Result<(usize, usize)> {
+    let pb = if output::verbosity() == 0 { ProgressBar::hidden() } else { ProgressBar::new(batch.len() as u64) };
     pb.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {msg}")?
@@ -261,58 +1824,188 @@ async fn send_bulk(config: &Config, cv: &[u8], log: &mut SentLog) -> Result<()>
     
     let mut success = 0;
     let mut failed = 0;
+    let mut dead = dead::load_dead();
+    let blocklist = blocklist::load_blocklist();
+    let mut pending: VecDeque<&(String, Config)> = batch.iter().collect();
+    let mut deferred: Vec<(Instant, &(String, Config))> = vec![];
+    let mut first = true;
+    let mut throttle = delay::Throttle::default();
+    let _interruptible = Interruptible::start();
+    if console::user_attended() && !ASSUME_YES.load(Ordering::Relaxed) && batch.len() > 1 {
+        pb.println(format!("  {} p ou espaço pausa depois do envio em curso", CLOCK));
+    }
+    // variantes do CV pedidas pelas linhas do mail-merge, lidas uma vez
+    let mut variants: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    // o que falta quando o lote pára a meio vai para o resume.json
+    let stop = |pb: &ProgressBar, unsent: Vec<&(String, Config)>| -> Result<()> {
+        outbox::save_resume(&unsent)?;
+        let emails: Vec<&str> = unsent.iter().map(|(e, _)| e.as_str()).collect();
+        pb.println(format!("  {} Parado, ficam por enviar: {}", CROSS, emails.join(", ")));
+        pb.println(format!("  {} `job-mailer resume` continua daqui", CLOCK));
+        pb.abandon_with_message("Parado");
+        Ok(())
+    };
     
-    for (i, email) in emails.iter().enumerate() {
-        pb.set_message(format!("→ {}", email));
+    loop {
+        // Ctrl-C durante o envio anterior: já ficou no log, o resto espera
+        if interrupted() {
+            stop(&pb, pending.iter().copied().chain(deferred.iter().map(|(_, item)| *item)).collect())?;
+            return Ok((success, failed));
+        }
+        // as novas tentativas que já chegaram à hora passam à frente
+        let due = deferred.iter().position(|(at, _)| *at <= Instant::now());
+        let (item, retry, waited) = if let Some(pos) = due {
+            (deferred.remove(pos).1, true, false)
+        } else if let Some(item) = pending.pop_front() {
+            (item, false, false)
+        } else if let Some(pos) = (0..deferred.len()).min_by_key(|&i| deferred[i].0) {
+            let (at, item) = deferred.remove(pos);
+            pb.set_message(format!("Aguardando nova tentativa para {}...", item.0));
+            sleep_interruptible(at.saturating_duration_since(Instant::now())).await;
+            if interrupted() {
+                stop(&pb, std::iter::once(item).chain(deferred.iter().map(|(_, item)| *item)).collect())?;
+                return Ok((success, failed));
+            }
+            (item, true, true)
+        } else {
+            break;
+        };
+        let (email, config) = item;
         
-        let result = send_email(config, email, cv).await;
+        if dead.contains(email) {
+            pb.println(format!("  {} {} - endereço morto, ignorado", CROSS, style(email).dim()));
+            pb.inc(1);
+            continue;
+        }
+        if let Some(pattern) = blocklist.matching(email) {
+            pb.println(format!("  {} {} - bloqueado ({}), ignorado", CROSS, style(email).dim(), pattern));
+            pb.inc(1);
+            continue;
+        }
+        
+        // delay entre envios (exceto antes do primeiro)
+        if !first && !waited {
+            let delay = throttle.scale(delay.next(&mut rand::thread_rng()));
+            pb.set_message(format!("Aguardando {}s...", delay));
+            if wait_or_pause(delay).await? {
+                let stopped = interrupted() || {
+                    let left = pending.len() + deferred.len() + 1;
+                    let choice = pb.suspend(|| {
+                        Select::with_theme(&ColorfulTheme::default())
+                            .with_prompt(format!("{} Pausado, {} por enviar", CLOCK, left))
+                            .items(&["Continuar", "Parar aqui"])
+                            .default(0)
+                            .interact()
+                    })?;
+                    choice == 1
+                };
+                if stopped {
+                    let unsent = std::iter::once(item).chain(pending.iter().copied()).chain(deferred.iter().map(|(_, item)| *item));
+                    stop(&pb, unsent.collect())?;
+                    return Ok((success, failed));
+                }
+            }
+        }
+        first = false;
+        pb.set_message(format!("→ {}", email));
         
-        let record = SentRecord {
-            email: email.clone(),
-            sent_at: Local::now(),
-            success: result.is_ok(),
-            error: result.as_ref().err().map(|e| e.to_string()),
+        let cv = match &config.cv_variant {
+            Some(name) => {
+                if !variants.contains_key(name) {
+                    variants.insert(name.clone(), load_cv_variant(config)?);
+                }
+                &variants[name][..]
+            }
+            None => cv,
         };
-        log.records.push(record);
-        save_log(log)?;
+        let (message, result) = send_email(config, email, cv).await;
+        if let Err(e) = &result {
+            let error = failure::SendError::classify(&format!("{:#}", e), None);
+            // rate limit: abranda o resto do lote em vez de insistir até a conta ser bloqueada
+            if error.is_rate_limited() && !retry {
+                let factor = throttle.slow_down();
+                deferred.push((Instant::now(), item));
+                pb.println(format!("  {} {} - o servidor pede para abrandar, nova tentativa mais tarde", CLOCK, style(email).yellow()));
+                pb.suspend(|| notify::show("Envio abrandado", &format!("{} ({}): delays agora x{}", error.message, email, factor)));
+                continue;
+            }
+            if error.is_greylisted() && !retry {
+                let [min, max] = config.sending.greylist_retry_minutes.unwrap_or([10, 15]);
+                let minutes = rand::thread_rng().gen_range(min..=max.max(min));
+                deferred.push((Instant::now() + Duration::from_secs(minutes * 60), item));
+                pb.println(format!("  {} {} - greylisting, nova tentativa em {} min", CLOCK, style(email).yellow(), minutes));
+                continue;
+            }
+        }
+        record_send(log, config, email, message, &result)?;
         
         match result {
             Ok(_) => {
                 success += 1;
                 pb.println(format!("  {} {}", CHECK, style(email).green()));
+                if let Some(factor) = throttle.accepted() {
+                    pb.println(format!("  {} delays de volta a x{}", CLOCK, factor));
+                }
             }
             Err(e) => {
                 failed += 1;
-                pb.println(format!("  {} {} - {}", CROSS, style(email).red(), e));
+                pb.suspend(|| eprintln!("  {} {} - {}", CROSS, style(email).red(), e));
+                // 5xx "user unknown": não vale a pena voltar a tentar este endereço
+                let error = log.records.last().and_then(|r| r.error.as_ref());
+                if let Some(error) = error.filter(|e| dead::is_dead(e)) {
+                    if dead.add(email, &error.message) {
+                        dead::save_dead(&dead)?;
+                        git_history::record(&format!("{} marcado como endereço morto", email));
+                    }
+                }
             }
         }
         
         pb.inc(1);
-        
-        // delay random entre envios (exceto no último)
-        if i < emails.len() - 1 {
-            let delay = rand::thread_rng().gen_range(min_delay..=max_delay);
-            pb.set_message(format!("Aguardando {}s...", delay));
-            tokio::time::sleep(Duration::from_secs(delay)).await;
-        }
     }
     
     pb.finish_with_message("Concluído!");
-    
-    println!();
-    println!("{} Resultado: {} enviados, {} falhados", 
-        SPARKLE,
-        style(success).green().bold(),
-        style(failed).red().bold()
-    );
-    
-    Ok(())
+    Ok((success, failed))
+}
+
+fn log_line(r: &SentRecord) -> String {
+    let status = if r.sandbox {
+        style("TEST").magenta()
+    } else if r.success { 
+        style("OK").green() 
+    } else if r.cancelled {
+        style("UNDO").dim()
+    } else { 
+        style("FAIL").red() 
+    };
+    let mut extra = String::new();
+    if r.status != ApplicationStatus::Sent {
+        extra = format!(" ({})", style(r.status.label()).cyan());
+    }
+    if let Some(i) = &r.interview {
+        extra.push_str(&format!(" {} {}", CLOCK, i.at.format("%d/%m %H:%M")));
+    }
+    if let Some(e) = &r.error {
+        extra.push_str(&format!(" {}", style(e.kind.label()).red().dim()));
+    }
+    if r.notes.is_some() {
+        extra.push_str(&format!(" {}", NOTE));
+    }
+    let copies = r.message.as_ref().map_or(0, |m| m.other_to.len() + m.cc.len());
+    let email = if copies > 0 { format!("{} (+{})", r.email, copies) } else { r.email.clone() };
+    format!("[{}] {} - {}{}", status, r.sent_at.format("%d/%m %H:%M"), email, extra)
+}
+
+// texto vazio apaga a nota
+fn normalize_notes(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
 }
 
-fn view_log(log: &SentLog) {
+async fn view_log(config: &Config, cv: &[u8], log: &mut SentLog) -> Result<()> {
     if log.records.is_empty() {
         println!("{} Nenhum email enviado ainda.", MAIL);
-        return;
+        return Ok(());
     }
     
     println!();
@@ -320,23 +2013,112 @@ fn view_log(log: &SentLog) {
     println!("{}", style("─".repeat(60)).dim());
     
     for r in log.records.iter().rev().take(20) {
-        let status = if r.success { 
-            style("OK").green() 
-        } else { 
-            style("FAIL").red() 
-        };
-        println!("  [{}] {} - {}", status, r.sent_at.format("%d/%m %H:%M"), r.email);
+        println!("  {}", log_line(r));
+        if let Some(notes) = &r.notes {
+            for line in notes.lines() {
+                println!("      {}", style(line).dim());
+            }
+        }
     }
     println!("{}", style("─".repeat(60)).dim());
+    
+    if !console::user_attended() {
+        return Ok(());
+    }
+    let shown = log.records.len().min(20);
+    let mut items: Vec<String> = log.records.iter().rev().take(shown).map(log_line).collect();
+    items.push("↩  Voltar".to_string());
+    
+    let sel = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Escolhe um envio")
+        .items(&items)
+        .default(shown)
+        .interact()?;
+    if sel == shown {
+        return Ok(());
+    }
+    let index = log.records.len() - 1 - sel;
+    
+    let actions = [
+        format!("{} Editar notas", NOTE),
+        format!("{} Reenviar este email", MAIL),
+        format!("{} Abrir a mensagem enviada (.eml)", MAIL),
+        "↩  Voltar".to_string(),
+    ];
+    let action = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(&log.records[index].email)
+        .items(&actions)
+        .default(0)
+        .interact()?;
+    
+    match action {
+        0 => {
+            let (email, sent_at) = (log.records[index].email.clone(), log.records[index].sent_at);
+            let current = log.records[index].notes.clone().unwrap_or_default();
+            if let Some(text) = Editor::new().extension(".txt").edit(&current)? {
+                // o log pode ter mudado enquanto o editor esteve aberto: o registo vai-se buscar de novo
+                update_log(log, |log| {
+                    let record = log
+                        .records
+                        .iter_mut()
+                        .find(|r| r.sent_at == sent_at && r.email == email)
+                        .with_context(|| format!("Record for {} no longer in the log", email))?;
+                    record.notes = normalize_notes(&text);
+                    Ok(())
+                })?;
+                git_history::record(&format!("notas de {} editadas", email));
+                println!("{} Notas guardadas para {}", CHECK, style(email).green());
+            }
+        }
+        1 => {
+            let email = log.records[index].email.clone();
+            match log.records[index].message.clone() {
+                Some(message) => send_one(config, cv, log, &email, Some(message)).await?,
+                None => println!("{} Este envio é anterior ao registo das mensagens, não dá para reenviar igual", CROSS),
+            }
+        }
+        2 => match log.records[index].message.as_ref().and_then(|m| m.eml.as_deref()) {
+            Some(path) => open_with_default_app(path)?,
+            None => println!("{} Sem cópia .eml deste envio (activa \"archive\" em sending)", CROSS),
+        },
+        _ => {}
+    }
+    Ok(())
+}
+
+// xdg-open/open/start: abre no cliente de email por omissão do sistema
+fn open_with_default_app(path: &str) -> Result<()> {
+    if !std::path::Path::new(path).is_file() {
+        anyhow::bail!("{} not found", path);
+    }
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    cmd.arg(path)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("Cannot open {}", path))?;
+    Ok(())
 }
 
 fn preview_email(config: &Config) {
-    let (subj, body) = build_email(config);
+    let (subj, body) = build_email(config, "", None);
     
     println!();
     println!("{} Preview do email:", MAIL);
     println!("{}", style("─".repeat(50)).dim());
     println!("{}: {}", style("Subject").cyan(), subj);
+    if !subj.is_ascii() {
+        let encoded = headers::encode("Subject", &subj, config.sending.subject_encoding);
+        println!("{}", style(format!("Subject: {}", encoded.replace("\r\n", "\n"))).dim());
+    }
     println!("{}", style("─".repeat(50)).dim());
     println!("{}", body);
     println!("{}", style("─".repeat(50)).dim());
@@ -346,24 +2128,38 @@ fn preview_email(config: &Config) {
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
+    let cli = cli::Cli::parse();
+    output::set_verbosity(cli.quiet, cli.verbose);
+    ASSUME_YES.store(cli.yes, Ordering::Relaxed);
+    git_history::record_config_changes();
+    if cli.command.is_some() {
+        return cli::run(cli).await;
+    }
     print_banner();
     
     // check config exists
-    if !std::path::Path::new(CONFIG_FILE).exists() {
+    if !std::path::Path::new(&overrides::path(CONFIG_FILE)).exists() {
         println!("{} config.json não encontrado!", CROSS);
         return Ok(());
     }
     
-    let config = load_config()?;
-    println!("{} Config carregado: {}", CHECK, style(&config.profile.name).green());
+    let mut config = load_profile_config(cli.profile.as_deref())?;
+    config.sending.sandbox |= cli.sandbox;
+    match &config.active_profile {
+        Some(name) => println!("{} Config carregado: {} (perfil {})", CHECK, style(&config.profile.name).green(), style(name).cyan()),
+        None => println!("{} Config carregado: {}", CHECK, style(&config.profile.name).green()),
+    }
+    if config.sending.sandbox {
+        println!("{} Modo sandbox: os emails vão para {}/ em vez de serem enviados", SPARKLE, overrides::path(SANDBOX_DIR));
+    }
     
     // check cv exists
-    if !std::path::Path::new(CV_FILE).exists() {
+    if !std::path::Path::new(&overrides::path(CV_FILE)).exists() {
         println!("{} cv.pdf não encontrado! Coloca o ficheiro na pasta.", CROSS);
         return Ok(());
     }
-    let cv = load_cv()?;
-    println!("{} CV carregado: {}KB", CHECK, style(cv.len() / 1024).cyan());
+    let cv = load_checked_cv(&config)?;
+    println!("{} CV carregado: {}", CHECK, style(cv::summary(&cv::inspect(&cv), cv.len())).cyan());
     
     let mut log = load_log();
     print_stats(&log);
@@ -388,8 +2184,8 @@ async fn main() -> Result<()> {
         match sel {
             0 => send_single(&config, &cv, &mut log).await?,
             1 => send_bulk(&config, &cv, &mut log).await?,
-            2 => preview_email(&config),
-            3 => view_log(&log),
+            2 => preview_email(&choose_template(&config)?),
+            3 => view_log(&config, &cv, &mut log).await?,
             4 => {
                 println!("{} Até a próxima mano!", SPARKLE);
                 break;
@@ -407,7 +2203,7 @@ async fn main() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::{env, fs, io::Write};
+    use std::{env, fs, sync::Mutex};
     use tempfile::TempDir;
 
     fn setup_test_env() -> TempDir {
@@ -424,15 +2220,55 @@ mod tests {
                 experience_years: 5,
                 linkedin: Some("linkedin.com/in/joao".to_string()),
                 github: Some("github.com/joao".to_string()),
+                pgp_key: None,
+                smime_cert: None,
+                smime_key: None,
+                smime_chain: None,
+                display_name: None,
+                reply_to: None,
+                signature: None,
+                cvs: BTreeMap::new(),
+                portfolio: None,
             },
             smtp: SmtpConfig {
                 host: "smtp.example.com".to_string(),
                 port: 587,
+                security: SmtpSecurity::Starttls,
+                accept_invalid_certs: false,
             },
             template: EmailTemplate {
                 subject: "Candidatura - {{name}} - {{title}}".to_string(),
+                subjects: vec![],
                 body: "Olá,\nNome: {{name}}\nEmail: {{email}}\nSkills: {{skills}}\nLinkedIn: {{linkedin}}".to_string(),
+                html: None,
+                language: None,
+                languages: BTreeMap::new(),
             },
+            templates: BTreeMap::new(),
+            campaigns: BTreeMap::new(),
+            sending: SendingConfig::default(),
+            sync: None,
+            git_history: false,
+            retention: None,
+            oauth: None,
+            drafting: None,
+            replies: Default::default(),
+            secrets: Default::default(),
+            imap: None,
+            links: None,
+            tracking: None,
+            notifications: None,
+            profiles: BTreeMap::new(),
+            template_version: None,
+            subject_lines: None,
+            active_profile: None,
+            cv_variant: None,
+            attach_portfolio: false,
+            no_track: false,
+            language: None,
+            template_name: None,
+            campaign: None,
+            sequence_step: 0,
         };
 
         let config_path = temp_dir.path().join(CONFIG_FILE);
@@ -449,8 +2285,12 @@ mod tests {
         temp_dir
     }
 
+    // o cwd é global ao processo, os testes que o mudam não podem correr em paralelo
+    static CWD_LOCK: Mutex<()> = Mutex::new(());
+
     macro_rules! with_temp_dir {
         ($temp_dir:expr, $body:expr) => {{
+            let _guard = CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
             let old_dir = std::env::current_dir().unwrap();
             std::env::set_current_dir($temp_dir.path()).unwrap();
             let result = $body;
@@ -470,11 +2310,20 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_smtp_security_defaults_to_starttls() {
+        let smtp: SmtpConfig = serde_json::from_str(r#"{"host":"smtp.gmail.com","port":587}"#).unwrap();
+        assert_eq!(smtp.security, SmtpSecurity::Starttls);
+
+        let smtp: SmtpConfig = serde_json::from_str(r#"{"host":"h","port":465,"security":"tls"}"#).unwrap();
+        assert_eq!(smtp.security, SmtpSecurity::Tls);
+    }
+
     #[test]
     fn test_load_cv() {
         let temp_dir = setup_test_env();
         with_temp_dir!(temp_dir, {
-            let cv = load_cv().unwrap();
+            let cv = load_cv(false).unwrap();
             assert!(cv.len() > 10);
         });
     }
@@ -498,6 +2347,7 @@ mod tests {
                 sent_at: Local::now(),
                 success: true,
                 error: None,
+                ..Default::default()
             });
             save_log(&log).unwrap();
 
@@ -505,6 +2355,43 @@ mod tests {
             assert_eq!(loaded.records.len(), 1);
             assert_eq!(loaded.records[0].email, "test@example.com");
             assert!(loaded.records[0].success);
+            // a troca é feita por rename: não fica temporário para trás
+            assert!(!std::path::Path::new(".sent_log.json.tmp").exists());
+        });
+    }
+
+    #[test]
+    fn test_unversioned_log_is_migrated() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let old = r#"{"records": [{"email": "a@x.com", "sent_at": "2024-07-01T09:00:00+01:00", "success": false, "error": "permanent error (550): mailbox unavailable"}]}"#;
+            fs::write(LOG_FILE, old).unwrap();
+
+            let mut log = load_log();
+            let error = log.records[0].error.clone().unwrap();
+            assert_eq!(error.kind, failure::ErrorKind::RecipientRejected);
+            assert_eq!(error.code, Some(550));
+
+            update_log(&mut log, |_| Ok(())).unwrap();
+            let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(LOG_FILE).unwrap()).unwrap();
+            assert_eq!(saved["version"], 1);
+            assert_eq!(saved["records"][0]["error"]["kind"], "recipient_rejected");
+        });
+    }
+
+    #[test]
+    fn test_corrupt_log_is_kept_aside() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            fs::write(LOG_FILE, "{\"records\": [{\"email\": \"a@x.com\", \"sent_").unwrap();
+            assert!(load_log().records.is_empty());
+            let copies: Vec<String> = fs::read_dir(".")
+                .unwrap()
+                .filter_map(|e| e.ok()?.file_name().into_string().ok())
+                .filter(|n| n.starts_with("sent_log.json.corrupt-"))
+                .collect();
+            assert_eq!(copies.len(), 1);
+            assert!(fs::read_to_string(&copies[0]).unwrap().contains("a@x.com"));
         });
     }
 
@@ -513,7 +2400,7 @@ mod tests {
         let temp_dir = setup_test_env();
         with_temp_dir!(temp_dir, {
             let config = load_config().unwrap();
-            let (subject, body) = build_email(&config);
+            let (subject, body) = build_email(&config, "", None);
 
             assert_eq!(subject, "Candidatura - João Silva - Desenvolvedor Rust");
             assert!(body.contains("João Silva"));
@@ -536,27 +2423,494 @@ mod tests {
                 experience_years: 3,
                 linkedin: None,
                 github: None,
+                pgp_key: None,
+                smime_cert: None,
+                smime_key: None,
+                smime_chain: None,
+                display_name: None,
+                reply_to: None,
+                signature: None,
+                cvs: BTreeMap::new(),
+                portfolio: None,
             },
             smtp: SmtpConfig {
                 host: "host".to_string(),
                 port: 25,
+                security: SmtpSecurity::None,
+                accept_invalid_certs: false,
             },
             template: EmailTemplate {
                 subject: "{{name}} - {{title}}".to_string(),
+                subjects: vec![],
                 body: "{{linkedin}} {{github}} {{experience_years}}".to_string(),
+                html: None,
+                language: None,
+                languages: BTreeMap::new(),
             },
+            templates: BTreeMap::new(),
+            campaigns: BTreeMap::new(),
+            sending: SendingConfig::default(),
+            sync: None,
+            git_history: false,
+            retention: None,
+            oauth: None,
+            drafting: None,
+            replies: Default::default(),
+            secrets: Default::default(),
+            imap: None,
+            links: None,
+            tracking: None,
+            notifications: None,
+            profiles: BTreeMap::new(),
+            template_version: None,
+            subject_lines: None,
+            active_profile: None,
+            cv_variant: None,
+            attach_portfolio: false,
+            no_track: false,
+            language: None,
+            template_name: None,
+            campaign: None,
+            sequence_step: 0,
         };
 
-        let (subject, body) = build_email(&config);
+        let (subject, body) = build_email(&config, "", None);
         assert_eq!(subject, "Ana - Dev");
         assert_eq!(body, "N/A N/A 3");
     }
 
+    #[test]
+    fn test_build_email_with_contact_company() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            config.template.subject = "{{title}} na {{company}}".to_string();
+            config.template.body = "Olá equipa {{company}}".to_string();
+            let contact = Contact {
+                email: "rh@acme.com".to_string(),
+                company: Some("Acme".to_string()),
+                ..Default::default()
+            };
+
+            let (subject, body) = build_email(&config, "rh@acme.com", Some(&contact));
+            assert_eq!(subject, "Desenvolvedor Rust na Acme");
+            assert_eq!(body, "Olá equipa Acme");
+
+            // sem empresa conhecida, cai no nome derivado do domínio
+            let (subject, _) = build_email(&config, "jobs@acme-corp.com", None);
+            assert_eq!(subject, "Desenvolvedor Rust na Acme Corp");
+        });
+    }
+
+    #[test]
+    fn test_build_email_placeholder_fallbacks() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            config.profile.github = None;
+            config.template.subject = "{{title|Dev}} na {{company|vossa empresa}}".to_string();
+            config.template.body = "Olá {{recruiter_name|equipa de recrutamento}}, {{github|}}{{github}} {{team}}".to_string();
+
+            let (subject, body) = build_email(&config, "joana@gmail.com", None);
+            assert_eq!(subject, "Desenvolvedor Rust na vossa empresa");
+            assert_eq!(body, "Olá equipa de recrutamento, N/A {{team}}");
+        });
+    }
+
+    #[test]
+    fn test_html_body_escapes_values() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            config.profile.summary = "Backend <Rust> & Go".to_string();
+            config.profile.signature = Some("João & Co".to_string());
+            config.template.body = "Olá {{company}}, {{summary}}".to_string();
+            config.template.html = Some("<html><body><p>Olá <b>{{company}}</b>, {{summary}}</p></body></html>".to_string());
+            let contact = Contact { company: Some("Tom & Jerry".to_string()), ..Default::default() };
+
+            let rendered = render_email(&config, "rh@acme.com", Some(&contact));
+            assert_eq!(rendered.body, "Olá Tom & Jerry, Backend <Rust> & Go\n\n-- \nJoão & Co");
+            assert_eq!(
+                rendered.html.as_deref(),
+                Some(
+                    "<html><body><p>Olá <b>Tom &amp; Jerry</b>, Backend &lt;Rust&gt; &amp; Go</p>\
+                     <p style=\"white-space: pre-wrap\">-- \nJoão &amp; Co</p></body></html>"
+                )
+            );
+
+            // só HTML: o texto simples sai dele, com os links por extenso
+            config.template.body = String::new();
+            config.template.html = Some("<p>Olá <b>{{company}}</b>,</p><p>Ver <a href=\"{{github}}\">GitHub</a></p>".to_string());
+            config.profile.github = Some("https://github.com/joao".to_string());
+            let rendered = render_email(&config, "rh@acme.com", Some(&contact));
+            assert_eq!(rendered.body, "Olá Tom & Jerry,\n\nVer GitHub (https://github.com/joao)\n\n-- \nJoão & Co");
+        });
+    }
+
+    #[test]
+    fn test_build_email_picks_contact_language() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            config.template.subject = "Candidatura - {{title}}".to_string();
+            config.template.body = "Olá".to_string();
+            config.template.languages.insert("en".to_string(), LanguageTemplate {
+                subject: "Application - {{title}}".to_string(),
+                body: "Hello".to_string(),
+                html: None,
+                signature: Some("Best regards".to_string()),
+            });
+            let contact = Contact { language: Some("EN".to_string()), ..Default::default() };
+
+            let (subject, body) = build_email(&config, "hr@acme.com", Some(&contact));
+            assert_eq!(subject, "Application - Desenvolvedor Rust");
+            assert_eq!(body, "Hello\n\n-- \nBest regards");
+
+            // a coluna do mail-merge manda sobre o contacto; língua sem variante cai no base
+            config.language = Some("fr".to_string());
+            let (subject, body) = build_email(&config, "hr@acme.com", Some(&contact));
+            assert_eq!(subject, "Candidatura - Desenvolvedor Rust");
+            assert_eq!(body, "Olá");
+            assert!(!config.template.has_language("fr"));
+            assert!(config.template.has_language("PT"));
+        });
+    }
+
+    #[test]
+    fn test_build_email_tags_my_links() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            config.template.subject = "{{title}} - {{github}}".to_string();
+            config.template.body = "{{github}}\nhttps://joao.dev/cases\nhttps://acme.com/vaga".to_string();
+            config.links = Some(links::LinkTagging {
+                params: "ref=application&company={{company}}".to_string(),
+                domains: vec!["joao.dev".to_string()],
+            });
+            let contact = Contact { company: Some("Acme Corp".to_string()), ..Default::default() };
+
+            let (subject, body) = build_email(&config, "rh@acme.com", Some(&contact));
+            assert_eq!(subject, "Desenvolvedor Rust - github.com/joao");
+            assert_eq!(
+                body,
+                "github.com/joao?ref=application&company=Acme%20Corp\n\
+                 https://joao.dev/cases?ref=application&company=Acme%20Corp\n\
+                 https://acme.com/vaga"
+            );
+        });
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            let mut freelance = config.profile.clone();
+            freelance.email = "joao@freelance.dev".to_string();
+            freelance.signature = Some("João Silva\nfreelance.dev".to_string());
+            let smtp = SmtpConfig {
+                host: "smtp.freelance.dev".to_string(),
+                port: 465,
+                security: SmtpSecurity::Tls,
+                accept_invalid_certs: false,
+            };
+            config.profiles.insert(
+                "freelance".to_string(),
+                NamedProfile { profile: freelance, smtp: Some(smtp), imap: None },
+            );
+            assert!(config.clone().select_profile("outro").is_err());
+
+            config.select_profile("freelance").unwrap();
+            assert_eq!(config.active_profile.as_deref(), Some("freelance"));
+            assert_eq!(config.profile.email, "joao@freelance.dev");
+            assert_eq!(config.smtp.host, "smtp.freelance.dev");
+
+            let (_, body) = build_email(&config, "rh@acme.com", None);
+            assert!(body.ends_with("\n\n-- \nJoão Silva\nfreelance.dev"));
+            assert_eq!(render_message(&config, "rh@acme.com", b"cv").profile.as_deref(), Some("freelance"));
+        });
+    }
+
+    #[test]
+    fn test_account_var_names() {
+        assert_eq!(account_var_names(&["SMTP_USER"], None), vec!["SMTP_USER"]);
+        assert_eq!(
+            account_var_names(&["IMAP_USER", "SMTP_USER"], Some("free-lance")),
+            vec!["IMAP_USER_FREE_LANCE", "SMTP_USER_FREE_LANCE", "IMAP_USER", "SMTP_USER"]
+        );
+    }
+
+    #[test]
+    fn test_record_cancelled() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut log = load_log();
+            record_cancelled(&mut log, &["rh@acme.com".to_string()]).unwrap();
+
+            let loaded = load_log();
+            assert_eq!(loaded.records.len(), 1);
+            assert!(loaded.records[0].cancelled);
+            assert!(!loaded.records[0].success);
+        });
+    }
+
+    #[test]
+    fn test_old_log_records_load_without_cancelled() {
+        let json = r#"{"records":[{"email":"a@b.com","sent_at":"2024-01-15T10:30:00+01:00","success":true,"error":null}]}"#;
+        let log: SentLog = serde_json::from_str(json).unwrap();
+        assert!(!log.records[0].cancelled);
+    }
+
+    #[test]
+    fn test_old_log_error_text_is_classified() {
+        let json = r#"{"records":[{"email":"a@b.com","sent_at":"2024-01-15T10:30:00+01:00","success":false,"error":"permanent error (550): 5.1.1 User unknown"}]}"#;
+        let log: SentLog = serde_json::from_str(json).unwrap();
+        let error = log.records[0].error.as_ref().unwrap();
+        assert_eq!(error.kind, failure::ErrorKind::RecipientRejected);
+        assert_eq!(error.code, Some(550));
+    }
+
+    #[test]
+    fn test_with_copies_dedups() {
+        let list = |items: &[&str]| items.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let message = SentMessage::default().with_copies(
+            "joana@x.com",
+            &list(&["rh@x.com", " JOANA@x.com"]),
+            &list(&["talent@x.com", "rh@x.com", ""]),
+        );
+        assert_eq!(message.other_to, vec!["rh@x.com"]);
+        assert_eq!(message.cc, vec!["talent@x.com"]);
+
+        let record = SentRecord { email: "joana@x.com".to_string(), message: Some(message), ..Default::default() };
+        assert!(record.involves("Talent@x.com"));
+        assert!(!record.involves("outro@x.com"));
+    }
+
+    #[test]
+    fn test_latest_message_includes_failed_sends() {
+        let message = |subject: &str| SentMessage { subject: subject.to_string(), ..Default::default() };
+        let log = SentLog {
+            records: vec![
+                SentRecord { email: "rh@acme.com".to_string(), success: true, message: Some(message("v1")), ..Default::default() },
+                SentRecord { email: "RH@acme.com".to_string(), message: Some(message("v2")), ..Default::default() },
+                SentRecord { email: "rh@acme.com".to_string(), cancelled: true, ..Default::default() },
+            ],
+        };
+        assert_eq!(log.latest_message("rh@acme.com").unwrap().subject, "v2");
+        assert!(log.latest_message("outro@acme.com").is_none());
+    }
+
+    #[test]
+    fn test_recent_at_domain() {
+        let at = |when: &str| interview::parse_interview_time(when).unwrap();
+        let sent = |email: &str, when: &str| SentRecord { email: email.to_string(), sent_at: at(when), success: true, ..Default::default() };
+        let log = SentLog {
+            records: vec![
+                sent("rh@acme.com", "2024-07-01 09:00"),
+                sent("jobs@ACME.com", "2024-06-01 09:00"),
+                sent("ana@gmail.com", "2024-07-01 09:00"),
+                SentRecord { sandbox: true, ..sent("cto@acme.com", "2024-07-02 09:00") },
+            ],
+        };
+        let recent: Vec<&str> = log.recent_at_domain("talent@Acme.com", at("2024-06-15 00:00")).iter().map(|r| r.email.as_str()).collect();
+        assert_eq!(recent, vec!["rh@acme.com"]);
+        assert!(log.recent_at_domain("joao@gmail.com", at("2024-06-15 00:00")).is_empty());
+        assert_eq!(log.recent_at_domain("A.na+jobs@gmail.com", at("2024-06-15 00:00")).len(), 1);
+        assert!(log.recent_at_domain("rh@globex.com", at("2024-06-15 00:00")).is_empty());
+    }
+
+    #[test]
+    fn test_replies_by_template_version() {
+        let record = |version: &str, status| SentRecord {
+            success: true,
+            status,
+            message: Some(SentMessage { template_version: version.to_string(), ..Default::default() }),
+            ..Default::default()
+        };
+        let mut log = SentLog {
+            records: vec![
+                record("aaa", ApplicationStatus::Replied),
+                record("bbb", ApplicationStatus::Sent),
+                record("aaa", ApplicationStatus::Sent),
+                record("bbb", ApplicationStatus::Interview),
+                record("", ApplicationStatus::Replied),
+            ],
+        };
+        log.records[2].sandbox = true;
+        assert_eq!(
+            replies_by_template_version(&log),
+            vec![("aaa".to_string(), 1, 1), ("bbb".to_string(), 2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_open_missing_eml_fails() {
+        let err = open_with_default_app("sent/nao-existe.eml").unwrap_err();
+        assert_eq!(err.to_string(), "sent/nao-existe.eml not found");
+    }
+
+    #[test]
+    fn test_use_named_template() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            let main_version = version_hash(&config.template);
+            config.templates.insert("follow-up-1".to_string(), EmailTemplate {
+                subject: "Seguimento - {{title}}".to_string(),
+                body: "Olá de novo".to_string(),
+                ..Default::default()
+            });
+            assert!(use_template(&mut config.clone(), "follow-up-2").is_err());
+
+            use_template(&mut config, "follow-up-1").unwrap();
+            let message = render_message(&config, "rh@acme.com", b"cv");
+            assert_eq!(message.subject, "Seguimento - Desenvolvedor Rust");
+            assert_eq!(message.template.as_deref(), Some("follow-up-1"));
+            assert_ne!(message.template_version, main_version);
+        });
+    }
+
+    #[test]
+    fn test_rotated_subject_is_recorded_unrendered() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            let version = version_hash(&config.template);
+            config.template.subject = "{{title}} na {{empresa}}".to_string();
+            config.template.subjects = vec!["Olá {{empresa}}".to_string()];
+            config.sending.subject_rotation = rotation::Rotation::Random;
+            let row = merge::MergeRow {
+                email: "rh@acme.com".to_string(),
+                vars: BTreeMap::from([("empresa".to_string(), "Acme".to_string())]),
+            };
+            let merged = merge::config_for_row(&config, &row);
+            for _ in 0..10 {
+                let message = render_message(&merged, "rh@acme.com", b"cv");
+                let choice = message.subject_choice.unwrap();
+                assert_eq!(choice.strategy, rotation::Rotation::Random);
+                match choice.subject.as_str() {
+                    "Olá {{empresa}}" => assert_eq!(message.subject, "Olá Acme"),
+                    _ => assert_eq!(message.subject, "Desenvolvedor Rust na Acme"),
+                }
+            }
+
+            // sem `subjects` nada é sorteado e a versão do template não muda
+            config.template.subjects.clear();
+            config.template.subject = load_config().unwrap().template.subject;
+            assert_eq!(version_hash(&config.template), version);
+            assert!(render_message(&config, "rh@acme.com", b"cv").subject_choice.is_none());
+        });
+    }
+
+    #[test]
+    fn test_matched_skills_from_job_description() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            config.template.body = "Tenho experiência em {{matched_skills}}.".to_string();
+            // sem anúncio vão todas
+            let (_, body) = build_email(&config, "rh@acme.com", None);
+            assert_eq!(body, "Tenho experiência em Rust, Tokio.");
+
+            let row = merge::MergeRow {
+                email: "rh@acme.com".to_string(),
+                vars: BTreeMap::from([("job_description".to_string(), "Procuramos dev Rust com Go".to_string())]),
+            };
+            let (_, body) = build_email(&merge::config_for_row(&config, &row), "rh@acme.com", None);
+            assert_eq!(body, "Tenho experiência em Rust.");
+        });
+    }
+
+    #[test]
+    fn test_eml_file_name() {
+        let at = interview::parse_interview_time("2024-07-01 09:30").unwrap();
+        assert_eq!(eml_file_name("rh@acme.com", at), "20240701-093000-rh@acme.com.eml");
+        assert_eq!(eml_file_name("a/b@x.com", at), "20240701-093000-a_b@x.com.eml");
+    }
+
+    #[test]
+    fn test_sandbox_writes_eml() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            config.sending.sandbox = true;
+            let cv = load_cv(false).unwrap();
+
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let (message, result) = rt.block_on(send_email(&config, "rh@acme.com", &cv));
+            result.unwrap();
+            assert_eq!(message.cv_sha256, cv_sha256(&cv));
+            assert_eq!(message.template_version, version_hash(&config.template));
+            assert_eq!(message.template_version.len(), 12);
+
+            let files: Vec<_> = fs::read_dir(SANDBOX_DIR).unwrap().collect();
+            assert_eq!(files.len(), 1);
+            let path = files[0].as_ref().unwrap().path();
+            assert_eq!(path.extension().unwrap(), "eml");
+            let eml = fs::read_to_string(path).unwrap();
+            assert!(eml.contains("To: rh@acme.com"));
+            assert!(eml.contains("application/pdf"));
+        });
+    }
+
+    #[test]
+    fn test_schedule_interview_writes_ics() {
+        let temp_dir = setup_test_env();
+        with_temp_dir!(temp_dir, {
+            let mut config = load_config().unwrap();
+            config.sending.sandbox = true;
+            let mut log = load_log();
+            record_send(&mut log, &config, "rh@acme.com", SentMessage::default(), &Ok(())).unwrap();
+            // só envios reais contam como candidatura
+            assert!(log.latest_application_mut("rh@acme.com").is_none());
+            log.records[0].sandbox = false;
+            save_log(&log).unwrap();
+
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(interview::schedule_interview(
+                &config,
+                "RH@acme.com",
+                "2030-03-04 10:30",
+                30,
+                Some("Maria".to_string()),
+                Some("https://meet.example.com/abc".to_string()),
+                true,
+            ))
+            .unwrap();
+
+            let record = &load_log().records[0];
+            assert_eq!(record.status, ApplicationStatus::Interview);
+            assert_eq!(record.interview.as_ref().unwrap().duration_minutes, 30);
+
+            let ics = fs::read_to_string("entrevista-20300304-1030-acme.com.ics").unwrap();
+            assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+            assert!(ics.contains("ATTENDEE:mailto:RH@acme.com"));
+            assert!(ics.contains("LOCATION:https://meet.example.com/abc"));
+            assert!(ics.replace("\r\n ", "").contains("Entrevistador: Maria"));
+
+            let eml = fs::read_dir(SANDBOX_DIR).unwrap().next().unwrap().unwrap().path();
+            let eml = fs::read_to_string(eml).unwrap();
+            assert!(eml.contains("text/calendar"));
+            assert!(eml.contains("method=PUBLISH"));
+        });
+    }
+
+    #[test]
+    fn test_normalize_notes() {
+        assert_eq!(normalize_notes("  falei com a Maria\n"), Some("falei com a Maria".to_string()));
+        assert_eq!(normalize_notes(" \n "), None);
+    }
+
+    #[test]
+    fn test_undo_window_disabled() {
+        assert!(undo_window(0).unwrap());
+    }
+
     #[test]
     fn test_get_smtp_creds_success() {
         env::set_var("SMTP_USER", "user@test.com");
         env::set_var("SMTP_PASS", "secret");
-        get_smtp_creds().unwrap();
+        get_smtp_creds(None, &secrets::SecretProvider::Env).unwrap();
         env::remove_var("SMTP_USER");
         env::remove_var("SMTP_PASS");
     }
@@ -565,14 +2919,14 @@ mod tests {
     fn test_get_smtp_creds_missing_user() {
         env::remove_var("SMTP_USER");
         env::remove_var("SMTP_PASS");
-        assert!(get_smtp_creds().is_err());
+        assert!(get_smtp_creds(None, &secrets::SecretProvider::Env).is_err());
     }
 
     #[test]
     fn test_get_smtp_creds_missing_pass() {
         env::set_var("SMTP_USER", "user@test.com");
         env::remove_var("SMTP_PASS");
-        assert!(get_smtp_creds().is_err());
+        assert!(get_smtp_creds(None, &secrets::SecretProvider::Env).is_err());
         env::remove_var("SMTP_USER");
     }
 
@@ -588,7 +2942,7 @@ mod tests {
     fn test_load_cv_missing_file() {
         let temp_dir = TempDir::new().unwrap();
         with_temp_dir!(temp_dir, {
-            assert!(load_cv().is_err());
+            assert!(load_cv(false).is_err());
         });
     }
 }
\ No newline at end of file
@@ -0,0 +1,560 @@
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use console::style;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::Read,
+};
+
+use crate::{
+    build_email, confirm,
+    contacts::{self, load_contacts, ContactBook},
+    delay::Delay,
+    outbox,
+    check_portfolio, choose_languages, confirm_recent_domains, git_history, language, load_checked_cv, record_cancelled, send_batch, undo_window, Config, EmailTemplate, SentLog, CHECK,
+    CLOCK, CROSS, MAIL, NOTE, ROCKET, SPARKLE,
+};
+
+// placeholders que o build_email resolve sozinho
+pub(crate) const BUILTIN_VARS: &[&str] = &[
+    "name",
+    "email",
+    "phone",
+    "title",
+    "summary",
+    "skills",
+    "matched_skills",
+    "cover_letter",
+    "experience_years",
+    "linkedin",
+    "github",
+    "company",
+    "company_from_domain",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeRow {
+    pub email: String,
+    pub vars: BTreeMap<String, String>,
+}
+
+// `{{nome|alternativa}}` → ("nome", Some("alternativa"))
+pub(crate) fn split_default(inner: &str) -> (&str, Option<&str>) {
+    match inner.split_once('|') {
+        Some((name, default)) => (name, Some(default)),
+        None => (inner, None),
+    }
+}
+
+/// Cada `{{...}}` do texto, pela ordem: o nome e a alternativa, se tiver.
+pub fn placeholders(text: &str) -> Vec<(String, Option<String>)> {
+    let mut found = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let (name, default) = split_default(&rest[start + 2..start + 2 + end]);
+        found.push((name.to_string(), default.map(str::to_string)));
+        rest = &rest[start + 2 + end + 2..];
+    }
+    found
+}
+
+/// Nomes dos `{{...}}` usados no texto, sem repetições.
+pub fn template_vars(text: &str) -> BTreeSet<String> {
+    placeholders(text).into_iter().map(|(name, _)| name).collect()
+}
+
+/// Substitui cada `{{nome}}`/`{{nome|alternativa}}` pelo que `value` devolver
+/// para (nome, alternativa); None deixa o placeholder como está.
+pub fn render_placeholders(text: &str, value: impl Fn(&str, Option<&str>) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + end];
+        let (name, default) = split_default(inner);
+        out.push_str(&rest[..start]);
+        match value(name, default) {
+            Some(v) => out.push_str(&v),
+            None => out.push_str(&format!("{{{{{}}}}}", inner)),
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Lê o CSV do mail-merge: a coluna `email` é o destinatário, todas as
+/// outras passam a ser variáveis do template com o nome do cabeçalho.
+pub fn parse_merge_csv(content: &str) -> Result<Vec<MergeRow>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .context("Invalid CSV header")?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let email_col = headers
+        .iter()
+        .position(|h| h.eq_ignore_ascii_case("email"))
+        .context("CSV has no 'email' column")?;
+
+    let mut rows = vec![];
+    for (i, row) in reader.records().enumerate() {
+        let row = row.with_context(|| format!("Invalid CSV row {}", i + 2))?;
+        let email = row.get(email_col).unwrap_or("").to_string();
+        if !email.contains('@') {
+            bail!("Row {}: invalid email '{}'", i + 2, email);
+        }
+        let vars = headers
+            .iter()
+            .zip(row.iter())
+            .enumerate()
+            .filter(|(col, _)| *col != email_col)
+            .map(|(_, (h, v))| (h.clone(), v.to_string()))
+            .collect();
+        rows.push(MergeRow { email, vars });
+    }
+    Ok(rows)
+}
+
+/// Variáveis que o template usa e a linha não preenche: (sem coluna, coluna vazia).
+/// As que têm alternativa (`{{nome|...}}`) nunca ficam por preencher.
+pub fn unresolved_vars(template: &EmailTemplate, row: &MergeRow) -> (Vec<String>, Vec<String>) {
+    let html = template.html.as_deref().unwrap_or_default();
+    let subjects = template.subjects.join("\n");
+    let used: BTreeSet<String> = placeholders(&format!("{}\n{}\n{}\n{}", template.subject, subjects, template.body, html))
+        .into_iter()
+        .filter(|(_, default)| default.is_none())
+        .map(|(name, _)| name)
+        .collect();
+    let (mut missing, mut blank) = (vec![], vec![]);
+    for var in used.into_iter().filter(|v| !BUILTIN_VARS.contains(&v.as_str())) {
+        match row.vars.get(&var) {
+            None => missing.push(var),
+            Some(value) if value.is_empty() => blank.push(var),
+            Some(_) => {}
+        }
+    }
+    (missing, blank)
+}
+
+/// Colunas com o nome de um placeholder que o build_email resolve sozinho
+/// (`name`, `company`, ...): nessas linhas o valor da coluna passa à frente.
+pub fn shadowed_columns(rows: &[MergeRow]) -> BTreeSet<String> {
+    rows.iter()
+        .flat_map(|row| row.vars.keys())
+        .filter(|column| BUILTIN_VARS.contains(&column.as_str()))
+        .cloned()
+        .collect()
+}
+
+// colunas vazias ficam por substituir, para o build_email usar a alternativa
+fn render(text: &str, vars: &BTreeMap<String, String>) -> String {
+    render_placeholders(text, |name, _| vars.get(name).filter(|v| !v.is_empty()).cloned())
+}
+
+// no corpo HTML as colunas entram escapadas
+fn render_html(text: &str, vars: &BTreeMap<String, String>) -> String {
+    render_placeholders(text, |name, _| vars.get(name).filter(|v| !v.is_empty()).map(|v| escape_html(v)))
+}
+
+/// Config com o template já preenchido pelas colunas da linha; os
+/// placeholders do perfil ficam para o build_email.
+pub fn config_for_row(config: &Config, row: &MergeRow) -> Config {
+    let mut merged = config.clone();
+    // coluna `template`: entrada de `templates` para esta linha (um nome que não existe pára o run_merge)
+    if let Some((name, template)) = row.vars.get("template").and_then(|n| config.templates.get_key_value(n)) {
+        merged.template = template.clone();
+        merged.template_name = Some(name.clone());
+    }
+    merged.template_version = Some(crate::template_version(&merged));
+    // coluna `job_description`: {{matched_skills}} só com as skills que o anúncio pede
+    let mut vars = row.vars.clone();
+    if let Some(description) = row.vars.get("job_description").filter(|d| !d.is_empty()) {
+        let matched = crate::jobs::matched_skills(&config.profile.skills, description);
+        vars.insert("matched_skills".to_string(), matched.join(", "));
+    }
+    let row = &MergeRow { email: row.email.clone(), vars };
+    let template = merged.template.clone();
+    merged.subject_lines = Some(std::iter::once(&template.subject).chain(&template.subjects).cloned().collect());
+    merged.template.subject = render(&template.subject, &row.vars);
+    merged.template.subjects = template.subjects.iter().map(|s| render(s, &row.vars)).collect();
+    merged.template.body = render(&template.body, &row.vars);
+    merged.template.html = template.html.as_deref().map(|html| render_html(html, &row.vars));
+    for variant in merged.template.languages.values_mut() {
+        variant.subject = render(&variant.subject, &row.vars);
+        variant.body = render(&variant.body, &row.vars);
+        variant.html = variant.html.as_deref().map(|html| render_html(html, &row.vars));
+    }
+    // coluna `language`: variante de `template.languages` para esta linha
+    if let Some(language) = row.vars.get("language").filter(|v| !v.is_empty()) {
+        merged.language = Some(language.to_lowercase());
+    }
+    // coluna `cv`: variante de `profile.cvs` para esta linha
+    if let Some(variant) = row.vars.get("cv").filter(|v| !v.is_empty()) {
+        merged.cv_variant = Some(variant.clone());
+    }
+    // coluna `portfolio`: "sim"/"yes"/"x"/... anexa o portefólio nesta linha
+    if let Some(value) = row.vars.get("portfolio") {
+        merged.attach_portfolio = ["sim", "s", "yes", "y", "true", "1", "x"].contains(&value.to_lowercase().as_str());
+    }
+    // coluna `track`: "não"/"no"/"0"/... tira o pixel de abertura nesta linha
+    if let Some(value) = row.vars.get("track") {
+        merged.no_track = ["não", "nao", "n", "no", "false", "0"].contains(&value.to_lowercase().as_str());
+    }
+    merged
+}
+
+pub struct PreviewRow {
+    pub line: usize,
+    pub email: String,
+    pub subject: String,
+    pub missing: Vec<String>,
+    pub blank: Vec<String>,
+}
+
+impl PreviewRow {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.blank.is_empty()
+    }
+}
+
+/// Assunto final de cada destinatário e as variáveis que ficaram por preencher.
+pub fn merge_preview(config: &Config, book: &ContactBook, rows: &[MergeRow]) -> Vec<PreviewRow> {
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let merged = config_for_row(config, row);
+            let contact = book.find(&row.email);
+            let (subject, _) = build_email(&merged, &row.email, contact);
+            let language = language::recipient_language(&merged, &row.email, contact);
+            let template = row.vars.get("template").and_then(|n| config.templates.get(n)).unwrap_or(&config.template);
+            let (missing, blank) = unresolved_vars(&template.localized(language.as_deref()), row);
+            PreviewRow { line: i + 2, email: row.email.clone(), subject, missing, blank }
+        })
+        .collect()
+}
+
+pub fn print_preview(preview: &[PreviewRow]) {
+    say!();
+    say!("{} Preview do mail-merge:", MAIL);
+    say!("{}", style("─".repeat(60)).dim());
+    for row in preview {
+        let status = if row.is_ok() { style("OK").green() } else { style("FALTA").red() };
+        say!("  [{}] {} - {}", status, style(&row.email).yellow(), row.subject);
+        if !row.missing.is_empty() {
+            say!("        sem coluna: {}", style(row.missing.join(", ")).red());
+        }
+        if !row.blank.is_empty() {
+            say!("        vazias: {}", style(row.blank.join(", ")).red());
+        }
+    }
+    say!("{}", style("─".repeat(60)).dim());
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn html_report(preview: &[PreviewRow]) -> String {
+    let mut rows = String::new();
+    for row in preview {
+        let problems = [
+            (!row.missing.is_empty()).then(|| format!("sem coluna: {}", row.missing.join(", "))),
+            (!row.blank.is_empty()).then(|| format!("vazias: {}", row.blank.join(", "))),
+        ];
+        let problems: Vec<String> = problems.into_iter().flatten().collect();
+        rows.push_str(&format!(
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            if row.is_ok() { "ok" } else { "falta" },
+            row.line,
+            escape_html(&row.email),
+            escape_html(&row.subject),
+            escape_html(&problems.join("; "))
+        ));
+    }
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Preview do mail-merge</title>\n\
+         <style>body{{font-family:sans-serif}}td,th{{padding:4px 8px;text-align:left}}\
+         .falta{{background:#fdd}}</style></head><body>\n\
+         <h1>Preview do mail-merge</h1>\n<table>\n\
+         <tr><th>Linha</th><th>Email</th><th>Assunto</th><th>Variáveis em falta</th></tr>\n{}</table>\n</body></html>\n",
+        rows
+    )
+}
+
+pub async fn run_merge(
+    config: &Config,
+    cv: &[u8],
+    log: &mut SentLog,
+    file: &str,
+    delay: &Delay,
+    report: Option<&str>,
+    preview_only: bool,
+) -> Result<()> {
+    let content = fs::read_to_string(file).with_context(|| format!("{} not found", file))?;
+    let rows = parse_merge_csv(&content)?;
+    if rows.is_empty() {
+        say!("{} Nenhuma linha em {}", CROSS, file);
+        return Ok(());
+    }
+
+    let preview = merge_preview(config, &load_contacts(), &rows);
+    print_preview(&preview);
+    if let Some(path) = report {
+        fs::write(path, html_report(&preview)).with_context(|| format!("Cannot write {}", path))?;
+        say!("{} Relatório gravado em {}", CHECK, style(path).green());
+    }
+
+    check_rows(config, &rows, &preview)?;
+    if preview_only {
+        return Ok(());
+    }
+    send_rows(config, cv, log, &rows, delay).await
+}
+
+/// Destinatários lidos de um pipe: um CSV com cabeçalho `email` (como no
+/// mail-merge) ou uma linha por destinatário, de que conta o primeiro
+/// endereço (a saída de um `grep`, uma linha de CSV sem cabeçalho).
+pub fn parse_recipients(content: &str) -> Result<Vec<MergeRow>> {
+    let header = content.lines().find(|l| !l.trim().is_empty()).unwrap_or_default();
+    if header.split(',').any(|h| h.trim().trim_matches('"').eq_ignore_ascii_case("email")) {
+        return parse_merge_csv(content);
+    }
+    let mut rows: Vec<MergeRow> = vec![];
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match contacts::extract_emails(line).into_iter().next() {
+            Some(email) if rows.iter().any(|r| contacts::same_email(&r.email, &email)) => {}
+            Some(email) => rows.push(MergeRow { email, vars: BTreeMap::new() }),
+            None => say!("{} Linha {}: sem email, ignorada", CROSS, i + 1),
+        }
+    }
+    Ok(rows)
+}
+
+/// `bulk --stdin`: os destinatários vêm do pipe e não se pergunta nada.
+pub async fn run_stdin(config: &Config, cv: &[u8], log: &mut SentLog, delay: &Delay) -> Result<()> {
+    let mut content = String::new();
+    std::io::stdin().read_to_string(&mut content).context("Cannot read stdin")?;
+    let rows = parse_recipients(&content)?;
+    if rows.is_empty() {
+        say!("{} Nenhum email no stdin", CROSS);
+        return Ok(());
+    }
+    let preview = merge_preview(config, &load_contacts(), &rows);
+    if preview.iter().any(|r| !r.is_ok()) {
+        print_preview(&preview);
+    }
+    check_rows(config, &rows, &preview)?;
+    send_rows(config, cv, log, &rows, delay).await
+}
+
+/// O que tem de estar certo antes do primeiro envio de um lote: variáveis,
+/// templates, línguas, variantes do CV e portefólio de cada linha.
+pub fn check_rows(config: &Config, rows: &[MergeRow], preview: &[PreviewRow]) -> Result<()> {
+    let problems = preview.iter().filter(|r| !r.is_ok()).count();
+    if problems > 0 {
+        bail!("{} rows are missing template variables", problems);
+    }
+    let shadowed = shadowed_columns(rows);
+    if !shadowed.is_empty() {
+        let names: Vec<&str> = shadowed.iter().map(String::as_str).collect();
+        say!("{} Colunas que substituem o valor do perfil/contacto: {}", NOTE, style(names.join(", ")).yellow());
+    }
+    // uma variante que não existe ou não é PDF pára tudo antes do primeiro envio
+    let mut checked = BTreeSet::new();
+    for (i, row) in rows.iter().enumerate() {
+        let merged = config_for_row(config, row);
+        if let Some(name) = row.vars.get("template").filter(|n| !n.is_empty() && !config.templates.contains_key(*n)) {
+            bail!("Row {}: template '{}' not found in templates", i + 2, name);
+        }
+        if let Some(language) = merged.language.as_deref().filter(|l| !merged.template.has_language(l)) {
+            bail!("Row {}: no template for language '{}'", i + 2, language);
+        }
+        if let Some(variant) = merged.cv_variant.clone().filter(|v| checked.insert(v.clone())) {
+            load_checked_cv(&merged).with_context(|| format!("Row {}: CV variant '{}'", i + 2, variant))?;
+        }
+    }
+    if let Some(mut merged) = rows.iter().map(|row| config_for_row(config, row)).find(|c| c.attach_portfolio) {
+        check_portfolio(&mut merged)?;
+    }
+    Ok(())
+}
+
+/// Confirma e envia um lote já verificado, com o `delay` entre envios; acima
+/// do limite diário o resto vai para a fila.
+pub async fn send_rows(config: &Config, cv: &[u8], log: &mut SentLog, rows: &[MergeRow], delay: &Delay) -> Result<()> {
+    say!(
+        "{} Mail-merge: {} emails, delay {}",
+        ROCKET,
+        style(rows.len()).cyan(),
+        style(delay.describe()).yellow()
+    );
+    if let Some(room) = outbox::room_today(&config.sending, log, Local::now()).filter(|room| *room < rows.len()) {
+        say!("{} Limite diário: {} hoje, {} ficam na fila para os dias seguintes", CLOCK, room, rows.len() - room);
+    }
+    let emails: Vec<String> = rows.iter().map(|r| r.email.clone()).collect();
+    if !confirm_recent_domains(config, log, &emails)? {
+        say!("Cancelado!");
+        return Ok(());
+    }
+    // a coluna `language` já decide; nas outras linhas pergunta-se nos domínios ambíguos
+    let unset: Vec<String> = rows
+        .iter()
+        .filter(|row| row.vars.get("language").is_none_or(|l| l.is_empty()))
+        .map(|row| row.email.clone())
+        .collect();
+    let languages = choose_languages(config, &unset)?;
+    if !confirm("Confirmar envio?", true)? {
+        say!("Cancelado!");
+        return Ok(());
+    }
+
+    if !undo_window(config.sending.undo_seconds)? {
+        record_cancelled(log, &emails)?;
+        git_history::record(&format!("mail-merge de {} emails desfeito", emails.len()));
+        say!("{} Envio desfeito, nada foi enviado", CROSS);
+        return Ok(());
+    }
+
+    let batch: Vec<(String, Config)> = rows
+        .iter()
+        .map(|row| {
+            let mut merged = config_for_row(config, row);
+            if let Some(language) = languages.get(&row.email) {
+                merged.language = Some(language.clone());
+            }
+            (row.email.clone(), merged)
+        })
+        .collect();
+    let batch = outbox::carry_over(batch, &config.sending, log, delay)?;
+    let (success, failed) = send_batch(cv, log, &batch, delay).await?;
+    git_history::record(&format!("mail-merge: {} enviados ({} falhados)", success, failed));
+
+    say!();
+    say!(
+        "{} Resultado: {} enviados, {} falhados",
+        SPARKLE,
+        style(success).green().bold(),
+        style(failed).red().bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(subject: &str, body: &str) -> EmailTemplate {
+        EmailTemplate {
+            subject: subject.to_string(),
+            subjects: vec![],
+            body: body.to_string(),
+            html: None,
+            language: None,
+            languages: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_template_vars() {
+        let vars = template_vars("{{name}} para {{role|Dev}} na {{company}} ({{name}}) {{unclosed");
+        let vars: Vec<&str> = vars.iter().map(String::as_str).collect();
+        assert_eq!(vars, vec!["company", "name", "role"]);
+    }
+
+    #[test]
+    fn test_parse_merge_csv_keeps_all_columns() {
+        let rows = parse_merge_csv("Email,role,hiring_manager\nrh@acme.com, Backend ,Maria\n").unwrap();
+        assert_eq!(rows[0].email, "rh@acme.com");
+        assert_eq!(rows[0].vars.get("role").map(String::as_str), Some("Backend"));
+        assert_eq!(rows[0].vars.get("hiring_manager").map(String::as_str), Some("Maria"));
+        assert!(!rows[0].vars.contains_key("Email"));
+
+        assert!(parse_merge_csv("email,role\nsem-arroba,x\n").is_err());
+        assert!(parse_merge_csv("role\nx\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_recipients_from_a_pipe() {
+        let rows = parse_recipients("rh@acme.com\n\n# comentário\nAcme Jobs,jobs@acme.com,Lisboa\nRH@acme.com\nsem endereço\n").unwrap();
+        let emails: Vec<&str> = rows.iter().map(|r| r.email.as_str()).collect();
+        assert_eq!(emails, vec!["rh@acme.com", "jobs@acme.com"]);
+        assert!(rows[1].vars.is_empty());
+
+        // com cabeçalho `email` é um CSV de mail-merge
+        let rows = parse_recipients("role,email\nBackend,rh@acme.com\n").unwrap();
+        assert_eq!(rows[0].email, "rh@acme.com");
+        assert_eq!(rows[0].vars.get("role").map(String::as_str), Some("Backend"));
+    }
+
+    #[test]
+    fn test_unresolved_vars() {
+        let t = template("{{role}} - {{name}}", "Olá {{hiring_manager}}, {{company}}");
+        let rows = parse_merge_csv("email,role,hiring_manager\na@x.com,Backend,\nb@x.com,Frontend,Rui\n").unwrap();
+        assert_eq!(unresolved_vars(&t, &rows[0]), (vec![], vec!["hiring_manager".to_string()]));
+        assert_eq!(unresolved_vars(&t, &rows[1]), (vec![], vec![]));
+
+        let t = template("{{role}}", "{{team}} {{hiring_manager}}");
+        let (missing, blank) = unresolved_vars(&t, &rows[0]);
+        assert_eq!(missing, vec!["team"]);
+        assert_eq!(blank, vec!["hiring_manager"]);
+
+        // com alternativa não faz falta
+        let t = template("{{role}}", "{{team|a equipa}} {{hiring_manager|}}");
+        assert_eq!(unresolved_vars(&t, &rows[0]), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_html_report_escapes() {
+        let preview = vec![
+            PreviewRow {
+                line: 2,
+                email: "a@x.com".to_string(),
+                subject: "Dev <Rust> & Go".to_string(),
+                missing: vec![],
+                blank: vec![],
+            },
+            PreviewRow {
+                line: 3,
+                email: "b@x.com".to_string(),
+                subject: "Dev {{role}}".to_string(),
+                missing: vec!["team".to_string()],
+                blank: vec!["role".to_string()],
+            },
+        ];
+        let html = html_report(&preview);
+        assert!(html.contains("Dev &lt;Rust&gt; &amp; Go"));
+        assert!(html.contains("<tr class=\"falta\"><td>3</td>"));
+        assert!(html.contains("sem coluna: team; vazias: role"));
+    }
+
+    #[test]
+    fn test_render_leaves_builtins() {
+        let rows = parse_merge_csv("email,role,company\na@x.com,Backend,Acme\n").unwrap();
+        let vars = &rows[0].vars;
+        assert!(shadowed_columns(&parse_merge_csv("email,role\na@x.com,Backend\n").unwrap()).is_empty());
+        assert_eq!(render("{{role}} na {{company}} - {{name}}", vars), "Backend na Acme - {{name}}");
+        assert_eq!(render("{{role|Dev}} - {{team|Engenharia}}", vars), "Backend - {{team|Engenharia}}");
+
+        let rows = parse_merge_csv("email,company\na@x.com,<b>Tom & Jerry</b>\n").unwrap();
+        assert_eq!(shadowed_columns(&rows).into_iter().collect::<Vec<_>>(), vec!["company"]);
+        assert_eq!(render_html("<p>{{company}}</p>", &rows[0].vars), "<p>&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;</p>");
+    }
+}
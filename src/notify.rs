@@ -0,0 +1,181 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+use crate::{failure::ErrorKind, ApplicationStatus, SentLog, SentRecord, MAIL};
+
+/// Notificações do sistema enquanto o daemon corre: respostas, envios
+/// falhados e candidaturas sem resposta há `follow_up_days` dias.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    #[serde(default = "default_follow_up_days")]
+    pub follow_up_days: i64,
+}
+
+fn default_follow_up_days() -> i64 {
+    7
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notice {
+    // fase nova (respondeu, entrevista, ...) vinda do `status` ou de um sync
+    Status { email: String, status: ApplicationStatus },
+    Failed { email: String, bounced: bool, reason: String },
+    FollowUp { email: String, days: i64 },
+}
+
+impl Notice {
+    pub fn title(&self) -> String {
+        match self {
+            Notice::Status { status, .. } => format!("Candidatura: {}", status.label()),
+            Notice::Failed { bounced: true, .. } => "Endereço rejeitado".to_string(),
+            Notice::Failed { bounced: false, .. } => "Envio falhou".to_string(),
+            Notice::FollowUp { .. } => "Follow-up em atraso".to_string(),
+        }
+    }
+
+    pub fn body(&self) -> String {
+        match self {
+            Notice::Status { email, .. } => email.clone(),
+            Notice::Failed { email, reason, .. } => format!("{}: {}", email, reason),
+            Notice::FollowUp { email, days } => format!("{} sem resposta há {} dias", email, days),
+        }
+    }
+}
+
+/// Quando o follow-up de uma candidatura fica em atraso: `days` dias depois do
+/// envio, ou, se for mais tarde, o dia em que a pessoa volta de uma ausência
+/// ou em que acaba o `snooze`.
+pub fn follow_up_due(record: &SentRecord, days: i64) -> DateTime<Local> {
+    let due = record.sent_at + Duration::days(days);
+    let morning = |d: NaiveDate| d.and_hms_opt(9, 0, 0).and_then(|d| d.and_local_timezone(Local).earliest());
+    [record.follow_up_after, record.snoozed_until].into_iter().flatten().filter_map(morning).fold(due, DateTime::max)
+}
+
+/// Candidaturas ainda em `sent` com o follow-up em atraso.
+pub fn overdue(log: &SentLog, now: DateTime<Local>, days: i64) -> Vec<&SentRecord> {
+    log.applications()
+        .into_iter()
+        .filter(|r| r.status == ApplicationStatus::Sent && follow_up_due(r, days) <= now)
+        .collect()
+}
+
+/// O que aconteceu entre `since` e `now`; cada coisa aparece numa só janela.
+pub fn notices(log: &SentLog, since: DateTime<Local>, now: DateTime<Local>, config: &NotifyConfig) -> Vec<Notice> {
+    let window = |at: DateTime<Local>| since < at && at <= now;
+    let mut out = vec![];
+    for r in &log.records {
+        if !r.success && !r.cancelled && window(r.sent_at) {
+            let error = r.error.as_ref();
+            out.push(Notice::Failed {
+                email: r.email.clone(),
+                bounced: error.is_some_and(|e| e.kind == ErrorKind::RecipientRejected),
+                reason: error.map(|e| e.kind.label().to_string()).unwrap_or_default(),
+            });
+        }
+        for change in r.status_history.iter().filter(|c| window(c.at)) {
+            out.push(Notice::Status { email: r.email.clone(), status: change.status });
+        }
+    }
+    for r in log.applications() {
+        let due = follow_up_due(r, config.follow_up_days);
+        if r.status == ApplicationStatus::Sent && window(due) {
+            out.push(Notice::FollowUp { email: r.email.clone(), days: (due - r.sent_at).num_days() });
+        }
+    }
+    out
+}
+
+/// Mostra a notificação no ambiente de trabalho e no terminal do daemon.
+pub fn show(title: &str, body: &str) {
+    say!("{} {}: {}", MAIL, title, body);
+    let (title, body) = (title.to_string(), body.to_string());
+    // numa thread: um D-Bus lento não atrasa os envios do daemon
+    std::thread::spawn(move || {
+        let shown = Notification::new().appname("job-mailer").summary(&title).body(&body).show();
+        // sem ambiente gráfico (servidor, cron) fica só a linha no terminal
+        if let Err(e) = shown {
+            verbose!("notificação não mostrada: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{failure::SendError, StatusChange};
+
+    fn at(text: &str) -> DateTime<Local> {
+        crate::interview::parse_interview_time(text).unwrap()
+    }
+
+    fn sent(email: &str, when: &str) -> SentRecord {
+        SentRecord { email: email.to_string(), sent_at: at(when), success: true, ..Default::default() }
+    }
+
+    #[test]
+    fn test_notices_in_window() {
+        let mut replied = sent("rh@acme.com", "2024-07-01 09:00");
+        replied.status = ApplicationStatus::Replied;
+        replied.status_history.push(StatusChange { at: at("2024-07-08 10:00"), status: ApplicationStatus::Replied });
+        let failed = SentRecord {
+            email: "ninguem@globex.com".to_string(),
+            sent_at: at("2024-07-08 10:30"),
+            error: Some(SendError::classify("permanent error (550): 5.1.1 user unknown", None)),
+            ..Default::default()
+        };
+        let log = SentLog {
+            records: vec![replied, sent("jobs@initech.com", "2024-07-01 10:00"), failed, sent("rh@x.com", "2024-07-05 09:00")],
+        };
+        let config = NotifyConfig { follow_up_days: 7 };
+
+        let notices = notices(&log, at("2024-07-08 09:00"), at("2024-07-08 11:00"), &config);
+        assert_eq!(
+            notices,
+            vec![
+                Notice::Status { email: "rh@acme.com".to_string(), status: ApplicationStatus::Replied },
+                Notice::Failed {
+                    email: "ninguem@globex.com".to_string(),
+                    bounced: true,
+                    reason: "destinatário rejeitado".to_string()
+                },
+                Notice::FollowUp { email: "jobs@initech.com".to_string(), days: 7 },
+            ]
+        );
+        // a janela seguinte já não repete nada
+        assert!(super::notices(&log, at("2024-07-08 11:00"), at("2024-07-08 12:00"), &config).is_empty());
+    }
+
+    #[test]
+    fn test_overdue_uses_latest_send() {
+        let mut log = SentLog { records: vec![sent("rh@acme.com", "2024-07-01 09:00")] };
+        assert_eq!(overdue(&log, at("2024-07-09 09:00"), 7).len(), 1);
+        // reenviado entretanto: conta a partir do último envio
+        log.records.push(sent("RH@acme.com", "2024-07-06 09:00"));
+        assert!(overdue(&log, at("2024-07-09 09:00"), 7).is_empty());
+    }
+
+    #[test]
+    fn test_follow_up_waits_for_the_return() {
+        let mut away = sent("rh@acme.com", "2024-07-01 10:00");
+        away.follow_up_after = NaiveDate::from_ymd_opt(2024, 7, 15);
+        assert_eq!(follow_up_due(&away, 7), at("2024-07-15 09:00"));
+        let log = SentLog { records: vec![away.clone()] };
+        assert!(overdue(&log, at("2024-07-10 09:00"), 7).is_empty());
+        assert_eq!(overdue(&log, at("2024-07-15 10:00"), 7).len(), 1);
+        let config = NotifyConfig { follow_up_days: 7 };
+        assert_eq!(
+            notices(&log, at("2024-07-15 08:00"), at("2024-07-15 10:00"), &config),
+            vec![Notice::FollowUp { email: "rh@acme.com".to_string(), days: 13 }]
+        );
+        // de volta antes do prazo normal: não muda nada
+        away.follow_up_after = NaiveDate::from_ymd_opt(2024, 7, 3);
+        assert_eq!(follow_up_due(&away, 7), at("2024-07-08 10:00"));
+        // em snooze até dia 1 do mês seguinte: nem aparece antes
+        away.snoozed_until = NaiveDate::from_ymd_opt(2024, 8, 1);
+        let log = SentLog { records: vec![away] };
+        assert!(overdue(&log, at("2024-07-31 18:00"), 7).is_empty());
+        assert!(notices(&log, at("2024-07-08 08:00"), at("2024-07-31 18:00"), &config).is_empty());
+        assert_eq!(overdue(&log, at("2024-08-01 09:00"), 7).len(), 1);
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::{bail, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{account_var, enrich, overrides, schema, Config, CHECK, CLOCK, CROSS, NOTE};
+
+pub const OAUTH_FILE: &str = "oauth_tokens.json";
+
+const TOKEN_SERVICE: &str = "job-mailer-oauth";
+
+const DEVICE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Login OAuth em vez de password (`oauth` no config.json). A aplicação
+/// (`client_id`) é registada pelo utilizador na Google Cloud ou no Azure.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthConfig {
+    pub provider: OAuthProvider,
+    pub client_id: String,
+    // a Google pede-o mesmo no fluxo por código; o Azure não
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    // inquilino do Azure: "common", "organizations" ou o id do diretório
+    #[serde(default = "default_tenant")]
+    pub tenant: String,
+    #[serde(default)]
+    pub token_store: TokenStore,
+}
+
+fn default_tenant() -> String {
+    "common".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OAuthProvider {
+    Google,
+    Microsoft,
+}
+
+/// Onde fica o refresh token.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenStore {
+    #[default]
+    Keyring,
+    // oauth_tokens.json só legível pelo dono, para servidores sem keyring
+    File,
+}
+
+impl OAuthConfig {
+    fn device_url(&self) -> String {
+        match self.provider {
+            OAuthProvider::Google => "https://oauth2.googleapis.com/device/code".to_string(),
+            OAuthProvider::Microsoft => {
+                format!("https://login.microsoftonline.com/{}/oauth2/v2.0/devicecode", self.tenant)
+            }
+        }
+    }
+
+    fn token_url(&self) -> String {
+        match self.provider {
+            OAuthProvider::Google => "https://oauth2.googleapis.com/token".to_string(),
+            OAuthProvider::Microsoft => format!("https://login.microsoftonline.com/{}/oauth2/v2.0/token", self.tenant),
+        }
+    }
+
+    // SMTP e IMAP (cópia nos enviados)
+    fn scope(&self) -> &'static str {
+        match self.provider {
+            OAuthProvider::Google => "https://mail.google.com/",
+            OAuthProvider::Microsoft => {
+                "offline_access https://outlook.office.com/SMTP.Send https://outlook.office.com/IMAP.AccessAsUser.All"
+            }
+        }
+    }
+
+    fn form<'a>(&'a self, fields: &[(&'a str, &'a str)]) -> Vec<(&'a str, &'a str)> {
+        let mut form = vec![("client_id", self.client_id.as_str())];
+        form.extend(self.client_secret.as_deref().map(|s| ("client_secret", s)));
+        form.extend_from_slice(fields);
+        form
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    // a Google chama-lhe verification_url
+    #[serde(alias = "verification_url")]
+    verification_uri: String,
+    expires_in: u64,
+    #[serde(default = "default_interval")]
+    interval: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TokenReply {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Poll {
+    Done,
+    Pending,
+    SlowDown,
+    Failed(String),
+}
+
+// RFC 8628 §3.5
+fn poll_outcome(reply: &TokenReply) -> Poll {
+    match reply.error.as_deref() {
+        None if reply.access_token.is_some() => Poll::Done,
+        None => Poll::Failed("reply without access_token".to_string()),
+        Some("authorization_pending") => Poll::Pending,
+        Some("slow_down") => Poll::SlowDown,
+        Some("access_denied") => Poll::Failed("the authorization was refused".to_string()),
+        Some("expired_token") => Poll::Failed("the code expired before it was used".to_string()),
+        Some(error) => Poll::Failed(format!("{} {}", error, reply.error_description.as_deref().unwrap_or_default())),
+    }
+}
+
+/// Texto do SASL XOAUTH2 (antes do base64), igual para SMTP e IMAP.
+pub fn xoauth2(user: &str, access_token: &str) -> String {
+    format!("user={}\x01auth=Bearer {}\x01\x01", user, access_token)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TokenFile {
+    #[serde(default)]
+    refresh_tokens: BTreeMap<String, String>,
+}
+
+fn keyring_entry(user: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(TOKEN_SERVICE, user).context("Cannot open the system keyring")
+}
+
+// só o dono lê, desde o temporário
+fn save_token_file(tokens: &TokenFile) -> Result<()> {
+    schema::INITIAL.save_with_mode(&overrides::path(OAUTH_FILE), tokens, 0o600)
+}
+
+fn store_refresh_token(store: TokenStore, user: &str, token: &str) -> Result<()> {
+    match store {
+        TokenStore::Keyring => {
+            let stored = keyring_entry(user).and_then(|entry| {
+                entry.set_password(token).with_context(|| format!("Cannot store the OAuth token for {} in the keyring", user))
+            });
+            // servidor sem Secret Service: o daemon perdia o login ao reiniciar
+            if let Err(e) = stored {
+                eprintln!("{} {:#}; fica em {}", CROSS, e, OAUTH_FILE);
+                return store_refresh_token(TokenStore::File, user, token);
+            }
+            Ok(())
+        }
+        TokenStore::File => {
+            let mut tokens: TokenFile = schema::INITIAL.load(&overrides::path(OAUTH_FILE));
+            tokens.refresh_tokens.insert(user.to_lowercase(), token.to_string());
+            save_token_file(&tokens)
+        }
+    }
+}
+
+fn refresh_token(store: TokenStore, user: &str) -> Option<String> {
+    match store {
+        TokenStore::Keyring => keyring_entry(user)
+            .ok()
+            .and_then(|entry| entry.get_password().ok())
+            .or_else(|| refresh_token(TokenStore::File, user)),
+        TokenStore::File => {
+            let tokens: TokenFile = schema::INITIAL.load(&overrides::path(OAUTH_FILE));
+            tokens.refresh_tokens.get(&user.to_lowercase()).cloned()
+        }
+    }
+}
+
+// access tokens já pedidos nesta execução, até perto de expirarem
+static ACCESS: Mutex<BTreeMap<String, (String, Instant)>> = Mutex::new(BTreeMap::new());
+
+fn remember(user: &str, reply: &TokenReply) {
+    if let Some(token) = &reply.access_token {
+        // um minuto de margem para o envio não apanhar o token a expirar
+        let valid = Duration::from_secs(reply.expires_in.unwrap_or(3600).saturating_sub(60));
+        ACCESS.lock().unwrap().insert(user.to_string(), (token.clone(), Instant::now() + valid));
+    }
+}
+
+async fn post(url: &str, form: &[(&str, &str)]) -> Result<reqwest::Response> {
+    enrich::http_client().post(url).form(form).send().await.with_context(|| format!("Cannot reach {}", url))
+}
+
+/// Um access token válido para a conta, renovado com o refresh token guardado.
+pub async fn access_token(config: &OAuthConfig, user: &str) -> Result<String> {
+    if let Some((token, until)) = ACCESS.lock().unwrap().get(user) {
+        if Instant::now() < *until {
+            return Ok(token.clone());
+        }
+    }
+    let refresh = refresh_token(config.token_store, user)
+        .with_context(|| format!("No OAuth token for {}; run `job-mailer oauth login`", user))?;
+    let form = config.form(&[("grant_type", "refresh_token"), ("refresh_token", &refresh)]);
+    let reply: TokenReply = post(&config.token_url(), &form).await?.json().await.context("Invalid token reply")?;
+    if let Poll::Failed(reason) = poll_outcome(&reply) {
+        bail!("Cannot refresh the OAuth token for {}: {}; run `job-mailer oauth login` again", user, reason);
+    }
+    // a Microsoft roda o refresh token a cada renovação
+    if let Some(new) = reply.refresh_token.as_deref().filter(|t| *t != refresh) {
+        store_refresh_token(config.token_store, user, new)?;
+    }
+    remember(user, &reply);
+    Ok(reply.access_token.unwrap_or_default())
+}
+
+fn oauth_config(config: &Config) -> Result<&OAuthConfig> {
+    config.oauth.as_ref().context("No `oauth` section in config.json")
+}
+
+fn oauth_user(config: &Config) -> Result<String> {
+    account_var(&["SMTP_USER"], config.active_profile.as_deref()).context("SMTP_USER not set in .env")
+}
+
+/// `oauth login`: fluxo por código (RFC 8628). Mostra o endereço e o código
+/// para abrir noutro dispositivo e espera pela autorização; não precisa de
+/// browser nem de servidor local.
+pub async fn run_login(config: &Config) -> Result<()> {
+    let oauth = oauth_config(config)?;
+    let user = oauth_user(config)?;
+    let form = oauth.form(&[("scope", oauth.scope())]);
+    let resp = post(&oauth.device_url(), &form).await?;
+    if !resp.status().is_success() {
+        bail!("Device code request refused ({}): {}", resp.status(), resp.text().await.unwrap_or_default());
+    }
+    let device: DeviceCode = resp.json().await.context("Invalid device code reply")?;
+
+    say!("{} Abre {} e escreve o código {}", NOTE, style(&device.verification_uri).cyan(), style(&device.user_code).yellow().bold());
+    say!("{} À espera da autorização de {} (expira em {} min)...", CLOCK, user, device.expires_in / 60);
+
+    let deadline = Instant::now() + Duration::from_secs(device.expires_in);
+    let mut interval = device.interval;
+    let form = oauth.form(&[("grant_type", DEVICE_GRANT), ("device_code", &device.device_code)]);
+    loop {
+        if Instant::now() >= deadline {
+            bail!("The code expired before it was used");
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        let reply: TokenReply = post(&oauth.token_url(), &form).await?.json().await.context("Invalid token reply")?;
+        match poll_outcome(&reply) {
+            Poll::Pending => continue,
+            // o servidor pede mais 5 segundos entre pedidos
+            Poll::SlowDown => interval += 5,
+            Poll::Failed(reason) => bail!("OAuth login failed: {}", reason),
+            Poll::Done => {
+                let refresh = reply.refresh_token.as_deref().context("The provider did not send a refresh token")?;
+                store_refresh_token(oauth.token_store, &user, refresh)?;
+                remember(&user, &reply);
+                let place = match oauth.token_store {
+                    TokenStore::Keyring => "no keyring do sistema".to_string(),
+                    TokenStore::File => format!("em {}", overrides::path(OAUTH_FILE)),
+                };
+                say!("{} Autorizado: o token de {} ficou guardado {}", CHECK, style(&user).green(), place);
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// `oauth logout`: esquece o refresh token da conta.
+pub fn run_logout(config: &Config) -> Result<()> {
+    let oauth = oauth_config(config)?;
+    let user = oauth_user(config)?;
+    match oauth.token_store {
+        TokenStore::Keyring => match keyring_entry(&user)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(e).context("Cannot remove the OAuth token from the keyring"),
+        },
+        TokenStore::File => {
+            let mut tokens: TokenFile = schema::INITIAL.load(&overrides::path(OAUTH_FILE));
+            if tokens.refresh_tokens.remove(&user.to_lowercase()).is_some() {
+                save_token_file(&tokens)?;
+            }
+        }
+    }
+    ACCESS.lock().unwrap().remove(&user);
+    say!("{} Token OAuth de {} apagado", CHECK, user);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(json: &str) -> TokenReply {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_poll_outcome() {
+        assert_eq!(poll_outcome(&reply(r#"{"error": "authorization_pending"}"#)), Poll::Pending);
+        assert_eq!(poll_outcome(&reply(r#"{"error": "slow_down"}"#)), Poll::SlowDown);
+        assert!(matches!(poll_outcome(&reply(r#"{"error": "access_denied"}"#)), Poll::Failed(_)));
+        let done = reply(r#"{"access_token": "ya29.a0", "refresh_token": "1//0g", "expires_in": 3599, "token_type": "Bearer"}"#);
+        assert_eq!(poll_outcome(&done), Poll::Done);
+    }
+
+    #[test]
+    fn test_provider_endpoints() {
+        let config: OAuthConfig =
+            serde_json::from_str(r#"{"provider": "microsoft", "client_id": "abc", "tenant": "organizations"}"#).unwrap();
+        assert_eq!(config.token_url(), "https://login.microsoftonline.com/organizations/oauth2/v2.0/token");
+        assert!(config.scope().contains("offline_access"));
+        assert_eq!(config.form(&[("scope", "x")]), vec![("client_id", "abc"), ("scope", "x")]);
+
+        // a Google devolve verification_url
+        let device: DeviceCode = serde_json::from_str(
+            r#"{"device_code": "d", "user_code": "ABCD-EFGH", "verification_url": "https://www.google.com/device", "expires_in": 1800}"#,
+        )
+        .unwrap();
+        assert_eq!((device.verification_uri.as_str(), device.interval), ("https://www.google.com/device", 5));
+        assert_eq!(xoauth2("joao@gmail.com", "tok"), "user=joao@gmail.com\x01auth=Bearer tok\x01\x01");
+    }
+}
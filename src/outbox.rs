@@ -0,0 +1,784 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use console::style;
+use dialoguer::{theme::ColorfulTheme, Input};
+use serde::{Deserialize, Serialize};
+use std::{fs, time::Duration};
+
+use crate::{
+    config_for_profile, contacts::{load_contacts, same_email}, delay::Delay, load_checked_cv, load_cv_variant, load_profile_config,
+    confirm, record_send, send_batch, send_email, Config, EmailTemplate, SendingConfig, SentLog, SentMessage, CHECK, CLOCK,
+    CROSS, ROCKET, SPARKLE,
+};
+
+pub const OUTBOX_FILE: &str = "outbox.json";
+// o que ficou por enviar de um lote parado a meio (pausa ou Ctrl-C)
+pub const RESUME_FILE: &str = "resume.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledEmail {
+    pub id: u64,
+    pub to: String,
+    pub send_at: DateTime<Utc>,
+    pub timezone: Option<String>,
+    pub created_at: DateTime<Local>,
+    // perfil (--profile) com que foi agendado
+    #[serde(default)]
+    pub profile: Option<String>,
+    // variante do CV (--cv); None = cv.pdf
+    #[serde(default)]
+    pub cv_variant: Option<String>,
+    // agendado com --no-track: sai sem pixel de abertura
+    #[serde(default)]
+    pub no_track: bool,
+    // passado de um bulk/mail-merge acima do `max_per_day`: o template tal como ia sair
+    // (named template, colunas já preenchidas); None = o template do config.json na hora
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<EmailTemplate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub attach_portfolio: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub campaign: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Outbox {
+    pub next_id: u64,
+    pub items: Vec<ScheduledEmail>,
+}
+
+impl Outbox {
+    pub fn push(
+        &mut self,
+        to: &str,
+        send_at: DateTime<Utc>,
+        timezone: Option<String>,
+        profile: Option<&str>,
+        cv_variant: Option<&str>,
+        no_track: bool,
+    ) -> u64 {
+        self.next_id += 1;
+        self.items.push(ScheduledEmail {
+            id: self.next_id,
+            to: to.to_string(),
+            send_at,
+            timezone,
+            created_at: Local::now(),
+            profile: profile.map(str::to_string),
+            cv_variant: cv_variant.map(str::to_string),
+            no_track,
+            template: None,
+            template_name: None,
+            language: None,
+            attach_portfolio: false,
+            campaign: None,
+        });
+        self.next_id
+    }
+
+    /// Um envio já preparado (bulk, mail-merge): leva o template e as escolhas do lote.
+    pub fn push_prepared(&mut self, to: &str, config: &Config, send_at: DateTime<Utc>, timezone: Option<String>) -> u64 {
+        let id = self.push(
+            to,
+            send_at,
+            timezone,
+            config.active_profile.as_deref(),
+            config.cv_variant.as_deref(),
+            config.no_track,
+        );
+        if let Some(item) = self.get_mut(id) {
+            item.template = Some(config.template.clone());
+            item.template_name = config.template_name.clone();
+            item.language = config.language.clone();
+            item.attach_portfolio = config.attach_portfolio;
+            item.campaign = config.campaign.clone();
+        }
+        id
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut ScheduledEmail> {
+        self.items.iter_mut().find(|i| i.id == id)
+    }
+
+    pub fn cancel(&mut self, id: u64) -> Option<ScheduledEmail> {
+        let idx = self.items.iter().position(|i| i.id == id)?;
+        Some(self.items.remove(idx))
+    }
+
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Vec<ScheduledEmail> {
+        let (due, pending) = self.items.drain(..).partition(|i| i.send_at <= now);
+        self.items = pending;
+        due
+    }
+}
+
+fn load_from(path: &str) -> Outbox {
+    crate::schema::INITIAL.load(path)
+}
+
+pub fn load_outbox() -> Outbox {
+    load_from(&crate::overrides::path(OUTBOX_FILE))
+}
+
+pub fn save_outbox(outbox: &Outbox) -> Result<()> {
+    crate::schema::INITIAL.save(&crate::overrides::path(OUTBOX_FILE), outbox)
+}
+
+/// Lê, muda e grava a fila com o lock, como o `update_log`: o daemon a despachar
+/// e um `schedule` noutro terminal não se pisam (nem repetem ids).
+pub fn update_outbox<R>(change: impl FnOnce(&mut Outbox) -> Result<R>) -> Result<R> {
+    let _lock = crate::lock::lock(&crate::overrides::path(OUTBOX_FILE))?;
+    let mut outbox = load_outbox();
+    let result = change(&mut outbox)?;
+    save_outbox(&outbox)?;
+    Ok(result)
+}
+
+/// Grava o resto de um lote parado a meio; junta-se ao que já lá estivesse.
+pub fn save_resume(unsent: &[&(String, Config)]) -> Result<()> {
+    let path = crate::overrides::path(RESUME_FILE);
+    let _lock = crate::lock::lock(&path)?;
+    let mut resume = load_from(&path);
+    let now = Utc::now();
+    for (email, config) in unsent {
+        resume.push_prepared(email, config, now, None);
+    }
+    crate::schema::INITIAL.save(&path, &resume)?;
+    crate::git_history::record(&format!("{} envios por retomar", unsent.len()));
+    Ok(())
+}
+
+/// Tira um destinatário da fila e do resume.json; devolve quantos envios saíram.
+pub fn remove_recipient(email: &str) -> Result<usize> {
+    let mut removed = update_outbox(|outbox| {
+        let before = outbox.items.len();
+        outbox.items.retain(|i| !same_email(&i.to, email));
+        Ok(before - outbox.items.len())
+    })?;
+    let path = crate::overrides::path(RESUME_FILE);
+    let _lock = crate::lock::lock(&path)?;
+    let mut resume = load_from(&path);
+    let before = resume.items.len();
+    resume.items.retain(|i| !same_email(&i.to, email));
+    if resume.items.len() != before {
+        removed += before - resume.items.len();
+        crate::schema::INITIAL.save(&path, &resume)?;
+    }
+    Ok(removed)
+}
+
+/// A config com que sai um envio da fila ou do resume.json.
+fn item_config(config: &Config, item: &ScheduledEmail) -> Result<Config> {
+    let mut config = config_for_profile(config, item.profile.as_deref())?;
+    config.cv_variant = item.cv_variant.clone();
+    config.no_track = item.no_track;
+    if let Some(template) = &item.template {
+        config.template = template.clone();
+        config.template_name = item.template_name.clone();
+    }
+    config.language = item.language.clone();
+    config.attach_portfolio = item.attach_portfolio;
+    config.campaign = item.campaign.clone();
+    Ok(config)
+}
+
+/// `resume`: envia, como lote, o que um bulk/mail-merge parado deixou por enviar.
+pub async fn run_resume(config: &Config, cv: &[u8], log: &mut SentLog) -> Result<()> {
+    // até o ficheiro sair, outro `resume` espera (e depois não encontra nada)
+    let path = crate::overrides::path(RESUME_FILE);
+    let resume_lock = crate::lock::lock(&path)?;
+    let resume = load_from(&path);
+    if resume.items.is_empty() {
+        say!("{} Nada por retomar", CHECK);
+        return Ok(());
+    }
+    // um perfil ou variante do CV que entretanto desapareceram param antes do primeiro envio
+    let batch = resume
+        .items
+        .iter()
+        .map(|item| {
+            let config = item_config(config, item)?;
+            if config.cv_variant.is_some() {
+                load_cv_variant(&config)?;
+            }
+            Ok((item.to.clone(), config))
+        })
+        .collect::<Result<Vec<(String, Config)>>>()?;
+    let delay = config.sending.delay.clone().unwrap_or_default();
+    say!("{} Retomar {} envios, delay {}", ROCKET, style(batch.len()).cyan(), style(delay.describe()).yellow());
+    if !confirm("Confirmar envio?", true)? {
+        say!("Cancelado!");
+        return Ok(());
+    }
+    // se voltar a parar, o send_batch grava de novo o que faltar
+    fs::remove_file(&path).with_context(|| format!("Cannot remove {}", path))?;
+    drop(resume_lock);
+
+    let batch = carry_over(batch, &config.sending, log, &delay)?;
+    let (success, failed) = send_batch(cv, log, &batch, &delay).await?;
+    crate::git_history::record(&format!("retomados: {} enviados ({} falhados)", success, failed));
+    say!();
+    say!(
+        "{} Resultado: {} enviados, {} falhados",
+        SPARKLE,
+        style(success).green().bold(),
+        style(failed).red().bold()
+    );
+    Ok(())
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.parse::<Tz>()
+        .map_err(|_| anyhow!("Unknown timezone '{}' (use IANA names like Europe/Lisbon)", name))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SendTime {
+    /// data e hora exactas no fuso do destinatário
+    At(NaiveDateTime),
+    /// próxima ocorrência desta hora no fuso do destinatário
+    Next(NaiveTime),
+}
+
+pub fn parse_send_time(input: &str) -> Result<SendTime> {
+    let input = input.trim();
+    if let Ok(dt) = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M") {
+        return Ok(SendTime::At(dt));
+    }
+    if let Ok(t) = NaiveTime::parse_from_str(input, "%H:%M") {
+        return Ok(SendTime::Next(t));
+    }
+    bail!("Invalid time '{}' (expected \"YYYY-MM-DD HH:MM\" or \"HH:MM\")", input)
+}
+
+fn localize<Z: TimeZone>(tz: &Z, when: SendTime, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let naive = match when {
+        SendTime::At(dt) => dt,
+        SendTime::Next(t) => {
+            let today = now.with_timezone(tz).date_naive().and_time(t);
+            let at = tz.from_local_datetime(&today).earliest()?;
+            if at.with_timezone(&Utc) > now {
+                today
+            } else {
+                today + ChronoDuration::days(1)
+            }
+        }
+    };
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Converte a hora pedida para UTC usando o fuso do destinatário (ou o local).
+pub fn resolve_send_at(
+    when: SendTime,
+    timezone: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>> {
+    let resolved = match timezone {
+        Some(name) => localize(&parse_timezone(name)?, when, now),
+        None => localize(&Local, when, now),
+    };
+    resolved.context("Time does not exist in the recipient's timezone (DST gap)")
+}
+
+pub fn schedule(
+    at: &str,
+    to: &[String],
+    all_contacts: bool,
+    profile: Option<&str>,
+    cv_variant: Option<&str>,
+    no_track: bool,
+) -> Result<()> {
+    let when = parse_send_time(at)?;
+    let recipients: Vec<String> = if all_contacts {
+        load_contacts().contacts.iter().map(|c| c.email.clone()).collect()
+    } else {
+        to.to_vec()
+    };
+    if recipients.is_empty() {
+        say!("{} Nenhum destinatário indicado!", CROSS);
+        return Ok(());
+    }
+
+    let queued = enqueue(Some(when), &recipients, profile, cv_variant, no_track)?;
+    for item in &queued {
+        say!(
+            "{} #{} {} → {} ({})",
+            CLOCK,
+            item.id,
+            style(&item.to).yellow(),
+            style(item.send_at.with_timezone(&Local).format("%d/%m %H:%M")).cyan(),
+            item.timezone.as_deref().unwrap_or("hora local")
+        );
+    }
+    say!("{} {} envios agendados", CHECK, style(queued.len()).cyan());
+    Ok(())
+}
+
+/// Põe os envios na fila; sem hora ficam para já (o próximo ciclo do daemon).
+pub fn enqueue(
+    when: Option<SendTime>,
+    recipients: &[String],
+    profile: Option<&str>,
+    cv_variant: Option<&str>,
+    no_track: bool,
+) -> Result<Vec<ScheduledEmail>> {
+    // um perfil ou variante do CV errados têm de falhar já, não quando o daemon pegar no envio
+    if profile.is_some() || cv_variant.is_some() {
+        let mut config = load_profile_config(profile)?;
+        config.cv_variant = cv_variant.map(str::to_string);
+        load_checked_cv(&config)?;
+    }
+    let book = load_contacts();
+    let blocklist = crate::blocklist::load_blocklist();
+    if let Some((email, pattern)) = recipients.iter().find_map(|e| blocklist.matching(e).map(|p| (e, p))) {
+        bail!("{} is blocked by blocklist pattern {}", email, pattern);
+    }
+
+    let now = Utc::now();
+    let queued = update_outbox(|outbox| {
+        let mut queued = vec![];
+        for email in recipients {
+            let tz = book.find(email).and_then(|c| c.timezone.clone());
+            let send_at = match when {
+                Some(when) => resolve_send_at(when, tz.as_deref(), now)?,
+                None => now,
+            };
+            let id = outbox.push(email, send_at, tz, profile, cv_variant, no_track);
+            queued.extend(outbox.get_mut(id).map(|i| i.clone()));
+        }
+        Ok(queued)
+    })?;
+    crate::git_history::record(&format!("agendados {} emails", recipients.len()));
+    Ok(queued)
+}
+
+// os dias seguintes de um lote acima do limite começam a esta hora local
+const CARRYOVER_HOUR: u32 = 9;
+
+/// Envios permitidos por dia: o `sending.max_per_day` e, enquanto dura, a
+/// rampa do `sending.warmup` (a começar hoje se ainda não houver envios).
+pub fn daily_limit(sending: &SendingConfig, log: &SentLog, now: DateTime<Local>) -> impl Fn(NaiveDate) -> Option<usize> {
+    let max = sending.max_per_day.filter(|max| *max > 0);
+    let warmup = sending.warmup.clone().map(|w| {
+        let start = w.start_date(log).unwrap_or(now.date_naive());
+        (w, start)
+    });
+    move |day| {
+        let ramp = warmup.as_ref().and_then(|(w, start)| w.cap_on(*start, day));
+        match (max, ramp) {
+            (Some(max), Some(ramp)) => Some(max.min(ramp)),
+            (max, ramp) => max.or(ramp),
+        }
+    }
+}
+
+/// Quantos envios ainda cabem hoje no limite diário (None sem limite).
+/// Os desfeitos e os do sandbox não contam; os falhados sim, chegaram ao servidor.
+pub fn room_today(sending: &SendingConfig, log: &SentLog, now: DateTime<Local>) -> Option<usize> {
+    let max = daily_limit(sending, log, now)(now.date_naive())?;
+    let today = log
+        .records
+        .iter()
+        .filter(|r| !r.cancelled && !r.sandbox && r.sent_at.date_naive() == now.date_naive())
+        .count();
+    Some(max.saturating_sub(today))
+}
+
+/// Horas dos `count` envios que passam para os dias seguintes: o limite de
+/// cada dia a partir das 9h, espaçados pelo `delay` como se fossem no mesmo lote.
+fn carryover_times(count: usize, limit: impl Fn(NaiveDate) -> usize, delay: &Delay, now: DateTime<Local>) -> Vec<DateTime<Utc>> {
+    let mut rng = rand::thread_rng();
+    let start = NaiveTime::from_hms_opt(CARRYOVER_HOUR, 0, 0).unwrap_or_default();
+    let mut times = vec![];
+    let mut day = now.date_naive();
+    let mut left = 0;
+    let mut at = now;
+    while times.len() < count {
+        if left == 0 {
+            day += ChronoDuration::days(1);
+            left = limit(day).max(1);
+            at = Local.from_local_datetime(&day.and_time(start)).earliest().unwrap_or(now);
+        } else {
+            at += ChronoDuration::seconds(delay.next(&mut rng) as i64);
+        }
+        times.push(at.with_timezone(&Utc));
+        left -= 1;
+    }
+    times
+}
+
+/// Com `sending.max_per_day`, o lote fica pelo que ainda cabe hoje e o resto
+/// vai para a fila dos dias seguintes, com o template já preparado de cada um.
+pub fn carry_over(
+    batch: Vec<(String, Config)>,
+    sending: &SendingConfig,
+    log: &SentLog,
+    delay: &Delay,
+) -> Result<Vec<(String, Config)>> {
+    let now = Local::now();
+    let Some(room) = room_today(sending, log, now) else {
+        return Ok(batch);
+    };
+    if batch.len() <= room {
+        return Ok(batch);
+    }
+    let mut batch = batch;
+    let rest = batch.split_off(room);
+    let limit = daily_limit(sending, log, now);
+    let times = carryover_times(rest.len(), |day| limit(day).unwrap_or(usize::MAX), delay, now);
+
+    let book = load_contacts();
+    update_outbox(|outbox| {
+        for ((email, config), send_at) in rest.iter().zip(&times) {
+            let tz = book.find(email).and_then(|c| c.timezone.clone());
+            outbox.push_prepared(email, config, *send_at, tz);
+        }
+        Ok(())
+    })?;
+    crate::git_history::record(&format!("{} emails passados para os dias seguintes (max_per_day)", rest.len()));
+    if let Some(last) = times.last() {
+        say!(
+            "{} {} emails acima do limite diário ({} hoje) ficam na fila, até {}",
+            CLOCK,
+            style(rest.len()).cyan(),
+            room,
+            style(last.with_timezone(&Local).format("%d/%m")).cyan()
+        );
+    }
+    Ok(batch)
+}
+
+fn format_send_at(item: &ScheduledEmail) -> String {
+    let local = item.send_at.with_timezone(&Local).format("%d/%m/%Y %H:%M");
+    match item.timezone.as_deref().and_then(|name| parse_timezone(name).ok()) {
+        Some(tz) => format!(
+            "{} ({} {})",
+            local,
+            item.send_at.with_timezone(&tz).format("%H:%M"),
+            tz.name()
+        ),
+        None => local.to_string(),
+    }
+}
+
+pub fn list_queue() {
+    let outbox = load_outbox();
+    if outbox.items.is_empty() {
+        println!("{} Fila vazia.", CLOCK);
+        return;
+    }
+
+    let mut items = outbox.items;
+    items.sort_by_key(|i| i.send_at);
+
+    println!("{} {} emails agendados:", CLOCK, style(items.len()).cyan());
+    println!("{}", style("─".repeat(60)).dim());
+    for item in &items {
+        println!(
+            "  #{:<4} {}  {}",
+            item.id,
+            style(format_send_at(item)).cyan(),
+            item.to
+        );
+    }
+    println!("{}", style("─".repeat(60)).dim());
+}
+
+pub fn cancel_queued(id: u64) -> Result<()> {
+    let item = update_outbox(|outbox| outbox.cancel(id).with_context(|| format!("No queued email with id #{}", id)))?;
+    crate::git_history::record(&format!("fila: #{} cancelado", id));
+
+    say!("{} #{} cancelado ({})", CHECK, id, item.to);
+    Ok(())
+}
+
+pub fn edit_queued(id: u64, send_at: Option<String>, to: Option<String>) -> Result<()> {
+    let mut outbox = load_outbox();
+    let item = outbox
+        .get_mut(id)
+        .with_context(|| format!("No queued email with id #{}", id))?;
+
+    // sem flags, pergunta a nova hora (no fuso do destinatário); a fila só fica presa depois da resposta
+    let send_at = match (send_at, &to) {
+        (None, None) => {
+            let current = match item.timezone.as_deref().and_then(|n| parse_timezone(n).ok()) {
+                Some(tz) => item.send_at.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string(),
+                None => item.send_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+            };
+            Some(
+                Input::<String>::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!("{} Nova hora (YYYY-MM-DD HH:MM)", CLOCK))
+                    .default(current)
+                    .interact_text()?,
+            )
+        }
+        (at, _) => at,
+    };
+
+    let timezone = to.as_ref().map(|to| load_contacts().find(to).and_then(|c| c.timezone.clone()));
+    let item = update_outbox(|outbox| {
+        // o daemon pode tê-lo despachado enquanto a pergunta estava aberta
+        let item = outbox
+            .get_mut(id)
+            .with_context(|| format!("No queued email with id #{}", id))?;
+        if let (Some(to), Some(timezone)) = (to, timezone) {
+            item.timezone = timezone;
+            item.to = to;
+        }
+        if let Some(at) = send_at {
+            item.send_at = resolve_send_at(parse_send_time(&at)?, item.timezone.as_deref(), Utc::now())?;
+        }
+        Ok(item.clone())
+    })?;
+
+    say!("{} #{} → {} {}", CHECK, id, style(&item.to).yellow(), format_send_at(&item));
+    crate::git_history::record(&format!("fila: #{} editado", id));
+    Ok(())
+}
+
+async fn flush_due(config: &Config, cv: &[u8], log: &mut SentLog) -> Result<usize> {
+    // tirar da fila antes de enviar, para um crash não duplicar envios
+    let due = update_outbox(|outbox| {
+        let mut due = outbox.take_due(Utc::now());
+        // acima do `max_per_day` o resto espera na fila pelo dia seguinte
+        if let Some(room) = room_today(&config.sending, log, Local::now()).filter(|room| *room < due.len()) {
+            due.sort_by_key(|i| i.send_at);
+            verbose!("limite diário: {} emails ficam na fila para amanhã", due.len() - room);
+            outbox.items.extend(due.split_off(room));
+        }
+        Ok(due)
+    })?;
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    let blocklist = crate::blocklist::load_blocklist();
+    let mut sent = 0;
+    for (i, item) in due.iter().enumerate() {
+        // Ctrl-C: os que ainda não saíram voltam para a fila
+        if crate::interrupted() {
+            update_outbox(|outbox| {
+                outbox.items.extend(due[i..].iter().cloned());
+                Ok(())
+            })?;
+            say!("{} {} envios devolvidos à fila", CLOCK, due.len() - i);
+            break;
+        }
+        sent += 1;
+        // bloqueado depois de agendado: sai da fila sem envio
+        if let Some(pattern) = blocklist.matching(&item.to) {
+            say!("{} [#{}] {} bloqueado ({}), não enviado", CROSS, item.id, style(&item.to).dim(), pattern);
+            continue;
+        }
+        // perfil ou variante do CV apagados do config.json entretanto: fica como envio falhado
+        let prepared = item_config(config, item).and_then(|config| {
+            let variant_cv = config.cv_variant.is_some().then(|| load_cv_variant(&config)).transpose()?;
+            Ok((config, variant_cv))
+        });
+        let (config, (message, result)) = match &prepared {
+            Ok((config, variant_cv)) => {
+                (config, send_email(config, &item.to, variant_cv.as_deref().unwrap_or(cv)).await)
+            }
+            Err(e) => (config, (SentMessage::default(), Err(anyhow!("{:#}", e)))),
+        };
+        record_send(log, config, &item.to, message, &result)?;
+
+        match result {
+            Ok(_) => say!("{} [#{}] Enviado para {}", CHECK, item.id, style(&item.to).green()),
+            Err(e) => eprintln!("{} [#{}] Falhou {}: {}", CROSS, item.id, style(&item.to).red(), e),
+        }
+    }
+    crate::git_history::record(&format!("daemon: enviados {} emails agendados", sent));
+    Ok(sent)
+}
+
+pub async fn run_daemon(
+    config: &Config,
+    cv: &[u8],
+    log: &mut SentLog,
+    interval: u64,
+    once: bool,
+) -> Result<()> {
+    say!("{} Daemon activo, a verificar a fila a cada {}s", CLOCK, interval);
+    let _interruptible = crate::Interruptible::start();
+    let mut since = Local::now();
+    let mut pruned_on = None;
+    if let Some(notifications) = &config.notifications {
+        let overdue = crate::notify::overdue(log, since, notifications.follow_up_days).len();
+        if overdue > 0 {
+            let body = format!("{} candidaturas sem resposta há {}+ dias", overdue, notifications.follow_up_days);
+            crate::notify::show("Follow-ups em atraso", &body);
+        }
+    }
+    loop {
+        if let Some(retention) = config.retention.as_ref().filter(|_| pruned_on != Some(Local::now().date_naive())) {
+            if let Err(e) = crate::retention::run(retention) {
+                eprintln!("{} Retenção falhou: {}", CROSS, e);
+            }
+            pruned_on = Some(Local::now().date_naive());
+        }
+        // `status`, `interview` e outros comandos mexem no log enquanto o daemon corre
+        *log = crate::load_log();
+        let sent = flush_due(config, cv, log).await? + crate::sequence::run_due(config, cv, log).await?;
+        if sent > 0 && config.sync.is_some() {
+            if let Err(e) = crate::sync::run_sync(config).await {
+                eprintln!("{} Sync falhou: {}", CROSS, e);
+            }
+            // o sync pode ter trazido registos de outra máquina
+            *log = crate::load_log();
+        }
+        if let Some(notifications) = &config.notifications {
+            let now = Local::now();
+            for notice in crate::notify::notices(log, since, now, notifications) {
+                crate::notify::show(&notice.title(), &notice.body());
+            }
+            since = now;
+        }
+        if once {
+            return Ok(());
+        }
+        crate::sleep_interruptible(Duration::from_secs(interval)).await;
+        if crate::interrupted() {
+            say!("{} Daemon parado", CHECK);
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_send_time() {
+        let at = parse_send_time("2024-07-01 09:00").unwrap();
+        let expected = NaiveDate::from_ymd_opt(2024, 7, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        assert_eq!(at, SendTime::At(expected));
+
+        let next = parse_send_time("09:30").unwrap();
+        assert_eq!(next, SendTime::Next(NaiveTime::from_hms_opt(9, 30, 0).unwrap()));
+
+        assert!(parse_send_time("amanhã").is_err());
+    }
+
+    #[test]
+    fn test_resolve_send_at_uses_recipient_timezone() {
+        let when = parse_send_time("2024-07-01 09:00").unwrap();
+        let now = utc(2024, 6, 1, 0, 0);
+
+        // Lisboa está em UTC+1 no verão, São Paulo em UTC-3
+        let lisbon = resolve_send_at(when, Some("Europe/Lisbon"), now).unwrap();
+        let sao_paulo = resolve_send_at(when, Some("America/Sao_Paulo"), now).unwrap();
+        assert_eq!(lisbon, utc(2024, 7, 1, 8, 0));
+        assert_eq!(sao_paulo, utc(2024, 7, 1, 12, 0));
+    }
+
+    #[test]
+    fn test_resolve_next_occurrence_rolls_over() {
+        let when = parse_send_time("09:00").unwrap();
+        // já passa das 9h em Luanda (UTC+1)
+        let now = utc(2024, 3, 10, 10, 0);
+        let at = resolve_send_at(when, Some("Africa/Luanda"), now).unwrap();
+        assert_eq!(at, utc(2024, 3, 11, 8, 0));
+    }
+
+    #[test]
+    fn test_resolve_send_at_unknown_timezone() {
+        let when = parse_send_time("09:00").unwrap();
+        assert!(resolve_send_at(when, Some("Mars/Olympus"), Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_cancel_and_edit() {
+        let mut outbox = Outbox::default();
+        let a = outbox.push("a@x.com", utc(2024, 1, 1, 9, 0), None, None, None, false);
+        let b = outbox.push("b@x.com", utc(2024, 1, 2, 9, 0), None, None, None, false);
+
+        assert_eq!(outbox.cancel(a).unwrap().to, "a@x.com");
+        assert!(outbox.cancel(a).is_none());
+
+        outbox.get_mut(b).unwrap().send_at = utc(2024, 2, 1, 9, 0);
+        assert!(outbox.take_due(utc(2024, 1, 3, 0, 0)).is_empty());
+        assert_eq!(outbox.items.len(), 1);
+    }
+
+    #[test]
+    fn test_take_due() {
+        let mut outbox = Outbox::default();
+        outbox.push("a@x.com", utc(2024, 1, 1, 9, 0), None, None, None, false);
+        outbox.push("b@x.com", utc(2024, 1, 2, 9, 0), None, None, None, false);
+
+        let due = outbox.take_due(utc(2024, 1, 1, 12, 0));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].to, "a@x.com");
+        assert_eq!(outbox.items.len(), 1);
+        assert_eq!(outbox.push("c@x.com", utc(2024, 1, 3, 9, 0), None, None, None, false), 3);
+    }
+
+    #[test]
+    fn test_daily_cap_and_carryover_times() {
+        let now = Local.with_ymd_and_hms(2024, 3, 10, 15, 0, 0).unwrap();
+        let record = |sent_at: DateTime<Local>, cancelled: bool| crate::SentRecord {
+            email: "a@x.com".to_string(),
+            sent_at,
+            cancelled,
+            ..Default::default()
+        };
+        let log = SentLog {
+            records: vec![
+                record(now - ChronoDuration::days(1), false),
+                record(now - ChronoDuration::hours(2), false),
+                record(now - ChronoDuration::hours(1), true),
+            ],
+        };
+        let mut sending = SendingConfig::default();
+        assert_eq!(room_today(&sending, &log, now), None);
+        sending.max_per_day = Some(3);
+        assert_eq!(room_today(&sending, &log, now), Some(2));
+        sending.max_per_day = Some(1);
+        assert_eq!(room_today(&sending, &log, now), Some(0));
+
+        let delay = Delay::Uniform { min: 60, max: 60 };
+        let times: Vec<DateTime<Local>> =
+            carryover_times(5, |_| 2, &delay, now).iter().map(|t| t.with_timezone(&Local)).collect();
+        let day = |d: u32, h: u32, m: u32| Local.with_ymd_and_hms(2024, 3, d, h, m, 0).unwrap();
+        assert_eq!(times, vec![day(11, 9, 0), day(11, 9, 1), day(12, 9, 0), day(12, 9, 1), day(13, 9, 0)]);
+    }
+
+    #[test]
+    fn test_warmup_ramp_limits_each_day() {
+        let now = Local.with_ymd_and_hms(2024, 3, 10, 15, 0, 0).unwrap();
+        let log = SentLog {
+            records: vec![crate::SentRecord { email: "a@x.com".to_string(), sent_at: now, ..Default::default() }],
+        };
+        let mut sending = SendingConfig {
+            warmup: Some(crate::warmup::Warmup { start: None, initial: 2, step: 1, every_days: 1, target: 4 }),
+            ..Default::default()
+        };
+        // rampa a começar hoje, no primeiro envio: 2, 3, depois acaba
+        assert_eq!(room_today(&sending, &log, now), Some(1));
+        let limit = daily_limit(&sending, &log, now);
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        assert_eq!((limit(date(11)), limit(date(12))), (Some(3), None));
+        sending.max_per_day = Some(2);
+        let limit = daily_limit(&sending, &log, now);
+        assert_eq!((limit(date(11)), limit(date(12))), (Some(2), Some(2)));
+
+        let delay = Delay::Uniform { min: 60, max: 60 };
+        let ramp = |day: NaiveDate| if day == date(11) { 1 } else { 3 };
+        let days: Vec<u32> = carryover_times(4, ramp, &delay, now).iter().map(|t| chrono::Datelike::day(&t.with_timezone(&Local))).collect();
+        assert_eq!(days, vec![11, 12, 12, 12]);
+    }
+}
@@ -0,0 +1,231 @@
+use console::style;
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Mutex,
+    },
+};
+use tracing::{field::Field, Event, Level, Subscriber};
+use tracing_subscriber::{
+    filter::Targets,
+    layer::{Context, SubscriberExt},
+    util::SubscriberInitExt,
+    Layer,
+};
+
+// 0 = -q (só erros), 1 = normal, 2 = -v (detalhe do template), 3 = -vv (diálogo SMTP)
+static VERBOSITY: AtomicU8 = AtomicU8::new(1);
+
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+pub fn set_verbosity(quiet: bool, verbose: u8) {
+    let level = if quiet { 0 } else { 1 + verbose.min(2) };
+    VERBOSITY.store(level, Ordering::Relaxed);
+    // o diálogo SMTP é sempre gravado (para o log em caso de falha), só é mostrado com -vv
+    let _ = tracing_subscriber::registry()
+        .with(SmtpDialogue.with_filter(Targets::new().with_target("lettre", Level::DEBUG)))
+        .try_init();
+}
+
+// lettre escreve "Wrote: <comando><CRLF>" e "<< <resposta><CRLF>"
+pub fn smtp_line(message: &str) -> String {
+    let line = match message.strip_prefix("Wrote: ") {
+        Some(cmd) => format!(">> {}", cmd),
+        None => message.to_string(),
+    };
+    line.trim_end_matches("<CRLF>").replace("<CRLF>", " / ")
+}
+
+/// Esconde as credenciais: o próprio comando AUTH e as linhas que o
+/// cliente envia até o servidor responder ao AUTH.
+#[derive(Default)]
+pub struct Redactor {
+    in_auth: bool,
+}
+
+impl Redactor {
+    pub const fn new() -> Self {
+        Self { in_auth: false }
+    }
+
+    pub fn redact(&mut self, line: &str) -> String {
+        if let Some(cmd) = line.strip_prefix(">> ") {
+            if cmd.to_ascii_uppercase().starts_with("AUTH ") {
+                self.in_auth = true;
+                let mechanism = cmd.split_whitespace().nth(1).unwrap_or("");
+                return format!(">> AUTH {} <redacted>", mechanism);
+            }
+            if self.in_auth {
+                return ">> <redacted>".to_string();
+            }
+        } else if line.starts_with("<< ") && !line.starts_with("<< 334") {
+            self.in_auth = false;
+        }
+        line.to_string()
+    }
+}
+
+/// Diálogo SMTP de um envio, já sem credenciais e sem o corpo da mensagem.
+#[derive(Default)]
+pub struct Transcript {
+    redactor: Redactor,
+    lines: Vec<String>,
+    in_data: bool,
+    data_bytes: usize,
+}
+
+impl Transcript {
+    pub const fn new() -> Self {
+        Self { redactor: Redactor::new(), lines: vec![], in_data: false, data_bytes: 0 }
+    }
+
+    /// Devolve a linha já redigida, para mostrar com -vv.
+    pub fn push(&mut self, message: &str) -> String {
+        let line = self.redactor.redact(&smtp_line(message));
+        if line.starts_with("<< 354") {
+            self.in_data = true;
+            self.data_bytes = 0;
+        } else if self.in_data && line.starts_with(">> ") {
+            self.data_bytes += line.len() - 3;
+            return line;
+        } else if self.in_data && line.starts_with("<< ") {
+            self.lines.push(format!(">> ({} bytes de mensagem)", self.data_bytes));
+            self.in_data = false;
+        }
+
+        // respostas multi-linha chegam acumuladas: "250-a", "250-a / 250 b"
+        match self.lines.last_mut() {
+            Some(last) if last.starts_with("<< ") && line.starts_with(last.as_str()) => *last = line.clone(),
+            _ => self.lines.push(line.clone()),
+        }
+        line
+    }
+
+    pub fn take(&mut self) -> Vec<String> {
+        let lines = std::mem::take(&mut self.lines);
+        *self = Self::new();
+        lines
+    }
+}
+
+static TRANSCRIPT: Mutex<Transcript> = Mutex::new(Transcript::new());
+
+fn transcript() -> std::sync::MutexGuard<'static, Transcript> {
+    TRANSCRIPT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Começa um diálogo novo (chamado antes de cada envio).
+pub fn clear_transcript() {
+    transcript().take();
+}
+
+pub fn take_transcript() -> Vec<String> {
+    transcript().take()
+}
+
+fn truncate(line: &str, max: usize) -> String {
+    match line.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}… ({} bytes)", &line[..idx], line.len()),
+        None => line.to_string(),
+    }
+}
+
+#[derive(Default)]
+pub struct MessageVisitor(pub String);
+
+impl tracing::field::Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+struct SmtpDialogue;
+
+impl<S: Subscriber> Layer<S> for SmtpDialogue {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let line = transcript().push(&visitor.0);
+        if verbosity() >= 3 {
+            eprintln!("   {} {}", style("SMTP").dim(), style(truncate(&line, 200)).dim());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_auth() {
+        let mut r = Redactor::default();
+        let lines: Vec<String> = [
+            "Wrote: EHLO localhost<CRLF>",
+            "<< 250 AUTH PLAIN LOGIN",
+            "Wrote: AUTH LOGIN<CRLF>",
+            "<< 334 VXNlcm5hbWU6",
+            "Wrote: am9hb0BleGFtcGxlLmNvbQ==<CRLF>",
+            "<< 334 UGFzc3dvcmQ6",
+            "Wrote: c2VjcmV0<CRLF>",
+            "<< 235 2.7.0 Accepted",
+            "Wrote: MAIL FROM:<joao@example.com><CRLF>",
+        ]
+        .iter()
+        .map(|l| r.redact(&smtp_line(l)))
+        .collect();
+
+        assert_eq!(lines[0], ">> EHLO localhost");
+        assert_eq!(lines[2], ">> AUTH LOGIN <redacted>");
+        assert_eq!(lines[4], ">> <redacted>");
+        assert_eq!(lines[6], ">> <redacted>");
+        assert_eq!(lines[8], ">> MAIL FROM:<joao@example.com>");
+        assert!(!lines.join("\n").contains("c2VjcmV0"));
+    }
+
+    #[test]
+    fn test_redacts_auth_plain_inline() {
+        let mut r = Redactor::default();
+        assert_eq!(r.redact(">> AUTH PLAIN AGpvYW8Ac2VjcmV0"), ">> AUTH PLAIN <redacted>");
+        assert_eq!(r.redact("<< 235 ok"), "<< 235 ok");
+        assert_eq!(r.redact(">> QUIT"), ">> QUIT");
+    }
+
+    #[test]
+    fn test_transcript_collapses_data_and_multiline() {
+        let mut t = Transcript::new();
+        for line in [
+            "<< 220 mx.acme.com ESMTP<CRLF>",
+            "Wrote: EHLO localhost<CRLF>",
+            "<< 250-mx.acme.com<CRLF>",
+            "<< 250-mx.acme.com<CRLF>250 8BITMIME<CRLF>",
+            "Wrote: AUTH PLAIN AGpvYW8Ac2VjcmV0<CRLF>",
+            "<< 235 ok<CRLF>",
+            "Wrote: DATA<CRLF>",
+            "<< 354 go ahead<CRLF>",
+            "Wrote: Subject: x<CRLF><CRLF>corpo<CRLF>",
+            "Wrote: <CRLF>.<CRLF>",
+            "<< 550 5.7.1 Message rejected as spam<CRLF>",
+        ] {
+            t.push(line);
+        }
+
+        let lines = t.take();
+        assert_eq!(lines[2], "<< 250-mx.acme.com / 250 8BITMIME");
+        assert_eq!(lines[3], ">> AUTH PLAIN <redacted>");
+        assert!(lines[7].starts_with(">> (") && lines[7].ends_with("bytes de mensagem)"));
+        assert_eq!(lines.last().unwrap(), "<< 550 5.7.1 Message rejected as spam");
+        assert_eq!(lines.len(), 9);
+        assert!(t.take().is_empty());
+    }
+
+    #[test]
+    fn test_truncate_long_lines() {
+        assert_eq!(truncate("curto", 200), "curto");
+        assert!(truncate(&"a".repeat(500), 200).ends_with("… (500 bytes)"));
+    }
+}
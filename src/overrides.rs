@@ -0,0 +1,116 @@
+use serde_json::Value;
+use std::{env, path::PathBuf};
+
+use crate::{
+    blocklist::BLOCKLIST_FILE, contacts::CONTACTS_FILE, correlation::REPLIES_FILE, dead::DEAD_FILE, oauth::OAUTH_FILE, outbox::OUTBOX_FILE, outbox::RESUME_FILE,
+    CONFIG_FILE, CV_FILE, LOG_FILE, SANDBOX_DIR, SENT_DIR,
+};
+
+/// Pasta com os ficheiros de dados; por omissão a pasta actual.
+const DIR_VAR: &str = "JOB_MAILER_DIR";
+
+// a variável que troca cada ficheiro (ou pasta) por outro caminho
+const PATH_VARS: &[(&str, &str)] = &[
+    (CONFIG_FILE, "JOB_MAILER_CONFIG"),
+    (CV_FILE, "JOB_MAILER_CV"),
+    (LOG_FILE, "JOB_MAILER_LOG"),
+    (CONTACTS_FILE, "JOB_MAILER_CONTACTS"),
+    (OUTBOX_FILE, "JOB_MAILER_OUTBOX"),
+    (RESUME_FILE, "JOB_MAILER_RESUME"),
+    (DEAD_FILE, "JOB_MAILER_DEAD"),
+    (BLOCKLIST_FILE, "JOB_MAILER_BLOCKLIST"),
+    (REPLIES_FILE, "JOB_MAILER_REPLIES"),
+    (OAUTH_FILE, "JOB_MAILER_OAUTH_TOKENS"),
+    (SANDBOX_DIR, "JOB_MAILER_SANDBOX_DIR"),
+    (SENT_DIR, "JOB_MAILER_SENT_DIR"),
+];
+
+/// `JOB_MAILER__SENDING__MAX_PER_DAY=20` muda `sending.max_per_day` do config.json.
+const CONFIG_PREFIX: &str = "JOB_MAILER__";
+
+fn var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+fn resolve(name: &str, var: impl Fn(&str) -> Option<String>) -> String {
+    let own = PATH_VARS.iter().find(|(file, _)| *file == name).and_then(|(_, v)| var(v));
+    match (own, var(DIR_VAR)) {
+        (Some(path), _) => path,
+        (None, Some(dir)) => PathBuf::from(dir).join(name).to_string_lossy().into_owned(),
+        (None, None) => name.to_string(),
+    }
+}
+
+/// Onde está um ficheiro de dados: a sua `JOB_MAILER_<X>`, senão dentro de
+/// `JOB_MAILER_DIR`, senão na pasta actual.
+pub fn path(name: &str) -> String {
+    resolve(name, var)
+}
+
+pub fn data_dir() -> PathBuf {
+    PathBuf::from(var(DIR_VAR).unwrap_or_else(|| ".".to_string()))
+}
+
+/// Aplica as `JOB_MAILER__<CHAVE>__<SUBCHAVE>` ao JSON do config antes de o ler.
+/// O valor é JSON quando o é (`20`, `true`, `[1,2]`), senão texto.
+pub fn apply_to_config(config: &mut Value, vars: impl IntoIterator<Item = (String, String)>) {
+    for (name, raw) in vars {
+        let Some(key) = name.strip_prefix(CONFIG_PREFIX) else {
+            continue;
+        };
+        let keys: Vec<String> = key.split("__").map(str::to_lowercase).collect();
+        let value = serde_json::from_str(&raw).unwrap_or(Value::String(raw));
+        let Some((last, parents)) = keys.split_last() else {
+            continue;
+        };
+        let mut target = &mut *config;
+        for key in parents {
+            if !target.get(key).is_some_and(Value::is_object) {
+                target[key] = Value::Object(Default::default());
+            }
+            target = &mut target[key];
+        }
+        if target.is_object() {
+            target[last] = value;
+        }
+    }
+}
+
+pub fn config_vars() -> Vec<(String, String)> {
+    env::vars().filter(|(name, _)| name.starts_with(CONFIG_PREFIX)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_paths_from_env() {
+        let vars = |set: &'static [(&str, &str)]| {
+            move |name: &str| set.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(resolve(LOG_FILE, vars(&[])), "sent_log.json");
+        let data = vars(&[("JOB_MAILER_DIR", "/data"), ("JOB_MAILER_CV", "/secrets/cv.pdf")]);
+        assert_eq!(resolve(LOG_FILE, data), "/data/sent_log.json");
+        assert_eq!(resolve(CV_FILE, data), "/secrets/cv.pdf");
+    }
+
+    #[test]
+    fn test_config_keys_from_env() {
+        let mut config = json!({"profile": {"name": "Ana"}, "sending": {"sandbox": false}});
+        let vars = [
+            ("JOB_MAILER__SENDING__MAX_PER_DAY", "20"),
+            ("JOB_MAILER__SENDING__SANDBOX", "true"),
+            ("JOB_MAILER__PROFILE__NAME", "Ana Lima"),
+            ("JOB_MAILER__SYNC__BACKEND", "webdav"),
+            ("JOB_MAILER_LOG", "/data/log.json"),
+        ];
+        apply_to_config(&mut config, vars.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+
+        assert_eq!(config["sending"], json!({"sandbox": true, "max_per_day": 20}));
+        assert_eq!(config["profile"]["name"], "Ana Lima");
+        assert_eq!(config["sync"], json!({"backend": "webdav"}));
+        assert!(config.get("log").is_none());
+    }
+}
@@ -0,0 +1,177 @@
+/// Gerador de PDF mínimo para relatórios: texto em Helvetica (WinAnsi), uma
+/// lista de páginas A4 sem compressão, com título e autor nos metadados.
+pub const A4: (f32, f32) = (595.0, 842.0);
+
+pub struct Text {
+    // pontos a partir do canto inferior esquerdo
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    pub bold: bool,
+    pub text: String,
+}
+
+/// O texto em WinAnsi (o que as fontes base do PDF conhecem); o resto vira "?".
+pub fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '→' => b'>',
+            _ => b'?',
+        })
+        .collect()
+}
+
+fn literal(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for b in win_ansi(text) {
+        if matches!(b, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        out.push(b);
+    }
+    out.push(b')');
+    out
+}
+
+// metadados em UTF-16BE, para os acentos aparecerem certos no leitor
+fn info_string(text: &str) -> String {
+    let hex: String = text.encode_utf16().map(|u| format!("{:04X}", u)).collect();
+    format!("<FEFF{}>", hex)
+}
+
+/// Largura aproximada em pontos (Helvetica tem em média ~0,5 em por carácter).
+pub fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * 0.52
+}
+
+/// Corta o texto para caber em `width` pontos, com "…" no fim.
+pub fn fit(text: &str, size: f32, width: f32) -> String {
+    if text_width(text, size) <= width {
+        return text.to_string();
+    }
+    let max = (width / (size * 0.52)) as usize;
+    let cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Divide o texto em linhas que caibam em `width` pontos, pelas palavras.
+pub fn wrap(text: &str, size: f32, width: f32) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+            if text_width(&candidate, size) > width && !line.is_empty() {
+                // uma palavra sozinha pode já ser mais larga que a linha
+                lines.push(fit(&std::mem::take(&mut line), size, width));
+                line = word.to_string();
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(fit(&line, size, width));
+    }
+    lines.retain(|l| !l.is_empty());
+    lines
+}
+
+pub fn document(pages: &[Vec<Text>], title: &str, author: &str) -> Vec<u8> {
+    // 1 catálogo, 2 páginas, 3-4 fontes, 5 info, depois página + conteúdo para cada uma
+    let first_page = 6;
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", first_page + i * 2)).collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        format!("<< /Title {} /Author {} /Producer (job-mailer) >>", info_string(title), info_string(author))
+            .into_bytes(),
+    ];
+    for (i, page) in pages.iter().enumerate() {
+        let mut content = vec![];
+        for t in page {
+            let font = if t.bold { "F2" } else { "F1" };
+            content.extend(format!("BT /{} {} Tf {:.1} {:.1} Td ", font, t.size, t.x, t.y).bytes());
+            content.extend(literal(&t.text));
+            content.extend(b" Tj ET\n");
+        }
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                A4.0,
+                A4.1,
+                first_page + i * 2 + 1
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"endstream");
+        objects.push(stream);
+    }
+
+    // o comentário binário diz aos programas que o ficheiro não é texto
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    pdf.extend(
+        format!("trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref)
+            .bytes(),
+    );
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_is_a_valid_pdf() {
+        let line = |y: f32, text: &str| Text { x: 40.0, y, size: 10.0, bold: false, text: text.to_string() };
+        let pages = vec![vec![line(800.0, "Candidatura (Acme) → Ana")], vec![line(800.0, "Página 2")]];
+        let pdf = document(&pages, "Registo de candidaturas", "João Silva");
+
+        let info = crate::cv::inspect(&pdf);
+        assert_eq!(info.pages, 2);
+        assert_eq!(info.title.as_deref(), Some("Registo de candidaturas"));
+        assert_eq!(info.author.as_deref(), Some("João Silva"));
+        assert!(info.xref_ok && info.eof);
+        // parênteses escapados e acentos em WinAnsi
+        assert!(pdf.windows(28).any(|w| w == b"(Candidatura \\(Acme\\) > Ana)"));
+        assert!(pdf.windows(10).any(|w| w == b"(P\xe1gina 2)"));
+    }
+
+    #[test]
+    fn test_wrap_and_fit() {
+        assert_eq!(wrap("pediu pretensões salariais\nligar 2ª feira", 10.0, 80.0), vec![
+            "pediu",
+            "pretensões",
+            "salariais",
+            "ligar 2ª feira"
+        ]);
+        assert_eq!(wrap("recrutamento@empresa-muito-grande.com ligar", 10.0, 60.0), vec!["recrutamen…", "ligar"]);
+        assert_eq!(fit("recrutamento@empresa-muito-grande.com", 10.0, 60.0), "recrutamen…");
+        assert_eq!(fit("rh@x.pt", 10.0, 60.0), "rh@x.pt");
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::{bail, Context, Result};
+use lettre::message::{
+    header::{ContentDisposition, ContentType},
+    MultiPart, SinglePart,
+};
+use sequoia_openpgp::{
+    armor,
+    crypto::KeyPair,
+    parse::Parse,
+    policy::StandardPolicy,
+    serialize::stream::{Armorer, Encryptor, LiteralWriter, Message, Signer},
+    types::HashAlgorithm,
+    Cert,
+};
+use std::{env, io::Write, path::Path};
+
+// a chave de assinatura do ficheiro, já desbloqueada com PGP_PASSPHRASE se for preciso
+fn signing_keypair(key_file: &Path) -> Result<KeyPair> {
+    let cert = Cert::from_file(key_file).with_context(|| format!("Cannot read PGP key {}", key_file.display()))?;
+    let policy = StandardPolicy::new();
+    let found = cert
+        .keys()
+        .with_policy(&policy, None)
+        .supported()
+        .alive()
+        .revoked(false)
+        .for_signing()
+        .secret()
+        .next()
+        .with_context(|| format!("No usable secret signing key in {}", key_file.display()))?;
+    let mut key = found.key().clone();
+    if key.secret().is_encrypted() {
+        let passphrase = env::var("PGP_PASSPHRASE").context("The PGP key is protected: set PGP_PASSPHRASE")?;
+        key.secret_mut()
+            .decrypt_in_place(found.key(), &passphrase.into())
+            .context("Wrong PGP_PASSPHRASE")?;
+    }
+    key.into_keypair()
+}
+
+/// Assinatura destacada (armored) de `data` com a chave privada em `key_file`.
+/// A passphrase, se existir, vem de PGP_PASSPHRASE.
+pub fn detached_signature(data: &[u8], key_file: &Path) -> Result<String> {
+    let keypair = signing_keypair(key_file)?;
+    let mut signature = vec![];
+    let message = Armorer::new(Message::new(&mut signature)).kind(armor::Kind::Signature).build()?;
+    let mut signer = Signer::new(message, keypair)?.detached().hash_algo(HashAlgorithm::SHA256)?.build()?;
+    signer.write_all(data)?;
+    signer.finalize()?;
+    Ok(String::from_utf8(signature)?)
+}
+
+/// Embrulha o conteúdo num multipart/signed (RFC 3156).
+pub fn sign(content: MultiPart, key_file: &str) -> Result<MultiPart> {
+    let signature = detached_signature(&content.formatted(), Path::new(key_file))?;
+
+    let signature_part = SinglePart::builder()
+        .header(ContentType::parse("application/pgp-signature; name=\"signature.asc\"")?)
+        .header(ContentDisposition::attachment("signature.asc"))
+        .body(signature);
+
+    Ok(
+        MultiPart::signed("application/pgp-signature".into(), "pgp-sha256".into())
+            .multipart(content)
+            .singlepart(signature_part),
+    )
+}
+
+pub fn is_public_key(text: &str) -> bool {
+    text.contains("-----BEGIN PGP PUBLIC KEY BLOCK-----")
+}
+
+/// Cifra o conteúdo para a chave pública do destinatário (e para a nossa,
+/// se houver, para o email enviado continuar legível) num multipart/encrypted.
+pub fn encrypt(content: MultiPart, recipient_key: &str, own_key_file: Option<&str>) -> Result<MultiPart> {
+    let policy = StandardPolicy::new();
+    let recipient = Cert::from_bytes(recipient_key.as_bytes()).context("Cannot read recipient PGP key")?;
+    let own = own_key_file
+        .map(|file| Cert::from_file(file).with_context(|| format!("Cannot read PGP key {}", file)))
+        .transpose()?;
+
+    let mut recipients = vec![];
+    // uma chave própria só de assinatura não impede o envio
+    for cert in std::iter::once(&recipient).chain(&own) {
+        recipients.extend(cert.keys().with_policy(&policy, None).supported().alive().revoked(false).for_transport_encryption());
+        if recipients.is_empty() {
+            bail!("The recipient PGP key {} cannot encrypt", recipient.fingerprint());
+        }
+    }
+
+    let mut ciphertext = vec![];
+    let message = Armorer::new(Message::new(&mut ciphertext)).build()?;
+    let message = Encryptor::for_recipients(message, recipients).build()?;
+    let mut literal = LiteralWriter::new(message).build()?;
+    literal.write_all(&content.formatted())?;
+    literal.finalize()?;
+
+    let control = SinglePart::builder()
+        .header(ContentType::parse("application/pgp-encrypted")?)
+        .body("Version: 1".to_string());
+    let payload = SinglePart::builder()
+        .header(ContentType::parse("application/octet-stream; name=\"encrypted.asc\"")?)
+        .header(ContentDisposition::inline_with_name("encrypted.asc"))
+        .body(String::from_utf8(ciphertext)?);
+
+    Ok(MultiPart::encrypted("application/pgp-encrypted".into())
+        .singlepart(control)
+        .singlepart(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sequoia_openpgp::{
+        cert::CertBuilder,
+        crypto::SessionKey,
+        packet::{PKESK, SKESK},
+        parse::stream::{DecryptionHelper, DecryptorBuilder, MessageStructure, VerificationHelper},
+        serialize::SerializeInto,
+        types::SymmetricAlgorithm,
+        KeyHandle, Packet,
+    };
+    use std::{fs, io::Read};
+
+    fn generate_key(user: &str) -> Cert {
+        CertBuilder::general_purpose(Some(user)).generate().unwrap().0
+    }
+
+    #[test]
+    fn test_sign_produces_verifiable_signature() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cert = generate_key("Teste <teste@example.com>");
+        let key_file = dir.path().join("secret.asc");
+        fs::write(&key_file, cert.as_tsk().armored().to_vec().unwrap()).unwrap();
+
+        let content = MultiPart::mixed().singlepart(SinglePart::plain("Olá, segue o CV.".to_string()));
+        let signed = sign(content.clone(), key_file.to_str().unwrap()).unwrap();
+        let raw = String::from_utf8(signed.formatted()).unwrap();
+
+        assert!(raw.contains("multipart/signed"));
+        assert!(raw.contains("protocol=\"application/pgp-signature\""));
+        assert!(raw.contains("-----BEGIN PGP SIGNATURE-----"));
+
+        // o que foi assinado tem de ser exactamente a parte embutida
+        let signed_part = content.formatted();
+        assert!(raw.as_bytes().windows(signed_part.len()).any(|w| w == signed_part));
+
+        let signature = detached_signature(&signed_part, &key_file).unwrap();
+        let Packet::Signature(signature) = Packet::from_bytes(signature.as_bytes()).unwrap() else {
+            panic!("not a signature");
+        };
+        assert_eq!(signature.hash_algo(), HashAlgorithm::SHA256);
+        let policy = StandardPolicy::new();
+        let signing = cert.keys().with_policy(&policy, None).for_signing().next().unwrap();
+        signature.verify_message(signing.key(), &signed_part).unwrap();
+        assert!(signature.verify_message(signing.key(), b"outra coisa").is_err());
+    }
+
+    // só para o teste: decifra com a chave secreta do destinatário
+    struct Recipient(Cert);
+
+    impl VerificationHelper for Recipient {
+        fn get_certs(&mut self, _ids: &[KeyHandle]) -> sequoia_openpgp::Result<Vec<Cert>> {
+            Ok(vec![])
+        }
+
+        fn check(&mut self, _structure: MessageStructure) -> sequoia_openpgp::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl DecryptionHelper for Recipient {
+        fn decrypt(
+            &mut self,
+            pkesks: &[PKESK],
+            _skesks: &[SKESK],
+            sym_algo: Option<SymmetricAlgorithm>,
+            decrypt: &mut dyn FnMut(Option<SymmetricAlgorithm>, &SessionKey) -> bool,
+        ) -> sequoia_openpgp::Result<Option<Cert>> {
+            let policy = StandardPolicy::new();
+            let key = self.0.keys().unencrypted_secret().with_policy(&policy, None).for_transport_encryption().next();
+            let mut pair = key.unwrap().key().clone().into_keypair()?;
+            for pkesk in pkesks {
+                if let Some((algo, session_key)) = pkesk.decrypt(&mut pair, sym_algo) {
+                    if decrypt(algo, &session_key) {
+                        break;
+                    }
+                }
+            }
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn test_encrypt_for_recipient() {
+        let recipient = generate_key("RH <rh@acme.com>");
+        let public = String::from_utf8(recipient.armored().to_vec().unwrap()).unwrap();
+        assert!(is_public_key(&public));
+
+        let content = MultiPart::mixed().singlepart(SinglePart::plain("Candidatura confidencial".to_string()));
+        let encrypted = encrypt(content.clone(), &public, None).unwrap();
+        let raw = String::from_utf8(encrypted.formatted()).unwrap();
+
+        assert!(raw.contains("multipart/encrypted"));
+        assert!(raw.contains("Version: 1"));
+        assert!(!raw.contains("Candidatura confidencial"));
+
+        let start = raw.find("-----BEGIN PGP MESSAGE-----").unwrap();
+        let end = raw.find("-----END PGP MESSAGE-----").unwrap() + "-----END PGP MESSAGE-----".len();
+        let policy = StandardPolicy::new();
+        let mut decryptor = DecryptorBuilder::from_bytes(&raw.as_bytes()[start..end])
+            .unwrap()
+            .with_policy(&policy, None, Recipient(recipient))
+            .unwrap();
+        let mut plain = vec![];
+        decryptor.read_to_end(&mut plain).unwrap();
+        assert_eq!(plain, content.formatted());
+    }
+
+    #[test]
+    fn test_sign_with_missing_key() {
+        let content = MultiPart::mixed().singlepart(SinglePart::plain("x".to_string()));
+        assert!(sign(content, "/nao/existe.asc").is_err());
+    }
+}
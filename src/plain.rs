@@ -0,0 +1,199 @@
+// conteúdo que não se lê: fica de fora por inteiro
+const SKIPPED: &[&str] = &["head", "style", "script", "title"];
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        let entity = tail[1..].find(';').filter(|end| *end <= 10).map(|end| &tail[1..end + 1]);
+        let decoded = entity.and_then(|e| match e {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let code = match e.strip_prefix("#x").or_else(|| e.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => e.strip_prefix('#').and_then(|d| d.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+        match (entity, decoded) {
+            (Some(e), Some(c)) => {
+                out.push(c);
+                rest = &tail[e.len() + 2..];
+            }
+            _ => {
+                out.push('&');
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn attr(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let start = lower.find(&format!("{}=", name))? + name.len() + 1;
+    let value = &tag[start..];
+    let value = match value.chars().next()? {
+        q @ ('"' | '\'') => value[1..].split(q).next()?,
+        _ => value.split(|c: char| c.is_whitespace() || c == '>').next()?,
+    };
+    Some(decode_entities(value))
+}
+
+// espaços do HTML: qualquer sequência vale um espaço
+fn push_text(out: &mut String, text: &str) {
+    let space = |out: &mut String| {
+        if !out.is_empty() && !out.ends_with([' ', '\n']) {
+            out.push(' ');
+        }
+    };
+    if text.starts_with(char::is_whitespace) {
+        space(out);
+    }
+    for (i, word) in text.split_whitespace().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    if text.ends_with(char::is_whitespace) {
+        space(out);
+    }
+}
+
+fn push_break(out: &mut String, blank_line: bool) {
+    while out.ends_with(' ') {
+        out.pop();
+    }
+    let wanted = if blank_line { "\n\n" } else { "\n" };
+    if !out.is_empty() && !out.ends_with(wanted) {
+        out.push('\n');
+        if blank_line && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+    }
+}
+
+/// Texto legível a partir do HTML, para a parte text/plain de um template
+/// que só tem `html`: sem tags, links como "texto (url)", parágrafos e
+/// listas em linhas próprias.
+pub fn html_to_text(html: &str) -> String {
+    let mut out = String::new();
+    let mut rest = html;
+    // link aberto: (href, posição do texto no `out`)
+    let mut link: Option<(String, usize)> = None;
+    while let Some(open) = rest.find('<') {
+        push_text(&mut out, &decode_entities(&rest[..open]));
+        let Some(close) = rest[open..].find('>') else {
+            rest = &rest[open..];
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        rest = &rest[open + close + 1..];
+        if tag.starts_with("!--") {
+            if let Some(end) = rest.find("-->") {
+                rest = &rest[end + 3..];
+            }
+            continue;
+        }
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        if !closing && SKIPPED.contains(&name.as_str()) {
+            let end_tag = format!("</{}", name);
+            rest = rest.to_ascii_lowercase().find(&end_tag).map_or("", |end| &rest[end..]);
+            continue;
+        }
+        match (name.as_str(), closing) {
+            ("br", _) => push_break(&mut out, false),
+            ("p" | "div" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "ul" | "ol" | "table" | "blockquote", _) => {
+                push_break(&mut out, true)
+            }
+            ("tr", true) => push_break(&mut out, false),
+            ("td" | "th", true) => out.push(' '),
+            ("hr", _) => {
+                push_break(&mut out, true);
+                out.push_str("---");
+                push_break(&mut out, true);
+            }
+            ("li", false) => {
+                push_break(&mut out, false);
+                out.push_str("- ");
+            }
+            ("a", false) => link = attr(tag, "href").map(|href| (href, out.len())),
+            ("a", true) => {
+                if let Some((href, start)) = link.take() {
+                    let text = out[start..].trim().to_string();
+                    // mailto: e links cujo texto já é o url não precisam de repetir
+                    let bare = href.strip_prefix("mailto:").unwrap_or(&href);
+                    if text.is_empty() {
+                        out.push_str(bare);
+                    } else if text != bare && text != href && !href.starts_with('#') {
+                        while out.ends_with(' ') {
+                            out.pop();
+                        }
+                        out.push_str(&format!(" ({})", bare));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    push_text(&mut out, &decode_entities(rest));
+
+    // no máximo uma linha em branco seguida, sem espaços nas pontas das linhas
+    let lines: Vec<&str> = out.lines().map(str::trim).collect();
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() && (i == 0 || lines[i - 1].is_empty()) {
+            continue;
+        }
+        text.push_str(line);
+        text.push('\n');
+    }
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text() {
+        let html = r#"<html><head><title>x</title><style>p { color: red }</style></head>
+            <body><p>Olá equipa <b>Tom &amp; Jerry</b>,</p>
+            <p>Vejam o meu <a href="https://joao.dev/cases?ref=a&amp;b=1">portefólio</a>
+            ou https://github.com/joao.<br>Obrigado!</p>
+            <ul><li>Rust</li><li>Go &lt;3</li></ul>
+            <!-- comentário -->
+            <p><a href="mailto:joao@x.pt">joao@x.pt</a> &#8212; <a href="https://x.pt"></a></p></body></html>"#;
+        assert_eq!(
+            html_to_text(html),
+            "Olá equipa Tom & Jerry,\n\n\
+             Vejam o meu portefólio (https://joao.dev/cases?ref=a&b=1) ou https://github.com/joao.\n\
+             Obrigado!\n\n\
+             - Rust\n\
+             - Go <3\n\n\
+             joao@x.pt — https://x.pt"
+        );
+    }
+
+    #[test]
+    fn test_entities() {
+        assert_eq!(decode_entities("a &amp; b &#x41; &#66; &bogus; & c"), "a & b A B &bogus; & c");
+    }
+}
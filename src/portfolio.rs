@@ -0,0 +1,233 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::Cell,
+    fs,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+/// Amostras de trabalho para as vagas que as pedem: um PDF já feito
+/// (`pdf`) ou um ZIP gerado na hora com os `files` (ficheiros ou pastas).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioConfig {
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub pdf: Option<String>,
+    // anexos grandes vão parar ao spam ou são recusados
+    #[serde(default = "default_max_kb")]
+    pub max_kb: u64,
+}
+
+fn default_max_kb() -> u64 {
+    5 * 1024
+}
+
+#[derive(Debug)]
+pub struct Bundle {
+    pub name: String,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+// pastas entram com o nome delas; ficheiros soltos ficam na raiz do ZIP
+fn collect(path: &Path, prefix: &str, out: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    let name = path
+        .file_name()
+        .with_context(|| format!("Invalid portfolio path: {}", path.display()))?
+        .to_string_lossy();
+    let entry = format!("{}{}", prefix, name);
+    if path.is_dir() {
+        let mut children: Vec<PathBuf> = fs::read_dir(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()?;
+        children.sort();
+        for child in children {
+            collect(&child, &format!("{}/", entry), out)?;
+        }
+    } else if path.is_file() {
+        out.push((entry, path.to_path_buf()));
+    } else {
+        bail!("{} not found", path.display());
+    }
+    Ok(())
+}
+
+// datas fixas: o mesmo conteúdo dá o mesmo ficheiro
+fn zip_options() -> SimpleFileOptions {
+    SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default())
+}
+
+// o ZIP em memória, com o tamanho já escrito à vista enquanto o ZipWriter o tem
+struct Sink {
+    bytes: Cursor<Vec<u8>>,
+    written: Rc<Cell<u64>>,
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.bytes.write(buf)?;
+        self.written.set(self.bytes.get_ref().len() as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Sink {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.bytes.seek(pos)
+    }
+}
+
+/// ZIP com deflate dos `paths`; pára logo que passe de `limit` bytes em vez de
+/// construir o resto em memória. Acima dos limites do ZIP clássico usa ZIP64.
+pub fn zip(paths: &[(String, PathBuf)], limit: u64) -> Result<Vec<u8>> {
+    let written = Rc::new(Cell::new(0));
+    let mut writer = ZipWriter::new(Sink { bytes: Cursor::new(vec![]), written: written.clone() });
+    for (name, path) in paths {
+        let size = fs::metadata(path).with_context(|| format!("Cannot read {}", path.display()))?.len();
+        writer.start_file(name.as_str(), zip_options().large_file(size >= u32::MAX as u64))?;
+        let mut file = fs::File::open(path).with_context(|| format!("Cannot read {}", path.display()))?;
+        let mut chunk = vec![0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut chunk).with_context(|| format!("Cannot read {}", path.display()))?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&chunk[..n])?;
+            if written.get() > limit {
+                bail!("Portfolio is over the {} KB limit (portfolio.max_kb)", limit / 1024);
+            }
+        }
+    }
+    Ok(writer.finish()?.bytes.into_inner())
+}
+
+// o que identifica o portefólio: se nada mudou, o ZIP feito antes serve
+type Fingerprint = Vec<(String, PathBuf, u64, Option<SystemTime>)>;
+
+// o último portefólio construído: um lote ou o daemon não o refazem a cada email
+static BUILT: Mutex<Option<(String, Fingerprint, Arc<Bundle>)>> = Mutex::new(None);
+
+fn fingerprint(paths: &[(String, PathBuf)]) -> Fingerprint {
+    paths
+        .iter()
+        .map(|(name, path)| {
+            let meta = fs::metadata(path).ok();
+            let size = meta.as_ref().map_or(0, |m| m.len());
+            (name.clone(), path.clone(), size, meta.and_then(|m| m.modified().ok()))
+        })
+        .collect()
+}
+
+/// O anexo do portefólio, já dentro do limite de `max_kb`. Feito uma vez e
+/// reaproveitado enquanto a configuração e os ficheiros não mudarem.
+pub fn build(config: &PortfolioConfig) -> Result<Arc<Bundle>> {
+    let limit = config.max_kb * 1024;
+    let paths = match &config.pdf {
+        Some(path) => vec![("Portfolio.pdf".to_string(), PathBuf::from(path))],
+        None => {
+            if config.files.is_empty() {
+                bail!("portfolio has neither pdf nor files");
+            }
+            let mut paths = vec![];
+            for file in &config.files {
+                collect(Path::new(file), "", &mut paths)?;
+            }
+            paths
+        }
+    };
+    let key = serde_json::to_string(config)?;
+    let current = fingerprint(&paths);
+    if let Some((_, _, bundle)) = BUILT.lock().unwrap().as_ref().filter(|(k, f, _)| *k == key && *f == current) {
+        return Ok(bundle.clone());
+    }
+    let bundle = match &config.pdf {
+        Some(path) => {
+            let bytes = fs::read(path).with_context(|| format!("{} not found", path))?;
+            if crate::cv::detect(&bytes) != crate::cv::CvFormat::Pdf {
+                bail!("Portfolio {} is not a PDF", path);
+            }
+            Bundle { name: "Portfolio.pdf".to_string(), content_type: "application/pdf", bytes }
+        }
+        None => Bundle { name: "Portfolio.zip".to_string(), content_type: "application/zip", bytes: zip(&paths, limit)? },
+    };
+    let size = bundle.bytes.len() as u64;
+    if size > limit {
+        bail!("Portfolio is {} KB, over the {} KB limit (portfolio.max_kb)", size.div_ceil(1024), config.max_kb);
+    }
+    let bundle = Arc::new(bundle);
+    *BUILT.lock().unwrap() = Some((key, current, bundle.clone()));
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zip_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let readme = dir.path().join("README.md");
+        fs::write(&readme, b"# projecto\n".repeat(50)).unwrap();
+        let shot = dir.path().join("ecra.png");
+        fs::write(&shot, [0u8, 1, 2, 3]).unwrap();
+        let paths = vec![("app/README.md".to_string(), readme), ("ecrã.png".to_string(), shot)];
+        let bytes = zip(&paths, 1024 * 1024).unwrap();
+        // o mesmo conteúdo, o mesmo ZIP
+        assert_eq!(bytes, zip(&paths, 1024 * 1024).unwrap());
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.len(), 2);
+        for (name, path) in &paths {
+            let mut entry = archive.by_name(name).unwrap();
+            assert_eq!(entry.compression(), CompressionMethod::Deflated);
+            let mut inflated = vec![];
+            entry.read_to_end(&mut inflated).unwrap();
+            assert_eq!(inflated, fs::read(path).unwrap());
+        }
+        // pára antes de ler o resto
+        assert!(zip(&paths, 10).is_err());
+    }
+
+    #[test]
+    fn test_build_zip_and_limits() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let shots = dir.path().join("screens");
+        fs::create_dir(&shots).unwrap();
+        fs::write(shots.join("b.png"), b"png").unwrap();
+        fs::write(shots.join("a.png"), b"png").unwrap();
+        let notes = dir.path().join("notas.md");
+        fs::write(&notes, b"notas").unwrap();
+
+        let mut paths = vec![];
+        collect(&shots, "", &mut paths).unwrap();
+        collect(&notes, "", &mut paths).unwrap();
+        let names: Vec<&str> = paths.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["screens/a.png", "screens/b.png", "notas.md"]);
+
+        let files = vec![shots.to_string_lossy().into_owned(), notes.to_string_lossy().into_owned()];
+        let config = PortfolioConfig { files: files.clone(), pdf: None, max_kb: 5 * 1024 };
+        let bundle = build(&config).unwrap();
+        assert_eq!(bundle.name, "Portfolio.zip");
+        assert_eq!(bundle.content_type, "application/zip");
+
+        fs::write(&notes, vec![b'x'; 3 * 1024 * 1024]).unwrap();
+        let err = build(&PortfolioConfig { files, pdf: None, max_kb: 1 }).unwrap_err();
+        assert!(err.to_string().contains("over the 1 KB limit"));
+
+        let missing = PortfolioConfig { files: vec!["nao-existe".to_string()], pdf: None, max_kb: 1024 };
+        assert!(build(&missing).is_err());
+    }
+}
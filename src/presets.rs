@@ -0,0 +1,176 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use crate::SmtpSecurity;
+
+/// Servidor SMTP conhecido, escolhido com `"smtp": {"provider": "gmail"}`.
+pub struct Preset {
+    pub name: &'static str,
+    pub host: &'static str,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    // certificado autoassinado (o Proton Mail Bridge gera o seu)
+    pub accept_invalid_certs: bool,
+    // limite diário do fornecedor, para `sending.max_per_day` quando não está definido
+    pub max_per_day: Option<usize>,
+    pub note: &'static str,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "gmail",
+        host: "smtp.gmail.com",
+        port: 587,
+        security: SmtpSecurity::Starttls,
+        accept_invalid_certs: false,
+        max_per_day: Some(500),
+        note: "precisa de uma App Password (verificação em 2 passos activa)",
+    },
+    Preset {
+        name: "outlook",
+        host: "smtp-mail.outlook.com",
+        port: 587,
+        security: SmtpSecurity::Starttls,
+        accept_invalid_certs: false,
+        max_per_day: Some(300),
+        note: "contas pessoais (outlook.com, hotmail.com); com 2 passos precisa de uma password de aplicação",
+    },
+    Preset {
+        name: "office365",
+        host: "smtp.office365.com",
+        port: 587,
+        security: SmtpSecurity::Starttls,
+        accept_invalid_certs: false,
+        max_per_day: Some(10000),
+        note: "o administrador tem de activar o SMTP AUTH para a caixa",
+    },
+    Preset {
+        name: "yahoo",
+        host: "smtp.mail.yahoo.com",
+        port: 465,
+        security: SmtpSecurity::Tls,
+        accept_invalid_certs: false,
+        max_per_day: Some(500),
+        note: "precisa de uma password de aplicação",
+    },
+    Preset {
+        name: "zoho",
+        host: "smtp.zoho.com",
+        port: 465,
+        security: SmtpSecurity::Tls,
+        accept_invalid_certs: false,
+        max_per_day: None,
+        note: "contas na região europeia usam smtp.zoho.eu (`zoho-eu`)",
+    },
+    Preset {
+        name: "zoho-eu",
+        host: "smtp.zoho.eu",
+        port: 465,
+        security: SmtpSecurity::Tls,
+        accept_invalid_certs: false,
+        max_per_day: None,
+        note: "",
+    },
+    Preset {
+        name: "icloud",
+        host: "smtp.mail.me.com",
+        port: 587,
+        security: SmtpSecurity::Starttls,
+        accept_invalid_certs: false,
+        max_per_day: Some(1000),
+        note: "precisa de uma password específica da app; SMTP_USER é o endereço @icloud.com",
+    },
+    Preset {
+        name: "proton",
+        host: "127.0.0.1",
+        port: 1025,
+        security: SmtpSecurity::Starttls,
+        accept_invalid_certs: true,
+        max_per_day: None,
+        note: "através do Proton Mail Bridge a correr; a password é a que o Bridge mostra, não a da conta",
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// O preset com este servidor, para `init` gravar só o nome.
+pub fn for_server(host: &str, port: u16) -> Option<&'static Preset> {
+    PRESETS.iter().find(|p| p.host.eq_ignore_ascii_case(host) && p.port == port)
+}
+
+pub fn names() -> Vec<&'static str> {
+    PRESETS.iter().map(|p| p.name).collect()
+}
+
+// completa uma secção smtp com o seu `provider`; o que estiver escrito ganha
+fn expand(smtp: &mut Value) -> Result<Option<&'static Preset>> {
+    let Some(name) = smtp.get("provider").and_then(Value::as_str) else {
+        return Ok(None);
+    };
+    let Some(preset) = find(name) else {
+        bail!("Unknown SMTP provider '{}' (known: {})", name, names().join(", "));
+    };
+    let defaults = [
+        ("host", Value::from(preset.host)),
+        ("port", Value::from(preset.port)),
+        ("security", serde_json::to_value(preset.security)?),
+        ("accept_invalid_certs", Value::from(preset.accept_invalid_certs)),
+    ];
+    for (key, value) in defaults {
+        if smtp.get(key).is_none() {
+            smtp[key] = value;
+        }
+    }
+    Ok(Some(preset))
+}
+
+/// Aplica os `provider` de `smtp` e de `profiles.<nome>.smtp` ao JSON do
+/// config antes de o ler; o limite do fornecedor só entra no `sending`
+/// quando não há `max_per_day`.
+pub fn apply_to_config(config: &mut Value) -> Result<()> {
+    if let Some(preset) = config.get_mut("smtp").map(expand).transpose()?.flatten() {
+        if let (Some(max), Some(sending)) = (preset.max_per_day, config.as_object_mut()) {
+            let sending = sending.entry("sending").or_insert_with(|| Value::Object(Default::default()));
+            if sending.is_object() && sending.get("max_per_day").is_none() {
+                sending["max_per_day"] = max.into();
+            }
+        }
+    }
+    if let Some(profiles) = config.get_mut("profiles").and_then(Value::as_object_mut) {
+        for smtp in profiles.values_mut().filter_map(|p| p.get_mut("smtp")) {
+            expand(smtp)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_provider_fills_the_smtp_section() {
+        let mut config = json!({
+            "smtp": {"provider": "Gmail"},
+            "profiles": {"freelance": {"smtp": {"provider": "proton", "port": 1026}}}
+        });
+        apply_to_config(&mut config).unwrap();
+        assert_eq!(config["smtp"]["host"], "smtp.gmail.com");
+        assert_eq!(config["smtp"]["security"], "starttls");
+        assert_eq!(config["sending"]["max_per_day"], 500);
+        let proton = &config["profiles"]["freelance"]["smtp"];
+        assert_eq!((proton["host"].as_str(), proton["port"].as_u64()), (Some("127.0.0.1"), Some(1026)));
+        assert_eq!(proton["accept_invalid_certs"], true);
+
+        // um limite escrito à mão fica
+        let mut config = json!({"smtp": {"provider": "outlook"}, "sending": {"max_per_day": 20}});
+        apply_to_config(&mut config).unwrap();
+        assert_eq!(config["sending"]["max_per_day"], 20);
+
+        let mut config = json!({"smtp": {"provider": "hotmale"}});
+        assert!(apply_to_config(&mut config).unwrap_err().to_string().contains("gmail"));
+    }
+}
@@ -0,0 +1,490 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use rand::Rng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, fs};
+
+use crate::{
+    contacts::{load_contacts, ContactBook},
+    enrich::company_from_domain,
+    failure::ErrorKind,
+    history::{recipient_events, Event},
+    load_log,
+    merge::escape_html,
+    pdf::{self, Text},
+    ApplicationStatus, SentLog, SentRecord, CHECK,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Html,
+    // registo de candidaturas para entregar (IEFP e afins)
+    Pdf,
+    // csv sem endereços, para partilhar o funil
+    Anonymized,
+}
+
+// fases por ordem; uma candidatura "chegou" a uma fase se passou por ela
+const STAGES: [ApplicationStatus; 4] = [
+    ApplicationStatus::Sent,
+    ApplicationStatus::Replied,
+    ApplicationStatus::Interview,
+    ApplicationStatus::Offer,
+];
+
+fn reached(record: &SentRecord, stage: ApplicationStatus) -> bool {
+    let passed = |s: ApplicationStatus| s == record.status || record.status_history.iter().any(|c| c.status == s);
+    match stage {
+        ApplicationStatus::Sent => true,
+        // uma rejeição também é uma resposta
+        ApplicationStatus::Replied => record.status != ApplicationStatus::Sent || !record.status_history.is_empty(),
+        ApplicationStatus::Interview => {
+            record.interview.is_some() || passed(ApplicationStatus::Interview) || passed(ApplicationStatus::Offer)
+        }
+        stage => passed(stage),
+    }
+}
+
+// a fase mais avançada; rejeitada conta como resposta
+fn furthest(record: &SentRecord) -> ApplicationStatus {
+    let stage = STAGES.iter().rev().copied().find(|&s| reached(record, s)).unwrap_or_default();
+    match (stage, record.status) {
+        (ApplicationStatus::Replied, ApplicationStatus::Rejected) => ApplicationStatus::Rejected,
+        _ => stage,
+    }
+}
+
+fn rank(stage: ApplicationStatus) -> usize {
+    match stage {
+        ApplicationStatus::Sent => 0,
+        ApplicationStatus::Replied | ApplicationStatus::Rejected => 1,
+        ApplicationStatus::Interview => 2,
+        ApplicationStatus::Offer => 3,
+    }
+}
+
+/// Quantas candidaturas chegaram a cada fase.
+pub fn funnel(applications: &[&SentRecord]) -> Vec<(ApplicationStatus, usize)> {
+    STAGES
+        .iter()
+        .map(|&stage| (stage, applications.iter().filter(|r| reached(r, stage)).count()))
+        .collect()
+}
+
+/// Envios com sucesso por semana ISO ("2024-W27"), por ordem.
+pub fn sends_per_week(log: &SentLog) -> Vec<(String, usize)> {
+    let mut weeks = BTreeMap::new();
+    for r in log.records.iter().filter(|r| r.success && !r.sandbox) {
+        let week = r.sent_at.iso_week();
+        *weeks.entry(format!("{}-W{:02}", week.year(), week.week())).or_insert(0) += 1;
+    }
+    weeks.into_iter().collect()
+}
+
+pub fn company(email: &str, book: &ContactBook) -> String {
+    book.find(email)
+        .and_then(|c| c.company.clone())
+        .or_else(|| company_from_domain(email))
+        .unwrap_or_else(|| email.split('@').nth(1).unwrap_or(email).to_string())
+}
+
+pub struct CompanyRow {
+    pub company: String,
+    pub applications: usize,
+    pub replied: usize,
+    // a fase mais avançada entre as candidaturas da empresa
+    pub best: ApplicationStatus,
+    pub last_sent: DateTime<Local>,
+}
+
+pub fn by_company(applications: &[&SentRecord], book: &ContactBook) -> Vec<CompanyRow> {
+    let mut rows: Vec<CompanyRow> = vec![];
+    for r in applications {
+        let name = company(&r.email, book);
+        let idx = match rows.iter().position(|row| row.company == name) {
+            Some(idx) => idx,
+            None => {
+                rows.push(CompanyRow {
+                    company: name,
+                    applications: 0,
+                    replied: 0,
+                    best: ApplicationStatus::Sent,
+                    last_sent: r.sent_at,
+                });
+                rows.len() - 1
+            }
+        };
+        let row = &mut rows[idx];
+        row.applications += 1;
+        if reached(r, ApplicationStatus::Replied) {
+            row.replied += 1;
+        }
+        let stage = furthest(r);
+        if rank(stage) > rank(row.best) {
+            row.best = stage;
+        }
+        row.last_sent = row.last_sent.max(r.sent_at);
+    }
+    rows.sort_by_key(|row| std::cmp::Reverse(row.last_sent));
+    rows
+}
+
+fn describe(event: &Event) -> String {
+    match event {
+        Event::Sent { .. } => "enviado".to_string(),
+        Event::Failed(error) => format!("falhou: {}", error),
+        Event::Cancelled => "envio desfeito".to_string(),
+        Event::Status(status) => format!("fase → {}", status.label()),
+        Event::Interview { location: Some(l) } => format!("entrevista ({})", l),
+        Event::Interview { location: None } => "entrevista".to_string(),
+        Event::Note(notes) => notes.replace('\n', " / "),
+        Event::Dead(reason) => format!("endereço morto: {}", reason),
+        Event::Queued { id } => format!("agendado (fila #{})", id),
+        Event::Opened => "aberto (pixel)".to_string(),
+    }
+}
+
+// barras em CSS: o relatório abre em qualquer browser, sem scripts nem rede
+fn bars(items: &[(String, usize)], class: &str) -> String {
+    let max = items.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+    items
+        .iter()
+        .map(|(label, n)| {
+            format!(
+                "<div class=\"bar {}\"><span class=\"label\">{}</span>\
+                 <span class=\"fill\" style=\"width: {}%\"></span><span class=\"n\">{}</span></div>\n",
+                class,
+                escape_html(label),
+                n * 100 / max,
+                n
+            )
+        })
+        .collect()
+}
+
+pub fn html(log: &SentLog, book: &ContactBook, owner: &str, now: DateTime<Local>) -> String {
+    let applications = log.applications();
+    let funnel = funnel(&applications);
+    let sent = funnel[0].1.max(1);
+    let funnel: Vec<(String, usize)> = funnel
+        .iter()
+        .map(|(stage, n)| (format!("{} ({}%)", stage.label(), n * 100 / sent), *n))
+        .collect();
+
+    let rows = by_company(&applications, book);
+    let mut companies = String::new();
+    for row in &rows {
+        companies.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&row.company),
+            row.applications,
+            row.replied,
+            row.best.label(),
+            row.last_sent.format("%d/%m/%Y")
+        ));
+    }
+
+    let mut events: Vec<(DateTime<Local>, String, Event)> = applications
+        .iter()
+        .flat_map(|r| recipient_events(log, &r.email).into_iter().map(|(at, e)| (at, r.email.clone(), e)))
+        .collect();
+    events.sort_by_key(|(at, _, _)| std::cmp::Reverse(*at));
+    events.dedup_by(|a, b| a.0 == b.0 && a.2 == b.2);
+    let mut timeline = String::new();
+    for (at, email, event) in &events {
+        timeline.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            at.format("%d/%m/%Y %H:%M"),
+            escape_html(email),
+            escape_html(&describe(event))
+        ));
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"pt\"><head><meta charset=\"utf-8\">\
+         <title>Candidaturas - {owner}</title>\n<style>\
+         body{{font-family:sans-serif;max-width:960px;margin:24px auto;color:#222}}\
+         h2{{margin-top:32px}}table{{border-collapse:collapse;width:100%}}\
+         td,th{{padding:4px 8px;text-align:left;border-bottom:1px solid #eee}}\
+         .bar{{display:flex;align-items:center;margin:4px 0}}.label{{width:180px}}\
+         .fill{{height:18px;background:#3b82f6;margin-right:8px}}.week .fill{{background:#10b981}}\
+         .muted{{color:#777}}</style></head><body>\n\
+         <h1>Candidaturas de {owner}</h1>\n<p class=\"muted\">Gerado a {generated}: {total} candidaturas, {companies_count} empresas.</p>\n\
+         <h2>Funil</h2>\n{funnel}\
+         <h2>Envios por semana</h2>\n{weeks}\
+         <h2>Por empresa</h2>\n<table>\n\
+         <tr><th>Empresa</th><th>Candidaturas</th><th>Respostas</th><th>Fase</th><th>Último envio</th></tr>\n{companies}</table>\n\
+         <h2>Cronologia</h2>\n<table>\n<tr><th>Data</th><th>Endereço</th><th>Evento</th></tr>\n{timeline}</table>\n\
+         </body></html>\n",
+        owner = escape_html(owner),
+        generated = now.format("%d/%m/%Y %H:%M"),
+        total = applications.len(),
+        companies_count = rows.len(),
+        funnel = bars(&funnel, "stage"),
+        weeks = bars(&sends_per_week(log), "week"),
+        companies = companies,
+        timeline = timeline,
+    )
+}
+
+/// Só os registos enviados entre `since` e `until`, inclusive.
+pub fn in_period(log: &SentLog, since: Option<NaiveDate>, until: Option<NaiveDate>) -> SentLog {
+    let records = log
+        .records
+        .iter()
+        .filter(|r| {
+            let day = r.sent_at.date_naive();
+            since.is_none_or(|s| day >= s) && until.is_none_or(|u| day <= u)
+        })
+        .cloned()
+        .collect();
+    SentLog { records }
+}
+
+fn period_label(since: Option<NaiveDate>, until: Option<NaiveDate>) -> String {
+    let day = |d: NaiveDate| d.format("%d/%m/%Y").to_string();
+    match (since, until) {
+        (Some(s), Some(u)) => format!("de {} a {}", day(s), day(u)),
+        (Some(s), None) => format!("desde {}", day(s)),
+        (None, Some(u)) => format!("até {}", day(u)),
+        (None, None) => "todo o histórico".to_string(),
+    }
+}
+
+#[derive(Serialize)]
+struct AnonymousRow {
+    recipient: String,
+    domain: String,
+    sent_on: NaiveDate,
+    success: bool,
+    error: Option<ErrorKind>,
+    status: ApplicationStatus,
+    furthest: ApplicationStatus,
+    replied_on: Option<NaiveDate>,
+    opened: bool,
+}
+
+/// Um envio por linha, com o endereço trocado por um hash: o `salt` é novo em
+/// cada export, por isso o mesmo recrutador tem o mesmo id dentro do ficheiro
+/// mas não dá para o descobrir a partir de uma lista de endereços conhecidos.
+fn anonymized(log: &SentLog, salt: &[u8]) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for r in log.records.iter().filter(|r| !r.sandbox && !r.cancelled) {
+        let email = r.email.to_lowercase();
+        let hash = Sha256::new().chain_update(salt).chain_update(email.as_bytes()).finalize();
+        writer.serialize(AnonymousRow {
+            recipient: hex::encode(&hash[..6]),
+            domain: email.rsplit_once('@').map_or("", |(_, d)| d).to_string(),
+            sent_on: r.sent_at.date_naive(),
+            success: r.success,
+            error: r.error.as_ref().map(|e| e.kind),
+            status: r.status,
+            furthest: furthest(r),
+            replied_on: r
+                .status_history
+                .iter()
+                .find(|c| c.status != ApplicationStatus::Sent)
+                .map(|c| c.at.date_naive()),
+            opened: !r.opens.is_empty(),
+        })?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+// colunas da tabela do PDF: título, posição x e largura em pontos
+const COLUMNS: [(&str, f32, f32); 5] = [
+    ("Data", 40.0, 55.0),
+    ("Empresa", 100.0, 95.0),
+    ("Endereço", 200.0, 140.0),
+    ("Fase", 345.0, 55.0),
+    ("Notas", 405.0, 150.0),
+];
+const SIZE: f32 = 9.0;
+const LINE: f32 = 12.0;
+const BOTTOM: f32 = 60.0;
+
+/// Registo de candidaturas em PDF: uma linha por endereço, com data, empresa,
+/// fase e notas, como prova de procura activa de emprego.
+pub fn pdf(log: &SentLog, book: &ContactBook, owner: (&str, &str), period: String, now: DateTime<Local>) -> Vec<u8> {
+    let mut applications = log.applications();
+    applications.sort_by_key(|r| r.sent_at);
+
+    let text = |x: f32, y: f32, size: f32, bold: bool, text: String| Text { x, y, size, bold, text };
+    let mut pages: Vec<Vec<Text>> = vec![];
+    let mut page = vec![
+        text(40.0, 790.0, 16.0, true, "Registo de candidaturas".to_string()),
+        text(40.0, 770.0, 10.0, false, format!("{} <{}>", owner.0, owner.1)),
+        text(40.0, 756.0, 10.0, false, format!("Período: {}", period)),
+        text(
+            40.0,
+            742.0,
+            10.0,
+            false,
+            format!("{} candidaturas. Gerado a {}.", applications.len(), now.format("%d/%m/%Y %H:%M")),
+        ),
+    ];
+    let header = |page: &mut Vec<Text>, y: f32| {
+        for (title, x, _) in COLUMNS {
+            page.push(text(x, y, SIZE, true, title.to_string()));
+        }
+    };
+    let mut y = 712.0;
+    header(&mut page, y);
+    y -= LINE + 4.0;
+
+    for r in applications {
+        let notes = r.notes.clone().or_else(|| r.message.as_ref().map(|m| format!("Assunto: {}", m.subject)));
+        let notes = pdf::wrap(&notes.unwrap_or_default(), SIZE, COLUMNS[4].2);
+        let height = LINE * notes.len().max(1) as f32;
+        // a linha não se parte entre páginas
+        if y - height < BOTTOM {
+            pages.push(std::mem::take(&mut page));
+            y = 800.0;
+            header(&mut page, y);
+            y -= LINE + 4.0;
+        }
+        let cells = [
+            r.sent_at.format("%d/%m/%Y").to_string(),
+            company(&r.email, book),
+            r.email.clone(),
+            r.status.label().to_string(),
+        ];
+        for (cell, (_, x, width)) in cells.iter().zip(COLUMNS) {
+            page.push(text(x, y, SIZE, false, pdf::fit(cell, SIZE, width)));
+        }
+        for (i, line) in notes.into_iter().enumerate() {
+            page.push(text(COLUMNS[4].1, y - LINE * i as f32, SIZE, false, line));
+        }
+        y -= height + 4.0;
+    }
+    pages.push(page);
+
+    let total = pages.len();
+    for (i, page) in pages.iter_mut().enumerate() {
+        page.push(text(40.0, 30.0, 8.0, false, format!("{} — página {} de {}", owner.0, i + 1, total)));
+    }
+    pdf::document(&pages, "Registo de candidaturas", owner.0)
+}
+
+pub fn run_report(
+    format: ReportFormat,
+    output: Option<String>,
+    owner: (&str, &str),
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Result<()> {
+    let now = Local::now();
+    let log = in_period(&load_log(), since, until);
+    let book = load_contacts();
+    let (content, extension) = match format {
+        ReportFormat::Html => (html(&log, &book, owner.0, now).into_bytes(), "html"),
+        ReportFormat::Pdf => (pdf(&log, &book, owner, period_label(since, until), now), "pdf"),
+        ReportFormat::Anonymized => (anonymized(&log, &rand::thread_rng().gen::<[u8; 16]>())?, "csv"),
+    };
+    let path = output.unwrap_or_else(|| format!("job-mailer-report-{}.{}", now.format("%Y%m%d"), extension));
+    fs::write(&path, content).with_context(|| format!("Cannot write {}", path))?;
+    say!("{} Relatório em {}", CHECK, path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{interview::parse_interview_time, StatusChange};
+
+    fn sent(email: &str, when: &str, status: ApplicationStatus) -> SentRecord {
+        let at = parse_interview_time(when).unwrap();
+        SentRecord {
+            email: email.to_string(),
+            sent_at: at,
+            success: true,
+            status,
+            status_history: (status != ApplicationStatus::Sent)
+                .then_some(StatusChange { at, status })
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_funnel_and_companies() {
+        let mut offer = sent("rh@acme.com", "2024-07-01 09:00", ApplicationStatus::Offer);
+        offer.status_history.insert(0, StatusChange { at: offer.sent_at, status: ApplicationStatus::Interview });
+        let log = SentLog {
+            records: vec![
+                offer,
+                sent("jobs@acme.com", "2024-07-02 09:00", ApplicationStatus::Rejected),
+                sent("jobs@globex.com", "2024-07-09 09:00", ApplicationStatus::Sent),
+            ],
+        };
+        let applications = log.applications();
+        let counts: Vec<usize> = funnel(&applications).into_iter().map(|(_, n)| n).collect();
+        assert_eq!(counts, vec![3, 2, 1, 1]);
+        assert_eq!(sends_per_week(&log), vec![("2024-W27".to_string(), 2), ("2024-W28".to_string(), 1)]);
+
+        let rows = by_company(&applications, &ContactBook::default());
+        assert_eq!(rows[0].company, "Globex");
+        assert_eq!(rows[1].company, "Acme");
+        assert_eq!((rows[1].applications, rows[1].replied), (2, 2));
+        assert_eq!(rows[1].best, ApplicationStatus::Offer);
+    }
+
+    #[test]
+    fn test_pdf_paginates_the_period() {
+        let records = (0..80)
+            .map(|i| {
+                let mut r = sent(&format!("rh{}@acme.com", i), "2024-07-01 09:00", ApplicationStatus::Sent);
+                r.sent_at += chrono::Duration::hours(i);
+                r.notes = (i == 0).then(|| "Candidatura espontânea (backend)".to_string());
+                r
+            })
+            .collect();
+        let log = SentLog { records };
+        let since = NaiveDate::from_ymd_opt(2024, 7, 2);
+        let july = in_period(&log, since, NaiveDate::from_ymd_opt(2024, 7, 3));
+        assert_eq!(july.records.len(), 48);
+
+        let out = pdf(&log, &ContactBook::default(), ("João Silva", "joao@x.pt"), period_label(since, None), Local::now());
+        let info = crate::cv::inspect(&out);
+        assert_eq!(info.pages, 2);
+        assert_eq!(info.author.as_deref(), Some("João Silva"));
+        assert!(info.xref_ok && info.eof);
+        assert!(out.windows(27).any(|w| w == b"(Per\xedodo: desde 02/07/2024)"));
+        assert!(out.windows(36).any(|w| w == b"(Candidatura espont\xe2nea \\(backend\\))"));
+    }
+
+    #[test]
+    fn test_html_escapes_and_lists_companies() {
+        let log = SentLog { records: vec![sent("rh@acme.com", "2024-07-01 09:00", ApplicationStatus::Replied)] };
+        let html = html(&log, &ContactBook::default(), "Ana <Dev>", Local::now());
+        assert!(html.contains("Candidaturas de Ana &lt;Dev&gt;"));
+        assert!(html.contains("<td>Acme</td><td>1</td><td>1</td><td>respondeu</td>"));
+        assert!(html.contains("fase → respondeu"));
+    }
+
+    #[test]
+    fn test_anonymized_keeps_domains_and_outcomes() {
+        let mut replied = sent("Rh@Acme.com", "2024-07-01 09:00", ApplicationStatus::Replied);
+        replied.opens.push(replied.sent_at);
+        let mut failed = sent("jobs@globex.com", "2024-07-02 09:00", ApplicationStatus::Sent);
+        failed.success = false;
+        failed.error = Some(crate::failure::SendError::classify("permanent error (550): no such user", None));
+        let log = SentLog { records: vec![replied, failed, sent("rh@acme.com", "2024-07-03 09:00", ApplicationStatus::Sent)] };
+
+        let csv = String::from_utf8(anonymized(&log, b"salt").unwrap()).unwrap();
+        let lines: Vec<Vec<&str>> = csv.lines().map(|l| l.split(',').collect()).collect();
+        assert_eq!(
+            lines[0],
+            vec!["recipient", "domain", "sent_on", "success", "error", "status", "furthest", "replied_on", "opened"]
+        );
+        assert_eq!(lines[1][1..], ["acme.com", "2024-07-01", "true", "", "replied", "replied", "2024-07-01", "true"]);
+        assert_eq!(lines[2][1..], ["globex.com", "2024-07-02", "false", "recipient_rejected", "sent", "sent", "", "false"]);
+        // o mesmo endereço tem o mesmo id; nenhum aparece no ficheiro
+        assert_eq!(lines[1][0], lines[3][0]);
+        assert_ne!(lines[1][0], lines[2][0]);
+        assert!(!csv.contains("rh@") && !csv.contains("jobs@"));
+        assert_ne!(anonymized(&log, b"other").unwrap(), csv.into_bytes());
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fs, path::PathBuf};
+
+use crate::{interview, update_log, SentLog, SentRecord, CHECK};
+
+/// Envios sem actividade há mais de `days` dias: o daemon anonimiza-os
+/// (`redact`, por omissão) ou apaga-os (`delete`), com as cópias .eml.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    pub days: i64,
+    #[serde(default)]
+    pub action: RetentionAction,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    // fica a data, o domínio e o resultado, para as estatísticas
+    #[default]
+    Redact,
+    Delete,
+}
+
+// o último sinal de vida da candidatura: envio, mudança de fase, entrevista ou abertura
+fn last_activity(record: &SentRecord) -> DateTime<Local> {
+    let changes = record.status_history.iter().map(|c| c.at);
+    let interview = record.interview.iter().map(|i| i.at);
+    let opens = record.opens.iter().copied();
+    changes.chain(interview).chain(opens).fold(record.sent_at, DateTime::max)
+}
+
+// ficheiros do envio: a cópia .eml e o convite .ics da entrevista
+fn files_of(record: &SentRecord) -> Vec<PathBuf> {
+    let eml = record.message.as_ref().and_then(|m| m.eml.clone()).map(PathBuf::from);
+    let ics = record.interview.as_ref().map(|i| PathBuf::from(interview::ics_file_name(&record.email, i)));
+    eml.into_iter().chain(ics).collect()
+}
+
+/// Tira do registo tudo o que identifica o destinatário ou o que lhe foi
+/// escrito; o endereço passa a um hash no mesmo domínio.
+fn redact(record: &mut SentRecord, salt: &[u8]) {
+    let email = record.email.to_lowercase();
+    let domain = email.rsplit_once('@').map_or("", |(_, d)| d);
+    let hash = Sha256::new().chain_update(salt).chain_update(email.as_bytes()).finalize();
+    record.email = format!("{}@{}", hex::encode(&hash[..6]), domain);
+    record.notes = None;
+    record.smtp_transcript = None;
+    if let Some(error) = &mut record.error {
+        error.message.clear();
+    }
+    if let Some(interview) = &mut record.interview {
+        interview.interviewer = None;
+        interview.location = None;
+    }
+    if let Some(message) = &mut record.message {
+        message.subject.clear();
+        message.body.clear();
+        message.eml = None;
+        message.other_to.clear();
+        message.cc.clear();
+    }
+    record.redacted = true;
+}
+
+/// Aplica a retenção ao log; devolve quantos envios mudaram e os ficheiros a apagar.
+pub fn apply(log: &mut SentLog, retention: &RetentionConfig, now: DateTime<Local>, salt: &[u8]) -> (usize, Vec<PathBuf>) {
+    let cutoff = now - Duration::days(retention.days);
+    let expired = |r: &SentRecord| !r.redacted && last_activity(r) < cutoff;
+    let mut files = vec![];
+    let mut count = 0;
+    match retention.action {
+        RetentionAction::Delete => log.records.retain(|r| {
+            if !expired(r) {
+                return true;
+            }
+            files.extend(files_of(r));
+            count += 1;
+            false
+        }),
+        RetentionAction::Redact => {
+            for record in log.records.iter_mut().filter(|r| expired(r)) {
+                files.extend(files_of(record));
+                redact(record, salt);
+                count += 1;
+            }
+        }
+    }
+    (count, files)
+}
+
+/// Chamado pelo daemon uma vez por dia.
+pub fn run(retention: &RetentionConfig) -> Result<usize> {
+    let salt = rand::thread_rng().gen::<[u8; 16]>();
+    let (count, files) = update_log(&mut SentLog::default(), |log| Ok(apply(log, retention, Local::now(), &salt)))?;
+    for file in files.into_iter().filter(|f| f.is_file()) {
+        fs::remove_file(&file).with_context(|| format!("Cannot remove {}", file.display()))?;
+    }
+    if count > 0 {
+        let done = match retention.action {
+            RetentionAction::Redact => "anonimizados",
+            RetentionAction::Delete => "apagados",
+        };
+        crate::git_history::record(&format!("retenção: {} envios {}", count, done));
+        say!("{} Retenção: {} envios com mais de {} dias {}", CHECK, count, retention.days, done);
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ApplicationStatus, SentMessage, StatusChange};
+
+    fn record(email: &str, days_ago: i64, now: DateTime<Local>) -> SentRecord {
+        SentRecord {
+            email: email.to_string(),
+            sent_at: now - Duration::days(days_ago),
+            success: true,
+            notes: Some("falei com a Maria".to_string()),
+            message: Some(SentMessage {
+                subject: "Candidatura".to_string(),
+                body: "Olá Maria".to_string(),
+                cc: vec!["ana@acme.com".to_string()],
+                eml: Some(format!("sent/{}.eml", email)),
+                template_version: "3b1f0c9a27de".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_redact_keeps_domain_date_and_outcome() {
+        let now = Local::now();
+        let mut active = record("jobs@globex.com", 400, now);
+        active.status_history.push(StatusChange { at: now - Duration::days(10), status: ApplicationStatus::Interview });
+        let mut log = SentLog {
+            records: vec![record("rh@acme.com", 400, now), active, record("new@initech.com", 5, now)],
+        };
+        let retention = RetentionConfig { days: 365, action: RetentionAction::Redact };
+
+        let (count, files) = apply(&mut log, &retention, now, b"salt");
+        assert_eq!(count, 1);
+        assert_eq!(files, vec![PathBuf::from("sent/rh@acme.com.eml")]);
+        let old = &log.records[0];
+        assert!(old.redacted && old.email.ends_with("@acme.com") && !old.email.starts_with("rh@"));
+        assert!(old.success && old.notes.is_none());
+        let message = old.message.as_ref().unwrap();
+        assert!(message.body.is_empty() && message.cc.is_empty() && message.eml.is_none());
+        assert_eq!(message.template_version, "3b1f0c9a27de");
+        // a actividade recente conta, não só a data do envio
+        assert_eq!(log.records[1].email, "jobs@globex.com");
+
+        // já anonimizados não voltam a contar
+        assert_eq!(apply(&mut log, &retention, now, b"salt").0, 0);
+    }
+
+    #[test]
+    fn test_delete_drops_old_records() {
+        let now = Local::now();
+        let mut log = SentLog { records: vec![record("rh@acme.com", 40, now), record("new@initech.com", 5, now)] };
+        let retention = RetentionConfig { days: 30, action: RetentionAction::Delete };
+
+        let (count, files) = apply(&mut log, &retention, now, b"salt");
+        assert_eq!((count, files.len()), (1, 1));
+        assert_eq!(log.records.len(), 1);
+        assert_eq!(log.records[0].email, "new@initech.com");
+    }
+}
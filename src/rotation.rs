@@ -0,0 +1,139 @@
+use chrono::{DateTime, Duration, Local};
+use rand::Rng;
+use rand_distr::{Beta, Distribution};
+use serde::{Deserialize, Serialize};
+
+use crate::{ApplicationStatus, SentLog, SentRecord};
+
+// um envio sem resposta só conta como falhado passados estes dias
+const SETTLE_DAYS: i64 = 7;
+
+/// Como se escolhe entre o `subject` e os `subjects` do template (`sending.subject_rotation`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Rotation {
+    // mais vezes os que têm mais respostas (amostragem de Thompson)
+    #[default]
+    Bandit,
+    // ao acaso, todos por igual
+    Random,
+}
+
+/// O subject sorteado para um envio, como está no template (antes das variáveis).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubjectChoice {
+    pub subject: String,
+    pub strategy: Rotation,
+}
+
+fn replied(record: &SentRecord) -> bool {
+    record.status != ApplicationStatus::Sent || !record.status_history.is_empty()
+}
+
+// (subject, envios, com resposta) dos envios com subject sorteado; com `settled_at` só os já decididos
+fn tally(log: &SentLog, settled_at: Option<DateTime<Local>>) -> Vec<(String, usize, usize)> {
+    let mut subjects: Vec<(String, usize, usize)> = vec![];
+    let sent = log.records.iter().filter(|r| r.success && !r.cancelled && !r.sandbox);
+    let settled = sent.filter(|r| settled_at.is_none_or(|now| replied(r) || now - r.sent_at >= Duration::days(SETTLE_DAYS)));
+    for r in settled {
+        let Some(choice) = r.message.as_ref().and_then(|m| m.subject_choice.as_ref()) else {
+            continue;
+        };
+        let idx = match subjects.iter().position(|(s, _, _)| *s == choice.subject) {
+            Some(idx) => idx,
+            None => {
+                subjects.push((choice.subject.clone(), 0, 0));
+                subjects.len() - 1
+            }
+        };
+        subjects[idx].1 += 1;
+        if replied(r) {
+            subjects[idx].2 += 1;
+        }
+    }
+    subjects
+}
+
+/// (subject, envios, com resposta) por ordem de primeiro uso, para as estatísticas.
+pub fn replies_by_subject(log: &SentLog) -> Vec<(String, usize, usize)> {
+    tally(log, None)
+}
+
+/// Índice do subject a usar entre `candidates`; o bandit sorteia de uma Beta
+/// por subject (respostas + 1, sem resposta + 1) e fica com o maior, por isso
+/// um subject novo ainda é experimentado.
+pub fn choose(candidates: &[String], strategy: Rotation, log: &SentLog, now: DateTime<Local>, rng: &mut impl Rng) -> usize {
+    if candidates.len() < 2 {
+        return 0;
+    }
+    match strategy {
+        Rotation::Random => rng.gen_range(0..candidates.len()),
+        Rotation::Bandit => {
+            let stats = tally(log, Some(now));
+            let draw = |rng: &mut _, subject: &String| {
+                let (sent, replied) = stats
+                    .iter()
+                    .find(|(s, _, _)| s == subject)
+                    .map_or((0, 0), |(_, sent, replied)| (*sent, *replied));
+                let beta = Beta::new(replied as f64 + 1.0, (sent - replied) as f64 + 1.0).expect("positive parameters");
+                beta.sample(rng)
+            };
+            let draws: Vec<f64> = candidates.iter().map(|s| draw(rng, s)).collect();
+            (0..draws.len()).max_by(|&a, &b| draws[a].total_cmp(&draws[b])).unwrap_or(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SentMessage;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn sent(subject: &str, days_ago: i64, status: ApplicationStatus, now: DateTime<Local>) -> SentRecord {
+        let choice = SubjectChoice { subject: subject.to_string(), strategy: Rotation::Bandit };
+        SentRecord {
+            sent_at: now - Duration::days(days_ago),
+            success: true,
+            status,
+            message: Some(SentMessage { subject_choice: Some(choice), ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bandit_prefers_the_subject_with_replies() {
+        let now = Local::now();
+        let mut records = vec![];
+        for i in 0..20 {
+            let status = if i < 8 { ApplicationStatus::Replied } else { ApplicationStatus::Sent };
+            records.push(sent("Candidatura: {{title}}", 10, status, now));
+            let status = if i < 1 { ApplicationStatus::Rejected } else { ApplicationStatus::Sent };
+            records.push(sent("Olá {{company}}", 10, status, now));
+        }
+        // sem resposta há 2 dias ainda não conta
+        records.push(sent("Olá {{company}}", 2, ApplicationStatus::Sent, now));
+        let log = SentLog { records };
+        assert_eq!(
+            replies_by_subject(&log),
+            vec![("Candidatura: {{title}}".to_string(), 20, 8), ("Olá {{company}}".to_string(), 21, 1)]
+        );
+        assert_eq!(tally(&log, Some(now))[1], ("Olá {{company}}".to_string(), 20, 1));
+
+        let candidates: Vec<String> = ["Olá {{company}}", "Candidatura: {{title}}", "Novo"].iter().map(|s| s.to_string()).collect();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut picks = [0; 3];
+        for _ in 0..300 {
+            picks[choose(&candidates, Rotation::Bandit, &log, now, &mut rng)] += 1;
+        }
+        // o pior quase não volta a sair; o novo, sem dados, é experimentado
+        assert!(picks[0] < 10 && picks[1] > 100 && picks[2] > 50, "{:?}", picks);
+
+        let mut picks = [0; 3];
+        for _ in 0..300 {
+            picks[choose(&candidates, Rotation::Random, &log, now, &mut rng)] += 1;
+        }
+        assert!(picks.iter().all(|&n| n > 60), "{:?}", picks);
+        assert_eq!(choose(&candidates[..1], Rotation::Bandit, &log, now, &mut rng), 0);
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+
+use crate::CROSS;
+
+/// Um passo de migração de um ficheiro de estado (o JSON inteiro, antes de
+/// virar struct): o passo `n` leva da versão `n` à `n + 1`.
+pub type Migration = fn(&mut Value);
+
+/// Forma de um ficheiro de estado ao longo do tempo. A versão actual é o
+/// número de passos; ficheiros de antes do campo `version` contam como 0.
+pub struct Schema {
+    pub migrations: &'static [Migration],
+}
+
+// v0 → v1: o ficheiro só passa a levar o campo `version`
+fn versioned(_: &mut Value) {}
+
+/// Para os ficheiros que ainda não mudaram de forma.
+pub const INITIAL: Schema = Schema { migrations: &[versioned] };
+
+impl Schema {
+    pub fn version(&self) -> u64 {
+        self.migrations.len() as u64
+    }
+
+    /// Lê o JSON e aplica as migrações que faltam. Um ficheiro de uma versão
+    /// mais recente lê-se como está: os campos que esta não conhece ficam de fora.
+    pub fn parse<T: DeserializeOwned>(&self, content: &str) -> Result<T> {
+        let mut value: Value = serde_json::from_str(content)?;
+        let from = stored_version(&value);
+        for migrate in self.migrations.iter().skip(from as usize) {
+            migrate(&mut value);
+        }
+        if let Value::Object(map) = &mut value {
+            map.remove("version");
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// JSON com `"version"` à cabeça, para quem abrir o ficheiro à mão.
+    pub fn to_string<T: Serialize>(&self, data: &T) -> Result<String> {
+        let mut map = Map::new();
+        map.insert("version".to_string(), self.version().into());
+        match serde_json::to_value(data)? {
+            Value::Object(fields) => map.extend(fields),
+            _ => bail!("State files must be JSON objects"),
+        }
+        Ok(serde_json::to_string_pretty(&map)?)
+    }
+
+    /// Sem ficheiro é um estado vazio; ilegível, fica uma cópia ao lado antes
+    /// que o próximo save escreva por cima.
+    pub fn load<T: DeserializeOwned + Default>(&self, path: &str) -> T {
+        let Ok(content) = fs::read_to_string(path) else {
+            return T::default();
+        };
+        match self.parse(&content) {
+            Ok(data) => data,
+            Err(e) => {
+                let copy = format!("{}.corrupt-{}", path, Local::now().format("%Y%m%d-%H%M%S"));
+                if fs::copy(path, &copy).is_ok() {
+                    eprintln!("{} {} ilegível ({}), cópia em {}", CROSS, path, e, copy);
+                }
+                T::default()
+            }
+        }
+    }
+
+    /// Não grava por cima de um ficheiro de uma versão mais recente: perdiam-se
+    /// os campos que esta versão não conhece.
+    pub fn save<T: Serialize>(&self, path: &str, data: &T) -> Result<()> {
+        self.save_with_mode(path, data, 0o666)
+    }
+
+    /// `save` com as permissões do ficheiro desde que é criado (0o600 para tokens).
+    pub fn save_with_mode<T: Serialize>(&self, path: &str, data: &T, mode: u32) -> Result<()> {
+        let stored = fs::read_to_string(path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+            .map_or(0, |v| stored_version(&v));
+        if stored > self.version() {
+            bail!(
+                "{} is at version {} but this job-mailer only knows up to {}; update it first",
+                path,
+                stored,
+                self.version()
+            );
+        }
+        crate::write_atomic_with_mode(path, self.to_string(data)?, mode).with_context(|| format!("Cannot save {}", path))
+    }
+}
+
+fn stored_version(value: &Value) -> u64 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+    struct Notes {
+        items: Vec<String>,
+    }
+
+    // v1 → v2: `entries` passou a chamar-se `items`
+    fn rename_entries(value: &mut Value) {
+        if let Some(entries) = value.as_object_mut().and_then(|m| m.remove("entries")) {
+            value["items"] = entries;
+        }
+    }
+
+    const NOTES: Schema = Schema { migrations: &[versioned, rename_entries] };
+
+    #[test]
+    fn test_old_files_are_migrated_and_stamped() {
+        let expected = Notes { items: vec!["a".to_string()] };
+        assert_eq!(NOTES.parse::<Notes>(r#"{"entries": ["a"]}"#).unwrap(), expected);
+        assert_eq!(NOTES.parse::<Notes>(r#"{"version": 1, "entries": ["a"]}"#).unwrap(), expected);
+        // já na versão actual, nada corre
+        assert_eq!(NOTES.parse::<Notes>(r#"{"version": 2, "items": ["a"]}"#).unwrap(), expected);
+
+        let written = NOTES.to_string(&expected).unwrap();
+        assert!(written.trim_start_matches(['{', '\n', ' ']).starts_with("\"version\": 2"));
+        assert_eq!(NOTES.parse::<Notes>(&written).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_newer_files_are_read_but_not_overwritten() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("notes.json").to_string_lossy().into_owned();
+        let newer = r#"{"version": 3, "items": ["a"], "tags": ["x"]}"#;
+        fs::write(&path, newer).unwrap();
+
+        let notes: Notes = NOTES.load(&path);
+        assert_eq!(notes.items, vec!["a"]);
+        assert!(NOTES.save(&path, &notes).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_files_are_never_readable_by_others() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("tokens.json").to_string_lossy().into_owned();
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        // temporário esquecido por um crash, aberto a todos
+        let temp = dir.path().join(".tokens.json.tmp");
+        fs::write(&temp, "").unwrap();
+        fs::set_permissions(&temp, fs::Permissions::from_mode(0o644)).unwrap();
+
+        NOTES.save_with_mode(&path, &Notes::default(), 0o600).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(!temp.exists());
+    }
+
+    #[test]
+    fn test_unreadable_files_are_kept_aside() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("notes.json").to_string_lossy().into_owned();
+        fs::write(&path, r#"{"items": "#).unwrap();
+
+        assert_eq!(NOTES.load::<Notes>(&path), Notes::default());
+        let copies = fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with("notes.json.corrupt-"))
+            .count();
+        assert_eq!(copies, 1);
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::{bail, Context, Result};
+use dialoguer::{theme::ColorfulTheme, Password};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    io::IsTerminal,
+    process::Command,
+    sync::{atomic::Ordering, Mutex},
+};
+
+use crate::{CHECK, CROSS};
+
+/// De onde vêm as passwords das contas SMTP/IMAP quando não há `*_PASS` no
+/// ambiente (`secrets` no config.json). `{user}` é trocado pela conta.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+pub enum SecretProvider {
+    // só as variáveis de ambiente / .env
+    #[default]
+    Env,
+    // keyring do sistema, entrada `job-mailer` com a conta como nome
+    Keyring,
+    // `pass show <path>`: a primeira linha é a password
+    Pass {
+        #[serde(default = "default_store_path")]
+        path: String,
+    },
+    Gopass {
+        #[serde(default = "default_store_path")]
+        path: String,
+    },
+    // ficheiro cifrado com age, uma linha `conta=password` por conta
+    Age { file: String, identity: String },
+    // qualquer comando que escreva a password (1Password, Bitwarden, ...)
+    Command { command: String },
+}
+
+fn default_store_path() -> String {
+    "job-mailer/{user}".to_string()
+}
+
+fn run(program: &str, args: &[&str], user: &str) -> Result<String> {
+    let out = Command::new(program)
+        .args(args)
+        .env("JOB_MAILER_USER", user)
+        .output()
+        .with_context(|| format!("{} not found in PATH", program))?;
+    if !out.status.success() {
+        bail!("{} failed: {}", program, String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+fn first_line(output: &str) -> Option<String> {
+    output.lines().next().map(str::trim_end).filter(|l| !l.is_empty()).map(str::to_string)
+}
+
+// `conta=password`, uma por linha; linhas vazias e `#` ignoradas
+fn find_in_secrets_file(content: &str, user: &str) -> Option<String> {
+    content
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .find(|(account, _)| account.trim().eq_ignore_ascii_case(user))
+        .map(|(_, pass)| pass.trim().to_string())
+}
+
+impl SecretProvider {
+    /// A password da conta; `None` quando o fornecedor não a tem.
+    pub fn lookup(&self, user: &str) -> Result<Option<String>> {
+        let path = |template: &str| template.replace("{user}", user);
+        match self {
+            SecretProvider::Env => Ok(None),
+            SecretProvider::Keyring => Ok(keyring_password(user)),
+            SecretProvider::Pass { path: template } => Ok(first_line(&run("pass", &["show", &path(template)], user)?)),
+            SecretProvider::Gopass { path: template } => {
+                Ok(first_line(&run("gopass", &["show", "--password", &path(template)], user)?))
+            }
+            SecretProvider::Age { file, identity } => {
+                let content = run("age", &["--decrypt", "-i", identity, file], user)?;
+                Ok(find_in_secrets_file(&content, user))
+            }
+            SecretProvider::Command { command } => {
+                let command = path(command);
+                let out = if cfg!(windows) {
+                    run("cmd", &["/C", &command], user)?
+                } else {
+                    run("sh", &["-c", &command], user)?
+                };
+                Ok(first_line(&out))
+            }
+        }
+    }
+}
+
+/// Com que se autentica: password, ou access token OAuth (`oauth`).
+pub enum Account {
+    Password(String, String),
+    OAuth(String, String),
+}
+
+impl Account {
+    pub fn user(&self) -> &str {
+        match self {
+            Account::Password(user, _) | Account::OAuth(user, _) => user,
+        }
+    }
+}
+
+const SERVICE: &str = "job-mailer";
+
+// passwords perguntadas nesta execução, para o bulk não perguntar a cada envio
+static PROMPTED: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+fn keyring_entry(user: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, user).context("Cannot open the system keyring")
+}
+
+/// A password guardada no keyring do sistema para esta conta, se houver.
+pub fn keyring_password(user: &str) -> Option<String> {
+    keyring_entry(user).ok()?.get_password().ok()
+}
+
+pub fn store_in_keyring(user: &str, pass: &str) -> Result<()> {
+    keyring_entry(user)?
+        .set_password(pass)
+        .with_context(|| format!("Cannot store the password for {} in the keyring", user))
+}
+
+// os testes e o --yes (cron, daemon) nunca ficam à espera de input
+fn can_prompt() -> bool {
+    !cfg!(test) && std::io::stdin().is_terminal() && !crate::ASSUME_YES.load(Ordering::Relaxed)
+}
+
+/// A password de uma conta sem `*_PASS` no ambiente: a do fornecedor
+/// configurado, a já perguntada nesta execução, a do keyring ou, num
+/// terminal, pedida agora sem eco.
+pub fn password(provider: &SecretProvider, user: &str) -> Result<String> {
+    if let Some(pass) = provider.lookup(user)? {
+        return Ok(pass);
+    }
+    if let Some(pass) = PROMPTED.lock().unwrap().get(user) {
+        return Ok(pass.clone());
+    }
+    if let Some(pass) = keyring_password(user) {
+        return Ok(pass);
+    }
+    if !can_prompt() {
+        bail!("No password for {}", user);
+    }
+    let pass = Password::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Password de {}", user))
+        .interact()?;
+    PROMPTED.lock().unwrap().insert(user.to_string(), pass.clone());
+    if crate::confirm("Guardar no keyring do sistema (não volta a perguntar)?", false)? {
+        match store_in_keyring(user, &pass) {
+            Ok(()) => say!("{} Password guardada no keyring", CHECK),
+            Err(e) => eprintln!("{} {:#}", CROSS, e),
+        }
+    }
+    Ok(pass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_config() {
+        let provider: SecretProvider = serde_json::from_str(r#"{"provider": "pass"}"#).unwrap();
+        assert_eq!(provider, SecretProvider::Pass { path: "job-mailer/{user}".to_string() });
+        let provider: SecretProvider =
+            serde_json::from_str(r#"{"provider": "age", "file": "secrets.age", "identity": "/home/joao/.age/key.txt"}"#).unwrap();
+        assert!(matches!(provider, SecretProvider::Age { .. }));
+        assert_eq!(SecretProvider::Env.lookup("joao@example.com").unwrap(), None);
+    }
+
+    #[test]
+    fn test_find_in_secrets_file() {
+        let content = "# contas\njoao@example.com = s3cr=t\n\nfreela@example.com=outra\n";
+        assert_eq!(find_in_secrets_file(content, "Joao@example.com").as_deref(), Some("s3cr=t"));
+        assert_eq!(find_in_secrets_file(content, "freela@example.com").as_deref(), Some("outra"));
+        assert_eq!(find_in_secrets_file(content, "ana@example.com"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_provider() {
+        let provider = SecretProvider::Command { command: "printf 'pw-for-%s\\nlixo' {user}".to_string() };
+        assert_eq!(provider.lookup("joao@example.com").unwrap().as_deref(), Some("pw-for-joao@example.com"));
+        let provider = SecretProvider::Command { command: "echo $JOB_MAILER_USER".to_string() };
+        assert_eq!(provider.lookup("ana@example.com").unwrap().as_deref(), Some("ana@example.com"));
+        let failing = SecretProvider::Command { command: "exit 3".to_string() };
+        assert!(failing.lookup("ana@example.com").is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    blocklist::Blocklist,
+    campaign::Campaign,
+    config_for_profile,
+    contacts::same_email,
+    dead::{self, DeadList},
+    load_cv_variant, notify,
+    outbox::room_today,
+    record_send, send_email, update_log, use_template, ApplicationStatus, Config, SentLog, SentRecord, CHECK, CROSS, NOTE,
+};
+
+/// Um passo de uma sequência: o template `template` sai `day` dias depois da candidatura.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Step {
+    pub day: i64,
+    pub template: String,
+}
+
+/// Porque parou a sequência de uma candidatura antes do fim.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    // respondeu (ou mudou de fase à mão)
+    Reply,
+    // o servidor rejeitou o endereço de vez, ou está nos endereços mortos
+    Bounce,
+    // bloqueado (`block`, `forget`)
+    DoNotContact,
+}
+
+impl StopReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StopReason::Reply => "resposta",
+            StopReason::Bounce => "endereço rejeitado",
+            StopReason::DoNotContact => "não contactar",
+        }
+    }
+}
+
+/// Quando e porque parou a sequência; fica na candidatura.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SequenceStop {
+    pub at: DateTime<Local>,
+    pub reason: StopReason,
+    // a fase, o erro do servidor ou o padrão do bloqueio
+    #[serde(default)]
+    pub detail: String,
+}
+
+/// Um follow-up de uma sequência que já devia ter saído.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DueStep {
+    pub email: String,
+    pub campaign: String,
+    // 1 = o primeiro follow-up
+    pub step: usize,
+    pub template: String,
+    // perfil e variante do CV da candidatura, para o follow-up sair igual
+    pub profile: Option<String>,
+    pub cv_variant: Option<String>,
+}
+
+/// O passo seguinte de uma candidatura da campanha e quando sai: contado desde
+/// o passo anterior, depois de um `snooze` ou de uma ausência. `None` depois
+/// do último passo, quando já não está em `sent` ou com a sequência parada.
+pub fn next_step<'a>(campaign: &'a Campaign, record: &SentRecord) -> Option<(&'a Step, DateTime<Local>)> {
+    let next = campaign.sequence.get(record.step)?;
+    if record.status != ApplicationStatus::Sent || record.sequence_stop.is_some() {
+        return None;
+    }
+    let previous = record.step.checked_sub(1).map_or(0, |i| campaign.sequence[i].day);
+    Some((next, notify::follow_up_due(record, next.day - previous)))
+}
+
+/// Porque é que a sequência de `record` já não pode continuar: uma resposta,
+/// uma rejeição permanente de um envio depois dele ou um bloqueio.
+pub fn stop_reason(log: &SentLog, record: &SentRecord, blocklist: &Blocklist, dead: &DeadList) -> Option<(StopReason, String)> {
+    if record.status != ApplicationStatus::Sent {
+        return Some((StopReason::Reply, record.status.label().to_string()));
+    }
+    let bounced = log
+        .records
+        .iter()
+        .filter(|t| !t.success && same_email(&t.email, &record.email) && t.sent_at >= record.sent_at)
+        .find_map(|t| t.error.as_ref().filter(|e| dead::is_dead(e)));
+    if let Some(error) = bounced {
+        return Some((StopReason::Bounce, error.message.clone()));
+    }
+    if let Some(entry) = dead.addresses.iter().find(|a| same_email(&a.email, &record.email)) {
+        return Some((StopReason::Bounce, entry.reason.clone()));
+    }
+    blocklist.matching(&record.email).map(|pattern| (StopReason::DoNotContact, pattern.to_string()))
+}
+
+/// Pára as sequências ainda com passos por enviar que já não devem continuar
+/// e guarda a razão na candidatura; devolve (email, campanha, paragem).
+pub fn stop_sequences(
+    log: &mut SentLog,
+    campaigns: &BTreeMap<String, Campaign>,
+    blocklist: &Blocklist,
+    dead: &DeadList,
+    now: DateTime<Local>,
+) -> Vec<(String, String, SequenceStop)> {
+    // o último envio com sucesso de cada endereço, como em `applications`
+    let mut latest: Vec<usize> = vec![];
+    for (i, r) in log.records.iter().enumerate().filter(|(_, r)| r.success && !r.sandbox) {
+        match latest.iter().position(|&l| same_email(&log.records[l].email, &r.email)) {
+            Some(at) => latest[at] = i,
+            None => latest.push(i),
+        }
+    }
+    let mut stopped = vec![];
+    for i in latest {
+        let r = &log.records[i];
+        let Some(name) = r.campaign.clone() else { continue };
+        let pending = campaigns.get(&name).is_some_and(|c| r.step < c.sequence.len());
+        if !pending || r.sequence_stop.is_some() {
+            continue;
+        }
+        if let Some((reason, detail)) = stop_reason(log, r, blocklist, dead) {
+            let stop = SequenceStop { at: now, reason, detail };
+            log.records[i].sequence_stop = Some(stop.clone());
+            stopped.push((log.records[i].email.clone(), name, stop));
+        }
+    }
+    stopped
+}
+
+// pára o que houver a parar, com uma linha por sequência
+fn halt(config: &Config, log: &mut SentLog) -> Result<()> {
+    let (blocklist, dead) = (crate::blocklist::load_blocklist(), dead::load_dead());
+    let stopped = update_log(log, |log| Ok(stop_sequences(log, &config.campaigns, &blocklist, &dead, Local::now())))?;
+    for (email, campaign, stop) in &stopped {
+        say!("{} [{}] {}: sequência parada ({}: {})", NOTE, campaign, email, stop.reason.label(), stop.detail);
+    }
+    if !stopped.is_empty() {
+        crate::git_history::record(&format!("daemon: {} sequências paradas", stopped.len()));
+    }
+    Ok(())
+}
+
+/// Os passos em atraso das candidaturas de todas as campanhas. Um passo que
+/// já foi tentado não se repete, mesmo que tenha falhado.
+pub fn due_steps(log: &SentLog, campaigns: &BTreeMap<String, Campaign>, now: DateTime<Local>) -> Vec<DueStep> {
+    let mut due = vec![];
+    for r in log.applications() {
+        let Some((name, campaign)) = r.campaign.as_ref().and_then(|name| campaigns.get_key_value(name)) else {
+            continue;
+        };
+        let Some((next, at)) = next_step(campaign, r) else { continue };
+        let tried = log
+            .records
+            .iter()
+            .any(|t| same_email(&t.email, &r.email) && t.campaign.as_ref() == Some(name) && t.step == r.step + 1);
+        if tried || at > now {
+            continue;
+        }
+        let message = r.message.as_ref();
+        due.push(DueStep {
+            email: r.email.clone(),
+            campaign: name.clone(),
+            step: r.step + 1,
+            template: next.template.clone(),
+            profile: message.and_then(|m| m.profile.clone()),
+            cv_variant: message.and_then(|m| m.cv_variant.clone()),
+        });
+    }
+    due
+}
+
+/// Envia os follow-ups em atraso (cada um fica no log como um envio seu),
+/// dentro do limite diário; os que não cabem ficam para o ciclo seguinte.
+pub async fn run_due(config: &Config, cv: &[u8], log: &mut SentLog) -> Result<usize> {
+    halt(config, log)?;
+    let now = Local::now();
+    let mut due = due_steps(log, &config.campaigns, now);
+    if let Some(room) = room_today(&config.sending, log, now) {
+        due.truncate(room);
+    }
+    let mut sent = 0;
+    for step in &due {
+        if crate::interrupted() {
+            break;
+        }
+        let prepared = config_for_profile(config, step.profile.as_deref()).and_then(|mut config| {
+            use_template(&mut config, &step.template)?;
+            config.cv_variant = step.cv_variant.clone();
+            config.campaign = Some(step.campaign.clone());
+            config.sequence_step = step.step;
+            let variant_cv = config.cv_variant.is_some().then(|| load_cv_variant(&config)).transpose()?;
+            Ok((config, variant_cv))
+        });
+        let (config, variant_cv) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                eprintln!("{} [{}] {} não enviado: {:#}", CROSS, step.campaign, style(&step.email).red(), e);
+                continue;
+            }
+        };
+        let (message, result) = send_email(&config, &step.email, variant_cv.as_deref().unwrap_or(cv)).await;
+        record_send(log, &config, &step.email, message, &result)?;
+        sent += 1;
+        match result {
+            Ok(_) => say!("{} [{}] Follow-up {} ({}) para {}", CHECK, step.campaign, step.step, step.template, style(&step.email).green()),
+            Err(e) => eprintln!("{} [{}] Follow-up {} falhou {}: {}", CROSS, step.campaign, step.step, style(&step.email).red(), e),
+        }
+    }
+    if sent > 0 {
+        crate::git_history::record(&format!("daemon: enviados {} follow-ups de sequências", sent));
+        // um follow-up rejeitado de vez pára já a sequência
+        halt(config, log)?;
+    }
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SentMessage;
+    use chrono::{Duration, TimeZone};
+
+    fn campaigns() -> BTreeMap<String, Campaign> {
+        let sequence = vec![
+            Step { day: 7, template: "follow-up-1".to_string() },
+            Step { day: 14, template: "follow-up-2".to_string() },
+        ];
+        BTreeMap::from([("backend-q3".to_string(), Campaign { sequence, ..Default::default() })])
+    }
+
+    fn sent(email: &str, at: DateTime<Local>, step: usize) -> SentRecord {
+        SentRecord {
+            email: email.to_string(),
+            sent_at: at,
+            success: true,
+            campaign: Some("backend-q3".to_string()),
+            step,
+            message: Some(SentMessage { cv_variant: Some("backend".to_string()), ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_due_steps_follow_the_sequence() {
+        let day0 = Local.with_ymd_and_hms(2026, 10, 1, 10, 0, 0).unwrap();
+        let mut log = SentLog {
+            records: vec![
+                sent("rh@acme.com", day0, 0),
+                sent("jobs@beta.pt", day0, 0),
+                SentRecord { campaign: None, ..sent("rh@gamma.io", day0, 0) },
+            ],
+        };
+        log.records[1].status = ApplicationStatus::Replied;
+        let campaigns = campaigns();
+        assert!(due_steps(&log, &campaigns, day0 + Duration::days(6)).is_empty());
+        let due = due_steps(&log, &campaigns, day0 + Duration::days(7));
+        assert_eq!(
+            due,
+            vec![DueStep {
+                email: "rh@acme.com".to_string(),
+                campaign: "backend-q3".to_string(),
+                step: 1,
+                template: "follow-up-1".to_string(),
+                profile: None,
+                cv_variant: Some("backend".to_string()),
+            }]
+        );
+
+        // o segundo conta a partir do primeiro follow-up, que saiu com um dia de atraso
+        log.records.push(sent("rh@acme.com", day0 + Duration::days(8), 1));
+        assert!(due_steps(&log, &campaigns, day0 + Duration::days(14)).is_empty());
+        assert_eq!(due_steps(&log, &campaigns, day0 + Duration::days(15))[0].step, 2);
+        // falhado: não volta a ser tentado
+        log.records.push(SentRecord { success: false, ..sent("rh@acme.com", day0 + Duration::days(15), 2) });
+        assert!(due_steps(&log, &campaigns, day0 + Duration::days(20)).is_empty());
+        // depois do último passo acaba
+        log.records.push(sent("jobs@beta.pt", day0, 0));
+        log.records.push(sent("jobs@beta.pt", day0 + Duration::days(14), 2));
+        assert!(due_steps(&log, &campaigns, day0 + Duration::days(60)).is_empty());
+    }
+
+    #[test]
+    fn test_stop_on_reply_bounce_or_block() {
+        let day0 = Local.with_ymd_and_hms(2026, 10, 1, 10, 0, 0).unwrap();
+        let mut log = SentLog {
+            records: vec![
+                sent("rh@acme.com", day0, 0),
+                sent("jobs@beta.pt", day0, 0),
+                sent("hr@gamma.io", day0, 0),
+                sent("ceo@delta.com", day0, 0),
+                // já no fim da sequência: nada a parar
+                SentRecord { status: ApplicationStatus::Rejected, ..sent("cto@eps.com", day0, 2) },
+            ],
+        };
+        log.records[0].status = ApplicationStatus::Rejected;
+        log.records.push(SentRecord {
+            success: false,
+            error: Some(crate::failure::SendError::classify("permanent error (550): 5.1.1 user unknown", None)),
+            ..sent("jobs@beta.pt", day0 + Duration::days(7), 1)
+        });
+        let mut blocklist = Blocklist::default();
+        blocklist.add("*@gamma.io");
+        let now = day0 + Duration::days(8);
+        let stopped = stop_sequences(&mut log, &campaigns(), &blocklist, &DeadList::default(), now);
+        let reasons: Vec<(&str, StopReason)> = stopped.iter().map(|(e, _, s)| (e.as_str(), s.reason)).collect();
+        assert_eq!(
+            reasons,
+            vec![("rh@acme.com", StopReason::Reply), ("jobs@beta.pt", StopReason::Bounce), ("hr@gamma.io", StopReason::DoNotContact)]
+        );
+        assert_eq!(stopped[2].2.detail, "*@gamma.io");
+        assert_eq!(log.records[0].sequence_stop.as_ref().unwrap().detail, "rejeitado");
+        // só ceo@ continua, e parar outra vez não repete nada
+        let due: Vec<String> = due_steps(&log, &campaigns(), now).into_iter().map(|d| d.email).collect();
+        assert_eq!(due, vec!["ceo@delta.com"]);
+        assert!(stop_sequences(&mut log, &campaigns(), &blocklist, &DeadList::default(), now).is_empty());
+
+        let mut dead = DeadList::default();
+        dead.add("CEO@delta.com", "550 no such user");
+        let stopped = stop_sequences(&mut log, &campaigns(), &blocklist, &dead, now);
+        assert_eq!((stopped[0].2.reason, stopped[0].2.detail.as_str()), (StopReason::Bounce, "550 no such user"));
+    }
+
+    #[test]
+    fn test_snooze_delays_the_next_step() {
+        let day0 = Local.with_ymd_and_hms(2026, 10, 1, 10, 0, 0).unwrap();
+        let mut log = SentLog { records: vec![sent("rh@acme.com", day0, 0)] };
+        log.records[0].snoozed_until = chrono::NaiveDate::from_ymd_opt(2026, 10, 20);
+        assert!(due_steps(&log, &campaigns(), day0 + Duration::days(10)).is_empty());
+        assert_eq!(due_steps(&log, &campaigns(), day0 + Duration::days(19)).len(), 1);
+    }
+}
@@ -0,0 +1,367 @@
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use console::style;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    env,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{contacts, leads, load_log, outbox, SentLog, Stats, MAIL};
+
+// pedidos maiores que isto não são de um dashboard
+const MAX_REQUEST: usize = 1024 * 1024;
+
+// prazo do pedido inteiro, não de cada leitura: quem manda um byte de cada vez também esbarra nele
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// a interface web: uma página estática que só fala com a API
+const UI: &str = include_str!("ui.html");
+
+/// Pedido HTTP já lido: só o que a API e o colector do pixel usam.
+#[derive(Debug, Default)]
+pub struct Request {
+    pub method: String,
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    pub fn path(&self) -> &str {
+        self.target.split('?').next().unwrap_or_default()
+    }
+
+    pub fn query(&self, key: &str) -> Option<String> {
+        let (_, query) = self.target.split_once('?')?;
+        query.split('&').find_map(|p| p.strip_prefix(&format!("{}=", key))).map(decode)
+    }
+}
+
+// %40 → @, + → espaço
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Linha de pedido e cabeçalhos; None se não for HTTP.
+pub fn parse_head(head: &str) -> Option<Request> {
+    let mut lines = head.lines();
+    let mut first = lines.next()?.split(' ');
+    let method = first.next()?.to_string();
+    let target = first.next()?.to_string();
+    if !first.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    let headers = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+    Some(Request { method, target, headers, body: vec![] })
+}
+
+async fn read_some(stream: &mut TcpStream, chunk: &mut [u8]) -> Option<usize> {
+    let n = stream.read(chunk).await.ok()?;
+    (n > 0).then_some(n)
+}
+
+/// Lê um pedido; um cliente parado ou que manda lixo dá None em vez de prender a ligação.
+pub async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    tokio::time::timeout(REQUEST_TIMEOUT, read_whole(stream)).await.ok()?
+}
+
+async fn read_whole(stream: &mut TcpStream) -> Option<Request> {
+    let mut buf = vec![];
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(at) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break at;
+        }
+        if buf.len() > MAX_REQUEST {
+            return None;
+        }
+        let n = read_some(stream, &mut chunk).await?;
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let mut request = parse_head(&String::from_utf8_lossy(&buf[..head_end]))?;
+    let length: usize = request.header("Content-Length").and_then(|l| l.parse().ok()).unwrap_or(0);
+    if length > MAX_REQUEST {
+        return None;
+    }
+    let mut body = buf.split_off(head_end + 4);
+    while body.len() < length {
+        let n = read_some(stream, &mut chunk).await?;
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(length);
+    request.body = body;
+    Some(request)
+}
+
+pub async fn respond(stream: &mut TcpStream, status: u16, content_type: &str, body: &[u8]) {
+    let reason = match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        content_type,
+        body.len()
+    );
+    // um cliente que fecha a ligação a meio não pára o servidor
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(body).await;
+}
+
+#[derive(Debug, Deserialize)]
+struct SendRequest {
+    to: Vec<String>,
+    // "YYYY-MM-DD HH:MM" ou "HH:MM"; sem hora vai no próximo ciclo do daemon
+    #[serde(default)]
+    send_at: Option<String>,
+    #[serde(default)]
+    profile: Option<String>,
+    #[serde(default)]
+    cv: Option<String>,
+    #[serde(default)]
+    no_track: bool,
+}
+
+fn error(status: u16, message: impl std::fmt::Display) -> (u16, Value) {
+    (status, json!({ "error": message.to_string() }))
+}
+
+fn enqueue(request: &Request, default_profile: Option<&str>) -> Result<Value> {
+    // um formulário ou text/plain de outra página chega sem preflight de CORS; JSON não
+    if !request.header("Content-Type").is_some_and(|t| t.starts_with("application/json")) {
+        bail!("Content-Type must be application/json");
+    }
+    let request: SendRequest = serde_json::from_slice(&request.body).context("Invalid JSON body")?;
+    if request.to.is_empty() {
+        bail!("to is empty");
+    }
+    if let Some(email) = request.to.iter().find(|e| !e.contains('@')) {
+        bail!("Invalid email: {}", email);
+    }
+    let when = request.send_at.as_deref().map(outbox::parse_send_time).transpose()?;
+    let profile = request.profile.as_deref().or(default_profile);
+    let queued = outbox::enqueue(when, &request.to, profile, request.cv.as_deref(), request.no_track)?;
+    Ok(json!({ "queued": queued }))
+}
+
+// o bookmarklet manda um formulário (sem preflight de CORS); outros clientes JSON
+fn capture(request: &Request) -> Result<Value> {
+    let form = request.header("Content-Type").is_some_and(|t| t.starts_with("application/x-www-form-urlencoded"));
+    let capture: leads::Capture = if form {
+        let body = String::from_utf8_lossy(&request.body);
+        let field = |key: &str| body.split('&').find_map(|p| p.strip_prefix(&format!("{}=", key))).map(decode);
+        leads::Capture { email: field("email"), text: field("text"), url: field("url"), title: field("title") }
+    } else {
+        serde_json::from_slice(&request.body).context("Invalid JSON body")?
+    };
+    // um pedido sem nada não chega a tocar no leads.json (nem no lock)
+    capture.emails_and_url()?;
+    let book = contacts::load_contacts();
+    let added = leads::update_leads(|leads| leads.capture(&capture, &book, Local::now()))?;
+    Ok(json!({ "captured": added }))
+}
+
+/// Resposta da API a um pedido já autenticado.
+pub fn route(request: &Request, default_profile: Option<&str>) -> (u16, Value) {
+    match (request.method.as_str(), request.path()) {
+        ("GET", "/api/log") => {
+            let log = load_log();
+            match request.query("email") {
+                Some(email) => {
+                    let records: Vec<_> = log.records.into_iter().filter(|r| r.involves(&email)).collect();
+                    (200, json!(SentLog { records }))
+                }
+                None => (200, json!(log)),
+            }
+        }
+        ("GET", "/api/stats") => (200, json!(Stats::from_log(&load_log()))),
+        ("GET", "/api/queue") => {
+            let mut items = outbox::load_outbox().items;
+            items.sort_by_key(|i| i.send_at);
+            (200, json!({ "items": items }))
+        }
+        ("POST", "/api/send") => match enqueue(request, default_profile) {
+            Ok(queued) => (201, queued),
+            Err(e) => error(400, format!("{:#}", e)),
+        },
+        ("POST", "/api/capture") => match capture(request) {
+            Ok(captured) => (201, captured),
+            Err(e) => error(400, format!("{:#}", e)),
+        },
+        ("GET", "/api/leads") => (200, json!(leads::load_leads())),
+        (_, "/api/log" | "/api/stats" | "/api/queue" | "/api/send" | "/api/capture" | "/api/leads") => {
+            error(405, "method not allowed")
+        }
+        _ => error(404, "not found"),
+    }
+}
+
+// "127.0.0.1:8080", "localhost", "[::1]:8080"
+fn is_loopback_host(host: &str) -> bool {
+    let name = host.rsplit_once(':').filter(|(_, port)| port.chars().all(|c| c.is_ascii_digit())).map_or(host, |(name, _)| name);
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    name.eq_ignore_ascii_case("localhost") || name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+// sem token, qualquer página aberta no browser chega ao 127.0.0.1: só passam
+// pedidos para um Host da própria máquina (não um domínio que o DNS rebinding
+// aponta para cá) e sem Origin de outro site
+fn same_machine(request: &Request) -> bool {
+    let Some(host) = request.header("Host") else {
+        return false;
+    };
+    is_loopback_host(host) && request.header("Origin").is_none_or(|origin| origin.strip_prefix("http://") == Some(host))
+}
+
+// SERVE_TOKEN no .env: os pedidos têm de trazer "Authorization: Bearer <token>";
+// o bookmarklet não pode mandar cabeçalhos e leva-o em `?token=` na captura
+fn authorized(request: &Request, token: Option<&str>) -> bool {
+    match token {
+        None => same_machine(request),
+        Some(token) => {
+            request.header("Authorization").and_then(|h| h.strip_prefix("Bearer ")) == Some(token)
+                || (request.path() == "/api/capture" && request.query("token").as_deref() == Some(token))
+        }
+    }
+}
+
+pub async fn serve(listen: &str, default_profile: Option<&str>) -> Result<()> {
+    let addr: SocketAddr = listen.parse().with_context(|| format!("Invalid address: {}", listen))?;
+    let token = env::var("SERVE_TOKEN").ok().filter(|t| !t.is_empty());
+    // a API envia emails em meu nome: fora da máquina, só com token
+    if token.is_none() && !addr.ip().is_loopback() {
+        bail!("SERVE_TOKEN not set in .env (required to listen on {})", addr);
+    }
+    let listener = TcpListener::bind(addr).await.with_context(|| format!("Cannot listen on {}", addr))?;
+    say!("{} Interface e API em http://{} (Ctrl-C para sair)", MAIL, style(addr).cyan());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        // cada ligação à parte: um cliente lento não prende a interface nem a API
+        tokio::spawn(handle(stream, token.clone(), default_profile.map(str::to_string)));
+    }
+}
+
+async fn handle(mut stream: TcpStream, token: Option<String>, default_profile: Option<String>) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    // a página não tem dados; o token é pedido quando ela chama a API
+    if request.method == "GET" && matches!(request.path(), "/" | "/index.html") {
+        respond(&mut stream, 200, "text/html; charset=utf-8", UI.as_bytes()).await;
+        return;
+    }
+    let (status, body) = if authorized(&request, token.as_deref()) {
+        route(&request, default_profile.as_deref())
+    } else if token.is_none() {
+        error(403, "request from another site or host refused")
+    } else {
+        error(401, "missing or wrong token")
+    };
+    verbose!("{} {} → {}", request.method, request.target, status);
+    respond(&mut stream, status, "application/json", body.to_string().as_bytes()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_head_and_query() {
+        let request = parse_head("GET /api/log?email=rh%40acme.com&x=1 HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer abc").unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path(), "/api/log");
+        assert_eq!(request.query("email").as_deref(), Some("rh@acme.com"));
+        assert_eq!(request.query("y"), None);
+        assert_eq!(request.header("authorization"), Some("Bearer abc"));
+        assert!(parse_head("\u{16}\u{3}\u{1} lixo").is_none());
+    }
+
+    #[test]
+    fn test_without_token_only_this_machine() {
+        let local = |head: &str| same_machine(&parse_head(&format!("POST /api/send HTTP/1.1\r\n{}", head)).unwrap());
+        assert!(local("Host: 127.0.0.1:8080"));
+        assert!(local("Host: localhost:8080\r\nOrigin: http://localhost:8080"));
+        assert!(local("Host: [::1]:8080"));
+        // outra página no browser
+        assert!(!local("Host: 127.0.0.1:8080\r\nOrigin: https://evil.example"));
+        assert!(!local("Host: 127.0.0.1:8080\r\nOrigin: null"));
+        // DNS rebinding: o domínio do atacante a apontar para 127.0.0.1
+        assert!(!local("Host: evil.example:8080"));
+        assert!(!local("Host: 127.0.0.1.evil.example"));
+        assert!(!local("Content-Length: 0"));
+    }
+
+    #[test]
+    fn test_token_and_unknown_routes() {
+        let mut request = parse_head("GET /api/stats HTTP/1.1\r\nHost: 127.0.0.1:8080").unwrap();
+        assert!(authorized(&request, None));
+        assert!(!authorized(&request, Some("abc")));
+        request.headers.push(("Authorization".to_string(), "Bearer abc".to_string()));
+        assert!(authorized(&request, Some("abc")));
+        assert!(!authorized(&request, Some("abcd")));
+
+        assert_eq!(route(&parse_head("GET /nada HTTP/1.1").unwrap(), None).0, 404);
+        assert_eq!(route(&parse_head("DELETE /api/log HTTP/1.1").unwrap(), None).0, 405);
+        let bad = Request {
+            body: b"{\"to\": []}".to_vec(),
+            ..parse_head("POST /api/send HTTP/1.1\r\nContent-Type: application/json").unwrap()
+        };
+        assert_eq!(route(&bad, None), (400, json!({ "error": "to is empty" })));
+        let plain = Request { body: b"{\"to\": [\"rh@acme.com\"]}".to_vec(), ..parse_head("POST /api/send HTTP/1.1").unwrap() };
+        assert_eq!(route(&plain, None), (400, json!({ "error": "Content-Type must be application/json" })));
+
+        let capture = parse_head("POST /api/capture?token=abc HTTP/1.1\r\nHost: 127.0.0.1:8080\r\nOrigin: https://jobs.example").unwrap();
+        assert!(authorized(&capture, Some("abc")));
+        // o formulário do bookmarklet vem de outro site: sem SERVE_TOKEN não entra
+        assert!(!authorized(&capture, None));
+        let elsewhere = parse_head("GET /api/log?token=abc HTTP/1.1").unwrap();
+        assert!(!authorized(&elsewhere, Some("abc")));
+        let mut empty = Request { body: b"url=&title=".to_vec(), ..capture };
+        empty.headers.push(("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()));
+        assert_eq!(route(&empty, None), (400, json!({ "error": "No email or url in the capture" })));
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{bail, Context, Result};
+use lettre::message::{
+    header::{ContentDisposition, ContentType},
+    MultiPart, SinglePart,
+};
+use std::{env, fs, process::Command};
+use tempfile::TempDir;
+
+use crate::Profile;
+
+/// Assinatura PKCS#7 destacada (DER) de `data` com o certificado do perfil.
+/// A passphrase da chave, se existir, vem de SMIME_PASSPHRASE.
+pub fn detached_signature(data: &[u8], cert: &str, key: &str, chain: Option<&str>) -> Result<Vec<u8>> {
+    let work = TempDir::new()?;
+    let input = work.path().join("content");
+    let output = work.path().join("smime.p7s");
+    fs::write(&input, data)?;
+
+    let mut cmd = Command::new("openssl");
+    cmd.args(["smime", "-sign", "-binary", "-md", "sha256", "-outform", "DER"])
+        .args(["-signer", cert, "-inkey", key])
+        .arg("-in")
+        .arg(&input)
+        .arg("-out")
+        .arg(&output);
+    if let Some(chain) = chain {
+        cmd.args(["-certfile", chain]);
+    }
+    if env::var("SMIME_PASSPHRASE").is_ok() {
+        cmd.args(["-passin", "env:SMIME_PASSPHRASE"]);
+    }
+
+    let out = cmd.output().context("openssl not found in PATH")?;
+    if !out.status.success() {
+        bail!("S/MIME signing failed: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(fs::read(output)?)
+}
+
+/// Embrulha o conteúdo num multipart/signed com application/pkcs7-signature.
+pub fn sign(content: MultiPart, profile: &Profile) -> Result<MultiPart> {
+    let (Some(cert), Some(key)) = (&profile.smime_cert, &profile.smime_key) else {
+        bail!("S/MIME needs both smime_cert and smime_key in the profile");
+    };
+    let signature = detached_signature(&content.formatted(), cert, key, profile.smime_chain.as_deref())?;
+
+    let signature_part = SinglePart::builder()
+        .header(ContentType::parse("application/pkcs7-signature; name=\"smime.p7s\"")?)
+        .header(ContentDisposition::attachment("smime.p7s"))
+        .body(signature);
+
+    Ok(
+        MultiPart::signed("application/pkcs7-signature".into(), "sha-256".into())
+            .multipart(content)
+            .singlepart(signature_part),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn self_signed(dir: &std::path::Path) -> (String, String) {
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        let out = Command::new("openssl")
+            .args(["req", "-x509", "-newkey", "rsa:2048", "-nodes", "-days", "1"])
+            .args(["-subj", "/CN=Teste/emailAddress=teste@example.com"])
+            .arg("-keyout")
+            .arg(&key)
+            .arg("-out")
+            .arg(&cert)
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        (cert.to_string_lossy().to_string(), key.to_string_lossy().to_string())
+    }
+
+    #[test]
+    fn test_detached_signature_verifies() {
+        let dir = TempDir::new().unwrap();
+        let (cert, key) = self_signed(dir.path());
+
+        let data = b"Content-Type: text/plain\r\n\r\nOla\r\n";
+        let signature = detached_signature(data, &cert, &key, None).unwrap();
+
+        let sig_file = dir.path().join("sig.der");
+        let data_file = dir.path().join("data");
+        fs::write(&sig_file, signature).unwrap();
+        fs::write(&data_file, data).unwrap();
+        let out = Command::new("openssl")
+            .args(["smime", "-verify", "-binary", "-inform", "DER", "-noverify"])
+            .arg("-in")
+            .arg(&sig_file)
+            .arg("-content")
+            .arg(&data_file)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    }
+
+    #[test]
+    fn test_sign_builds_multipart_signed() {
+        let dir = TempDir::new().unwrap();
+        let (cert, key) = self_signed(dir.path());
+        let profile: Profile = serde_json::from_value(serde_json::json!({
+            "name": "Ana", "email": "ana@example.com", "phone": "1", "title": "Dev",
+            "summary": "s", "skills": [], "experience_years": 1,
+            "linkedin": null, "github": null,
+            "smime_cert": cert, "smime_key": key
+        }))
+        .unwrap();
+
+        let content = MultiPart::mixed().singlepart(SinglePart::plain("Olá".to_string()));
+        let raw = String::from_utf8(sign(content, &profile).unwrap().formatted()).unwrap();
+        assert!(raw.contains("multipart/signed"));
+        assert!(raw.contains("protocol=\"application/pkcs7-signature\""));
+        assert!(raw.contains("micalg=\"sha-256\"") || raw.contains("micalg=sha-256"));
+        assert!(raw.contains("smime.p7s"));
+    }
+}
@@ -0,0 +1,333 @@
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use console::style;
+use std::{collections::BTreeMap, fs, sync::Mutex, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, BufReader},
+    net::TcpStream,
+    time::timeout,
+};
+
+use crate::{
+    enrich::email_domain,
+    oauth, presets,
+    secrets::Account,
+    verify::{reply_lines, send_line},
+    Config, SmtpConfig, SmtpSecurity, CHECK, CROSS, NOTE, CV_FILE,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// O que o servidor SMTP disse, sem ter enviado nada.
+#[derive(Debug, Default)]
+pub struct Probe {
+    pub greeting: String,
+    pub tls: bool,
+    // as linhas do EHLO depois do nome do servidor ("SIZE 35882577", "AUTH PLAIN LOGIN", ...)
+    pub extensions: Vec<String>,
+    pub auth: AuthOutcome,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub enum AuthOutcome {
+    // o servidor não anuncia AUTH (relay local)
+    #[default]
+    NotOffered,
+    Accepted(String),
+    Refused(String),
+    // só mecanismos que o job-mailer não usa (XOAUTH2, CRAM-MD5...)
+    Unsupported(Vec<String>),
+}
+
+fn extension<'a>(extensions: &'a [String], name: &str) -> Option<&'a str> {
+    extensions.iter().find_map(|e| {
+        let (keyword, args) = e.split_once(' ').unwrap_or((e, ""));
+        keyword.eq_ignore_ascii_case(name).then_some(args.trim())
+    })
+}
+
+/// Tamanho máximo de uma mensagem (`SIZE`), em bytes; `None` sem limite anunciado.
+pub fn max_size(extensions: &[String]) -> Option<u64> {
+    extension(extensions, "SIZE")?.parse().ok().filter(|&size| size > 0)
+}
+
+pub fn auth_mechanisms(extensions: &[String]) -> Vec<String> {
+    extension(extensions, "AUTH")
+        .map(|m| m.split_whitespace().map(str::to_uppercase).collect())
+        .unwrap_or_default()
+}
+
+async fn ehlo<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut BufReader<S>, helo: &str) -> Result<Vec<String>> {
+    send_line(stream, &format!("EHLO {}", helo)).await?;
+    let (code, lines) = reply_lines(stream).await?;
+    if code != 250 {
+        bail!("EHLO refused ({}): {}", code, lines.join(" "));
+    }
+    Ok(lines.into_iter().skip(1).collect())
+}
+
+async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut BufReader<S>,
+    mechanisms: &[String],
+    account: &Account,
+) -> Result<AuthOutcome> {
+    let offers = |name: &str| mechanisms.iter().any(|m| m == name);
+    let (mechanism, steps) = match account {
+        Account::OAuth(user, token) if offers("XOAUTH2") => {
+            ("XOAUTH2", vec![format!("AUTH XOAUTH2 {}", STANDARD.encode(oauth::xoauth2(user, token)))])
+        }
+        Account::Password(user, pass) if offers("PLAIN") => {
+            ("PLAIN", vec![format!("AUTH PLAIN {}", STANDARD.encode(format!("\0{}\0{}", user, pass)))])
+        }
+        Account::Password(user, pass) if offers("LOGIN") => {
+            ("LOGIN", vec!["AUTH LOGIN".to_string(), STANDARD.encode(user), STANDARD.encode(pass)])
+        }
+        _ => return Ok(AuthOutcome::Unsupported(mechanisms.to_vec())),
+    };
+    for step in steps {
+        send_line(stream, &step).await?;
+        let (code, lines) = reply_lines(stream).await?;
+        match code {
+            235 => return Ok(AuthOutcome::Accepted(mechanism.to_string())),
+            334 => continue,
+            _ => return Ok(AuthOutcome::Refused(format!("{} {}", code, lines.join(" ")))),
+        }
+    }
+    // o XOAUTH2 recusado manda o erro num 334: linha vazia e vem a resposta final
+    send_line(stream, "").await?;
+    let (code, lines) = reply_lines(stream).await?;
+    Ok(match code {
+        235 => AuthOutcome::Accepted(mechanism.to_string()),
+        _ => AuthOutcome::Refused(format!("{} {}", code, lines.join(" "))),
+    })
+}
+
+// depois do TLS (se houver): EHLO, AUTH (sem conta não) e QUIT
+async fn session<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: BufReader<S>,
+    helo: &str,
+    account: Option<&Account>,
+    probe: &mut Probe,
+) -> Result<()> {
+    probe.extensions = ehlo(&mut stream, helo).await?;
+    let mechanisms = auth_mechanisms(&probe.extensions);
+    if let Some(account) = account.filter(|_| !mechanisms.is_empty()) {
+        probe.auth = authenticate(&mut stream, &mechanisms, account).await?;
+    }
+    let _ = send_line(&mut stream, "QUIT").await;
+    Ok(())
+}
+
+async fn greeting<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut BufReader<S>) -> Result<String> {
+    let (code, lines) = reply_lines(stream).await?;
+    if code != 220 {
+        bail!("SMTP greeting {}: {}", code, lines.join(" "));
+    }
+    Ok(lines.join(" "))
+}
+
+async fn tls_connect(smtp: &SmtpConfig, tcp: TcpStream) -> Result<tokio_native_tls::TlsStream<TcpStream>> {
+    let connector = tokio_native_tls::native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(smtp.accept_invalid_certs)
+        .build()?;
+    tokio_native_tls::TlsConnector::from(connector)
+        .connect(&smtp.host, tcp)
+        .await
+        .context("SMTP TLS handshake failed")
+}
+
+/// Liga-se ao servidor do config como o envio faria (TLS, STARTTLS ou sem
+/// encriptação) e autentica-se com a conta, se vier, sem MAIL FROM.
+pub async fn probe(config: &Config, account: Option<&Account>) -> Result<Probe> {
+    let smtp = &config.smtp;
+    let helo = email_domain(&config.profile.email).unwrap_or("localhost");
+    let mut probe = Probe::default();
+    let work = async {
+        let tcp = TcpStream::connect((smtp.host.as_str(), smtp.port))
+            .await
+            .with_context(|| format!("Cannot connect to {}:{}", smtp.host, smtp.port))?;
+        match smtp.security {
+            SmtpSecurity::None => {
+                let mut stream = BufReader::new(tcp);
+                probe.greeting = greeting(&mut stream).await?;
+                session(stream, helo, account, &mut probe).await
+            }
+            SmtpSecurity::Tls => {
+                let mut stream = BufReader::new(tls_connect(smtp, tcp).await?);
+                probe.tls = true;
+                probe.greeting = greeting(&mut stream).await?;
+                session(stream, helo, account, &mut probe).await
+            }
+            SmtpSecurity::Starttls => {
+                let mut stream = BufReader::new(tcp);
+                probe.greeting = greeting(&mut stream).await?;
+                if extension(&ehlo(&mut stream, helo).await?, "STARTTLS").is_none() {
+                    bail!("{} does not offer STARTTLS; try \"security\": \"tls\" (port 465)", smtp.host);
+                }
+                send_line(&mut stream, "STARTTLS").await?;
+                let (code, lines) = reply_lines(&mut stream).await?;
+                if code != 220 {
+                    bail!("STARTTLS refused ({}): {}", code, lines.join(" "));
+                }
+                let tls = tls_connect(smtp, stream.into_inner()).await?;
+                probe.tls = true;
+                session(BufReader::new(tls), helo, account, &mut probe).await
+            }
+        }
+    };
+    timeout(TIMEOUT, work).await.context("SMTP timed out")??;
+    Ok(probe)
+}
+
+// SMTPUTF8 por servidor, perguntado uma vez por execução
+static SMTPUTF8: Mutex<BTreeMap<(String, u16), bool>> = Mutex::new(BTreeMap::new());
+
+/// Se o servidor do config anuncia SMTPUTF8 (RFC 6531); sem resposta conta como não.
+pub async fn supports_smtputf8(config: &Config) -> bool {
+    let key = (config.smtp.host.clone(), config.smtp.port);
+    if let Some(known) = SMTPUTF8.lock().unwrap().get(&key) {
+        return *known;
+    }
+    let supported = match probe(config, None).await {
+        Ok(probe) => extension(&probe.extensions, "SMTPUTF8").is_some(),
+        Err(e) => {
+            verbose!("SMTPUTF8 não confirmado: {:#}", e);
+            false
+        }
+    };
+    SMTPUTF8.lock().unwrap().insert(key, supported);
+    supported
+}
+
+// o maior dos CVs do perfil, como vai no email (base64 ocupa 4/3)
+fn largest_cv(config: &Config) -> Option<(String, u64)> {
+    let paths = std::iter::once(crate::overrides::path(CV_FILE)).chain(config.profile.cvs.values().cloned());
+    paths
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.len() * 4 / 3, path)))
+        .max()
+        .map(|(size, path)| (path, size))
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1}MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// `smtp test`: liga, negocia TLS e autentica; falha se a password for recusada.
+pub async fn run_test(config: &Config) -> Result<()> {
+    let smtp = &config.smtp;
+    let profile = config.active_profile.as_deref();
+    let account = match &config.oauth {
+        Some(oauth) => {
+            let user = crate::account_var(&["SMTP_USER"], profile).context("SMTP_USER not set in .env")?;
+            let token = oauth::access_token(oauth, &user).await?;
+            Account::OAuth(user, token)
+        }
+        None => {
+            let (user, pass) = crate::smtp_account(profile, &config.secrets)?;
+            Account::Password(user, pass)
+        }
+    };
+    say!("{} {}:{} ({:?}) como {}", NOTE, smtp.host, smtp.port, smtp.security, style(account.user()).cyan());
+    let probe = probe(config, Some(&account)).await?;
+
+    say!("{} Ligado: {}", CHECK, probe.greeting);
+    if probe.tls {
+        say!("{} TLS negociado", CHECK);
+    } else {
+        say!("{} Sem TLS: a password e os emails passam em claro", CROSS);
+    }
+    say!("  Extensões: {}", probe.extensions.join(", "));
+    match max_size(&probe.extensions) {
+        Some(size) => {
+            say!("  Tamanho máximo de uma mensagem: {}", megabytes(size));
+            if let Some((path, cv)) = largest_cv(config).filter(|(_, cv)| *cv >= size) {
+                say!("{} {} fica com ~{} no email: acima do limite do servidor", CROSS, path, megabytes(cv));
+            }
+        }
+        None => say!("  Tamanho máximo de uma mensagem: não anunciado"),
+    }
+    match probe.auth {
+        AuthOutcome::Accepted(mechanism) => {
+            say!("{} Autenticado ({})", CHECK, mechanism);
+            Ok(())
+        }
+        AuthOutcome::NotOffered => {
+            say!("{} O servidor não pede autenticação", NOTE);
+            Ok(())
+        }
+        AuthOutcome::Unsupported(mechanisms) => {
+            bail!("The server only offers AUTH {}, which job-mailer does not support", mechanisms.join(" "))
+        }
+        AuthOutcome::Refused(reply) => {
+            if let Some(preset) = presets::for_server(&smtp.host, smtp.port).filter(|p| !p.note.is_empty()) {
+                say!("{} {}: {}", NOTE, preset.name, preset.note);
+            }
+            bail!("Authentication failed for {}: {}", account.user(), reply)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_and_auth_from_ehlo() {
+        let extensions: Vec<String> = ["PIPELINING", "SIZE 35882577", "8BITMIME", "AUTH LOGIN plain XOAUTH2", "STARTTLS"]
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(max_size(&extensions), Some(35882577));
+        assert_eq!(auth_mechanisms(&extensions), vec!["LOGIN", "PLAIN", "XOAUTH2"]);
+        assert_eq!(extension(&extensions, "starttls"), Some(""));
+        // "SIZE" sem número ou 0: sem limite
+        assert_eq!(max_size(&["SIZE".to_string()]), None);
+        assert!(auth_mechanisms(&[]).is_empty());
+    }
+
+    // servidor falso: uma resposta por cada linha recebida; devolve as linhas
+    fn authenticate_against(answers: &'static [&'static str], offered: &str, account: Account) -> (AuthOutcome, Vec<String>) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (client, server) = tokio::io::duplex(4096);
+            let server = async move {
+                let mut server = BufReader::new(server);
+                let mut seen = vec![];
+                for answer in answers {
+                    let mut line = String::new();
+                    tokio::io::AsyncBufReadExt::read_line(&mut server, &mut line).await.unwrap();
+                    seen.push(line.trim_end().to_string());
+                    send_line(&mut server, answer).await.unwrap();
+                }
+                seen
+            };
+            let mut client = BufReader::new(client);
+            let mechanisms = auth_mechanisms(&[format!("AUTH {}", offered)]);
+            let (outcome, seen) = tokio::join!(authenticate(&mut client, &mechanisms, &account), server);
+            (outcome.unwrap(), seen)
+        })
+    }
+
+    #[test]
+    fn test_login_when_plain_is_not_offered() {
+        let answers = &["334 VXNlcm5hbWU6", "334 UGFzc3dvcmQ6", "535 5.7.8 bad credentials"];
+        let account = Account::Password("joao".to_string(), "s3cret".to_string());
+        let (outcome, seen) = authenticate_against(answers, "LOGIN", account);
+        assert_eq!(seen, vec!["AUTH LOGIN".to_string(), STANDARD.encode("joao"), STANDARD.encode("s3cret")]);
+        assert_eq!(outcome, AuthOutcome::Refused("535 5.7.8 bad credentials".to_string()));
+    }
+
+    #[test]
+    fn test_xoauth2_error_is_read_after_an_empty_line() {
+        let answers = &["334 eyJzdGF0dXMiOiI0MDAifQ==", "535 5.7.8 Username and Password not accepted"];
+        let account = Account::OAuth("joao@gmail.com".to_string(), "ya29.expired".to_string());
+        let (outcome, seen) = authenticate_against(answers, "LOGIN PLAIN XOAUTH2", account);
+        assert_eq!(seen[0], format!("AUTH XOAUTH2 {}", STANDARD.encode(oauth::xoauth2("joao@gmail.com", "ya29.expired"))));
+        assert_eq!(seen[1], "");
+        assert!(matches!(outcome, AuthOutcome::Refused(reply) if reply.starts_with("535")));
+
+        // sem XOAUTH2 anunciado, o token não serve
+        let account = Account::OAuth("joao@gmail.com".to_string(), "t".to_string());
+        assert!(matches!(authenticate_against(&[], "PLAIN", account).0, AuthOutcome::Unsupported(_)));
+    }
+}
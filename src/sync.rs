@@ -0,0 +1,394 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use console::style;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+
+use crate::{
+    contacts::{load_contacts, save_contacts, ContactBook, CONTACTS_FILE},
+    schema::INITIAL,
+    update_log, Config, SentLog, SentRecord, CHECK, LOG_FILE, LOG_SCHEMA,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum SyncConfig {
+    /// credenciais em SYNC_USER / SYNC_PASS
+    Webdav { url: String },
+    /// credenciais em AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+enum Remote {
+    Webdav {
+        base: Url,
+        user: String,
+        pass: String,
+    },
+    S3 {
+        base: Url,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+fn with_trailing_slash(url: &str) -> String {
+    if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{}/", url)
+    }
+}
+
+impl Remote {
+    fn from_config(sync: &SyncConfig) -> Result<Self> {
+        match sync {
+            SyncConfig::Webdav { url } => Ok(Remote::Webdav {
+                base: Url::parse(&with_trailing_slash(url)).context("Invalid sync.url")?,
+                user: env::var("SYNC_USER").context("SYNC_USER not set in .env")?,
+                pass: env::var("SYNC_PASS").context("SYNC_PASS not set in .env")?,
+            }),
+            SyncConfig::S3 { endpoint, bucket, region, prefix } => {
+                // path-style: funciona com AWS, MinIO, Backblaze, etc.
+                let base = format!("{}{}/{}", with_trailing_slash(endpoint), bucket, prefix);
+                Ok(Remote::S3 {
+                    base: Url::parse(&with_trailing_slash(&base)).context("Invalid sync.endpoint")?,
+                    region: region.clone(),
+                    access_key: env::var("AWS_ACCESS_KEY_ID")
+                        .context("AWS_ACCESS_KEY_ID not set in .env")?,
+                    secret_key: env::var("AWS_SECRET_ACCESS_KEY")
+                        .context("AWS_SECRET_ACCESS_KEY not set in .env")?,
+                })
+            }
+        }
+    }
+
+    fn request(&self, client: &Client, method: &str, name: &str, body: Vec<u8>) -> Result<reqwest::RequestBuilder> {
+        let (Remote::Webdav { base, .. } | Remote::S3 { base, .. }) = self;
+        let url = base.join(name)?;
+        let builder = client.request(method.parse()?, url.clone());
+
+        Ok(match self {
+            Remote::Webdav { user, pass, .. } => builder.basic_auth(user, Some(pass)).body(body),
+            Remote::S3 { region, access_key, secret_key, .. } => {
+                let headers = sigv4_headers(method, &url, region, access_key, secret_key, &body, Utc::now());
+                headers
+                    .into_iter()
+                    .fold(builder, |b, (k, v)| b.header(k, v))
+                    .body(body)
+            }
+        })
+    }
+
+    async fn get(&self, client: &Client, name: &str) -> Result<Option<Vec<u8>>> {
+        let resp = self.request(client, "GET", name, vec![])?.send().await?;
+        match resp.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            s if s.is_success() => Ok(Some(resp.bytes().await?.to_vec())),
+            s => bail!("GET {} failed: {}", name, s),
+        }
+    }
+
+    async fn put(&self, client: &Client, name: &str, body: Vec<u8>) -> Result<()> {
+        let resp = self.request(client, "PUT", name, body)?.send().await?;
+        if !resp.status().is_success() {
+            bail!("PUT {} failed: {}", name, resp.status());
+        }
+        Ok(())
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k_date = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date);
+    let k_region = hmac_sha256(&k_date, region);
+    let k_service = hmac_sha256(&k_region, service);
+    hmac_sha256(&k_service, "aws4_request")
+}
+
+/// Cabeçalhos de autenticação AWS Signature V4 para um pedido S3 sem query string.
+fn sigv4_headers(
+    method: &str,
+    url: &Url,
+    region: &str,
+    access_key: &str,
+    secret_key: &str,
+    payload: &[u8],
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex::encode(Sha256::digest(payload));
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or(""), port),
+        None => url.host_str().unwrap_or("").to_string(),
+    };
+
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        method,
+        url.path(),
+        host,
+        payload_hash,
+        amz_date,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let signature = hex::encode(hmac_sha256(
+        &signing_key(secret_key, &date, region, "s3"),
+        &string_to_sign,
+    ));
+
+    vec![
+        ("x-amz-date".to_string(), amz_date),
+        ("x-amz-content-sha256".to_string(), payload_hash),
+        (
+            "authorization".to_string(),
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                access_key, scope, signed_headers, signature
+            ),
+        ),
+    ]
+}
+
+// última mudança conhecida do registo: o envio, uma fase ou uma abertura
+fn last_change(record: &SentRecord) -> DateTime<Local> {
+    record
+        .status_history
+        .iter()
+        .map(|change| change.at)
+        .chain(record.opens.iter().copied())
+        .fold(record.sent_at, DateTime::max)
+}
+
+// o mesmo envio visto dos dois lados: fica o valor do mais recente e o que só
+// o outro tem; true se o local mudou
+fn merge_record(local: &mut SentRecord, remote: SentRecord) -> bool {
+    let before = serde_json::to_value(&*local).ok();
+    let (mut merged, older) = if last_change(&remote) > last_change(local) {
+        (remote, std::mem::take(local))
+    } else {
+        (std::mem::take(local), remote)
+    };
+    merged.status_history.extend(older.status_history);
+    merged.status_history.sort_by_key(|change| change.at);
+    merged.status_history.dedup();
+    if let Some(last) = merged.status_history.last() {
+        merged.status = last.status;
+    }
+    merged.opens.extend(older.opens);
+    merged.opens.sort();
+    merged.opens.dedup();
+    merged.cancelled |= older.cancelled;
+    merged.interview = merged.interview.or(older.interview);
+    merged.notes = merged.notes.or(older.notes);
+    merged.smtp_transcript = merged.smtp_transcript.or(older.smtp_transcript);
+    merged.message = merged.message.or(older.message);
+    merged.follow_up_after = merged.follow_up_after.or(older.follow_up_after);
+    merged.snoozed_until = merged.snoozed_until.or(older.snoozed_until);
+    merged.sequence_stop = merged.sequence_stop.or(older.sequence_stop);
+    *local = merged;
+    serde_json::to_value(&*local).ok() != before
+}
+
+/// Junta o log remoto ao local; devolve (registos novos, registos actualizados).
+pub fn merge_logs(local: &mut SentLog, remote: SentLog) -> (usize, usize) {
+    let (mut added, mut updated) = (0, 0);
+    for record in remote.records {
+        let existing = local
+            .records
+            .iter_mut()
+            .find(|r| r.sent_at == record.sent_at && r.email.eq_ignore_ascii_case(&record.email));
+        match existing {
+            Some(existing) => {
+                if merge_record(existing, record) {
+                    updated += 1;
+                }
+            }
+            None => {
+                local.records.push(record);
+                added += 1;
+            }
+        }
+    }
+    local.records.sort_by_key(|r| r.sent_at);
+    (added, updated)
+}
+
+pub fn merge_contacts(local: &mut ContactBook, remote: ContactBook) -> usize {
+    let mut added = 0;
+    for contact in remote.contacts {
+        if local.find(&contact.email).is_none() {
+            local.contacts.push(contact);
+            added += 1;
+        }
+    }
+    added
+}
+
+pub async fn run_sync(config: &Config) -> Result<()> {
+    let sync = config
+        .sync
+        .as_ref()
+        .context("No 'sync' section in config.json")?;
+    let remote = Remote::from_config(sync)?;
+    let client = Client::new();
+
+    let theirs: Option<SentLog> = match remote.get(&client, LOG_FILE).await? {
+        Some(bytes) => Some(
+            LOG_SCHEMA
+                .parse(&String::from_utf8_lossy(&bytes))
+                .context("Invalid remote sent_log.json")?,
+        ),
+        None => None,
+    };
+    let mut log = SentLog::default();
+    let (new_records, updated_records) =
+        update_log(&mut log, |log| Ok(theirs.map_or((0, 0), |theirs| merge_logs(log, theirs))))?;
+    remote.put(&client, LOG_FILE, LOG_SCHEMA.to_string(&log)?.into_bytes()).await?;
+
+    let mut book = load_contacts();
+    let mut new_contacts = 0;
+    if let Some(bytes) = remote.get(&client, CONTACTS_FILE).await? {
+        let theirs: ContactBook = INITIAL
+            .parse(&String::from_utf8_lossy(&bytes))
+            .context("Invalid remote contacts.json")?;
+        new_contacts = merge_contacts(&mut book, theirs);
+    }
+    save_contacts(&book)?;
+    remote.put(&client, CONTACTS_FILE, INITIAL.to_string(&book)?.into_bytes()).await?;
+    crate::git_history::record("sincronizado com o remoto");
+
+    say!(
+        "{} Sincronizado: {} registos novos, {} actualizados e {} contactos novos vindos do remoto",
+        CHECK,
+        style(new_records).cyan(),
+        style(updated_records).cyan(),
+        style(new_contacts).cyan()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{contacts::Contact, ApplicationStatus, StatusChange};
+    use chrono::{Local, TimeZone};
+
+    fn record(email: &str, minute: u32) -> SentRecord {
+        SentRecord {
+            email: email.to_string(),
+            sent_at: Local.with_ymd_and_hms(2024, 7, 1, 9, minute, 0).unwrap(),
+            success: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_signing_key_matches_aws_example() {
+        // exemplo da documentação AWS (serviço iam)
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_sigv4_headers() {
+        let url = Url::parse("http://localhost:9000/bucket/sent_log.json").unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 7, 1, 9, 0, 0).unwrap();
+        let headers = sigv4_headers("PUT", &url, "eu-west-1", "AKID", "SECRET", b"{}", now);
+
+        assert_eq!(headers[0], ("x-amz-date".to_string(), "20240701T090000Z".to_string()));
+        assert!(headers[2]
+            .1
+            .starts_with("AWS4-HMAC-SHA256 Credential=AKID/20240701/eu-west-1/s3/aws4_request"));
+    }
+
+    #[test]
+    fn test_merge_logs_is_idempotent() {
+        let mut local = SentLog { records: vec![record("a@x.com", 0), record("b@x.com", 5)] };
+        let remote = SentLog { records: vec![record("A@x.com", 0), record("c@x.com", 2)] };
+
+        assert_eq!(merge_logs(&mut local, remote), (1, 0));
+        let emails: Vec<&str> = local.records.iter().map(|r| r.email.as_str()).collect();
+        assert_eq!(emails, vec!["a@x.com", "c@x.com", "b@x.com"]);
+
+        let again = SentLog { records: local.records.clone() };
+        assert_eq!(merge_logs(&mut local, again), (0, 0));
+    }
+
+    #[test]
+    fn test_merge_logs_updates_matching_records() {
+        let mut local = SentLog { records: vec![record("a@x.com", 0)] };
+        local.records[0].notes = Some("pediu pretensões".to_string());
+        let mut remote = SentLog { records: vec![record("a@x.com", 0)] };
+        let replied = Local.with_ymd_and_hms(2024, 7, 2, 10, 0, 0).unwrap();
+        remote.records[0].status = ApplicationStatus::Replied;
+        remote.records[0].status_history = vec![StatusChange { at: replied, status: ApplicationStatus::Replied }];
+        remote.records[0].opens = vec![replied];
+
+        assert_eq!(merge_logs(&mut local, remote.clone()), (0, 1));
+        let merged = &local.records[0];
+        assert_eq!(merged.status, ApplicationStatus::Replied);
+        assert_eq!(merged.opens, vec![replied]);
+        // o remoto é mais recente mas não tem notas: ficam as locais
+        assert_eq!(merged.notes.as_deref(), Some("pediu pretensões"));
+
+        assert_eq!(merge_logs(&mut local, remote), (0, 0));
+    }
+
+    #[test]
+    fn test_merge_contacts_keeps_local_version() {
+        let mut local = ContactBook {
+            contacts: vec![Contact {
+                email: "rh@acme.com".to_string(),
+                company: Some("Acme".to_string()),
+                ..Default::default()
+            }],
+        };
+        let remote = ContactBook {
+            contacts: vec![
+                Contact {
+                    email: "rh@acme.com".to_string(),
+                    company: Some("Old".to_string()),
+                    ..Default::default()
+                },
+                Contact {
+                    email: "jobs@globex.com".to_string(),
+                    ..Default::default()
+                },
+            ],
+        };
+
+        assert_eq!(merge_contacts(&mut local, remote), 1);
+        assert_eq!(local.find("rh@acme.com").unwrap().company.as_deref(), Some("Acme"));
+    }
+}
@@ -0,0 +1,419 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use console::style;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, process::Command};
+
+use crate::{enrich, git_history, load_config, merge, EmailTemplate, CHECK, CONFIG_FILE, CROSS, NOTE};
+
+// versão do formato; um ficheiro de uma versão mais recente é recusado
+pub const TEMPLATE_FORMAT: u32 = 1;
+
+/// Um template isolado para partilhar: assunto, corpos (com as línguas) e
+/// alguns metadados, sem nada do resto da config.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateFile {
+    pub job_mailer_template: u32,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    // área a que se destina ("tecnologia", "saúde"), para os catálogos remotos
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub industry: Option<String>,
+    pub exported_at: DateTime<Local>,
+    // só informativo: as variáveis que o template usa além das do perfil
+    #[serde(default)]
+    pub columns: Vec<String>,
+    pub template: EmailTemplate,
+}
+
+// variáveis que não vêm do perfil e têm de vir de uma coluna do mail-merge
+fn merge_columns(template: &EmailTemplate) -> Vec<String> {
+    let mut text = format!("{}\n{}\n{}", template.subject, template.body, template.html.as_deref().unwrap_or_default());
+    for subject in &template.subjects {
+        text.push_str(&format!("\n{}", subject));
+    }
+    for variant in template.languages.values() {
+        let html = variant.html.as_deref().unwrap_or_default();
+        text.push_str(&format!("\n{}\n{}\n{}", variant.subject, variant.body, html));
+    }
+    merge::template_vars(&text)
+        .into_iter()
+        .filter(|v| !merge::BUILTIN_VARS.contains(&v.as_str()))
+        .collect()
+}
+
+pub fn export_file(
+    name: &str,
+    template: &EmailTemplate,
+    author: &str,
+    description: Option<String>,
+    industry: Option<String>,
+) -> TemplateFile {
+    TemplateFile {
+        job_mailer_template: TEMPLATE_FORMAT,
+        name: name.to_string(),
+        description,
+        author: (!author.is_empty()).then(|| author.to_string()),
+        industry,
+        exported_at: Local::now(),
+        columns: merge_columns(template),
+        template: template.clone(),
+    }
+}
+
+pub fn parse_file(content: &str) -> Result<TemplateFile> {
+    let file: TemplateFile = serde_json::from_str(content).context("Invalid template file")?;
+    if file.job_mailer_template > TEMPLATE_FORMAT {
+        bail!(
+            "Template file format {} is newer than this version supports ({})",
+            file.job_mailer_template,
+            TEMPLATE_FORMAT
+        );
+    }
+    if file.name.trim().is_empty() {
+        bail!("Template file has no name");
+    }
+    Ok(file)
+}
+
+// mexe só no template (o principal com None, senão em `templates`); o resto do
+// config.json fica como estava, incluindo a ordem
+fn insert_into_config(config_json: &str, name: Option<&str>, template: &EmailTemplate) -> Result<String> {
+    let mut config: serde_json::Value = serde_json::from_str(config_json).context("Invalid config.json")?;
+    let root = config.as_object_mut().context("Invalid config.json")?;
+    let value = serde_json::to_value(template)?;
+    match name {
+        None => {
+            root.insert("template".to_string(), value);
+        }
+        Some(name) => {
+            root.entry("templates")
+                .or_insert_with(|| serde_json::json!({}))
+                .as_object_mut()
+                .context("Invalid templates in config.json")?
+                .insert(name.to_string(), value);
+        }
+    }
+    Ok(serde_json::to_string_pretty(&config)?)
+}
+
+/// Grava o template no config.json sem tocar no resto.
+pub fn save_template(name: Option<&str>, template: &EmailTemplate) -> Result<()> {
+    let path = crate::overrides::path(CONFIG_FILE);
+    let config_json = fs::read_to_string(&path).context("config.json not found")?;
+    crate::write_atomic(&path, insert_into_config(&config_json, name, template)?)
+}
+
+pub fn run_export(
+    name: &str,
+    output: Option<String>,
+    description: Option<String>,
+    industry: Option<String>,
+) -> Result<()> {
+    let config = load_config()?;
+    let template = config
+        .templates
+        .get(name)
+        .with_context(|| format!("Template '{}' not found in templates", name))?;
+    let file = export_file(name, template, &config.profile.name, description, industry);
+    let path = output.unwrap_or_else(|| format!("{}.template.json", name));
+    fs::write(&path, serde_json::to_string_pretty(&file)?).with_context(|| format!("Cannot write {}", path))?;
+    say!("{} Template {} exportado para {}", CHECK, style(name).cyan(), style(&path).green());
+    Ok(())
+}
+
+pub fn run_import(path: &str, rename: Option<String>, force: bool) -> Result<()> {
+    let content = fs::read_to_string(path).with_context(|| format!("{} not found", path))?;
+    install(parse_file(&content)?, rename, force)
+}
+
+fn install(file: TemplateFile, rename: Option<String>, force: bool) -> Result<()> {
+    let name = rename.unwrap_or_else(|| file.name.clone());
+    let config = load_config()?;
+    if config.templates.contains_key(&name) && !force {
+        bail!("Template '{}' already exists (use --name or --force)", name);
+    }
+
+    save_template(Some(&name), &file.template)?;
+    git_history::record(&format!("template {} importado", name));
+
+    say!("{} Template {} importado", CHECK, style(&name).cyan());
+    if let Some(author) = &file.author {
+        say!("   de {} ({})", author, file.exported_at.format("%d/%m/%Y"));
+    }
+    if let Some(description) = &file.description {
+        say!("   {}", description);
+    }
+    if !file.template.languages.is_empty() {
+        let languages: Vec<&str> = file.template.languages.keys().map(String::as_str).collect();
+        say!("   línguas: {}", languages.join(", "));
+    }
+    let columns = merge_columns(&file.template);
+    if !columns.is_empty() {
+        say!("{} Precisa das colunas do mail-merge: {}", NOTE, style(columns.join(", ")).yellow());
+    }
+    Ok(())
+}
+
+/// Catálogo HTTPS: um JSON com os ficheiros relativos ao próprio índice.
+#[derive(Debug, Deserialize)]
+struct Index {
+    templates: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    file: String,
+}
+
+// um repositório git (URL ou pasta local) em vez de um índice HTTPS
+fn is_git_source(source: &str) -> bool {
+    source.ends_with(".git")
+        || source.starts_with("git@")
+        || source.starts_with("git://")
+        || source.starts_with("ssh://")
+        || Path::new(source).join(".git").exists()
+}
+
+fn index_urls(index_url: &Url, index_json: &str) -> Result<Vec<Url>> {
+    let index: Index = serde_json::from_str(index_json).context("Invalid template index")?;
+    index
+        .templates
+        .iter()
+        .map(|t| index_url.join(&t.file).with_context(|| format!("Invalid template path in index: {}", t.file)))
+        .collect()
+}
+
+async fn get_text(client: &reqwest::Client, url: &Url) -> Result<String> {
+    let resp = client.get(url.clone()).send().await?.error_for_status()?;
+    resp.text().await.with_context(|| format!("Cannot read {}", url))
+}
+
+async fn fetch_index(source: &str) -> Result<Vec<TemplateFile>> {
+    let url = Url::parse(source).with_context(|| format!("Invalid template index URL: {}", source))?;
+    let client = enrich::http_client();
+    let index = get_text(&client, &url).await.with_context(|| format!("Cannot fetch {}", source))?;
+    let mut files = vec![];
+    for file_url in index_urls(&url, &index)? {
+        match get_text(&client, &file_url).await.and_then(|content| parse_file(&content)) {
+            Ok(file) => files.push(file),
+            Err(e) => eprintln!("{} {}: {:#}", CROSS, file_url, e),
+        }
+    }
+    Ok(files)
+}
+
+// todos os *.template.json do repositório, por ordem de caminho; os links
+// simbólicos ficam de fora (o repositório é de terceiros: `a -> .` não acabava
+// e `b -> /` lia o disco todo)
+fn template_files_in(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut found = vec![];
+    let Ok(entries) = fs::read_dir(dir) else {
+        return found;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(kind) = entry.file_type() else {
+            continue;
+        };
+        if kind.is_symlink() || path.file_name().is_some_and(|n| n == ".git") {
+            continue;
+        }
+        if kind.is_dir() {
+            found.extend(template_files_in(&path));
+        } else if kind.is_file() && path.to_string_lossy().ends_with(".template.json") {
+            found.push(path);
+        }
+    }
+    found.sort();
+    found
+}
+
+fn fetch_git(source: &str) -> Result<Vec<TemplateFile>> {
+    // "--upload-pack=..." seria uma opção do git, não um repositório
+    if source.starts_with('-') {
+        bail!("Invalid git source: {}", source);
+    }
+    let dir = tempfile::TempDir::new()?;
+    let out = Command::new("git")
+        .args(["clone", "-q", "--depth", "1", "--", source])
+        .arg(dir.path())
+        .output()
+        .context("git not found in PATH")?;
+    if !out.status.success() {
+        bail!("git clone failed: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    let mut files = vec![];
+    for path in template_files_in(dir.path()) {
+        let relative = path.strip_prefix(dir.path()).unwrap_or(&path).display().to_string();
+        match fs::read_to_string(&path).map_err(anyhow::Error::from).and_then(|c| parse_file(&c)) {
+            Ok(file) => files.push(file),
+            Err(e) => eprintln!("{} {}: {:#}", CROSS, relative, e),
+        }
+    }
+    Ok(files)
+}
+
+pub async fn fetch(source: &str) -> Result<Vec<TemplateFile>> {
+    if is_git_source(source) {
+        fetch_git(source)
+    } else {
+        fetch_index(source).await
+    }
+}
+
+/// Mostra os templates de um catálogo (repositório git ou índice HTTPS) e, com
+/// `install`, junta um deles ao config.json como o `template import`.
+pub async fn run_fetch(
+    source: &str,
+    industry: Option<String>,
+    install_name: Option<String>,
+    rename: Option<String>,
+    force: bool,
+) -> Result<()> {
+    let mut files = fetch(source).await?;
+    if let Some(industry) = &industry {
+        files.retain(|f| f.industry.as_deref().is_some_and(|i| i.eq_ignore_ascii_case(industry)));
+    }
+    if let Some(name) = install_name {
+        let file = files
+            .into_iter()
+            .find(|f| f.name == name)
+            .with_context(|| format!("Template '{}' not found in {}", name, source))?;
+        return install(file, rename, force);
+    }
+
+    if files.is_empty() {
+        println!("{} Nenhum template em {}", CROSS, source);
+        return Ok(());
+    }
+    println!("{} Templates em {} ({}):", NOTE, source, files.len());
+    println!("{}", style("─".repeat(60)).dim());
+    for f in &files {
+        let industry = f.industry.as_deref().map(|i| format!(" [{}]", i)).unwrap_or_default();
+        println!("  {}{} - {}", style(&f.name).cyan(), style(industry).dim(), f.template.subject);
+        if let Some(description) = &f.description {
+            println!("      {}", description);
+        }
+        if !f.columns.is_empty() {
+            println!("      colunas: {}", style(f.columns.join(", ")).yellow());
+        }
+    }
+    println!("{}", style("─".repeat(60)).dim());
+    println!("Para instalar: template fetch {} --install <nome>", source);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_and_import_round_trip() {
+        let template = EmailTemplate {
+            subject: "Candidatura - {{role}}".to_string(),
+            body: "Olá {{hiring_manager}}, sou {{name}}".to_string(),
+            ..Default::default()
+        };
+        let file = export_file("reply-to-posting", &template, "João", Some("Resposta a anúncio".to_string()), None);
+        assert_eq!(file.columns, vec!["hiring_manager", "role"]);
+
+        let parsed = parse_file(&serde_json::to_string(&file).unwrap()).unwrap();
+        assert_eq!(parsed.name, "reply-to-posting");
+        assert_eq!(parsed.template.subject, template.subject);
+        assert_eq!(parsed.author.as_deref(), Some("João"));
+
+        let newer = serde_json::to_string(&file).unwrap().replace("\"job_mailer_template\":1", "\"job_mailer_template\":2");
+        assert!(parse_file(&newer).is_err());
+    }
+
+    #[test]
+    fn test_insert_keeps_the_rest_of_the_config() {
+        let config = r#"{"profile": {"name": "Ana"}, "smtp": {"port": 587}, "template": {"subject": "a", "body": "b"}}"#;
+        let template = EmailTemplate { subject: "s".to_string(), body: "b".to_string(), ..Default::default() };
+        let updated = insert_into_config(config, Some("follow-up-1"), &template).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&updated).unwrap();
+        let keys: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(keys, vec!["profile", "smtp", "template", "templates"]);
+        assert_eq!(value["templates"]["follow-up-1"]["subject"], "s");
+        assert_eq!(value["profile"]["name"], "Ana");
+
+        let updated = insert_into_config(&updated, None, &template).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(value["template"]["subject"], "s");
+        assert_eq!(value["templates"]["follow-up-1"]["subject"], "s");
+    }
+
+    #[test]
+    fn test_index_paths_are_relative_to_the_index() {
+        let url = Url::parse("https://example.org/templates/index.json").unwrap();
+        let index = r#"{"templates": [{"file": "tech/spontaneous.template.json"}, {"file": "/abs/x.template.json"}]}"#;
+        let urls: Vec<String> = index_urls(&url, index).unwrap().iter().map(Url::to_string).collect();
+        assert_eq!(urls, vec![
+            "https://example.org/templates/tech/spontaneous.template.json",
+            "https://example.org/abs/x.template.json"
+        ]);
+        assert!(index_urls(&url, "[]").is_err());
+        assert!(is_git_source("https://github.com/x/templates.git"));
+        assert!(!is_git_source("https://example.org/templates/index.json"));
+    }
+
+    #[test]
+    fn test_fetch_from_git_repository() {
+        let repo = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let out = Command::new("git").args(args).current_dir(repo.path()).output().unwrap();
+            assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        };
+        let template = EmailTemplate { subject: "Candidatura - {{title}}".to_string(), ..Default::default() };
+        let file = export_file("spontaneous", &template, "", None, Some("tecnologia".to_string()));
+        fs::create_dir(repo.path().join("tech")).unwrap();
+        fs::write(repo.path().join("tech/spontaneous.template.json"), serde_json::to_string(&file).unwrap()).unwrap();
+        fs::write(repo.path().join("README.md"), "starter templates").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["-c", "user.name=t", "-c", "user.email=t@localhost", "commit", "-q", "-m", "templates"]);
+
+        let source = repo.path().to_string_lossy().to_string();
+        assert!(is_git_source(&source));
+        let files = fetch_git(&source).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "spontaneous");
+        assert_eq!(files[0].industry.as_deref(), Some("tecnologia"));
+
+        let err = fetch_git("--upload-pack=touch pwned;x.git").unwrap_err();
+        assert!(err.to_string().contains("Invalid git source"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fetch_skips_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let repo = tempfile::TempDir::new().unwrap();
+        let outside = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let out = Command::new("git").args(args).current_dir(repo.path()).output().unwrap();
+            assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        };
+        let file = export_file("spontaneous", &EmailTemplate::default(), "", None, None);
+        let json = serde_json::to_string(&file).unwrap();
+        fs::write(repo.path().join("spontaneous.template.json"), &json).unwrap();
+        fs::write(outside.path().join("secret.template.json"), &json).unwrap();
+        // `a -> .` e `b -> .` cresciam sem fim; os outros saem do clone
+        symlink(".", repo.path().join("a")).unwrap();
+        symlink(".", repo.path().join("b")).unwrap();
+        symlink(outside.path(), repo.path().join("fora")).unwrap();
+        symlink(outside.path().join("secret.template.json"), repo.path().join("link.template.json")).unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["-c", "user.name=t", "-c", "user.email=t@localhost", "commit", "-q", "-m", "templates"]);
+
+        let files = fetch_git(&repo.path().to_string_lossy()).unwrap();
+        assert_eq!(files.len(), 1);
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use console::style;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::{
+    merge::escape_html,
+    server::{read_request, respond},
+    update_log, SentLog, CROSS, MAIL,
+};
+
+/// Pixel de abertura (opt-in): com esta secção cada email leva uma versão
+/// HTML com uma imagem de 1x1 cujo endereço identifica o envio.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackingConfig {
+    // endereço público do pixel, ex.: "https://t.joaosilva.dev/o/{{id}}.gif"
+    pub url: String,
+    // onde o `job-mailer track` fica à escuta (atrás de um proxy ou túnel)
+    #[serde(default = "default_listen")]
+    pub listen: String,
+}
+
+fn default_listen() -> String {
+    "127.0.0.1:8787".to_string()
+}
+
+// GIF transparente de 1x1
+const PIXEL: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff,
+    0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02,
+    0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+// 8 bytes aleatórios em hex: 16 caracteres
+const ID_LEN: usize = 16;
+
+pub fn new_id() -> String {
+    hex::encode(rand::thread_rng().gen::<[u8; ID_LEN / 2]>())
+}
+
+// sem `{{id}}` no url vai como parâmetro, para um colector externo qualquer
+pub fn pixel_url(config: &TrackingConfig, id: &str) -> String {
+    if config.url.contains("{{id}}") {
+        config.url.replace("{{id}}", id)
+    } else {
+        crate::links::tag_url(&config.url, &format!("id={}", id))
+    }
+}
+
+pub fn pixel_img(pixel: &str) -> String {
+    format!("<img src=\"{}\" width=\"1\" height=\"1\" alt=\"\" style=\"display: block\">", escape_html(pixel))
+}
+
+/// O corpo em texto como HTML (quebras de linha preservadas) com o pixel no fim.
+pub fn html_body(text: &str, pixel: &str) -> String {
+    format!(
+        "<html><body><div style=\"white-space: pre-wrap; font-family: sans-serif\">{}</div>{}</body></html>",
+        escape_html(text),
+        pixel_img(pixel)
+    )
+}
+
+/// Acrescenta ao fim do documento, antes do `</body>` se existir.
+pub fn append_html(html: &str, extra: &str) -> String {
+    match html.to_ascii_lowercase().rfind("</body>") {
+        Some(at) => format!("{}{}{}", &html[..at], extra, &html[at..]),
+        None => format!("{}{}", html, extra),
+    }
+}
+
+/// O id de um pedido ao colector: "/o/<id>.gif" ou "...?id=<id>".
+pub fn open_id(path: &str) -> Option<String> {
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let from_query = query.split('&').find_map(|p| p.strip_prefix("id="));
+    let from_path = path.rsplit('/').next().map(|s| s.trim_end_matches(".gif"));
+    let id = from_query.or(from_path)?;
+    // só ids como os do new_id: "/favicon.ico" ou "/cafe" não são aberturas
+    (id.len() == ID_LEN && id.chars().all(|c| c.is_ascii_hexdigit())).then(|| id.to_lowercase())
+}
+
+/// Regista a abertura no envio com este id; devolve o destinatário.
+pub fn record_open(log: &mut SentLog, id: &str) -> Option<String> {
+    let record = log
+        .records
+        .iter_mut()
+        .rev()
+        .find(|r| r.message.as_ref().is_some_and(|m| m.track_id.as_deref() == Some(id)))?;
+    record.opens.push(Local::now());
+    Some(record.email.clone())
+}
+
+/// Colector HTTP mínimo: responde sempre com o pixel e regista as aberturas no log.
+pub async fn serve(config: &TrackingConfig, listen: Option<&str>) -> Result<()> {
+    let addr = listen.unwrap_or(&config.listen);
+    let listener = TcpListener::bind(addr).await.with_context(|| format!("Cannot listen on {}", addr))?;
+    say!("{} À escuta em {} (Ctrl-C para sair)", MAIL, style(addr).cyan());
+    loop {
+        let (stream, _) = listener.accept().await?;
+        // cada ligação à parte: um cliente lento não atrasa as outras aberturas
+        tokio::spawn(handle(stream));
+    }
+}
+
+async fn handle(mut stream: TcpStream) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    if let Some(id) = (request.method == "GET").then(|| open_id(&request.target)).flatten() {
+        // um log bloqueado ou ilegível perde esta abertura, não o colector
+        match update_log(&mut SentLog::default(), |log| Ok(record_open(log, &id))) {
+            Ok(Some(email)) => {
+                say!("   {} aberto por {}", style(Local::now().format("%H:%M")).dim(), style(email).green());
+            }
+            Ok(None) => verbose!("pixel desconhecido: {}", id),
+            Err(e) => eprintln!("{} Abertura {} não registada: {:#}", CROSS, id, e),
+        }
+    }
+    respond(&mut stream, 200, "image/gif", PIXEL).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SentMessage, SentRecord};
+
+    #[test]
+    fn test_pixel_url_and_open_id() {
+        let mut config = TrackingConfig { url: "https://t.dev/o/{{id}}.gif".to_string(), listen: default_listen() };
+        assert_eq!(pixel_url(&config, "ab12"), "https://t.dev/o/ab12.gif");
+        config.url = "https://t.dev/p?src=mail".to_string();
+        assert_eq!(pixel_url(&config, "ab12"), "https://t.dev/p?src=mail&id=ab12");
+
+        assert_eq!(open_id("/o/0A1B2C3D4E5F6A7B.gif").as_deref(), Some("0a1b2c3d4e5f6a7b"));
+        assert_eq!(open_id("/p?src=mail&id=0a1b2c3d4e5f6a7b").as_deref(), Some("0a1b2c3d4e5f6a7b"));
+        assert_eq!(open_id(&format!("/o/{}.gif", new_id())).map(|id| id.len()), Some(ID_LEN));
+        assert_eq!(open_id("/favicon.ico"), None);
+        assert_eq!(open_id("/cafe"), None);
+        assert_eq!(open_id("/o/0a1b2c3d4e5f6a7b8c.gif"), None);
+        assert_eq!(open_id("/"), None);
+    }
+
+    #[test]
+    fn test_html_body_escapes_text() {
+        let html = html_body("Olá <RH>,\nC & C", "https://t.dev/o/1.gif");
+        assert!(html.contains("Olá &lt;RH&gt;,\nC &amp; C"));
+        assert!(html.contains("<img src=\"https://t.dev/o/1.gif\" width=\"1\" height=\"1\""));
+    }
+
+    #[test]
+    fn test_record_open_latest_send() {
+        let sent = |email: &str, id: Option<&str>| SentRecord {
+            email: email.to_string(),
+            success: true,
+            message: Some(SentMessage { track_id: id.map(str::to_string), ..Default::default() }),
+            ..Default::default()
+        };
+        let mut log = SentLog { records: vec![sent("a@x.com", Some("ab")), sent("b@x.com", None), sent("a@x.com", Some("ab"))] };
+        assert_eq!(record_open(&mut log, "ab").as_deref(), Some("a@x.com"));
+        assert!(log.records[0].opens.is_empty());
+        assert_eq!(log.records[2].opens.len(), 1);
+        assert_eq!(record_open(&mut log, "cd"), None);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::contacts::Contact;
+
+// linhas dobradas: a que começa por espaço ou tab continua a anterior; no
+// quoted-printable do vCard 2.1 um "=" no fim também
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut soft_break = false;
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        match lines.last_mut() {
+            Some(last) if soft_break => last.push_str(line),
+            Some(last) if line.starts_with([' ', '\t']) => last.push_str(&line[1..]),
+            _ => lines.push(line.to_string()),
+        }
+        soft_break = line.ends_with('=') && lines.last().is_some_and(|l| l.to_uppercase().contains("QUOTED-PRINTABLE"));
+        if soft_break {
+            lines.last_mut().unwrap().pop();
+        }
+    }
+    lines
+}
+
+// "Jo=C3=A3o" → "João"
+fn quoted_printable(value: &str) -> String {
+    let mut bytes = vec![];
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        let hex = tail.get(..2).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(decoded) if b == b'=' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn unescape(value: &str) -> String {
+    value.replace("\\n", " ").replace("\\N", " ").replace("\\,", ",").replace("\\;", ";").replace("\\\\", "\\")
+}
+
+struct Property {
+    name: String,
+    params: String,
+    value: String,
+}
+
+// "item1.EMAIL;TYPE=WORK:rh@acme.com" → EMAIL, TYPE=WORK, rh@acme.com
+fn property(line: &str) -> Option<Property> {
+    let (key, value) = line.split_once(':')?;
+    let (name, params) = key.split_once(';').unwrap_or((key, ""));
+    let name = name.rsplit('.').next().unwrap_or(name).to_uppercase();
+    let params = params.to_uppercase();
+    let value = if params.contains("QUOTED-PRINTABLE") { quoted_printable(value) } else { value.to_string() };
+    Some(Property { name, params, value: unescape(value.trim()) })
+}
+
+/// Contactos de um ficheiro .vcf com um ou vários cartões (FN, EMAIL, ORG,
+/// TITLE). Com vários emails fica o preferido, ou o de trabalho, ou o
+/// primeiro; os cartões sem email contam como inválidos.
+pub fn parse_vcards(content: &str) -> (Vec<Contact>, usize) {
+    let (mut contacts, mut invalid) = (vec![], 0);
+    let mut card: Option<Vec<Property>> = None;
+    for line in unfold(content) {
+        let upper = line.trim().to_uppercase();
+        if upper == "BEGIN:VCARD" {
+            card = Some(vec![]);
+        } else if upper == "END:VCARD" {
+            match card.take().and_then(|props| contact(&props)) {
+                Some(contact) => contacts.push(contact),
+                None => invalid += 1,
+            }
+        } else if let Some(props) = card.as_mut() {
+            props.extend(property(&line));
+        }
+    }
+    (contacts, invalid)
+}
+
+fn contact(props: &[Property]) -> Option<Contact> {
+    let value = |name: &str| props.iter().find(|p| p.name == name).map(|p| p.value.clone()).filter(|v| !v.is_empty());
+    let emails: Vec<&Property> = props.iter().filter(|p| p.name == "EMAIL" && p.value.contains('@')).collect();
+    let email = emails
+        .iter()
+        .find(|p| p.params.contains("PREF"))
+        .or_else(|| emails.iter().find(|p| p.params.contains("WORK")))
+        .or(emails.first())?;
+    // ORG é "empresa;departamento"
+    let company = value("ORG").and_then(|org| org.split(';').next().map(|o| o.trim().to_string())).filter(|o| !o.is_empty());
+    Some(Contact {
+        email: email.value.clone(),
+        name: value("FN"),
+        company,
+        title: value("TITLE"),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vcards() {
+        let vcf = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Joana Silva\r\nORG:Acme\\, Lda;Recursos Humanos\r\nTITLE:Talent Acquisition\r\n\
+                   EMAIL;TYPE=HOME:joana@gmail.com\r\nitem1.EMAIL;TYPE=INTERNET,WORK:joana.silva@acme.com\r\nEND:VCARD\r\n\
+                   BEGIN:VCARD\nVERSION:2.1\nFN;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE:Jo=C3=A3o Ferr=\n=C3=A3o\nEMAIL;PREF;INTERNET:joao@globex.com\n\
+                   EMAIL:outro@globex.com\nEND:VCARD\n\
+                   BEGIN:VCARD\nVERSION:3.0\nFN:Só telefone\nTEL:+351 900 000 000\nEND:VCARD\n\
+                   BEGIN:VCARD\nVERSION:4.0\nFN:Ana\n  Costa\nEMAIL:ana@initech.com\nEND:VCARD\n";
+        let (contacts, invalid) = parse_vcards(vcf);
+        assert_eq!(invalid, 1);
+        assert_eq!(contacts.len(), 3);
+        assert_eq!(contacts[0].email, "joana.silva@acme.com");
+        assert_eq!(contacts[0].name.as_deref(), Some("Joana Silva"));
+        assert_eq!(contacts[0].company.as_deref(), Some("Acme, Lda"));
+        assert_eq!(contacts[0].title.as_deref(), Some("Talent Acquisition"));
+        assert_eq!(contacts[1].email, "joao@globex.com");
+        assert_eq!(contacts[1].name.as_deref(), Some("João Ferrão"));
+        assert_eq!(contacts[2].name.as_deref(), Some("Ana Costa"));
+        assert_eq!(contacts[2].company, None);
+    }
+}
@@ -0,0 +1,480 @@
+use anyhow::{bail, Context, Result};
+use console::style;
+use rand::Rng;
+use std::{fs, net::SocketAddr, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpStream, UdpSocket},
+    time::timeout,
+};
+
+use crate::{
+    contacts::{ascii_domain, load_contacts},
+    enrich::email_domain,
+    CHECK, CROSS, MAIL,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// O que o servidor do domínio diz de um endereço, sem enviar nada.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Valid,
+    Invalid(String),
+    // o domínio aceita qualquer endereço: o "sim" não quer dizer que a caixa exista
+    Unverifiable,
+    Unknown(String),
+}
+
+impl Verdict {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::Valid => "válido",
+            Verdict::Invalid(_) => "não existe",
+            Verdict::Unverifiable => "não verificável (catch-all)",
+            Verdict::Unknown(_) => "desconhecido",
+        }
+    }
+}
+
+// primeiro `nameserver` do resolv.conf; sem ele (Windows) um resolver público
+fn nameserver() -> SocketAddr {
+    fs::read_to_string("/etc/resolv.conf")
+        .ok()
+        .and_then(|conf| {
+            conf.lines()
+                .filter_map(|l| l.trim().strip_prefix("nameserver"))
+                .find_map(|ip| ip.trim().parse::<std::net::IpAddr>().ok())
+        })
+        .map(|ip| SocketAddr::new(ip, 53))
+        .unwrap_or_else(|| "1.1.1.1:53".parse().unwrap())
+}
+
+const MX: u16 = 15;
+const SRV: u16 = 33;
+
+fn query(id: u16, domain: &str, kind: u16) -> Vec<u8> {
+    // cabeçalho: id, recursão pedida, uma pergunta
+    let mut packet = id.to_be_bytes().to_vec();
+    packet.extend([0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in domain.trim_end_matches('.').split('.') {
+        packet.push(label.len() as u8);
+        packet.extend(label.as_bytes());
+    }
+    // fim do nome, QTYPE, QCLASS IN
+    packet.push(0);
+    packet.extend(kind.to_be_bytes());
+    packet.extend([0, 1]);
+    packet
+}
+
+// nome com compressão; devolve o nome e onde acaba no sítio onde começou
+fn read_name(packet: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels = vec![];
+    let mut end = None;
+    for _ in 0..64 {
+        let len = *packet.get(pos)? as usize;
+        match len {
+            0 => {
+                return Some((labels.join("."), end.unwrap_or(pos + 1)));
+            }
+            l if l & 0xC0 == 0xC0 => {
+                let target = ((l & 0x3F) << 8) | *packet.get(pos + 1)? as usize;
+                end.get_or_insert(pos + 2);
+                pos = target;
+            }
+            l => {
+                labels.push(String::from_utf8_lossy(packet.get(pos + 1..pos + 1 + l)?).into_owned());
+                pos += 1 + l;
+            }
+        }
+    }
+    None
+}
+
+fn read_u16(packet: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*packet.get(pos)?, *packet.get(pos + 1)?]))
+}
+
+/// Onde começam os dados de cada resposta do tipo `kind`.
+fn answers(id: u16, packet: &[u8], kind: u16) -> Result<Vec<usize>> {
+    if read_u16(packet, 0) != Some(id) {
+        bail!("DNS reply for another query");
+    }
+    let rcode = packet.get(3).context("Short DNS reply")? & 0x0F;
+    if rcode == 3 {
+        bail!("domain does not exist");
+    }
+    if rcode != 0 {
+        bail!("DNS error (rcode {})", rcode);
+    }
+    let questions = read_u16(packet, 4).unwrap_or(0);
+    let count = read_u16(packet, 6).unwrap_or(0);
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = read_name(packet, pos).context("Invalid DNS reply")?.1 + 4;
+    }
+    let mut found = vec![];
+    for _ in 0..count {
+        let (_, after) = read_name(packet, pos).context("Invalid DNS reply")?;
+        let len = read_u16(packet, after + 8).context("Invalid DNS reply")? as usize;
+        if read_u16(packet, after).context("Invalid DNS reply")? == kind {
+            found.push(after + 10);
+        }
+        pos = after + 10 + len;
+    }
+    Ok(found)
+}
+
+/// Registos MX da resposta, por preferência.
+fn parse_mx(id: u16, packet: &[u8]) -> Result<Vec<String>> {
+    let mut hosts = vec![];
+    for data in answers(id, packet, MX)? {
+        let preference = read_u16(packet, data).context("Invalid DNS reply")?;
+        let (host, _) = read_name(packet, data + 2).context("Invalid DNS reply")?;
+        hosts.push((preference, host));
+    }
+    hosts.sort();
+    Ok(hosts.into_iter().map(|(_, host)| host).filter(|h| !h.is_empty()).collect())
+}
+
+/// Registos SRV (anfitrião, porta), por prioridade; "." quer dizer "não há".
+fn parse_srv(id: u16, packet: &[u8]) -> Result<Vec<(String, u16)>> {
+    let mut targets = vec![];
+    for data in answers(id, packet, SRV)? {
+        let priority = read_u16(packet, data).context("Invalid DNS reply")?;
+        let port = read_u16(packet, data + 4).context("Invalid DNS reply")?;
+        let (host, _) = read_name(packet, data + 6).context("Invalid DNS reply")?;
+        targets.push((priority, host, port));
+    }
+    targets.sort();
+    Ok(targets.into_iter().filter(|(_, host, _)| !host.is_empty()).map(|(_, host, port)| (host, port)).collect())
+}
+
+async fn lookup(name: &str, kind: u16) -> Result<(u16, Vec<u8>)> {
+    lookup_at(nameserver(), name, kind).await
+}
+
+async fn lookup_at(server: SocketAddr, name: &str, kind: u16) -> Result<(u16, Vec<u8>)> {
+    let id = rand::thread_rng().gen();
+    let packet = query(id, name, kind);
+    // da família do servidor: um socket IPv4 não chega a um nameserver IPv6
+    let local: SocketAddr = if server.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" }.parse()?;
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(server).await?;
+    socket.send(&packet).await?;
+    let mut buf = [0u8; 1500];
+    let n = timeout(TIMEOUT, socket.recv(&mut buf)).await.context("DNS timeout")??;
+    // TC: não coube em UDP, a mesma pergunta vai por TCP
+    if buf.get(2).is_some_and(|flags| flags & 0x02 != 0) {
+        let reply = timeout(TIMEOUT, lookup_tcp(server, &packet)).await.context("DNS timeout")??;
+        return Ok((id, reply));
+    }
+    Ok((id, buf[..n].to_vec()))
+}
+
+// por TCP cada mensagem leva o tamanho à frente (RFC 1035 4.2.2)
+async fn lookup_tcp(server: SocketAddr, packet: &[u8]) -> Result<Vec<u8>> {
+    let mut stream = TcpStream::connect(server).await?;
+    let mut framed = (packet.len() as u16).to_be_bytes().to_vec();
+    framed.extend(packet);
+    stream.write_all(&framed).await?;
+    let len = stream.read_u16().await? as usize;
+    let mut reply = vec![0; len];
+    stream.read_exact(&mut reply).await?;
+    Ok(reply)
+}
+
+/// Servidores de email do domínio; sem MX, o próprio domínio (RFC 5321).
+pub async fn mx_hosts(domain: &str) -> Result<Vec<String>> {
+    let (id, packet) = lookup(domain, MX).await?;
+    let hosts = parse_mx(id, &packet)?;
+    Ok(if hosts.is_empty() { vec![domain.to_string()] } else { hosts })
+}
+
+/// `_submission._tcp.acme.com` e afins (RFC 6186).
+pub async fn srv_targets(name: &str) -> Result<Vec<(String, u16)>> {
+    let (id, packet) = lookup(name, SRV).await?;
+    parse_srv(id, &packet)
+}
+
+// resposta SMTP completa ("250-..." até "250 ..."), o texto de cada linha à parte
+pub async fn reply_lines<S: AsyncRead + Unpin>(stream: &mut BufReader<S>) -> Result<(u16, Vec<String>)> {
+    let mut text = vec![];
+    loop {
+        let mut line = String::new();
+        if timeout(TIMEOUT, stream.read_line(&mut line)).await.context("SMTP timeout")?? == 0 {
+            bail!("SMTP server closed the connection");
+        }
+        let line = line.trim_end();
+        let code = line.get(..3).and_then(|c| c.parse().ok()).context("Invalid SMTP reply")?;
+        text.push(line.get(4..).unwrap_or_default().to_string());
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok((code, text));
+        }
+    }
+}
+
+async fn reply<S: AsyncRead + Unpin>(stream: &mut BufReader<S>) -> Result<(u16, String)> {
+    let (code, text) = reply_lines(stream).await?;
+    Ok((code, text.join(" ")))
+}
+
+pub async fn send_line<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut BufReader<S>, line: &str) -> Result<()> {
+    stream.write_all(format!("{}\r\n", line).as_bytes()).await?;
+    Ok(stream.flush().await?)
+}
+
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut BufReader<S>, line: &str) -> Result<(u16, String)> {
+    send_line(stream, line).await?;
+    reply(stream).await
+}
+
+/// Pergunta pelos endereços com RCPT TO, sem DATA; um endereço inventado no
+/// mesmo domínio diz se o servidor aceita tudo.
+pub async fn probe<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    from: &str,
+    domain: &str,
+    addresses: &[String],
+) -> Result<Vec<Verdict>> {
+    let mut stream = BufReader::new(stream);
+    let (code, text) = reply(&mut stream).await?;
+    if code != 220 {
+        bail!("SMTP greeting {}: {}", code, text);
+    }
+    let helo = email_domain(from).unwrap_or("localhost");
+    if command(&mut stream, &format!("EHLO {}", helo)).await?.0 != 250 {
+        command(&mut stream, &format!("HELO {}", helo)).await?;
+    }
+    let (code, text) = command(&mut stream, &format!("MAIL FROM:<{}>", from)).await?;
+    if code != 250 {
+        bail!("MAIL FROM refused ({}): {}", code, text);
+    }
+
+    let mut verdicts = vec![];
+    for address in addresses {
+        let (code, text) = command(&mut stream, &format!("RCPT TO:<{}>", ascii_domain(address))).await?;
+        verdicts.push(match code {
+            250 | 251 => Verdict::Valid,
+            550..=553 => Verdict::Invalid(format!("{} {}", code, text)),
+            _ => Verdict::Unknown(format!("{} {}", code, text)),
+        });
+    }
+    if verdicts.contains(&Verdict::Valid) {
+        let made_up = format!("jm-{}@{}", crate::tracking::new_id(), domain);
+        if matches!(command(&mut stream, &format!("RCPT TO:<{}>", made_up)).await?.0, 250 | 251) {
+            for v in verdicts.iter_mut().filter(|v| **v == Verdict::Valid) {
+                *v = Verdict::Unverifiable;
+            }
+        }
+    }
+    let _ = command(&mut stream, "QUIT").await;
+    Ok(verdicts)
+}
+
+async fn verify_domain(from: &str, domain: &str, addresses: &[String]) -> Vec<Verdict> {
+    let unknown = |reason: String| vec![Verdict::Unknown(reason); addresses.len()];
+    let hosts = match mx_hosts(domain).await {
+        Ok(hosts) => hosts,
+        Err(e) => return unknown(format!("sem MX: {:#}", e)),
+    };
+    let mut last = String::new();
+    for host in &hosts {
+        match timeout(TIMEOUT, TcpStream::connect((host.as_str(), 25))).await {
+            Ok(Ok(stream)) => match probe(stream, from, domain, addresses).await {
+                Ok(verdicts) => return verdicts,
+                Err(e) => last = format!("{}: {:#}", host, e),
+            },
+            // muitas redes domésticas bloqueiam a porta 25
+            Ok(Err(e)) => last = format!("{}:25 {}", host, e),
+            Err(_) => last = format!("{}:25 sem resposta (porta bloqueada?)", host),
+        }
+    }
+    unknown(last)
+}
+
+/// Verifica cada endereço no servidor do seu domínio, um domínio de cada vez.
+pub async fn verify(from: &str, emails: &[String]) -> Vec<(String, Verdict)> {
+    let mut domains: Vec<(String, Vec<String>)> = vec![];
+    for email in emails {
+        let Some(domain) = email_domain(&ascii_domain(email)).map(str::to_lowercase) else {
+            continue;
+        };
+        match domains.iter_mut().find(|(d, _)| *d == domain) {
+            Some((_, list)) => list.push(email.clone()),
+            None => domains.push((domain, vec![email.clone()])),
+        }
+    }
+    let mut out = vec![];
+    for (domain, addresses) in domains {
+        let verdicts = verify_domain(from, &domain, &addresses).await;
+        out.extend(addresses.into_iter().zip(verdicts));
+    }
+    let invalid: Vec<(String, Verdict)> = emails
+        .iter()
+        .filter(|e| email_domain(e).is_none())
+        .map(|e| (e.clone(), Verdict::Invalid("endereço mal formado".to_string())))
+        .collect();
+    out.extend(invalid);
+    out
+}
+
+pub async fn run_verify(from: &str, emails: Vec<String>, all_contacts: bool) -> Result<()> {
+    let mut emails = emails;
+    if all_contacts {
+        emails.extend(load_contacts().contacts.into_iter().map(|c| c.email));
+    }
+    if emails.is_empty() {
+        bail!("No addresses to verify");
+    }
+    say!("{} A verificar {} endereços (MX + RCPT, nada é enviado)...", MAIL, emails.len());
+    let results = verify(from, &emails).await;
+    for (email, verdict) in &results {
+        let (mark, email) = match verdict {
+            Verdict::Valid => (CHECK.to_string(), style(email).green()),
+            Verdict::Invalid(_) => (CROSS.to_string(), style(email).red()),
+            Verdict::Unverifiable | Verdict::Unknown(_) => ("?".to_string(), style(email).yellow()),
+        };
+        match verdict {
+            Verdict::Invalid(why) | Verdict::Unknown(why) => {
+                println!("  {} {} - {} ({})", mark, email, verdict.label(), why)
+            }
+            _ => println!("  {} {} - {}", mark, email, verdict.label()),
+        }
+    }
+    let count = |f: fn(&Verdict) -> bool| results.iter().filter(|(_, v)| f(v)).count();
+    say!(
+        "{} válidos, {} não existem, {} não verificáveis (catch-all), {} desconhecidos",
+        count(|v| *v == Verdict::Valid),
+        count(|v| matches!(v, Verdict::Invalid(_))),
+        count(|v| *v == Verdict::Unverifiable),
+        count(|v| matches!(v, Verdict::Unknown(_)))
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{io::DuplexStream, net::TcpListener};
+
+    #[test]
+    fn test_mx_query_and_reply() {
+        let query = query(0x1234, "acme.com", MX);
+        assert_eq!(&query[12..], b"\x04acme\x03com\x00\x00\x0f\x00\x01");
+
+        // a pergunta, depois dois MX; o segundo nome usa um ponteiro para "acme.com"
+        let mut reply = vec![0x12, 0x34, 0x81, 0x80, 0, 1, 0, 2, 0, 0, 0, 0];
+        reply.extend(&query[12..]);
+        reply.extend([0xC0, 12, 0, 15, 0, 1, 0, 0, 1, 0, 0, 8, 0, 20]);
+        reply.extend(b"\x03mx2\xC0\x0C");
+        reply.extend([0xC0, 12, 0, 15, 0, 1, 0, 0, 1, 0, 0, 8, 0, 10]);
+        reply.extend(b"\x03mx1\xC0\x0C");
+        assert_eq!(parse_mx(0x1234, &reply).unwrap(), vec!["mx1.acme.com", "mx2.acme.com"]);
+
+        assert!(parse_mx(0x9999, &reply).is_err());
+        let nxdomain = [0x12, 0x34, 0x81, 0x83, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(parse_mx(0x1234, &nxdomain).unwrap_err().to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn test_srv_reply() {
+        let query = query(0x4321, "_submission._tcp.acme.com", SRV);
+        let mut reply = vec![0x43, 0x21, 0x81, 0x80, 0, 1, 0, 2, 0, 0, 0, 0];
+        reply.extend(&query[12..]);
+        // prioridade 10, peso 1, porta 587
+        reply.extend([0xC0, 12, 0, 33, 0, 1, 0, 0, 1, 0, 0, 21, 0, 10, 0, 1, 0x02, 0x4B]);
+        reply.extend(b"\x04smtp\x04acme\x03com\x00");
+        // prioridade 0 com alvo ".": o serviço não existe
+        reply.extend([0xC0, 12, 0, 33, 0, 1, 0, 0, 1, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(parse_srv(0x4321, &reply).unwrap(), vec![("smtp.acme.com".to_string(), 587)]);
+    }
+
+    #[test]
+    fn test_truncated_reply_retries_over_tcp() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let hosts = runtime.block_on(async {
+            let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let server = udp.local_addr().unwrap();
+            let tcp = TcpListener::bind(server).await.unwrap();
+            tokio::spawn(async move {
+                // por UDP só o cabeçalho, com TC
+                let mut buf = [0u8; 512];
+                let (n, from) = udp.recv_from(&mut buf).await.unwrap();
+                let mut cut = buf[..12].to_vec();
+                cut[2] |= 0x82;
+                udp.send_to(&cut, from).await.unwrap();
+
+                let (mut stream, _) = tcp.accept().await.unwrap();
+                let len = stream.read_u16().await.unwrap() as usize;
+                let mut query = vec![0; len];
+                stream.read_exact(&mut query).await.unwrap();
+                assert_eq!(query, buf[..n]);
+                let mut reply = query.clone();
+                reply[2] = 0x81;
+                reply[3] = 0x80;
+                reply[7] = 1;
+                reply.extend([0xC0, 12, 0, 15, 0, 1, 0, 0, 1, 0, 0, 8, 0, 10]);
+                reply.extend(b"\x03mx1\xC0\x0C");
+                stream.write_all(&(reply.len() as u16).to_be_bytes()).await.unwrap();
+                stream.write_all(&reply).await.unwrap();
+            });
+            let (id, packet) = lookup_at(server, "acme.com", MX).await.unwrap();
+            parse_mx(id, &packet).unwrap()
+        });
+        assert_eq!(hosts, vec!["mx1.acme.com"]);
+    }
+
+    // servidor SMTP falso: responde a cada linha com o que `answer` disser
+    async fn fake_server(mut stream: DuplexStream, answer: fn(&str) -> &'static str) {
+        stream.write_all(b"220 mx.test ESMTP\r\n").await.unwrap();
+        let mut buf = vec![0u8; 1024];
+        loop {
+            let Ok(n) = stream.read(&mut buf).await else { return };
+            if n == 0 {
+                return;
+            }
+            let line = String::from_utf8_lossy(&buf[..n]).trim_end().to_string();
+            let _ = stream.write_all(answer(&line).as_bytes()).await;
+            if line == "QUIT" {
+                return;
+            }
+        }
+    }
+
+    fn run_probe(answer: fn(&str) -> &'static str) -> Vec<Verdict> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (client, server) = tokio::io::duplex(4096);
+            tokio::spawn(fake_server(server, answer));
+            let addresses = vec!["rh@acme.com".to_string(), "nobody@acme.com".to_string()];
+            probe(client, "joao@example.com", "acme.com", &addresses).await.unwrap()
+        })
+    }
+
+    #[test]
+    fn test_probe_detects_catch_all() {
+        let strict = |line: &str| match line {
+            l if l.starts_with("EHLO") => "250-mx.test\r\n250 8BITMIME\r\n",
+            l if l.contains("rh@acme.com") => "250 2.1.5 OK\r\n",
+            l if l.starts_with("RCPT") => "550 5.1.1 User unknown\r\n",
+            "QUIT" => "221 bye\r\n",
+            _ => "250 OK\r\n",
+        };
+        assert_eq!(
+            run_probe(strict),
+            vec![Verdict::Valid, Verdict::Invalid("550 5.1.1 User unknown".to_string())]
+        );
+
+        let catch_all = |line: &str| match line {
+            l if l.contains("nobody@") => "450 4.2.1 try later\r\n",
+            "QUIT" => "221 bye\r\n",
+            _ => "250 OK\r\n",
+        };
+        assert_eq!(
+            run_probe(catch_all),
+            vec![Verdict::Unverifiable, Verdict::Unknown("450 4.2.1 try later".to_string())]
+        );
+    }
+}
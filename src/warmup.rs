@@ -0,0 +1,89 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::SentLog;
+
+fn default_initial() -> usize {
+    5
+}
+
+fn default_step() -> usize {
+    5
+}
+
+fn default_every_days() -> i64 {
+    2
+}
+
+fn default_target() -> usize {
+    50
+}
+
+/// Aquecimento de uma conta ou domínio novo (`sending.warmup`): `initial`
+/// envios por dia ao início, mais `step` a cada `every_days` dias, até `target`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Warmup {
+    // primeiro dia da rampa; por omissão o dia do primeiro envio no log
+    #[serde(default)]
+    pub start: Option<NaiveDate>,
+    #[serde(default = "default_initial")]
+    pub initial: usize,
+    #[serde(default = "default_step")]
+    pub step: usize,
+    #[serde(default = "default_every_days")]
+    pub every_days: i64,
+    #[serde(default = "default_target")]
+    pub target: usize,
+}
+
+impl Warmup {
+    /// O dia em que a rampa começou: o `start` do config ou o primeiro envio
+    /// real (sem os desfeitos e os do sandbox); sem nenhum, `None`.
+    pub fn start_date(&self, log: &SentLog) -> Option<NaiveDate> {
+        let first = || {
+            log.records
+                .iter()
+                .filter(|r| !r.cancelled && !r.sandbox)
+                .map(|r| r.sent_at.date_naive())
+                .min()
+        };
+        self.start.or_else(first)
+    }
+
+    /// Limite da rampa em `day`; `None` quando o aquecimento já acabou.
+    pub fn cap_on(&self, start: NaiveDate, day: NaiveDate) -> Option<usize> {
+        let steps = (day - start).num_days().max(0) / self.every_days.max(1);
+        let cap = self.initial.saturating_add(self.step.saturating_mul(steps as usize));
+        (cap < self.target).then_some(cap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    #[test]
+    fn test_ramp_from_the_first_send() {
+        let warmup: Warmup = serde_json::from_str(r#"{"initial": 10, "step": 10, "every_days": 7, "target": 40}"#).unwrap();
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let log = SentLog {
+            records: vec![
+                crate::SentRecord { sent_at: Local.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap(), sandbox: true, ..Default::default() },
+                crate::SentRecord { sent_at: Local.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap(), ..Default::default() },
+            ],
+        };
+        let start = warmup.start_date(&log).unwrap();
+        assert_eq!(start, date(4));
+        assert_eq!(warmup.cap_on(start, date(4)), Some(10));
+        assert_eq!(warmup.cap_on(start, date(10)), Some(10));
+        assert_eq!(warmup.cap_on(start, date(11)), Some(20));
+        assert_eq!(warmup.cap_on(start, date(18)), Some(30));
+        // a partir da 4.ª semana chega ao alvo: a rampa acaba
+        assert_eq!(warmup.cap_on(start, date(25)), None);
+
+        let explicit = Warmup { start: Some(date(20)), ..warmup };
+        assert_eq!(explicit.start_date(&log), Some(date(20)));
+        assert_eq!(explicit.start_date(&SentLog::default()), Some(date(20)));
+    }
+}
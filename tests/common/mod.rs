@@ -0,0 +1,356 @@
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    process::{Command, Output},
+    sync::{Arc, Mutex},
+    thread,
+};
+use tempfile::TempDir;
+
+#[derive(Debug, Clone, Default)]
+pub struct ReceivedMail {
+    pub from: String,
+    pub rcpt: Vec<String>,
+    pub data: String,
+}
+
+struct RejectRule {
+    rcpt: String,
+    reply: String,
+    // None = rejeita sempre
+    remaining: Option<usize>,
+}
+
+type Rules = Arc<Mutex<Vec<RejectRule>>>;
+
+/// Servidor SMTP mínimo em processo: aceita tudo (salvo regras de `reject`)
+/// e guarda as mensagens.
+pub struct TestSmtpServer {
+    pub port: u16,
+    messages: Arc<Mutex<Vec<ReceivedMail>>>,
+    rules: Rules,
+}
+
+impl TestSmtpServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let messages = Arc::new(Mutex::new(vec![]));
+        let rules: Rules = Arc::new(Mutex::new(vec![]));
+
+        let (store, session_rules) = (messages.clone(), rules.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (store, rules) = (store.clone(), session_rules.clone());
+                thread::spawn(move || handle_session(stream, store, rules));
+            }
+        });
+
+        Self { port, messages, rules }
+    }
+
+    /// Responde `reply` ao RCPT TO deste endereço (`times` vezes, ou sempre).
+    pub fn reject(&self, rcpt: &str, reply: &str, times: Option<usize>) {
+        self.rules.lock().unwrap().push(RejectRule {
+            rcpt: rcpt.to_string(),
+            reply: reply.to_string(),
+            remaining: times,
+        });
+    }
+
+    pub fn messages(&self) -> Vec<ReceivedMail> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+fn rejection(rules: &Rules, rcpt: &str) -> Option<String> {
+    let mut rules = rules.lock().unwrap();
+    let rule = rules
+        .iter_mut()
+        .find(|r| r.rcpt.eq_ignore_ascii_case(rcpt) && r.remaining != Some(0))?;
+    if let Some(n) = rule.remaining.as_mut() {
+        *n -= 1;
+    }
+    Some(rule.reply.clone())
+}
+
+fn handle_session(stream: TcpStream, store: Arc<Mutex<Vec<ReceivedMail>>>, rules: Rules) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut reply = |line: &str| {
+        let _ = writer.write_all(format!("{}\r\n", line).as_bytes());
+    };
+
+    reply("220 localhost ESMTP test");
+    let mut mail = ReceivedMail::default();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let cmd = line.trim_end().to_string();
+        let upper = cmd.to_uppercase();
+
+        if upper.starts_with("EHLO") || upper.starts_with("HELO") {
+            reply("250-localhost");
+            reply("250-SIZE 10485760");
+            reply("250-AUTH PLAIN LOGIN");
+            reply("250 8BITMIME");
+        } else if upper.starts_with("AUTH") {
+            reply("235 2.7.0 Authentication successful");
+        } else if upper.starts_with("MAIL FROM:") {
+            mail = ReceivedMail {
+                from: address(&cmd["MAIL FROM:".len()..]),
+                ..Default::default()
+            };
+            reply("250 OK");
+        } else if upper.starts_with("RCPT TO:") {
+            let rcpt = address(&cmd["RCPT TO:".len()..]);
+            match rejection(&rules, &rcpt) {
+                Some(answer) => reply(&answer),
+                None => {
+                    mail.rcpt.push(rcpt);
+                    reply("250 OK");
+                }
+            }
+        } else if upper == "DATA" {
+            reply("354 End data with <CR><LF>.<CR><LF>");
+            let mut data = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    return;
+                }
+                if line == ".\r\n" {
+                    break;
+                }
+                data.push_str(line.strip_prefix('.').unwrap_or(&line));
+            }
+            mail.data = data;
+            store.lock().unwrap().push(std::mem::take(&mut mail));
+            reply("250 OK queued");
+        } else if upper == "QUIT" {
+            reply("221 Bye");
+            return;
+        } else {
+            reply("250 OK");
+        }
+    }
+}
+
+/// Servidor IMAP mínimo: aceita qualquer LOGIN, guarda o que recebe por
+/// APPEND e devolve a caixa de entrada inteira a qualquer SEARCH.
+pub struct TestImapServer {
+    pub port: u16,
+    appended: Arc<Mutex<Vec<(String, String)>>>,
+    inbox: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestImapServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let appended = Arc::new(Mutex::new(vec![]));
+        let inbox = Arc::new(Mutex::new(vec![]));
+
+        let (store, messages) = (appended.clone(), inbox.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (store, messages) = (store.clone(), messages.clone());
+                thread::spawn(move || handle_imap_session(stream, store, messages));
+            }
+        });
+
+        Self { port, appended, inbox }
+    }
+
+    /// (pasta, mensagem) de cada APPEND.
+    pub fn appended(&self) -> Vec<(String, String)> {
+        self.appended.lock().unwrap().clone()
+    }
+
+    /// Põe uma mensagem na caixa de entrada.
+    pub fn receive(&self, raw: &str) {
+        self.inbox.lock().unwrap().push(raw.to_string());
+    }
+}
+
+fn handle_imap_session(stream: TcpStream, store: Arc<Mutex<Vec<(String, String)>>>, inbox: Arc<Mutex<Vec<String>>>) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut reply = |line: &str| {
+        let _ = writer.write_all(format!("{}\r\n", line).as_bytes());
+    };
+
+    reply("* OK test IMAP ready");
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let cmd = line.trim_end().to_string();
+        let (tag, rest) = cmd.split_once(' ').unwrap_or((&cmd, ""));
+        let upper = rest.to_uppercase();
+
+        if upper.starts_with("LOGIN") {
+            reply(&format!("{} OK LOGIN completed", tag));
+        } else if upper.starts_with("APPEND") {
+            // APPEND "pasta" (\Seen) {N}
+            let folder = rest.split('"').nth(1).unwrap_or("").to_string();
+            let size: usize = rest
+                .rsplit_once('{')
+                .and_then(|(_, n)| n.trim_end_matches('}').parse().ok())
+                .unwrap_or(0);
+            reply("+ Ready for literal data");
+            let mut data = vec![0; size];
+            if reader.read_exact(&mut data).is_err() {
+                return;
+            }
+            line.clear();
+            let _ = reader.read_line(&mut line);
+            store.lock().unwrap().push((folder, String::from_utf8_lossy(&data).into_owned()));
+            reply(&format!("{} OK APPEND completed", tag));
+        } else if upper.starts_with("EXAMINE") {
+            reply(&format!("* {} EXISTS", inbox.lock().unwrap().len()));
+            reply(&format!("{} OK [READ-ONLY] EXAMINE completed", tag));
+        } else if upper.starts_with("UID SEARCH") {
+            let uids: Vec<String> = (1..=inbox.lock().unwrap().len()).map(|uid| uid.to_string()).collect();
+            reply(format!("* SEARCH {}", uids.join(" ")).trim_end());
+            reply(&format!("{} OK SEARCH completed", tag));
+        } else if upper.starts_with("UID FETCH") {
+            for (i, raw) in inbox.lock().unwrap().iter().enumerate() {
+                reply(&format!("* {} FETCH (UID {} BODY[] {{{}}}\r\n{})", i + 1, i + 1, raw.len(), raw));
+            }
+            reply(&format!("{} OK FETCH completed", tag));
+        } else if upper.starts_with("LOGOUT") {
+            reply("* BYE");
+            reply(&format!("{} OK LOGOUT completed", tag));
+            return;
+        } else {
+            reply(&format!("{} BAD unknown command", tag));
+        }
+    }
+}
+
+fn address(arg: &str) -> String {
+    arg.trim()
+        .trim_start_matches('<')
+        .split('>')
+        .next()
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Pasta de trabalho com config.json (a apontar para o servidor) e cv.pdf.
+pub fn workspace(server: &TestSmtpServer) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "profile": {
+            "name": "João Silva",
+            "email": "joao@example.com",
+            "phone": "+351 912 345 678",
+            "title": "Desenvolvedor Rust",
+            "summary": "Desenvolvedor experiente",
+            "skills": ["Rust", "Tokio"],
+            "experience_years": 5,
+            "linkedin": null,
+            "github": "github.com/joao"
+        },
+        "smtp": { "host": "127.0.0.1", "port": server.port, "security": "none" },
+        "template": {
+            "subject": "Candidatura - {{title}} - {{name}}",
+            "body": "Olá,\nSkills: {{skills}}\nGitHub: {{github}}"
+        }
+    });
+    fs::write(dir.path().join("config.json"), config.to_string()).unwrap();
+    // o comentário binário a seguir ao cabeçalho é o que os PDFs reais trazem
+    fs::write(dir.path().join("cv.pdf"), b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\nfake").unwrap();
+    dir
+}
+
+/// Muda o config.json de uma pasta de `workspace`.
+pub fn edit_config(dir: &Path, change: impl FnOnce(&mut serde_json::Value)) {
+    let path = dir.join("config.json");
+    let mut config: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    change(&mut config);
+    fs::write(&path, config.to_string()).unwrap();
+}
+
+pub fn run_cli(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_job-mailer"))
+        .args(args)
+        .current_dir(dir)
+        .env("SMTP_USER", "joao@example.com")
+        .env("SMTP_PASS", "secret")
+        .output()
+        .unwrap()
+}
+
+/// Um porto livre em 127.0.0.1, para servidores lançados pelo binário.
+pub fn free_addr() -> String {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    format!("127.0.0.1:{}", port)
+}
+
+/// Pedido HTTP a um servidor que pode ainda estar a arrancar; devolve (status, corpo).
+pub fn http(addr: &str, method: &str, path: &str, headers: &[&str], body: &str) -> (u16, String) {
+    let mut stream = (0..50)
+        .find_map(|_| {
+            TcpStream::connect(addr).ok().or_else(|| {
+                thread::sleep(std::time::Duration::from_millis(100));
+                None
+            })
+        })
+        .expect("server did not start");
+    let mut request = format!("{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n", method, path, addr, body.len());
+    for header in headers {
+        request.push_str(&format!("{}\r\n", header));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, body.to_string())
+}
+
+/// Separa cabeçalhos e corpo de uma entidade MIME.
+pub fn split_entity(raw: &str) -> (String, String) {
+    let raw = raw.replace("\r\n", "\n");
+    let (head, body) = raw.split_once("\n\n").unwrap_or((&raw, ""));
+    // desdobrar cabeçalhos multi-linha
+    (head.replace("\n ", " ").replace("\n\t", " "), body.to_string())
+}
+
+pub fn header(head: &str, name: &str) -> Option<String> {
+    let prefix = format!("{}:", name.to_lowercase());
+    head.lines()
+        .find(|l| l.to_lowercase().starts_with(&prefix))
+        .map(|l| l[prefix.len()..].trim().to_string())
+}
+
+pub fn boundary(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .find_map(|p| p.trim().strip_prefix("boundary="))
+        .unwrap()
+        .trim_matches('"')
+        .to_string()
+}
+
+pub fn multipart_parts(body: &str, boundary: &str) -> Vec<(String, String)> {
+    let delimiter = format!("--{}", boundary);
+    body.split(&delimiter)
+        .skip(1)
+        .filter(|p| !p.starts_with("--"))
+        .map(|p| split_entity(p.trim_start_matches('\n')))
+        .collect()
+}
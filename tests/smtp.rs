@@ -0,0 +1,1077 @@
+mod common;
+
+use common::{
+    boundary, edit_config, free_addr, header, http, multipart_parts, run_cli, split_entity, workspace, TestImapServer,
+    TestSmtpServer,
+};
+
+#[test]
+fn test_send_delivers_full_mime_message() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let messages = server.messages();
+    assert_eq!(messages.len(), 1);
+    let mail = &messages[0];
+    assert_eq!(mail.from, "joao@example.com");
+    assert_eq!(mail.rcpt, vec!["rh@acme.com"]);
+
+    let (head, body) = split_entity(&mail.data);
+    // "João Silva" em RFC 2047
+    assert_eq!(header(&head, "From").unwrap(), "=?utf-8?b?Sm/Do28gU2lsdmE=?= <joao@example.com>");
+    assert_eq!(header(&head, "To").unwrap(), "rh@acme.com");
+    assert!(header(&head, "Subject").is_some());
+    assert!(header(&head, "Date").is_some());
+    assert_eq!(header(&head, "MIME-Version").unwrap(), "1.0");
+
+    let content_type = header(&head, "Content-Type").unwrap();
+    assert!(content_type.starts_with("multipart/mixed"));
+
+    let parts = multipart_parts(&body, &boundary(&content_type));
+    assert_eq!(parts.len(), 2);
+
+    let (text_head, text_body) = &parts[0];
+    assert!(header(text_head, "Content-Type").unwrap().starts_with("text/plain"));
+    assert!(text_body.contains("Skills: Rust, Tokio"));
+    assert!(text_body.contains("GitHub: github.com/joao"));
+
+    let (pdf_head, pdf_body) = &parts[1];
+    assert_eq!(header(pdf_head, "Content-Type").unwrap(), "application/pdf");
+    assert!(header(pdf_head, "Content-Disposition").unwrap().contains("CV.pdf"));
+    assert_eq!(header(pdf_head, "Content-Transfer-Encoding").unwrap(), "base64");
+    assert!(pdf_body.starts_with("JVBERi0xLjQK"));
+}
+
+#[test]
+fn test_send_records_success_in_log() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success());
+
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    assert_eq!(log["records"][0]["email"], "rh@acme.com");
+    assert_eq!(log["records"][0]["success"], true);
+}
+
+#[test]
+fn test_sandbox_does_not_touch_smtp() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com", "--sandbox"]);
+    assert!(output.status.success());
+
+    assert!(server.messages().is_empty());
+    assert_eq!(std::fs::read_dir(dir.path().join("outbox")).unwrap().count(), 1);
+}
+
+#[test]
+fn test_paths_and_config_keys_from_env() {
+    let server = TestSmtpServer::start();
+    let data = workspace(&server);
+    let elsewhere = tempfile::TempDir::new().unwrap();
+    let log = elsewhere.path().join("log.json");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_job-mailer"))
+        .args(["send", "--to", "rh@acme.com"])
+        .current_dir(elsewhere.path())
+        .env("SMTP_USER", "joao@example.com")
+        .env("SMTP_PASS", "secret")
+        .env("JOB_MAILER_DIR", data.path())
+        .env("JOB_MAILER_LOG", &log)
+        .env("JOB_MAILER__SENDING__SANDBOX", "true")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert!(server.messages().is_empty());
+    assert_eq!(std::fs::read_dir(data.path().join("outbox")).unwrap().count(), 1);
+    assert!(std::fs::read_to_string(&log).unwrap().contains("rh@acme.com"));
+    assert!(!data.path().join("sent_log.json").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_password_from_secrets_command() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    let send = |dir: &std::path::Path| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_job-mailer"))
+            .args(["send", "--to", "rh@acme.com"])
+            .current_dir(dir)
+            .env("SMTP_USER", "joao@example.com")
+            .env_remove("SMTP_PASS")
+            .output()
+            .unwrap()
+    };
+
+    edit_config(dir.path(), |config| {
+        config["secrets"] = serde_json::json!({ "provider": "command", "command": "exit 1" });
+    });
+    let output = send(dir.path());
+    let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    assert!(text.contains("SMTP_PASS not set"), "{}", text);
+    assert!(server.messages().is_empty());
+
+    edit_config(dir.path(), |config| {
+        config["secrets"] = serde_json::json!({ "provider": "command", "command": "echo pw-for-{user}" });
+    });
+    let output = send(dir.path());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(server.messages().len(), 1);
+}
+
+#[test]
+fn test_smtp_test_authenticates_without_sending() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+
+    let output = run_cli(dir.path(), &["smtp", "test"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("AUTH PLAIN LOGIN"), "{}", stdout);
+    assert!(stdout.contains("10.0MB"), "{}", stdout);
+    assert!(stdout.contains("Autenticado (PLAIN)"), "{}", stdout);
+    assert!(server.messages().is_empty());
+}
+
+#[test]
+fn test_merge_with_yes_runs_unattended() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["template"]["subject"] = "{{role}} - {{title}}".into();
+    });
+    std::fs::write(
+        dir.path().join("vagas.csv"),
+        "email,role\nrh@acme.com,Backend\njobs@globex.com,Platform\n",
+    )
+    .unwrap();
+
+    // sem --yes a confirmação falha por não haver terminal, e nada é enviado
+    let output = run_cli(dir.path(), &["merge", "vagas.csv", "--min-delay", "0", "--max-delay", "0"]);
+    assert!(!output.status.success());
+    assert!(server.messages().is_empty());
+
+    let output = run_cli(dir.path(), &["merge", "vagas.csv", "--min-delay", "0", "--max-delay", "0", "--yes"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let subjects: Vec<String> = server
+        .messages()
+        .iter()
+        .map(|m| header(&split_entity(&m.data).0, "Subject").unwrap())
+        .collect();
+    assert_eq!(subjects, vec!["Backend - Desenvolvedor Rust", "Platform - Desenvolvedor Rust"]);
+}
+
+#[test]
+fn test_bulk_reads_recipients_from_stdin() {
+    use std::io::Write;
+
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    let mut bulk = std::process::Command::new(env!("CARGO_BIN_EXE_job-mailer"))
+        .args(["bulk", "--stdin", "--min-delay", "0", "--max-delay", "0"])
+        .current_dir(dir.path())
+        .env("SMTP_USER", "joao@example.com")
+        .env("SMTP_PASS", "secret")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    // sem --yes: com o stdin num pipe não há perguntas
+    bulk.stdin.take().unwrap().write_all(b"rh@acme.com\nAcme,jobs@globex.com\nRH@acme.com\n").unwrap();
+    let output = bulk.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let rcpts: Vec<String> = server.messages().iter().flat_map(|m| m.rcpt.clone()).collect();
+    assert_eq!(rcpts, vec!["rh@acme.com", "jobs@globex.com"]);
+}
+
+#[test]
+fn test_verbosity_levels() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com", "-q"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com", "-vv"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("{{skills}} = Rust, Tokio"));
+    assert!(stderr.contains(">> MAIL FROM:<joao@example.com>"));
+    assert!(stderr.contains(">> AUTH PLAIN <redacted>"));
+    // AUTH PLAIN leva "\0user\0pass" em base64
+    assert!(!stderr.contains("AGpvYW9AZXhhbXBsZS5jb20Ac2VjcmV0"));
+}
+
+#[test]
+fn test_failed_send_keeps_smtp_transcript() {
+    let server = TestSmtpServer::start();
+    server.reject("nobody@acme.com", "550 5.1.1 User unknown", None);
+    let dir = workspace(&server);
+
+    let output = run_cli(dir.path(), &["send", "--to", "nobody@acme.com"]);
+    assert!(output.status.success());
+    assert!(server.messages().is_empty());
+
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    let record = &log["records"][0];
+    assert_eq!(record["success"], false);
+    assert_eq!(record["error"]["kind"], "recipient_rejected");
+    assert_eq!(record["error"]["code"], 550);
+    let transcript: Vec<&str> = record["smtp_transcript"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| l.as_str().unwrap())
+        .collect();
+    assert!(transcript.contains(&">> RCPT TO:<nobody@acme.com>"));
+    assert!(transcript.contains(&"<< 550 5.1.1 User unknown"));
+    assert!(transcript.contains(&">> AUTH PLAIN <redacted>"));
+}
+
+#[test]
+fn test_bulk_marks_unknown_users_dead() {
+    let server = TestSmtpServer::start();
+    server.reject("gone@acme.com", "550 5.1.1 User unknown", Some(1));
+    let dir = workspace(&server);
+    std::fs::write(dir.path().join("vagas.csv"), "email\ngone@acme.com\nrh@globex.com\n").unwrap();
+    let merge = ["merge", "vagas.csv", "--min-delay", "0", "--max-delay", "0", "--yes"];
+
+    let output = run_cli(dir.path(), &merge);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let dead = std::fs::read_to_string(dir.path().join("dead_addresses.json")).unwrap();
+    let dead: serde_json::Value = serde_json::from_str(&dead).unwrap();
+    assert_eq!(dead["addresses"][0]["email"], "gone@acme.com");
+
+    // a regra de rejeição já se esgotou: se voltasse a tentar, o envio passava
+    let output = run_cli(dir.path(), &merge);
+    assert!(output.status.success());
+    let rcpts: Vec<String> = server.messages().iter().flat_map(|m| m.rcpt.clone()).collect();
+    assert_eq!(rcpts, vec!["rh@globex.com", "rh@globex.com"]);
+
+    std::fs::write(dir.path().join("contactos.csv"), "email\ngone@acme.com\nrh@globex.com\n").unwrap();
+    let output = run_cli(dir.path(), &["contacts", "import", "contactos.csv"]);
+    assert!(output.status.success());
+    let contacts = std::fs::read_to_string(dir.path().join("contacts.json")).unwrap();
+    assert!(!contacts.contains("gone@acme.com"));
+    assert!(contacts.contains("rh@globex.com"));
+}
+
+#[test]
+fn test_greylisted_recipient_is_retried_in_the_same_batch() {
+    let server = TestSmtpServer::start();
+    server.reject("rh@acme.com", "451 4.7.1 Greylisted, please try again later", Some(1));
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["sending"] = serde_json::json!({ "greylist_retry_minutes": [0, 0] });
+    });
+    std::fs::write(dir.path().join("vagas.csv"), "email\nrh@acme.com\njobs@globex.com\n").unwrap();
+
+    let output = run_cli(dir.path(), &["merge", "vagas.csv", "--min-delay", "0", "--max-delay", "0", "--yes"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // com 0 minutos a nova tentativa já está na hora e passa à frente do resto
+    let rcpts: Vec<String> = server.messages().iter().flat_map(|m| m.rcpt.clone()).collect();
+    assert_eq!(rcpts, vec!["rh@acme.com", "jobs@globex.com"]);
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    let records = log["records"].as_array().unwrap();
+    assert_eq!(records.len(), 2);
+    assert!(records.iter().all(|r| r["success"] == true));
+}
+
+#[test]
+fn test_merge_over_daily_cap_goes_to_the_queue() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["template"]["subject"] = "{{role}} - {{title}}".into();
+        config["sending"] = serde_json::json!({ "max_per_day": 1 });
+    });
+    std::fs::write(
+        dir.path().join("vagas.csv"),
+        "email,role\nrh@acme.com,Backend\njobs@globex.com,Platform\nhr@initech.com,Data\n",
+    )
+    .unwrap();
+
+    let output = run_cli(dir.path(), &["merge", "vagas.csv", "--min-delay", "0", "--max-delay", "0", "--yes"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let rcpts: Vec<String> = server.messages().iter().flat_map(|m| m.rcpt.clone()).collect();
+    assert_eq!(rcpts, vec!["rh@acme.com"]);
+
+    // o resto fica na fila, um por dia, com a linha do CSV já no template
+    let outbox = std::fs::read_to_string(dir.path().join("outbox.json")).unwrap();
+    let outbox: serde_json::Value = serde_json::from_str(&outbox).unwrap();
+    let items = outbox["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["to"], "jobs@globex.com");
+    assert_eq!(items[0]["template"]["subject"], "Platform - {{title}}");
+    assert_eq!(items[1]["to"], "hr@initech.com");
+    assert!(items[0]["send_at"].as_str() < items[1]["send_at"].as_str());
+
+    // só amanhã às 9h: o daemon de hoje não pega neles
+    let output = run_cli(dir.path(), &["daemon", "--once"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(server.messages().len(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_contacts_import_dir_reports_each_file() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    let leads = dir.path().join("leads");
+    std::fs::create_dir(&leads).unwrap();
+    std::fs::write(leads.join("a.csv"), "email,company\nrh@acme.com,Acme\nsem-arroba,X\nRH@acme.com,Acme\n").unwrap();
+    std::fs::write(leads.join("b.txt"), "jobs@globex.com\nrh@acme.com\nligar amanha\n").unwrap();
+    std::fs::write(leads.join("notas.md"), "hr@initech.com\n").unwrap();
+
+    let output = run_cli(dir.path(), &["contacts", "import", "--dir", "leads"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("a.csv: 1 novos, 0 actualizados, 1 repetidos, 1 inválidos"), "{}", stdout);
+    assert!(stdout.contains("b.txt: 1 novos, 0 actualizados, 1 repetidos, 1 inválidos"), "{}", stdout);
+    assert!(stdout.contains("2 ficheiros: 2 novos"), "{}", stdout);
+
+    let contacts = std::fs::read_to_string(dir.path().join("contacts.json")).unwrap();
+    assert!(contacts.contains("jobs@globex.com"));
+    assert!(!contacts.contains("hr@initech.com"));
+}
+
+#[test]
+fn test_ctrl_c_stops_merge_and_resume_finishes_it() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    std::fs::write(dir.path().join("vagas.csv"), "email\nrh@acme.com\njobs@globex.com\nhr@initech.com\n").unwrap();
+
+    let merge = std::process::Command::new(env!("CARGO_BIN_EXE_job-mailer"))
+        .args(["merge", "vagas.csv", "--min-delay", "30", "--max-delay", "30", "--yes"])
+        .current_dir(dir.path())
+        .env("SMTP_USER", "joao@example.com")
+        .env("SMTP_PASS", "secret")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    // o primeiro sai logo; o Ctrl-C chega durante a espera pelo segundo
+    for _ in 0..100 {
+        if !server.messages().is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    let kill = std::process::Command::new("kill").args(["-INT", &merge.id().to_string()]).status().unwrap();
+    assert!(kill.success());
+    let status = merge.wait_with_output().unwrap().status;
+    assert!(status.success());
+    assert_eq!(server.messages().len(), 1);
+
+    let resume = std::fs::read_to_string(dir.path().join("resume.json")).unwrap();
+    let resume: serde_json::Value = serde_json::from_str(&resume).unwrap();
+    let unsent: Vec<&str> = resume["items"].as_array().unwrap().iter().map(|i| i["to"].as_str().unwrap()).collect();
+    assert_eq!(unsent, vec!["jobs@globex.com", "hr@initech.com"]);
+
+    edit_config(dir.path(), |config| {
+        config["sending"] = serde_json::json!({ "delay": { "strategy": "uniform", "min": 0, "max": 0 } });
+    });
+    let output = run_cli(dir.path(), &["resume", "--yes"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let rcpts: Vec<String> = server.messages().iter().flat_map(|m| m.rcpt.clone()).collect();
+    assert_eq!(rcpts, vec!["rh@acme.com", "jobs@globex.com", "hr@initech.com"]);
+    assert!(!dir.path().join("resume.json").exists());
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    assert_eq!(log["records"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn test_resend_uses_the_stored_message() {
+    let server = TestSmtpServer::start();
+    server.reject("rh@acme.com", "550 5.2.1 Mailbox disabled", Some(1));
+    let dir = workspace(&server);
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success());
+    assert!(server.messages().is_empty());
+
+    // o template mudou entretanto; o reenvio leva o texto original
+    edit_config(dir.path(), |config| {
+        config["template"]["subject"] = "Outro assunto".into();
+    });
+
+    let output = run_cli(dir.path(), &["resend", "rh@acme.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let messages = server.messages();
+    assert_eq!(messages.len(), 1);
+    let (head, _) = split_entity(&messages[0].data);
+    assert!(header(&head, "Subject").unwrap().starts_with("Candidatura - Desenvolvedor Rust - "));
+
+    let output = run_cli(dir.path(), &["resend", "outro@acme.com"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_archive_keeps_exact_copy_of_sent_message() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["sending"] = serde_json::json!({ "archive": true });
+    });
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success());
+
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    let eml = log["records"][0]["message"]["eml"].as_str().unwrap();
+    assert!(eml.starts_with("sent/") && eml.ends_with("-rh@acme.com.eml"));
+    let archived = std::fs::read_to_string(dir.path().join(eml)).unwrap();
+    // o servidor recebe os mesmos cabeçalhos e o mesmo corpo que ficaram no arquivo
+    let (head, body) = split_entity(&server.messages()[0].data);
+    let (archived_head, archived_body) = split_entity(&archived);
+    assert_eq!(header(&archived_head, "Subject"), header(&head, "Subject"));
+    assert_eq!(header(&archived_head, "Content-Type"), header(&head, "Content-Type"));
+    assert_eq!(archived_body.trim_end(), body.trim_end());
+
+    // um envio recusado não fica em sent/
+    server.reject("nobody@acme.com", "550 5.1.1 User unknown", None);
+    run_cli(dir.path(), &["send", "--to", "nobody@acme.com"]);
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    assert_eq!(log["records"][1]["success"], false);
+    assert!(log["records"][1]["message"]["eml"].is_null());
+    assert_eq!(std::fs::read_dir(dir.path().join("sent")).unwrap().count(), 1);
+}
+
+#[test]
+fn test_forget_erases_and_blocks_the_address() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["sending"] = serde_json::json!({ "archive": true });
+    });
+
+    for args in [
+        &["contacts", "add", "rh@acme.com", "--name", "Maria", "--no-enrich"][..],
+        &["send", "--to", "rh@acme.com"],
+        &["send", "--to", "jobs@globex.com"],
+    ] {
+        let output = run_cli(dir.path(), args);
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    let output = run_cli(dir.path(), &["forget", "rh@acme.com", "--yes", "--report", "apagado.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report = std::fs::read_to_string(dir.path().join("apagado.txt")).unwrap();
+    assert!(report.contains("- 1 envios apagados do registo"));
+    assert!(report.contains("- contacto apagado"));
+    assert!(report.contains("- 1 ficheiros apagados"));
+
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    assert!(!log.contains("rh@acme.com") && log.contains("jobs@globex.com"));
+    assert!(!std::fs::read_to_string(dir.path().join("contacts.json")).unwrap().contains("rh@acme.com"));
+    assert_eq!(std::fs::read_dir(dir.path().join("sent")).unwrap().count(), 1);
+
+    let again = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(!again.status.success());
+    assert!(String::from_utf8_lossy(&again.stderr).contains("blocklist"));
+}
+
+#[test]
+fn test_bcc_self_adds_hidden_recipient() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["sending"] = serde_json::json!({ "bcc_self": true });
+    });
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success());
+
+    let mail = &server.messages()[0];
+    assert_eq!(mail.rcpt, vec!["rh@acme.com", "joao@example.com"]);
+    let (head, _) = split_entity(&mail.data);
+    assert_eq!(header(&head, "Bcc"), None);
+    assert_eq!(header(&head, "To").unwrap(), "rh@acme.com");
+}
+
+#[test]
+fn test_utf8_subject_falls_back_without_smtputf8() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["sending"] = serde_json::json!({ "subject_encoding": "utf8" });
+    });
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // o servidor de teste não anuncia SMTPUTF8: o assunto vai em RFC 2047
+    let (head, _) = split_entity(&server.messages()[0].data);
+    let subject = header(&head, "Subject").unwrap();
+    assert!(subject.is_ascii(), "{}", subject);
+    assert!(subject.contains("=?utf-8?b?"), "{}", subject);
+}
+
+#[test]
+fn test_imap_append_after_successful_send() {
+    let server = TestSmtpServer::start();
+    server.reject("gone@acme.com", "550 5.1.1 User unknown", None);
+    let imap = TestImapServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["imap"] = serde_json::json!({
+            "host": "127.0.0.1",
+            "port": imap.port,
+            "security": "none",
+            "folder": "Enviados"
+        });
+    });
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    // envios falhados não vão para os enviados
+    let output = run_cli(dir.path(), &["send", "--to", "gone@acme.com"]);
+    assert!(output.status.success());
+    // mesmo domínio há pouco: sem terminal fica só o aviso
+    assert!(String::from_utf8_lossy(&output.stdout).contains("rh@acme.com contactado a"));
+
+    let appended = imap.appended();
+    assert_eq!(appended.len(), 1);
+    assert_eq!(appended[0].0, "Enviados");
+    let (head, body) = split_entity(&appended[0].1);
+    assert_eq!(header(&head, "To").unwrap(), "rh@acme.com");
+    assert_eq!(body.trim_end(), split_entity(&server.messages()[0].data).1.trim_end());
+}
+
+#[test]
+fn test_send_with_several_to_and_cc() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+
+    let args = ["send", "--to", "joana@acme.com", "--to", "rh@acme.com", "--cc", "talent@acme.com"];
+    let output = run_cli(dir.path(), &args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let messages = server.messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].rcpt, vec!["joana@acme.com", "rh@acme.com", "talent@acme.com"]);
+    let (head, _) = split_entity(&messages[0].data);
+    assert_eq!(header(&head, "To").unwrap(), "joana@acme.com, rh@acme.com");
+    assert_eq!(header(&head, "Cc").unwrap(), "talent@acme.com");
+
+    // uma só candidatura no log, com os outros endereços
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    let records = log["records"].as_array().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["email"], "joana@acme.com");
+    assert_eq!(records[0]["message"]["other_to"], serde_json::json!(["rh@acme.com"]));
+    assert_eq!(records[0]["message"]["cc"], serde_json::json!(["talent@acme.com"]));
+
+    let output = run_cli(dir.path(), &["status", "talent@acme.com", "replied"]);
+    assert!(output.status.success());
+
+    // um CC bloqueado pára o envio todo
+    assert!(run_cli(dir.path(), &["block", "add", "hr@*.gov"]).status.success());
+    let output = run_cli(dir.path(), &["send", "--to", "ok@x.com", "--cc", "hr@blocked.gov"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("blocklist"));
+    assert_eq!(server.messages().len(), 1);
+}
+
+#[test]
+fn test_from_display_name_and_reply_to() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success());
+
+    edit_config(dir.path(), |config| {
+        config["profile"]["display_name"] = "Joao Silva - Backend Engineer".into();
+        config["profile"]["reply_to"] = "joao.silva@pessoal.pt".into();
+    });
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success());
+
+    let heads: Vec<String> = server.messages().iter().map(|m| split_entity(&m.data).0).collect();
+    // sem display_name vai o nome do perfil
+    assert!(header(&heads[0], "From").unwrap().ends_with("<joao@example.com>"));
+    assert_eq!(header(&heads[0], "Reply-To"), None);
+    assert_eq!(header(&heads[1], "From").unwrap(), "\"Joao Silva - Backend Engineer\" <joao@example.com>");
+    assert_eq!(header(&heads[1], "Reply-To").unwrap(), "joao.silva@pessoal.pt");
+    assert_eq!(server.messages()[1].from, "joao@example.com");
+}
+
+#[test]
+fn test_profile_uses_its_own_identity_and_account() {
+    let server = TestSmtpServer::start();
+    let freelance = TestSmtpServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        let mut profile = config["profile"].clone();
+        profile["email"] = "joao@freelance.dev".into();
+        profile["display_name"] = "Joao Silva".into();
+        profile["signature"] = "Joao Silva\nfreelance.dev".into();
+        config["profiles"] = serde_json::json!({
+            "freelance": {
+                "profile": profile,
+                "smtp": { "host": "127.0.0.1", "port": freelance.port, "security": "none" }
+            }
+        });
+    });
+
+    let output = run_cli(dir.path(), &["--profile", "freelance", "send", "--to", "rh@acme.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(server.messages().is_empty());
+    let mail = &freelance.messages()[0];
+    assert_eq!(mail.from, "joao@freelance.dev");
+    let (head, _) = split_entity(&mail.data);
+    assert_eq!(header(&head, "From").unwrap(), "\"Joao Silva\" <joao@freelance.dev>");
+
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    let message = &log["records"][0]["message"];
+    assert_eq!(message["profile"], "freelance");
+    assert!(message["body"].as_str().unwrap().ends_with("\n\n-- \nJoao Silva\nfreelance.dev"));
+
+    // o reenvio sai pela mesma conta, mesmo sem --profile
+    let output = run_cli(dir.path(), &["resend", "rh@acme.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(freelance.messages().len(), 2);
+    assert!(server.messages().is_empty());
+
+    let output = run_cli(dir.path(), &["--profile", "outro", "send", "--to", "rh@acme.com"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_word_document_named_cv_pdf_is_refused() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    std::fs::write(dir.path().join("cv.pdf"), b"PK\x03\x04\x14\0\0\0[Content_Types].xml word/document.xml").unwrap();
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not a PDF"));
+    assert!(server.messages().is_empty());
+}
+
+#[test]
+fn test_cv_variant_per_recipient() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    std::fs::write(dir.path().join("cv-backend.pdf"), b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\nbackend").unwrap();
+    edit_config(dir.path(), |config| {
+        config["profile"]["cvs"] = serde_json::json!({ "backend": "cv-backend.pdf" });
+    });
+    std::fs::write(dir.path().join("vagas.csv"), "email,cv\nrh@acme.com,backend\njobs@globex.com,\n").unwrap();
+
+    let args = ["merge", "vagas.csv", "--min-delay", "0", "--max-delay", "0", "--yes"];
+    let output = run_cli(dir.path(), &args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = run_cli(dir.path(), &["send", "--to", "talent@initech.com", "--cv", "backend"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = run_cli(dir.path(), &["resend", "rh@acme.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // "%PDF-1.7" em base64 começa por JVBERi0xLjcK, o cv.pdf (1.4) por JVBERi0xLjQK
+    let attachments: Vec<String> = server
+        .messages()
+        .iter()
+        .map(|m| {
+            let (head, body) = split_entity(&m.data);
+            multipart_parts(&body, &boundary(&header(&head, "Content-Type").unwrap()))[1].1.clone()
+        })
+        .collect();
+    assert_eq!(attachments.len(), 4);
+    assert!(attachments[0].starts_with("JVBERi0xLjcK"));
+    assert!(attachments[1].starts_with("JVBERi0xLjQK"));
+    assert!(attachments[2].starts_with("JVBERi0xLjcK"));
+    assert!(attachments[3].starts_with("JVBERi0xLjcK"));
+
+    let log = std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    assert_eq!(log["records"][0]["message"]["cv_variant"], "backend");
+    assert_eq!(log["records"][1]["message"]["cv_variant"], serde_json::Value::Null);
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com", "--cv", "data"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("CV variant 'data' not found"));
+    assert_eq!(server.messages().len(), 4);
+}
+
+#[test]
+fn test_send_with_portfolio_zip() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    std::fs::create_dir(dir.path().join("projectos")).unwrap();
+    std::fs::write(dir.path().join("projectos").join("api.md"), "# API de pagamentos\n").unwrap();
+    edit_config(dir.path(), |config| {
+        config["profile"]["portfolio"] = serde_json::json!({ "files": ["projectos"], "max_kb": 100 });
+    });
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success());
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com", "--portfolio"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let parts: Vec<Vec<(String, String)>> = server
+        .messages()
+        .iter()
+        .map(|m| {
+            let (head, body) = split_entity(&m.data);
+            multipart_parts(&body, &boundary(&header(&head, "Content-Type").unwrap()))
+        })
+        .collect();
+    // só com --portfolio
+    assert_eq!(parts[0].len(), 2);
+    assert_eq!(parts[1].len(), 3);
+    let (zip_head, zip_body) = &parts[1][2];
+    assert_eq!(header(zip_head, "Content-Type").unwrap(), "application/zip");
+    assert!(header(zip_head, "Content-Disposition").unwrap().contains("Portfolio.zip"));
+    // "PK\x03\x04" em base64
+    assert!(zip_body.starts_with("UEsDB"));
+
+    edit_config(dir.path(), |config| config["profile"]["portfolio"]["max_kb"] = 0.into());
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com", "--portfolio"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("portfolio.max_kb"));
+    assert_eq!(server.messages().len(), 2);
+}
+
+#[test]
+fn test_tracking_pixel_records_opens() {
+    use std::io::{Read, Write};
+
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let listen = format!("127.0.0.1:{}", port);
+    edit_config(dir.path(), |config| {
+        config["tracking"] = serde_json::json!({ "url": format!("http://{}/o/{{{{id}}}}.gif", listen), "listen": listen });
+    });
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = run_cli(dir.path(), &["send", "--to", "rh@globex.com", "--no-track"]);
+    assert!(output.status.success());
+
+    let read_log = || -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap()).unwrap()
+    };
+    let log = read_log();
+    let id = log["records"][0]["message"]["track_id"].as_str().unwrap().to_string();
+    assert!(log["records"][1]["message"]["track_id"].is_null());
+
+    let messages = server.messages();
+    let (head, body) = split_entity(&messages[0].data);
+    let parts = multipart_parts(&body, &boundary(&header(&head, "Content-Type").unwrap()));
+    let alternative = header(&parts[0].0, "Content-Type").unwrap();
+    assert!(alternative.starts_with("multipart/alternative"));
+    let versions = multipart_parts(&parts[0].1, &boundary(&alternative));
+    assert!(header(&versions[0].0, "Content-Type").unwrap().starts_with("text/plain"));
+    assert!(header(&versions[1].0, "Content-Type").unwrap().starts_with("text/html"));
+    // quoted-printable: desfazer as quebras de linha suaves
+    let html = versions[1].1.replace("=\n", "").replace("=3D", "=");
+    assert!(html.contains(&format!("/o/{}.gif", id)), "{}", html);
+    // sem pixel, só texto
+    let (head, body) = split_entity(&messages[1].data);
+    let parts = multipart_parts(&body, &boundary(&header(&head, "Content-Type").unwrap()));
+    assert!(header(&parts[0].0, "Content-Type").unwrap().starts_with("text/plain"));
+
+    let mut collector = std::process::Command::new(env!("CARGO_BIN_EXE_job-mailer"))
+        .arg("track")
+        .current_dir(dir.path())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let request = || -> std::io::Result<Vec<u8>> {
+        // um cliente parado a meio do pedido não atrasa os outros
+        let mut stalled = (0..50)
+            .find_map(|_| {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                std::net::TcpStream::connect(&listen).ok()
+            })
+            .expect("collector did not start");
+        stalled.write_all(b"GET /o/")?;
+        let mut stream = std::net::TcpStream::connect(&listen)?;
+        stream.set_read_timeout(Some(std::time::Duration::from_secs(3)))?;
+        // em dois bocados, como um cliente lento
+        write!(stream, "GET /o/{}.gif HTTP/1.1\r\n", id)?;
+        stream.flush()?;
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        write!(stream, "Host: {}\r\n\r\n", listen)?;
+        let mut response = vec![];
+        stream.read_to_end(&mut response)?;
+        Ok(response)
+    };
+    let response = request();
+    collector.kill().unwrap();
+    collector.wait().unwrap();
+    let response = response.unwrap();
+
+    assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+    assert!(response.ends_with(b"\x3b"));
+    let log = read_log();
+    assert_eq!(log["records"][0]["opens"].as_array().unwrap().len(), 1);
+    assert!(log["records"][1]["opens"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_serve_api_queues_and_reports() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com"]);
+    assert!(output.status.success());
+
+    let addr = free_addr();
+    let mut api = std::process::Command::new(env!("CARGO_BIN_EXE_job-mailer"))
+        .args(["serve", "--listen", &addr])
+        .current_dir(dir.path())
+        .env("SERVE_TOKEN", "segredo")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let auth = "Authorization: Bearer segredo";
+    let json_body = "Content-Type: application/json";
+    // um cliente parado a meio do pedido não prende a API
+    let mut stalled = (0..50)
+        .find_map(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            std::net::TcpStream::connect(&addr).ok()
+        })
+        .expect("server did not start");
+    std::io::Write::write_all(&mut stalled, b"GET /api/").unwrap();
+    let started = std::time::Instant::now();
+    let responses = [
+        http(&addr, "GET", "/api/stats", &[], ""),
+        http(&addr, "GET", "/api/stats", &[auth], ""),
+        http(&addr, "POST", "/api/send", &[auth, json_body], r#"{"to": ["jobs@globex.com"], "send_at": "2030-01-01 09:00"}"#),
+        http(&addr, "POST", "/api/send", &[auth, json_body], r#"{"to": ["globex"]}"#),
+        http(&addr, "GET", "/api/queue", &[auth], ""),
+        http(&addr, "GET", "/api/log?email=RH%40acme.com", &[auth], ""),
+        http(&addr, "GET", "/", &[], ""),
+        http(
+            &addr,
+            "POST",
+            "/api/capture?token=segredo",
+            &["Content-Type: application/x-www-form-urlencoded"],
+            "text=Envie+para+talent%40initech.com&url=https%3A%2F%2Fjobs.example%2F42&title=Backend",
+        ),
+    ];
+    let elapsed = started.elapsed();
+    api.kill().unwrap();
+    api.wait().unwrap();
+
+    assert!(elapsed < std::time::Duration::from_secs(5), "{:?}", elapsed);
+    let json = |body: &str| -> serde_json::Value { serde_json::from_str(body).unwrap() };
+    assert_eq!(responses[0].0, 401);
+    assert_eq!(responses[1].0, 200);
+    assert_eq!(json(&responses[1].1)["success"], 1);
+    assert_eq!(responses[2].0, 201);
+    assert_eq!(json(&responses[2].1)["queued"][0]["to"], "jobs@globex.com");
+    assert_eq!(responses[3].0, 400);
+    assert_eq!(json(&responses[3].1)["error"], "Invalid email: globex");
+    let queue = json(&responses[4].1);
+    assert_eq!(queue["items"].as_array().unwrap().len(), 1);
+    assert!(queue["items"][0]["send_at"].as_str().unwrap().starts_with("2030-01-01"));
+    let log = json(&responses[5].1);
+    assert_eq!(log["records"].as_array().unwrap().len(), 1);
+    // a página abre sem token; é ela que o pede à API
+    assert_eq!(responses[6].0, 200);
+    assert!(responses[6].1.contains("/api/stats"));
+    // o bookmarklet leva o token no endereço e manda um formulário
+    assert_eq!(responses[7].0, 201);
+    assert_eq!(json(&responses[7].1)["captured"][0]["email"], "talent@initech.com");
+    let output = run_cli(dir.path(), &["leads", "accept", "1"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let contacts = std::fs::read_to_string(dir.path().join("contacts.json")).unwrap();
+    assert!(contacts.contains("talent@initech.com"));
+    // nada saiu: a API só põe na fila
+    assert_eq!(server.messages().len(), 1);
+}
+
+#[test]
+fn test_idn_domain_goes_out_in_punycode() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+
+    // o servidor de teste não anuncia SMTPUTF8: só passa com o domínio em punycode
+    let output = run_cli(dir.path(), &["send", "--to", "rh@café.pt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let messages = server.messages();
+    assert_eq!(messages[0].rcpt, vec!["rh@xn--caf-dma.pt"]);
+    let (head, _) = split_entity(&messages[0].data);
+    assert_eq!(header(&head, "To").unwrap(), "rh@xn--caf-dma.pt");
+
+    // parte local acentuada sem SMTPUTF8 no servidor: falha com uma razão clara
+    let output = run_cli(dir.path(), &["send", "--to", "joão@acme.pt"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SMTPUTF8"));
+    assert_eq!(server.messages().len(), 1);
+}
+
+#[test]
+fn test_replies_scan_marks_the_answered_application() {
+    let server = TestSmtpServer::start();
+    let imap = TestImapServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["imap"] = serde_json::json!({ "host": "127.0.0.1", "port": imap.port, "security": "none" });
+    });
+
+    for to in ["rh@acme.com", "jobs@acme.com"] {
+        let output = run_cli(dir.path(), &["send", "--to", to]);
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+    let (head, _) = split_entity(&server.messages()[0].data);
+    let message_id = header(&head, "Message-ID").unwrap();
+    let log: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap()).unwrap();
+    assert_eq!(log["records"][0]["message"]["message_id"], message_id.as_str());
+
+    // responde outra pessoa da Acme, no fio do primeiro envio; e uma terceira sem fio nem assunto
+    imap.receive(&format!(
+        "From: Maria <maria@acme.com>\r\nTo: joao@example.com\r\nSubject: Re: Candidatura\r\n\
+         Message-ID: <r1@acme.com>\r\nIn-Reply-To: {}\r\n\r\nPodemos marcar uma entrevista?\r\n",
+        message_id
+    ));
+    imap.receive("From: ana@acme.com\r\nTo: joao@example.com\r\nSubject: Convite\r\nMessage-ID: <r2@acme.com>\r\n\r\nOla\r\n");
+    // a resposta automática de jobs@ não conta como resposta
+    imap.receive(
+        "From: jobs@acme.com\r\nSubject: Re: Candidatura\r\nAuto-Submitted: auto-replied\r\nMessage-ID: <r3@acme.com>\r\n\r\nRecebido\r\n",
+    );
+
+    let output = run_cli(dir.path(), &["replies", "scan"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("rh@acme.com → entrevista (pedido de entrevista)"), "{}", stdout);
+    assert!(stdout.contains("1 respostas por decidir"), "{}", stdout);
+    assert!(stdout.contains("jobs@acme.com: resposta automática"), "{}", stdout);
+    let log: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap()).unwrap();
+    assert_eq!(log["records"][0]["status"], "interview");
+    assert_eq!(log["records"][1]["status"], "sent");
+
+    // as mesmas mensagens outra vez não contam
+    let output = run_cli(dir.path(), &["replies", "scan"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("0 candidaturas actualizadas"));
+
+    let output = run_cli(dir.path(), &["replies", "pending"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[1]") && stdout.contains("ana@acme.com"), "{}", stdout);
+    let output = run_cli(dir.path(), &["replies", "resolve", "1", "jobs@acme.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let log: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("sent_log.json")).unwrap()).unwrap();
+    assert_eq!(log["records"][1]["status"], "replied");
+    assert!(String::from_utf8_lossy(&run_cli(dir.path(), &["replies", "pending"]).stdout).contains("Nenhuma"));
+}
+
+#[test]
+fn test_daemon_sends_the_campaign_follow_ups() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["templates"] = serde_json::json!({
+            "follow-up-1": { "subject": "Seguimento - {{title}}", "body": "Olá de novo" }
+        });
+        config["campaigns"] = serde_json::json!({
+            "backend-q3": { "sequence": [{ "day": 7, "template": "follow-up-1" }] }
+        });
+    });
+
+    let output = run_cli(dir.path(), &["send", "--to", "rh@acme.com", "--campaign", "backend-q3"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = run_cli(dir.path(), &["daemon", "--once"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(server.messages().len(), 1);
+
+    // uma semana depois o daemon envia o passo seguinte, uma só vez
+    let log_path = dir.path().join("sent_log.json");
+    let mut log: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&log_path).unwrap()).unwrap();
+    let week_ago = chrono::Local::now() - chrono::Duration::days(7);
+    log["records"][0]["sent_at"] = week_ago.to_rfc3339().into();
+    std::fs::write(&log_path, log.to_string()).unwrap();
+    for _ in 0..2 {
+        let output = run_cli(dir.path(), &["daemon", "--once"]);
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+    let messages = server.messages();
+    assert_eq!(messages.len(), 2);
+    let (head, _) = split_entity(&messages[1].data);
+    assert!(header(&head, "Subject").unwrap().starts_with("Seguimento - "));
+    let log: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&log_path).unwrap()).unwrap();
+    assert_eq!(log["records"][1]["campaign"], "backend-q3");
+    assert_eq!(log["records"][1]["step"], 1);
+    assert_eq!(log["records"][1]["message"]["template"], "follow-up-1");
+
+    let output = run_cli(dir.path(), &["send", "--to", "x@acme.com", "--campaign", "outra"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_campaign_run_sends_once_and_reports_status() {
+    let server = TestSmtpServer::start();
+    let dir = workspace(&server);
+    edit_config(dir.path(), |config| {
+        config["templates"] = serde_json::json!({
+            "spontaneous": { "subject": "Candidatura espontanea - {{title}}", "body": "Olá" },
+            "follow-up-1": { "subject": "Seguimento - {{title}}", "body": "Olá de novo" }
+        });
+        config["sending"] = serde_json::json!({ "delay": { "strategy": "uniform", "min": 0, "max": 0 } });
+        config["campaigns"] = serde_json::json!({
+            "backend-q3": {
+                "template": "spontaneous",
+                "recipients": ["rh@acme.com", "jobs@globex.com"],
+                "sequence": [{ "day": 7, "template": "follow-up-1" }]
+            },
+            "later": { "recipients": ["hr@initech.com"], "schedule": "2099-01-05 09:00" }
+        });
+    });
+
+    let output = run_cli(dir.path(), &["campaign", "run", "backend-q3", "--yes"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let messages = server.messages();
+    assert_eq!(messages.len(), 2);
+    let (head, _) = split_entity(&messages[0].data);
+    assert!(header(&head, "Subject").unwrap().starts_with("Candidatura espontanea - "));
+
+    // quem já recebeu não volta a receber
+    let output = run_cli(dir.path(), &["campaign", "run", "backend-q3", "--yes"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("nada por enviar"));
+    assert_eq!(server.messages().len(), 2);
+
+    let output = run_cli(dir.path(), &["campaign", "status", "backend-q3"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("jobs@globex.com") && stdout.contains("candidatura a ") && stdout.contains("follow-up-1 a "), "{}", stdout);
+
+    // com `schedule` vai para a fila, marcada com a campanha
+    let output = run_cli(dir.path(), &["campaign", "run", "later", "--yes"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let outbox: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("outbox.json")).unwrap()).unwrap();
+    assert_eq!(outbox["items"][0]["to"], "hr@initech.com");
+    assert_eq!(outbox["items"][0]["campaign"], "later");
+    let output = run_cli(dir.path(), &["campaign", "list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("2 enviadas") && stdout.contains("1 na fila"), "{}", stdout);
+    let output = run_cli(dir.path(), &["campaign", "compare"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("backend-q3") && stdout.contains("0 (0%)") && stdout.contains("Menos de 10"), "{}", stdout);
+}